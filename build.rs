@@ -9,8 +9,17 @@ fn main() {
 
     let target = env::var("TARGET").unwrap();
 
+    // The experimental API is only available in the vendored HarfBuzz, since
+    // system libraries are built without it.
+    let experimental_api = env::var_os("CARGO_FEATURE_EXPERIMENTAL_API").is_some();
+    // Likewise system libraries are rarely built with DirectWrite support.
+    let directwrite =
+        target.contains("windows") && env::var_os("CARGO_FEATURE_DIRECTWRITE").is_some();
+
     println!("cargo:rerun-if-env-changed=HARFBUZZ_SYS_NO_PKG_CONFIG");
-    if (target.contains("wasm32") || env::var_os("HARFBUZZ_SYS_NO_PKG_CONFIG").is_none())
+    if !experimental_api
+        && !directwrite
+        && (target.contains("wasm32") || env::var_os("HARFBUZZ_SYS_NO_PKG_CONFIG").is_none())
         && pkg_config::probe_library("harfbuzz").is_ok()
     {
         return;
@@ -23,6 +32,15 @@ fn main() {
         .include("harfbuzz/src")
         .file("harfbuzz/src/harfbuzz.cc");
 
+    if experimental_api {
+        cfg.define("HB_EXPERIMENTAL_API", "1");
+    }
+
+    if directwrite {
+        cfg.define("HAVE_DIRECTWRITE", "1");
+        println!("cargo:rustc-link-lib=dwrite");
+    }
+
     if !target.contains("windows") {
         cfg.define("HAVE_PTHREAD", "1");
     }
@@ -0,0 +1,33 @@
+use harfbuzz_rs::tools::preview;
+use harfbuzz_rs::Feature;
+
+// Shapes some text and prints the result, similar to `hb-shape`.
+//
+// Usage: cargo run --example preview -- <font file> <text> [features...]
+//
+// Features use the syntax of `hb-shape`, e.g. `smcp`, `-liga` or `kern[3:5]=0`.
+// For example `cargo run --example preview -- testfiles/SourceSansVariable-Roman.ttf Hi`
+// prints:
+// direction=Ltr script=Latn language=en-us
+// [gid9=0+634|gid36=1+222]
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 {
+        eprintln!("usage: preview <font file> <text> [features...]");
+        std::process::exit(2);
+    }
+    let features: Vec<Feature> = match args[2..].iter().map(|arg| arg.parse()).collect() {
+        Ok(features) => features,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(2);
+        }
+    };
+    match preview(&args[0], &args[1], &features) {
+        Ok(output) => print!("{}", output),
+        Err(error) => {
+            eprintln!("error reading {}: {}", args[0], error);
+            std::process::exit(1);
+        }
+    }
+}
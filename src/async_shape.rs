@@ -0,0 +1,72 @@
+//! Shaping from async code.
+//!
+//! This module is only available with the `async` feature.
+
+use crate::buffer::{GlyphBuffer, UnicodeBuffer};
+use crate::common::Shared;
+use crate::font::Font;
+use crate::{shape, Feature};
+
+/// Shape `text` with `font` and `features` on tokio's blocking thread pool.
+///
+/// Shaping long paragraphs can take long enough to stall an async executor,
+/// so this function moves the work off the executor's worker threads. The
+/// segment properties of the text are guessed as in [`shape()`](crate::shape).
+///
+/// This function must be called from within a tokio runtime.
+///
+/// # Panics
+///
+/// If shaping panics, the panic is resumed in the task awaiting the result.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font: Shared<Font<'static>> = Font::new(face).into();
+///
+/// let output = runtime.block_on(shape_async(font, "Hello".to_string(), vec![]));
+/// assert_eq!(output.len(), 5);
+/// ```
+pub async fn shape_async(
+    font: Shared<Font<'static>>,
+    text: String,
+    features: Vec<Feature>,
+) -> GlyphBuffer {
+    let result = tokio::task::spawn_blocking(move || {
+        let buffer = UnicodeBuffer::new().add_str(&text);
+        shape(&font, buffer, &features)
+    })
+    .await;
+    match result {
+        Ok(output) => output,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Face;
+
+    #[test]
+    fn test_shape_async() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font: Shared<Font<'static>> = Font::new(face).into();
+
+        let expected = shape(&font, UnicodeBuffer::new().add_str("Hello World!"), &[]);
+        let tasks: Vec<_> = (0..4)
+            .map(|_| runtime.spawn(shape_async(font.clone(), "Hello World!".into(), vec![])))
+            .collect();
+        for task in tasks {
+            let output = runtime.block_on(task).unwrap();
+            assert_eq!(output.content_hash(), expected.content_hash());
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use crate::bindings::{
+    hb_ot_metrics_get_position_with_fallback, hb_ot_metrics_tag_t,
+    HB_OT_METRICS_TAG_SUBSCRIPT_EM_X_OFFSET, HB_OT_METRICS_TAG_SUBSCRIPT_EM_X_SIZE,
+    HB_OT_METRICS_TAG_SUBSCRIPT_EM_Y_OFFSET, HB_OT_METRICS_TAG_SUBSCRIPT_EM_Y_SIZE,
+    HB_OT_METRICS_TAG_SUPERSCRIPT_EM_X_OFFSET, HB_OT_METRICS_TAG_SUPERSCRIPT_EM_X_SIZE,
+    HB_OT_METRICS_TAG_SUPERSCRIPT_EM_Y_OFFSET, HB_OT_METRICS_TAG_SUPERSCRIPT_EM_Y_SIZE,
+};
+use crate::buffer::GlyphBuffer;
+use crate::common::HarfbuzzObject;
+use crate::font::{Font, Position};
+
+/// The kind of baseline shift of a run of text, like the `super` and `sub`
+/// values of CSS' `vertical-align`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BaselineShift {
+    /// The run is raised as a superscript.
+    Superscript,
+    /// The run is lowered as a subscript.
+    Subscript,
+}
+
+/// The placement of a superscript or subscript run as suggested by a font.
+///
+/// The values come from the `ySuperscript*` and `ySubscript*` fields of the
+/// OS/2 table (as varied by the `MVAR` table) and are in the units of the
+/// font they were computed for. HarfBuzz synthesizes values for fonts
+/// without these fields.
+///
+/// To set a run as a superscript or subscript, shape it with a copy of the
+/// font scaled by [`x_scale`](Self::x_scale) and
+/// [`y_scale`](Self::y_scale) and move the resulting glyphs with
+/// [`apply()`](Self::apply).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ScriptPlacement {
+    /// The horizontal offset of the run.
+    pub x_offset: Position,
+    /// The vertical offset of the run; positive for superscripts, negative
+    /// for subscripts.
+    pub y_offset: Position,
+    /// The suggested horizontal scale of the run relative to the font.
+    pub x_scale: f32,
+    /// The suggested vertical scale of the run relative to the font.
+    pub y_scale: f32,
+}
+
+fn ot_metric(font: &Font<'_>, tag: hb_ot_metrics_tag_t) -> Position {
+    let mut position = 0;
+    unsafe { hb_ot_metrics_get_position_with_fallback(font.as_raw(), tag, &mut position) };
+    position
+}
+
+impl ScriptPlacement {
+    /// Returns the placement `font` suggests for runs with the baseline
+    /// `shift`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    ///
+    /// let superscript = ScriptPlacement::from_font(&font, BaselineShift::Superscript);
+    /// let subscript = ScriptPlacement::from_font(&font, BaselineShift::Subscript);
+    /// assert!(superscript.y_offset > 0 && subscript.y_offset < 0);
+    /// assert!(superscript.y_scale < 1.0);
+    /// ```
+    pub fn from_font(font: &Font<'_>, shift: BaselineShift) -> ScriptPlacement {
+        let (x_offset, y_offset, x_size, y_size) = match shift {
+            BaselineShift::Superscript => (
+                HB_OT_METRICS_TAG_SUPERSCRIPT_EM_X_OFFSET,
+                HB_OT_METRICS_TAG_SUPERSCRIPT_EM_Y_OFFSET,
+                HB_OT_METRICS_TAG_SUPERSCRIPT_EM_X_SIZE,
+                HB_OT_METRICS_TAG_SUPERSCRIPT_EM_Y_SIZE,
+            ),
+            BaselineShift::Subscript => (
+                HB_OT_METRICS_TAG_SUBSCRIPT_EM_X_OFFSET,
+                HB_OT_METRICS_TAG_SUBSCRIPT_EM_Y_OFFSET,
+                HB_OT_METRICS_TAG_SUBSCRIPT_EM_X_SIZE,
+                HB_OT_METRICS_TAG_SUBSCRIPT_EM_Y_SIZE,
+            ),
+        };
+        // the OS/2 table measures the subscript offset downwards
+        let y_offset = match shift {
+            BaselineShift::Superscript => ot_metric(font, y_offset),
+            BaselineShift::Subscript => -ot_metric(font, y_offset),
+        };
+        // the sizes are relative to the em, which is the scale of the font
+        let (x_em, y_em) = font.scale();
+        let relative = |size: Position, em: i32| {
+            if em == 0 {
+                1.0
+            } else {
+                size as f32 / em as f32
+            }
+        };
+        ScriptPlacement {
+            x_offset: ot_metric(font, x_offset),
+            y_offset,
+            x_scale: relative(ot_metric(font, x_size), x_em),
+            y_scale: relative(ot_metric(font, y_size), y_em),
+        }
+    }
+
+    /// Moves all glyphs of `glyphs` by the offsets of the placement.
+    ///
+    /// The advances are not changed, so the run takes the same space on the
+    /// line as before.
+    pub fn apply(&self, glyphs: &mut GlyphBuffer) {
+        for position in glyphs.0.get_glyph_positions_mut() {
+            position.x_offset += self.x_offset;
+            position.y_offset += self.y_offset;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{shape, Face, UnicodeBuffer};
+
+    #[test]
+    fn test_script_placement() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let font = Font::new(Face::from_file(path, 0).unwrap());
+        let superscript = ScriptPlacement::from_font(&font, BaselineShift::Superscript);
+        let subscript = ScriptPlacement::from_font(&font, BaselineShift::Subscript);
+        assert!(superscript.y_offset > 0);
+        assert!(subscript.y_offset < 0);
+        for placement in &[superscript, subscript] {
+            assert!(placement.x_scale > 0.0 && placement.x_scale < 1.0);
+            assert!(placement.y_scale > 0.0 && placement.y_scale < 1.0);
+        }
+
+        // the placement scales with the font
+        let mut large = Font::new(Face::from_file(path, 0).unwrap());
+        let (x_scale, y_scale) = font.scale();
+        large.set_scale(x_scale * 2, y_scale * 2);
+        let large_superscript = ScriptPlacement::from_font(&large, BaselineShift::Superscript);
+        assert_eq!(large_superscript.y_offset, superscript.y_offset * 2);
+        assert_eq!(large_superscript.y_scale, superscript.y_scale);
+
+        let mut glyphs = shape(&font, UnicodeBuffer::new().add_str("ab"), &[]);
+        let advances: Vec<_> = glyphs.x_advances().collect();
+        subscript.apply(&mut glyphs);
+        for position in glyphs.get_glyph_positions() {
+            assert_eq!(position.x_offset, subscript.x_offset);
+            assert_eq!(position.y_offset, subscript.y_offset);
+        }
+        assert_eq!(glyphs.x_advances().collect::<Vec<_>>(), advances);
+    }
+}
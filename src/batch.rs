@@ -0,0 +1,433 @@
+use crate::buffer::{GlyphBuffer, SegmentProperties, ShapedGlyph, UnicodeBuffer};
+use crate::font::{Font, Position};
+use crate::shape_plan::PlanCache;
+use crate::{resolve_segment_properties, shape, Feature};
+
+use std::ops::Range;
+
+/// A run of text to be shaped by a [`BatchShaper`].
+#[derive(Debug, Clone)]
+pub struct BatchRun<'t> {
+    /// The index of the font in the font set of the `BatchShaper`.
+    pub font: usize,
+    /// The text of the run.
+    pub text: &'t str,
+    /// The features to apply to the run.
+    pub features: &'t [Feature],
+    /// The segment properties of the run. If `None` they are guessed from the
+    /// text.
+    pub segment_properties: Option<SegmentProperties>,
+}
+
+impl<'t> BatchRun<'t> {
+    /// Creates a run shaping `text` with the font at index `font` without any
+    /// additional features.
+    pub fn new(font: usize, text: &'t str) -> BatchRun<'t> {
+        BatchRun {
+            font,
+            text,
+            features: &[],
+            segment_properties: None,
+        }
+    }
+}
+
+/// Describes a shaped run stored in a [`ShapedBatch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunInfo {
+    /// The index of the font the run was shaped with.
+    pub font: usize,
+    /// The range of the run's glyphs in [`ShapedBatch::glyphs()`].
+    pub glyphs: Range<usize>,
+    /// The sum of the horizontal advances of the run's glyphs.
+    pub x_advance: Position,
+    /// The sum of the vertical advances of the run's glyphs.
+    pub y_advance: Position,
+}
+
+/// The results of shaping many runs with a [`BatchShaper`].
+///
+/// The glyphs of all runs are stored contiguously in a single `Vec`, so
+/// shaping thousands of small runs needs only a handful of allocations. The
+/// glyph clusters are relative to the start of the text of each run.
+#[derive(Debug, Default, Clone)]
+pub struct ShapedBatch {
+    glyphs: Vec<ShapedGlyph>,
+    runs: Vec<RunInfo>,
+}
+
+impl ShapedBatch {
+    /// Creates an empty batch.
+    pub fn new() -> ShapedBatch {
+        ShapedBatch::default()
+    }
+
+    /// Returns the glyphs of all runs.
+    pub fn glyphs(&self) -> &[ShapedGlyph] {
+        &self.glyphs
+    }
+
+    /// Returns the descriptions of the shaped runs in the order they were
+    /// shaped.
+    pub fn runs(&self) -> &[RunInfo] {
+        &self.runs
+    }
+
+    /// Returns the glyphs of the run at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn run_glyphs(&self, index: usize) -> &[ShapedGlyph] {
+        &self.glyphs[self.runs[index].glyphs.clone()]
+    }
+
+    /// Returns an iterator over the runs and their glyphs.
+    pub fn iter(&self) -> impl Iterator<Item = (&RunInfo, &[ShapedGlyph])> + '_ {
+        self.runs
+            .iter()
+            .map(move |run| (run, &self.glyphs[run.glyphs.clone()]))
+    }
+
+    /// Returns the number of runs.
+    pub fn len(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Returns `true` if the batch contains no runs.
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Removes all runs while keeping the allocated memory.
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        self.runs.clear();
+    }
+}
+
+/// Shapes many runs of text against a set of fonts.
+///
+/// A `BatchShaper` reuses a single HarfBuzz buffer for all runs and stores the
+/// results in a [`ShapedBatch`], which avoids most of the per-run allocations
+/// of calling [`shape()`](crate::shape) for every run. This is useful for
+/// services that shape many small runs per request.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let path = "testfiles/SourceSansVariable-Roman.ttf";
+/// let regular = Font::new(Face::from_file(path, 0).unwrap());
+/// let mut bold = Font::new(Face::from_file(path, 0).unwrap());
+/// bold.set_variations(&[Variation::new(b"wght", 700.0)]);
+///
+/// let mut shaper = BatchShaper::new(&[&regular, &bold]);
+/// let batch = shaper.shape(vec![BatchRun::new(0, "Hello "), BatchRun::new(1, "World")]);
+///
+/// assert_eq!(batch.len(), 2);
+/// assert_eq!(batch.run_glyphs(1).len(), 5);
+/// assert_eq!(batch.glyphs().len(), 11);
+/// ```
+#[derive(Debug)]
+pub struct BatchShaper<'a> {
+    fonts: Vec<&'a Font<'a>>,
+    buffer: Option<UnicodeBuffer>,
+}
+
+impl<'a> BatchShaper<'a> {
+    /// Creates a shaper for the given set of fonts. Runs refer to the fonts by
+    /// their index.
+    pub fn new(fonts: &[&'a Font<'a>]) -> BatchShaper<'a> {
+        BatchShaper {
+            fonts: fonts.to_vec(),
+            buffer: Some(UnicodeBuffer::new()),
+        }
+    }
+
+    /// Returns the font set of the shaper.
+    pub fn fonts(&self) -> &[&'a Font<'a>] {
+        &self.fonts
+    }
+
+    /// Shapes `runs` and returns the results in a new [`ShapedBatch`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if a run refers to a font index that is out of bounds.
+    pub fn shape<'t>(&mut self, runs: impl IntoIterator<Item = BatchRun<'t>>) -> ShapedBatch {
+        let mut batch = ShapedBatch::new();
+        self.shape_into(runs, &mut batch);
+        batch
+    }
+
+    /// Shapes `runs` and appends the results to `batch`.
+    ///
+    /// Reusing a cleared `ShapedBatch` for consecutive calls avoids
+    /// allocations once the batch has grown large enough.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a run refers to a font index that is out of bounds.
+    pub fn shape_into<'t>(
+        &mut self,
+        runs: impl IntoIterator<Item = BatchRun<'t>>,
+        batch: &mut ShapedBatch,
+    ) {
+        for run in runs {
+            let font = run.font;
+            self.shape_run(run, |output| {
+                let start = batch.glyphs.len();
+                batch.glyphs.extend(output.iter());
+                let glyphs = &batch.glyphs[start..];
+                batch.runs.push(RunInfo {
+                    font,
+                    glyphs: start..batch.glyphs.len(),
+                    x_advance: glyphs.iter().map(|glyph| glyph.x_advance).sum(),
+                    y_advance: glyphs.iter().map(|glyph| glyph.y_advance).sum(),
+                });
+            });
+        }
+    }
+
+    /// Shapes `run` and returns only its total horizontal and vertical
+    /// advance.
+    ///
+    /// No glyphs are retained, which makes this suitable for measurement
+    /// passes that only need the size of a run before doing the actual layout.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the run refers to a font index that is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let mut shaper = BatchShaper::new(&[&font]);
+    ///
+    /// let (width, height) = shaper.measure(BatchRun::new(0, "Hello"));
+    /// assert!(width > 0);
+    /// assert_eq!(height, 0);
+    /// ```
+    pub fn measure(&mut self, run: BatchRun<'_>) -> (Position, Position) {
+        let mut advance = (0, 0);
+        self.shape_run(run, |output| {
+            for pos in output.get_glyph_positions() {
+                advance.0 += pos.x_advance;
+                advance.1 += pos.y_advance;
+            }
+        });
+        advance
+    }
+
+    // Shapes `run` using the reused buffer and passes the result to `f`.
+    fn shape_run(&mut self, run: BatchRun<'_>, f: impl FnOnce(&GlyphBuffer)) {
+        let font = self.fonts[run.font];
+        let mut buffer = self.buffer.take().unwrap_or_default().add_str(run.text);
+        if let Some(props) = run.segment_properties {
+            buffer = buffer.set_segment_properties(props);
+        }
+        let output = shape(font, buffer, run.features);
+        f(&output);
+        self.buffer = Some(output.reset());
+    }
+}
+
+/// A run of text to be shaped with [`shape_runs()`].
+#[derive(Debug, Clone)]
+pub struct Run<'t> {
+    /// The text of the run.
+    pub text: &'t str,
+    /// The features to apply to the run.
+    pub features: &'t [Feature],
+    /// The segment properties of the run. If `None` they are guessed from the
+    /// text.
+    pub segment_properties: Option<SegmentProperties>,
+}
+
+impl<'t> Run<'t> {
+    /// Creates a run shaping `text` without any additional features.
+    pub fn new(text: &'t str) -> Run<'t> {
+        Run {
+            text,
+            features: &[],
+            segment_properties: None,
+        }
+    }
+}
+
+/// The result of shaping a [`Run`] with [`shape_runs()`].
+///
+/// The glyph clusters are relative to the start of the text of the run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShapedRun {
+    /// The segment properties the run was shaped with.
+    pub segment_properties: SegmentProperties,
+    /// The glyphs of the run.
+    pub glyphs: Vec<ShapedGlyph>,
+    /// The sum of the horizontal advances of the glyphs.
+    pub x_advance: Position,
+    /// The sum of the vertical advances of the glyphs.
+    pub y_advance: Position,
+}
+
+// The number of distinct plans kept by `shape_runs`, which is plenty for the
+// script, direction and feature combinations of a paragraph.
+const RUN_PLAN_CAPACITY: usize = 16;
+
+/// Shapes many runs of text with `font`.
+///
+/// This produces the same glyphs as calling [`shape()`](crate::shape) for
+/// every run, but reuses a single buffer for all runs and creates a shape
+/// plan only once for every combination of segment properties and features,
+/// so shaping a paragraph as many small runs does not pay the setup cost of
+/// every run.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let kern = [Feature::new(b"kern", 0, ..)];
+///
+/// let runs = vec![
+///     Run::new("Hello "),
+///     Run { features: &kern, ..Run::new("World") },
+/// ];
+/// let shaped = shape_runs(&font, runs);
+/// assert_eq!(shaped.len(), 2);
+/// assert_eq!(shaped[1].glyphs.len(), 5);
+/// ```
+pub fn shape_runs<'t>(font: &Font<'_>, runs: impl IntoIterator<Item = Run<'t>>) -> Vec<ShapedRun> {
+    let mut shaper = RunShaper::new();
+    runs.into_iter()
+        .map(|run| shaper.shape(font, &run))
+        .collect()
+}
+
+// The buffer and plans reused for shaping `Run`s.
+pub(crate) struct RunShaper {
+    plans: PlanCache,
+    buffer: Option<UnicodeBuffer>,
+}
+
+impl RunShaper {
+    pub(crate) fn new() -> RunShaper {
+        RunShaper {
+            plans: PlanCache::new(RUN_PLAN_CAPACITY),
+            buffer: Some(UnicodeBuffer::new()),
+        }
+    }
+
+    pub(crate) fn shape(&mut self, font: &Font<'_>, run: &Run<'_>) -> ShapedRun {
+        let mut buffer = self.buffer.take().unwrap_or_default().add_str(run.text);
+        if let Some(props) = run.segment_properties {
+            buffer = buffer.set_segment_properties(props);
+        }
+        let buffer = resolve_segment_properties(buffer);
+        let props = buffer.get_segment_properties();
+        let plan = self.plans.get(font, &props, run.features);
+        let output = plan.execute(font, buffer, run.features);
+        let glyphs: Vec<ShapedGlyph> = output.iter().collect();
+        self.buffer = Some(output.reset());
+        ShapedRun {
+            segment_properties: props,
+            x_advance: glyphs.iter().map(|glyph| glyph.x_advance).sum(),
+            y_advance: glyphs.iter().map(|glyph| glyph.y_advance).sum(),
+            glyphs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Direction, Face};
+
+    #[test]
+    fn test_shape_runs_matches_shape() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let smcp = [Feature::new(b"smcp", 1, ..)];
+        let rtl = UnicodeBuffer::new()
+            .add_str("a")
+            .set_direction(Direction::Rtl)
+            .guess_segment_properties()
+            .get_segment_properties();
+        let runs = vec![
+            Run::new("Hello"),
+            Run::new(""),
+            Run {
+                features: &smcp,
+                ..Run::new("Hello")
+            },
+            Run {
+                segment_properties: Some(rtl),
+                ..Run::new("ab")
+            },
+            Run::new("fi"),
+        ];
+
+        let shaped = shape_runs(&font, runs.clone());
+        assert_eq!(shaped.len(), runs.len());
+        for (run, shaped) in runs.iter().zip(&shaped) {
+            let mut buffer = UnicodeBuffer::new().add_str(run.text);
+            if let Some(props) = run.segment_properties {
+                buffer = buffer.set_segment_properties(props);
+            }
+            let expected = shape(&font, buffer, run.features);
+            assert_eq!(shaped.glyphs, expected.iter().collect::<Vec<_>>());
+            assert_eq!(shaped.segment_properties, expected.get_segment_properties());
+            assert_eq!(
+                shaped.x_advance,
+                expected.iter().map(|g| g.x_advance).sum::<Position>()
+            );
+        }
+        assert_ne!(shaped[0].glyphs, shaped[2].glyphs);
+        assert_eq!(shaped[3].segment_properties.direction, Direction::Rtl);
+    }
+
+    #[test]
+    fn test_batch_matches_shape() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let texts = ["Hello", "", "World!", "fi"];
+
+        let mut shaper = BatchShaper::new(&[&font]);
+        let mut batch = shaper.shape(texts.iter().map(|text| BatchRun::new(0, text)));
+        assert_eq!(batch.len(), texts.len());
+        for ((run, glyphs), text) in batch.iter().zip(&texts) {
+            let expected = shape(&font, UnicodeBuffer::new().add_str(text), &[]);
+            assert_eq!(glyphs, expected.iter().collect::<Vec<_>>().as_slice());
+            assert_eq!(
+                run.x_advance,
+                expected.iter().map(|g| g.x_advance).sum::<Position>()
+            );
+            let measured = shaper.measure(BatchRun::new(0, text));
+            assert_eq!(measured, (run.x_advance, run.y_advance));
+        }
+
+        // reusing the batch keeps appending runs
+        let props = UnicodeBuffer::new()
+            .add_str("a")
+            .set_direction(Direction::Rtl)
+            .guess_segment_properties()
+            .get_segment_properties();
+        let run = BatchRun {
+            segment_properties: Some(props),
+            ..BatchRun::new(0, "ab")
+        };
+        batch.clear();
+        shaper.shape_into(vec![run], &mut batch);
+        assert_eq!(batch.len(), 1);
+        // right-to-left runs have their glyphs in visual order
+        assert_eq!(batch.run_glyphs(0)[0].cluster, 1);
+    }
+}
@@ -1,5 +1,6 @@
 use std::os::raw::c_void;
 
+use std::convert::TryFrom;
 use std::marker::PhantomData;
 
 use std::fmt;
@@ -17,10 +18,52 @@ use crate::bindings::hb_blob_is_immutable;
 use crate::bindings::hb_blob_make_immutable;
 use crate::bindings::hb_blob_reference;
 use crate::bindings::hb_blob_t;
+use crate::bindings::hb_memory_mode_t;
+use crate::bindings::HB_MEMORY_MODE_DUPLICATE;
 use crate::bindings::HB_MEMORY_MODE_READONLY;
+use crate::bindings::HB_MEMORY_MODE_READONLY_MAY_MAKE_WRITABLE;
 use crate::bindings::HB_MEMORY_MODE_WRITABLE;
 use crate::common::{HarfbuzzObject, Owned, Shared};
 
+// The initial value of a 64-bit FNV-1a hash.
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+// Continues the 64-bit FNV-1a hash `hash` with `bytes`.
+pub(crate) fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Specifies how HarfBuzz may access memory that is wrapped by a `Blob`.
+///
+/// See [`Blob::from_raw_parts()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MemoryMode {
+    /// HarfBuzz immediately makes a copy of the data.
+    Duplicate,
+    /// Neither HarfBuzz nor anybody else will modify the data.
+    ReadOnly,
+    /// The data was copied solely for HarfBuzz, so HarfBuzz may modify it.
+    Writable,
+    /// The data is read-only but HarfBuzz may try to make it writable (e.g.
+    /// using `mprotect` on memory-mapped files) instead of copying it.
+    ReadOnlyMayMakeWritable,
+}
+
+impl MemoryMode {
+    /// Convert into raw value of type `hb_memory_mode_t`.
+    pub fn into_raw(self) -> hb_memory_mode_t {
+        match self {
+            MemoryMode::Duplicate => HB_MEMORY_MODE_DUPLICATE,
+            MemoryMode::ReadOnly => HB_MEMORY_MODE_READONLY,
+            MemoryMode::Writable => HB_MEMORY_MODE_WRITABLE,
+            MemoryMode::ReadOnlyMayMakeWritable => HB_MEMORY_MODE_READONLY_MAY_MAKE_WRITABLE,
+        }
+    }
+}
+
 /// A `Blob` manages raw data like e.g. file contents. It refers to a slice of
 /// bytes that can be either owned by the `Blob` or not.
 ///
@@ -44,6 +87,32 @@ pub struct Blob<'a> {
     marker: PhantomData<&'a [u8]>,
 }
 impl<'a> Blob<'a> {
+    /// Returns the raw `hb_blob_t` pointer of the blob for FFI calls.
+    ///
+    /// This is the same as [`HarfbuzzObject::as_raw()`] without the need to
+    /// import the trait. The reference count is not changed, so the pointer
+    /// must not be used after the blob is destroyed.
+    ///
+    /// Note that this shadows the `as_ptr()` method of `[u8]`, which `Blob`
+    /// dereferences to. Use `get_data().as_ptr()` to get a pointer to the data
+    /// of the blob.
+    pub fn as_ptr(&self) -> *mut hb_blob_t {
+        self.as_raw()
+    }
+
+    /// Wraps a raw `hb_blob_t` pointer, e.g. one created by a C library.
+    ///
+    /// The reference count is increased, so the caller still has to release
+    /// its own reference.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a valid pointer to a `hb_blob_t` whose data lives at least
+    /// as long as `'a`.
+    pub unsafe fn from_ptr(ptr: *mut hb_blob_t) -> Shared<Blob<'a>> {
+        Shared::from_raw_ref(ptr)
+    }
+
     /// Create a new `Blob` from the slice `bytes`. The blob will not own the
     /// slice's data.
     pub fn with_bytes(bytes: &'a [u8]) -> Owned<Blob<'a>> {
@@ -103,6 +172,65 @@ impl<'a> Blob<'a> {
         unsafe { Owned::from_raw(hb_blob) }
     }
 
+    /// Create a new `Blob` from memory that is owned by some other system, like
+    /// a memory-mapped file, a GPU staging buffer or a C allocation.
+    ///
+    /// `destroy` is called exactly once when HarfBuzz no longer needs the
+    /// memory, i.e. when the last reference to the blob (and to all blobs and
+    /// faces created from it) is gone. With `MemoryMode::Duplicate` the data
+    /// is copied right away and `destroy` is called before this function
+    /// returns.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to `len` bytes of memory that stay valid until
+    /// `destroy` is called. Unless `mode` is `MemoryMode::Duplicate` the
+    /// memory must not be modified by anybody else during that time, and with
+    /// `MemoryMode::Writable` it must also be writable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` does not fit into a `u32`, the length type of HarfBuzz.
+    /// `destroy` is dropped without being called in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Blob, MemoryMode};
+    ///
+    /// let data = vec![1u8, 2, 3];
+    /// let (ptr, len) = (data.as_ptr(), data.len());
+    ///
+    /// // moving the vector into the closure keeps its heap allocation alive
+    /// let blob = unsafe { Blob::from_raw_parts(ptr, len, MemoryMode::ReadOnly, move || drop(data)) };
+    /// assert_eq!(blob.get_data(), &[1, 2, 3]);
+    /// ```
+    pub unsafe fn from_raw_parts<F>(
+        ptr: *const u8,
+        len: usize,
+        mode: MemoryMode,
+        destroy: F,
+    ) -> Owned<Blob<'a>>
+    where
+        F: 'a + Send + FnOnce(),
+    {
+        extern "C" fn destroy_closure<F: FnOnce()>(user_data: *mut c_void) {
+            let closure = unsafe { Box::from_raw(user_data as *mut F) };
+            closure();
+        }
+
+        let len = u32::try_from(len).expect("blob data is too long");
+        let user_data = Box::into_raw(Box::new(destroy));
+        let hb_blob = hb_blob_create(
+            ptr as *const _,
+            len,
+            mode.into_raw(),
+            user_data as *mut _,
+            Some(destroy_closure::<F>),
+        );
+        Owned::from_raw(hb_blob)
+    }
+
     /// Create a `Blob` from the contents of the file at `path` whose contents
     /// will be read into memory.
     ///
@@ -122,10 +250,36 @@ impl<'a> Blob<'a> {
         unsafe {
             let mut length = hb_blob_get_length(self.as_raw());
             let data_ptr = hb_blob_get_data(self.as_raw(), &mut length as *mut _);
+            if data_ptr.is_null() {
+                // the empty blob has no data pointer
+                return &[];
+            }
             std::slice::from_raw_parts(data_ptr as *const u8, length as usize)
         }
     }
 
+    /// Returns a hash of the bytes of the blob.
+    ///
+    /// The hash function is fixed (64-bit FNV-1a), so the digest is stable
+    /// across runs and platforms. Blobs with the same contents have the same
+    /// digest regardless of where their data came from, which makes it
+    /// suitable for identifying fonts, e.g. to deduplicate the same font
+    /// loaded from different paths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::Blob;
+    ///
+    /// let data = std::fs::read("testfiles/SourceSansVariable-Roman.ttf").unwrap();
+    /// let blob = Blob::from_file("testfiles/SourceSansVariable-Roman.ttf").unwrap();
+    /// assert_eq!(Blob::with_bytes(&data).digest(), blob.digest());
+    /// assert_ne!(Blob::with_bytes(&data[1..]).digest(), blob.digest());
+    /// ```
+    pub fn digest(&self) -> u64 {
+        fnv1a(FNV_OFFSET_BASIS, self.get_data())
+    }
+
     /// Creates an immutable `Blob` that contains part of the data of the parent
     /// `Blob`. The parent `Blob` will be immutable after this and the sub`Blob`
     /// cannot outlive its parent.
@@ -254,6 +408,13 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_digest_is_fnv1a() {
+        assert_eq!(Blob::with_bytes(&[]).digest(), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(Blob::with_bytes(b"a").digest(), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(Blob::with_bytes(b"foobar").digest(), 0x8594_4171_f739_67e8);
+    }
+
     use std::sync::Arc;
     #[test]
     fn test_arc_to_blob_conversion() {
@@ -286,4 +447,43 @@ mod tests {
         std::mem::drop(blob);
         assert_eq!(Arc::strong_count(&rc_slice), 1);
     }
+
+    #[test]
+    fn test_blob_from_raw_parts() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static DESTROYED: AtomicUsize = AtomicUsize::new(0);
+        let data = [1u8, 2, 3, 4];
+
+        let blob = unsafe {
+            Blob::from_raw_parts(data.as_ptr(), data.len(), MemoryMode::ReadOnly, || {
+                DESTROYED.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        let sub_blob = blob.create_sub_blob(1, 2);
+        std::mem::drop(blob);
+        assert_eq!(DESTROYED.load(Ordering::SeqCst), 0);
+        assert_eq!(sub_blob.get_data(), &[2, 3]);
+        std::mem::drop(sub_blob);
+        assert_eq!(DESTROYED.load(Ordering::SeqCst), 1);
+
+        // duplicated data is released immediately
+        let blob = unsafe {
+            Blob::from_raw_parts(data.as_ptr(), data.len(), MemoryMode::Duplicate, || {
+                DESTROYED.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        assert_eq!(DESTROYED.load(Ordering::SeqCst), 2);
+        assert_eq!(blob.get_data(), &data);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    #[should_panic(expected = "too long")]
+    fn test_blob_from_raw_parts_too_long() {
+        let data = [0u8];
+        // the length is checked before the memory is used
+        let len = u32::MAX as usize + 1;
+        let _ = unsafe { Blob::from_raw_parts(data.as_ptr(), len, MemoryMode::ReadOnly, || {}) };
+    }
 }
@@ -1,16 +1,20 @@
 use crate::bindings::{
-    hb_buffer_add, hb_buffer_add_utf8, hb_buffer_append, hb_buffer_clear_contents,
-    hb_buffer_cluster_level_t, hb_buffer_content_type_t, hb_buffer_create, hb_buffer_destroy,
-    hb_buffer_get_cluster_level, hb_buffer_get_content_type, hb_buffer_get_direction,
-    hb_buffer_get_empty, hb_buffer_get_glyph_infos, hb_buffer_get_glyph_positions,
-    hb_buffer_get_language, hb_buffer_get_length, hb_buffer_get_script,
-    hb_buffer_get_segment_properties, hb_buffer_guess_segment_properties, hb_buffer_pre_allocate,
-    hb_buffer_reference, hb_buffer_reverse, hb_buffer_reverse_range, hb_buffer_serialize_format_t,
-    hb_buffer_serialize_glyphs, hb_buffer_set_cluster_level, hb_buffer_set_content_type,
-    hb_buffer_set_direction, hb_buffer_set_language, hb_buffer_set_script, hb_buffer_t,
-    hb_glyph_flags_t, hb_glyph_info_get_glyph_flags, hb_glyph_info_t, hb_mask_t,
-    hb_script_from_iso15924_tag, hb_script_t, hb_script_to_iso15924_tag, hb_segment_properties_t,
-    hb_var_int_t, HB_BUFFER_CLUSTER_LEVEL_CHARACTERS, HB_BUFFER_CLUSTER_LEVEL_MONOTONE_CHARACTERS,
+    hb_buffer_add, hb_buffer_add_latin1, hb_buffer_add_utf16, hb_buffer_add_utf32,
+    hb_buffer_add_utf8, hb_buffer_append, hb_buffer_clear_contents, hb_buffer_cluster_level_t,
+    hb_buffer_content_type_t, hb_buffer_create, hb_buffer_destroy, hb_buffer_get_cluster_level,
+    hb_buffer_get_content_type, hb_buffer_get_direction, hb_buffer_get_empty,
+    hb_buffer_get_glyph_infos, hb_buffer_get_glyph_positions, hb_buffer_get_invisible_glyph,
+    hb_buffer_get_language, hb_buffer_get_length, hb_buffer_get_not_found_glyph,
+    hb_buffer_get_replacement_codepoint, hb_buffer_get_script, hb_buffer_get_segment_properties,
+    hb_buffer_guess_segment_properties, hb_buffer_pre_allocate, hb_buffer_reference,
+    hb_buffer_reset, hb_buffer_reverse, hb_buffer_reverse_clusters, hb_buffer_reverse_range,
+    hb_buffer_serialize_format_t, hb_buffer_serialize_glyphs, hb_buffer_set_cluster_level,
+    hb_buffer_set_content_type, hb_buffer_set_direction, hb_buffer_set_invisible_glyph,
+    hb_buffer_set_language, hb_buffer_set_not_found_glyph, hb_buffer_set_replacement_codepoint,
+    hb_buffer_set_script, hb_buffer_t, hb_glyph_flags_t, hb_glyph_info_get_glyph_flags,
+    hb_glyph_info_t, hb_mask_t, hb_script_from_iso15924_tag, hb_script_t,
+    hb_script_to_iso15924_tag, hb_segment_properties_t, hb_var_int_t,
+    HB_BUFFER_CLUSTER_LEVEL_CHARACTERS, HB_BUFFER_CLUSTER_LEVEL_MONOTONE_CHARACTERS,
     HB_BUFFER_CLUSTER_LEVEL_MONOTONE_GRAPHEMES, HB_BUFFER_CONTENT_TYPE_GLYPHS,
     HB_BUFFER_CONTENT_TYPE_UNICODE, HB_BUFFER_SERIALIZE_FLAG_GLYPH_EXTENTS,
     HB_BUFFER_SERIALIZE_FLAG_GLYPH_FLAGS, HB_BUFFER_SERIALIZE_FLAG_NO_ADVANCES,
@@ -18,17 +22,46 @@ use crate::bindings::{
     HB_BUFFER_SERIALIZE_FLAG_NO_POSITIONS, HB_BUFFER_SERIALIZE_FORMAT_JSON,
     HB_BUFFER_SERIALIZE_FORMAT_TEXT, HB_GLYPH_FLAG_UNSAFE_TO_BREAK,
 };
-use crate::common::{Direction, HarfbuzzObject, Language, Owned, Script, Tag};
-use crate::font::Position;
+use crate::bindings::{
+    hb_buffer_allocation_successful, hb_buffer_create_similar, hb_buffer_get_user_data,
+    hb_buffer_has_positions, hb_buffer_serialize_format_from_string,
+    hb_buffer_serialize_list_formats, hb_buffer_set_segment_properties, hb_buffer_set_user_data,
+    hb_segment_properties_hash, hb_segment_properties_overlay, HB_BUFFER_CONTENT_TYPE_INVALID,
+    HB_BUFFER_SERIALIZE_FORMAT_INVALID,
+};
+use crate::bindings::{
+    hb_buffer_get_flags, hb_buffer_set_flags, hb_buffer_set_length, HB_BUFFER_FLAG_BOT,
+    HB_BUFFER_FLAG_DO_NOT_INSERT_DOTTED_CIRCLE, HB_BUFFER_FLAG_EOT,
+    HB_BUFFER_FLAG_PRESERVE_DEFAULT_IGNORABLES, HB_BUFFER_FLAG_PRODUCE_SAFE_TO_INSERT_TATWEEL,
+    HB_BUFFER_FLAG_PRODUCE_UNSAFE_TO_CONCAT, HB_BUFFER_FLAG_REMOVE_DEFAULT_IGNORABLES,
+    HB_BUFFER_FLAG_VERIFY, HB_GLYPH_FLAG_SAFE_TO_INSERT_TATWEEL, HB_GLYPH_FLAG_UNSAFE_TO_CONCAT,
+};
+use crate::blob::{fnv1a, FNV_OFFSET_BASIS};
+use crate::common::{
+    mirror_char, Direction, FromRaw, HarfbuzzObject, IntoRaw, Language, Owned, Script, Tag,
+    UserDataKey,
+};
+use crate::font::{Font, Glyph, Position, Rect};
+use crate::glyph_metrics::GlyphMetricsSource;
 
 use fmt::Formatter;
+use std::cell::Cell;
+#[cfg(feature = "unicode-normalization")]
+use std::cell::RefCell;
+use std::convert::TryFrom;
 use std::io::Read;
+use std::ops::Range;
 use std::os;
 use std::os::raw::c_uint;
 use std::ptr::NonNull;
 use std::{fmt, io};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+/// The segment properties (direction, script and language) of a buffer.
+///
+/// Equality and hashing behave like `hb_segment_properties_equal` and
+/// `hb_segment_properties_hash`, so `SegmentProperties` can be used as a key
+/// the same way HarfBuzz does internally.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(C)]
 pub struct SegmentProperties {
     pub direction: Direction,
@@ -57,6 +90,52 @@ impl SegmentProperties {
             reserved2: std::ptr::null_mut(),
         }
     }
+
+    /// Fills the properties of `self` that are unset (invalid direction,
+    /// invalid script or no language) with the corresponding values of `src`.
+    ///
+    /// If the direction of `self` is already set and the language is unset,
+    /// the language is only taken from `src` if the directions agree, as
+    /// done by `hb_segment_properties_overlay`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Direction, SegmentProperties, UnicodeBuffer};
+    ///
+    /// let mut props = UnicodeBuffer::new().get_segment_properties();
+    /// let guessed = UnicodeBuffer::new()
+    ///     .add_str("abc")
+    ///     .guess_segment_properties()
+    ///     .get_segment_properties();
+    /// props.overlay(&guessed);
+    /// assert_eq!(props, guessed);
+    /// ```
+    pub fn overlay(&mut self, src: &SegmentProperties) {
+        let mut raw = self.into_raw();
+        let src = src.into_raw();
+        unsafe { hb_segment_properties_overlay(&mut raw, &src) };
+        *self = SegmentProperties::from_raw(raw);
+    }
+}
+
+impl FromRaw<hb_segment_properties_t> for SegmentProperties {
+    unsafe fn from_raw(raw: hb_segment_properties_t) -> Self {
+        SegmentProperties::from_raw(raw)
+    }
+}
+
+impl IntoRaw<hb_segment_properties_t> for SegmentProperties {
+    fn into_raw(self) -> hb_segment_properties_t {
+        SegmentProperties::into_raw(self)
+    }
+}
+
+impl std::hash::Hash for SegmentProperties {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let raw = self.into_raw();
+        state.write_u32(unsafe { hb_segment_properties_hash(&raw) });
+    }
 }
 
 /// `GlyphPosition` is the structure that holds the positions of the glyph in
@@ -127,6 +206,38 @@ impl GlyphFlags {
     pub fn unsafe_to_break(&self) -> bool {
         self.0 & HB_GLYPH_FLAG_UNSAFE_TO_BREAK == HB_GLYPH_FLAG_UNSAFE_TO_BREAK
     }
+
+    /// If `true`, indicates that if input text is changed on one side of the
+    /// beginning of the cluster this glyph is part of, then the shaping
+    /// results for the other side might change. Note that the absence of this
+    /// flag does not by itself mean that it is safe to concatenate text: only
+    /// two pieces of text which are both clear of this flag can be
+    /// concatenated safely. This can be used to avoid reshaping whole lines
+    /// after line-breaking or hyphenation.
+    ///
+    /// This flag is only produced if the buffer was shaped with
+    /// `BufferFlags::PRODUCE_UNSAFE_TO_CONCAT`. `unsafe_to_break` always
+    /// implies this flag.
+    pub fn unsafe_to_concat(&self) -> bool {
+        self.0 & HB_GLYPH_FLAG_UNSAFE_TO_CONCAT == HB_GLYPH_FLAG_UNSAFE_TO_CONCAT
+    }
+
+    /// If `true`, indicates that it is safe to insert a U+0640 TATWEEL
+    /// character before this cluster for elongation in scripts that use
+    /// elongation (Arabic, Mongolian, Syriac, etc.) without interrupting the
+    /// text shaping. It does not determine the script-specific elongation
+    /// places.
+    ///
+    /// This flag is only produced if the buffer was shaped with
+    /// `BufferFlags::PRODUCE_SAFE_TO_INSERT_TATWEEL`.
+    pub fn safe_to_insert_tatweel(&self) -> bool {
+        self.0 & HB_GLYPH_FLAG_SAFE_TO_INSERT_TATWEEL == HB_GLYPH_FLAG_SAFE_TO_INSERT_TATWEEL
+    }
+
+    /// Returns the raw flag bits.
+    pub fn bits(&self) -> hb_glyph_flags_t {
+        self.0
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -150,10 +261,58 @@ impl std::fmt::Debug for GlyphInfo {
 }
 
 impl GlyphInfo {
+    /// Creates the info of glyph `glyph` belonging to `cluster` with an empty
+    /// mask.
+    ///
+    /// This is useful for building a [`GlyphBuffer`] manually, e.g. in a
+    /// fallback shaper. Glyph flags can be set with [`GlyphInfo::set_mask()`].
+    pub const fn new(glyph: Glyph, cluster: u32) -> Self {
+        GlyphInfo {
+            codepoint: glyph,
+            mask: 0,
+            cluster,
+            var1: hb_var_int_t { u32_: 0 },
+            var2: hb_var_int_t { u32_: 0 },
+        }
+    }
+
     pub fn glyph_flags(&self) -> GlyphFlags {
         GlyphFlags(unsafe { hb_glyph_info_get_glyph_flags(self.as_raw()) })
     }
 
+    /// Returns the mask of the glyph.
+    ///
+    /// During shaping HarfBuzz uses the mask bits to record which features
+    /// apply to the glyph. After shaping the lowest bits hold the
+    /// [`GlyphFlags`]. The meaning of the other bits is private to HarfBuzz and
+    /// may be used for custom per-glyph data after shaping.
+    pub fn mask(&self) -> hb_mask_t {
+        self.mask
+    }
+
+    /// Replaces the mask of the glyph.
+    ///
+    /// Note that this also replaces the glyph flags stored in the lowest bits
+    /// of the mask.
+    pub fn set_mask(&mut self, mask: hb_mask_t) {
+        self.mask = mask;
+    }
+
+    /// Returns `true` if all bits of `bits` are set in the mask of the glyph.
+    pub fn has_mask_bits(&self, bits: hb_mask_t) -> bool {
+        self.mask & bits == bits
+    }
+
+    /// Sets (if `value` is `true`) or clears the bits of `bits` in the mask of
+    /// the glyph while leaving all other bits unchanged.
+    pub fn set_mask_bits(&mut self, bits: hb_mask_t, value: bool) {
+        if value {
+            self.mask |= bits;
+        } else {
+            self.mask &= !bits;
+        }
+    }
+
     fn as_raw(&self) -> *const hb_glyph_info_t {
         (self as *const GlyphInfo) as *const _
     }
@@ -186,17 +345,92 @@ impl ClusterLevel {
     }
 }
 
+/// The kind of content of a buffer.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ContentType {
+    /// The buffer is new or was cleared and has no content yet.
+    #[default]
+    Invalid,
+    /// The buffer contains Unicode characters (before shaping).
+    Unicode,
+    /// The buffer contains glyphs (after shaping).
+    Glyphs,
+}
+
+impl ContentType {
+    /// Create from raw value of type `hb_buffer_content_type_t`.
+    ///
+    /// Unknown values are mapped to `ContentType::Invalid`.
+    pub fn from_raw(raw: hb_buffer_content_type_t) -> Self {
+        match raw {
+            HB_BUFFER_CONTENT_TYPE_UNICODE => ContentType::Unicode,
+            HB_BUFFER_CONTENT_TYPE_GLYPHS => ContentType::Glyphs,
+            _ => ContentType::Invalid,
+        }
+    }
+
+    /// Convert into raw value of type `hb_buffer_content_type_t`.
+    pub fn into_raw(self) -> hb_buffer_content_type_t {
+        match self {
+            ContentType::Invalid => HB_BUFFER_CONTENT_TYPE_INVALID,
+            ContentType::Unicode => HB_BUFFER_CONTENT_TYPE_UNICODE,
+            ContentType::Glyphs => HB_BUFFER_CONTENT_TYPE_GLYPHS,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct GenericBuffer {
     raw: NonNull<hb_buffer_t>,
 }
+
+// Converts the length of a text to the `int` HarfBuzz expects, panicking if it
+// is too long.
+fn text_len(len: usize) -> os::raw::c_int {
+    os::raw::c_int::try_from(len).expect("text is too long for HarfBuzz")
+}
+
+// Returns the range of a text of `len` code units that is passed to HarfBuzz
+// to add the `item` range of it: the item and the `CONTEXT_LENGTH`
+// codepoints HarfBuzz reads on each side of it, where a codepoint has at most
+// `max_units` code units. Passing only this window instead of the whole text
+// keeps long texts within the length HarfBuzz accepts once the item is limited.
+fn context_window(len: usize, item: Range<usize>, max_units: usize) -> Range<usize> {
+    let reach = CONTEXT_LENGTH * max_units;
+    item.start.saturating_sub(reach)..len.min(item.end.saturating_add(reach))
+}
+
 impl GenericBuffer {
     pub(crate) fn new() -> Owned<GenericBuffer> {
         let buffer = unsafe { hb_buffer_create() };
         unsafe { Owned::from_raw(buffer) }
     }
 
-    #[allow(unused)]
+    // Creates a new, empty buffer with the same settings and segment
+    // properties as `self`.
+    pub(crate) fn create_similar(&self) -> Owned<GenericBuffer> {
+        let mut similar: Owned<GenericBuffer> =
+            unsafe { Owned::from_raw(hb_buffer_create_similar(self.as_raw())) };
+        similar.set_segment_properties(self.get_segment_properties());
+        similar
+    }
+
+    // Creates a new buffer with the same settings, segment properties,
+    // contents and context as `self`.
+    pub(crate) fn deep_copy(&self) -> Owned<GenericBuffer> {
+        let mut copy = self.create_similar();
+        copy.append(self, 0, c_uint::MAX);
+        copy.set_content_type(self.content_type());
+        if let Some(state) = self.get_user_data(&LENGTH_LIMIT) {
+            let state = LengthLimitState {
+                limit: state.limit,
+                exceeded: state.exceeded.clone(),
+            };
+            copy.set_user_data(&LENGTH_LIMIT, state);
+        }
+        copy
+    }
+
     pub(crate) fn empty() -> Owned<GenericBuffer> {
         let buffer = unsafe { hb_buffer_get_empty() };
         unsafe { Owned::from_raw(buffer) }
@@ -210,7 +444,61 @@ impl GenericBuffer {
         self.len() == 0
     }
 
+    // Returns the length of the longest prefix of an item of `item_len` units
+    // that may be added without exceeding the length limit of the buffer.
+    // `ends` yields the end offset of every codepoint in the item.
+    fn limit_item(&self, item_len: usize, mut ends: impl Iterator<Item = usize>) -> usize {
+        let state = match self.get_user_data(&LENGTH_LIMIT) {
+            Some(state) => state,
+            None => return item_len,
+        };
+        let remaining = state.limit.max_len.saturating_sub(self.len());
+        let prefix_end = match remaining.checked_sub(1) {
+            None => 0,
+            Some(last) => match ends.nth(last) {
+                Some(end) => end,
+                None => return item_len,
+            },
+        };
+        if ends.next().is_none() {
+            return item_len;
+        }
+        state.exceeded.set(true);
+        match state.limit.policy {
+            LengthLimitPolicy::Truncate => prefix_end,
+            LengthLimitPolicy::Reject => 0,
+        }
+    }
+
+    pub(crate) fn set_length_limit(&mut self, limit: LengthLimit) {
+        let state = LengthLimitState {
+            limit,
+            exceeded: Cell::new(false),
+        };
+        self.set_user_data(&LENGTH_LIMIT, state);
+    }
+
+    pub(crate) fn get_length_limit(&self) -> Option<LengthLimit> {
+        self.get_user_data(&LENGTH_LIMIT).map(|state| state.limit)
+    }
+
+    pub(crate) fn check_length_limit(&self) -> Result<(), LengthLimitExceeded> {
+        match self.get_user_data(&LENGTH_LIMIT) {
+            Some(state) if state.exceeded.get() => Err(LengthLimitExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    fn reset_length_limit(&self) {
+        if let Some(state) = self.get_user_data(&LENGTH_LIMIT) {
+            state.exceeded.set(false);
+        }
+    }
+
     pub(crate) fn add(&mut self, codepoint: u32, cluster: u32) {
+        if self.limit_item(1, std::iter::once(1)) == 0 {
+            return;
+        }
         unsafe {
             hb_buffer_add(self.as_raw(), codepoint, cluster);
         }
@@ -218,22 +506,277 @@ impl GenericBuffer {
 
     pub(crate) fn add_str_item(&mut self, string: &str, item_start: usize, item_len: usize) {
         assert!(item_start + item_len <= string.len());
-        let utf8_ptr = string.as_ptr() as *const _;
+        let ends = (item_start + 1..=item_start + item_len)
+            .filter(|&end| string.is_char_boundary(end))
+            .map(|end| end - item_start);
+        let item_len = self.limit_item(item_len, ends);
+        let item_end = item_start + item_len;
+        // a UTF-8 sequence has at most four bytes
+        let mut window = context_window(string.len(), item_start..item_end, 4);
+        while window.start < item_start && !string.is_char_boundary(window.start) {
+            window.start += 1;
+        }
+        while window.end > item_end && !string.is_char_boundary(window.end) {
+            window.end -= 1;
+        }
+        let text = &string.as_bytes()[window.clone()];
+        #[cfg(feature = "unicode-normalization")]
+        let was_empty = self.is_empty();
+        let start = self.len();
         unsafe {
             hb_buffer_add_utf8(
                 self.as_raw(),
-                utf8_ptr,
-                string.len() as os::raw::c_int,
-                item_start as os::raw::c_uint,
+                text.as_ptr() as *const _,
+                text_len(text.len()),
+                (item_start - window.start) as os::raw::c_uint,
+                item_len as os::raw::c_int,
+            );
+        }
+        self.offset_clusters(start, window.start);
+        #[cfg(feature = "unicode-normalization")]
+        self.update_item_context(
+            was_empty && item_start > 0,
+            string[window.start..item_start]
+                .chars()
+                .rev()
+                .map(u32::from),
+            string[item_end..window.end].chars().map(u32::from),
+        );
+    }
+
+    pub(crate) fn add_str_with_cluster_offset(&mut self, string: &str, base_cluster: u32) {
+        let fits = u32::try_from(string.len())
+            .ok()
+            .and_then(|len| base_cluster.checked_add(len))
+            .is_some();
+        assert!(fits, "cluster values of the string overflow u32");
+        let start = self.len();
+        self.add_str_item(string, 0, string.len());
+        for info in &mut self.get_glyph_infos_mut()[start..] {
+            info.cluster += base_cluster;
+        }
+    }
+
+    pub(crate) fn add_utf16_item(&mut self, text: &[u16], item_start: usize, item_len: usize) {
+        assert!(item_start + item_len <= text.len());
+        let item = &text[item_start..item_start + item_len];
+        let ends = std::char::decode_utf16(item.iter().copied()).scan(0, |end, c| {
+            // unpaired surrogates are replaced by a single codepoint
+            *end += c.map_or(1, char::len_utf16);
+            Some(*end)
+        });
+        let item_len = self.limit_item(item_len, ends);
+        let item_end = item_start + item_len;
+        // the window may split a surrogate pair at its ends, but only beyond
+        // the codepoints HarfBuzz reads
+        let window = context_window(text.len(), item_start..item_end, 2);
+        #[cfg(feature = "unicode-normalization")]
+        let was_empty = self.is_empty();
+        let start = self.len();
+        unsafe {
+            hb_buffer_add_utf16(
+                self.as_raw(),
+                text[window.clone()].as_ptr(),
+                text_len(window.len()),
+                (item_start - window.start) as os::raw::c_uint,
+                item_len as os::raw::c_int,
+            );
+        }
+        self.offset_clusters(start, window.start);
+        #[cfg(feature = "unicode-normalization")]
+        {
+            let replacement = self.get_replacement_codepoint();
+            let decode = |units: &[u16]| -> Vec<u32> {
+                std::char::decode_utf16(units.iter().copied())
+                    .map(|c| c.map_or(replacement, u32::from))
+                    .collect()
+            };
+            self.update_item_context(
+                was_empty && item_start > 0,
+                decode(&text[window.start..item_start]).into_iter().rev(),
+                decode(&text[item_end..window.end]).into_iter(),
+            );
+        }
+    }
+
+    pub(crate) fn add_utf32_item(&mut self, text: &[u32], item_start: usize, item_len: usize) {
+        assert!(item_start + item_len <= text.len());
+        let item_len = self.limit_item(item_len, 1..=item_len);
+        let item_end = item_start + item_len;
+        let window = context_window(text.len(), item_start..item_end, 1);
+        #[cfg(feature = "unicode-normalization")]
+        let was_empty = self.is_empty();
+        let start = self.len();
+        unsafe {
+            hb_buffer_add_utf32(
+                self.as_raw(),
+                text[window.clone()].as_ptr(),
+                text_len(window.len()),
+                (item_start - window.start) as os::raw::c_uint,
+                item_len as os::raw::c_int,
+            );
+        }
+        self.offset_clusters(start, window.start);
+        #[cfg(feature = "unicode-normalization")]
+        {
+            let replacement = self.get_replacement_codepoint();
+            let validate = |&c: &u32| std::char::from_u32(c).map_or(replacement, u32::from);
+            self.update_item_context(
+                was_empty && item_start > 0,
+                text[window.start..item_start].iter().rev().map(validate),
+                text[item_end..window.end].iter().map(validate),
+            );
+        }
+    }
+
+    pub(crate) fn add_latin1_item(&mut self, text: &[u8], item_start: usize, item_len: usize) {
+        assert!(item_start + item_len <= text.len());
+        let item_len = self.limit_item(item_len, 1..=item_len);
+        let item_end = item_start + item_len;
+        let window = context_window(text.len(), item_start..item_end, 1);
+        #[cfg(feature = "unicode-normalization")]
+        let was_empty = self.is_empty();
+        let start = self.len();
+        unsafe {
+            hb_buffer_add_latin1(
+                self.as_raw(),
+                text[window.clone()].as_ptr(),
+                text_len(window.len()),
+                (item_start - window.start) as os::raw::c_uint,
                 item_len as os::raw::c_int,
             );
         }
+        self.offset_clusters(start, window.start);
+        #[cfg(feature = "unicode-normalization")]
+        self.update_item_context(
+            was_empty && item_start > 0,
+            text[window.start..item_start]
+                .iter()
+                .rev()
+                .map(|&c| u32::from(c)),
+            text[item_end..window.end].iter().map(|&c| u32::from(c)),
+        );
+    }
+
+    // Adds `offset` to the clusters of the codepoints from `start` on, which
+    // HarfBuzz computed relative to the context window they were added from.
+    fn offset_clusters(&mut self, start: usize, offset: usize) {
+        if offset == 0 {
+            return;
+        }
+        let offset = u32::try_from(offset).ok();
+        for info in &mut self.get_glyph_infos_mut()[start..] {
+            info.cluster = offset
+                .and_then(|offset| info.cluster.checked_add(offset))
+                .expect("cluster values of the text overflow u32");
+        }
     }
 
     pub(crate) fn append(&mut self, source: &GenericBuffer, start: c_uint, end: c_uint) {
+        let count = end.min(source.len() as c_uint).saturating_sub(start) as usize;
+        let end = start + self.limit_item(count, 1..=count) as c_uint;
+        #[cfg(feature = "unicode-normalization")]
+        let was_empty = self.is_empty();
         unsafe {
             hb_buffer_append(self.as_raw(), source.as_raw(), start, end);
         }
+        #[cfg(feature = "unicode-normalization")]
+        self.update_appended_context(source, start, end, was_empty);
+    }
+
+    // Updates the tracked context after appending the range `start..end` of
+    // `source`. HarfBuzz takes the context of Unicode text from the source
+    // buffer: the codepoints around the appended range, followed by the
+    // context of the source.
+    #[cfg(feature = "unicode-normalization")]
+    fn update_appended_context(
+        &mut self,
+        source: &GenericBuffer,
+        start: c_uint,
+        end: c_uint,
+        was_empty: bool,
+    ) {
+        if start >= end || source.content_type() != HB_BUFFER_CONTENT_TYPE_UNICODE {
+            return;
+        }
+        let (start, end) = (start as usize, end as usize);
+        let infos = source.get_glyph_infos();
+        let BufferContext {
+            pre: source_pre,
+            post: source_post,
+        } = source.context();
+        self.update_context(
+            (was_empty && (start > 0 || !source_pre.is_empty())).then(|| {
+                let pre = infos[..start].iter().rev().map(|info| info.codepoint);
+                let mut pre: Vec<u32> = pre.chain(source_pre.into_iter().rev()).collect();
+                pre.truncate(CONTEXT_LENGTH);
+                pre.reverse();
+                pre
+            }),
+            infos[end..]
+                .iter()
+                .map(|info| info.codepoint)
+                .chain(source_post)
+                .take(CONTEXT_LENGTH)
+                .collect(),
+        );
+    }
+
+    // Updates the tracked context after adding an item of a text like
+    // `hb_buffer_add_utf()` does: the pre-context is only replaced by the
+    // text before the item (`before`, nearest first) if `set_pre` is true,
+    // the post-context is always replaced by the text after the item.
+    #[cfg(feature = "unicode-normalization")]
+    fn update_item_context(
+        &mut self,
+        set_pre: bool,
+        before: impl Iterator<Item = u32>,
+        after: impl Iterator<Item = u32>,
+    ) {
+        let pre = set_pre.then(|| {
+            let mut pre: Vec<u32> = before.take(CONTEXT_LENGTH).collect();
+            pre.reverse();
+            pre
+        });
+        self.update_context(pre, after.take(CONTEXT_LENGTH).collect());
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    fn update_context(&mut self, pre: Option<Vec<u32>>, post: Vec<u32>) {
+        match self.get_user_data(&CONTEXT) {
+            Some(context) => {
+                let mut context = context.borrow_mut();
+                if let Some(pre) = pre {
+                    context.pre = pre;
+                }
+                context.post = post;
+            }
+            None => {
+                let pre = pre.unwrap_or_default();
+                if !pre.is_empty() || !post.is_empty() {
+                    self.set_user_data(&CONTEXT, RefCell::new(BufferContext { pre, post }));
+                }
+            }
+        }
+    }
+
+    // Returns the context of the buffer as far as it was set through this
+    // crate.
+    #[cfg(feature = "unicode-normalization")]
+    pub(crate) fn context(&self) -> BufferContext {
+        self.get_user_data(&CONTEXT)
+            .map(|context| context.borrow().clone())
+            .unwrap_or_default()
+    }
+
+    // Sets the context of the (usually empty) buffer to `context`.
+    #[cfg(feature = "unicode-normalization")]
+    pub(crate) fn set_context(&mut self, context: &BufferContext) {
+        // adding an empty item sets the context without adding codepoints
+        if self.is_empty() && !context.pre.is_empty() {
+            self.add_utf32_item(&context.pre, context.pre.len(), 0);
+        }
+        self.add_utf32_item(&context.post, 0, 0);
     }
 
     pub(crate) fn set_direction(&mut self, direction: Direction) {
@@ -270,6 +813,11 @@ impl GenericBuffer {
         unsafe { hb_buffer_guess_segment_properties(self.as_raw()) };
     }
 
+    pub(crate) fn set_segment_properties(&mut self, props: SegmentProperties) {
+        let raw = props.into_raw();
+        unsafe { hb_buffer_set_segment_properties(self.as_raw(), &raw) };
+    }
+
     pub(crate) fn get_segment_properties(&self) -> SegmentProperties {
         unsafe {
             let mut segment_props: hb_segment_properties_t = std::mem::zeroed();
@@ -286,19 +834,98 @@ impl GenericBuffer {
         ClusterLevel::from_raw(unsafe { hb_buffer_get_cluster_level(self.as_raw()) })
     }
 
-    pub(crate) fn pre_allocate(&mut self, size: usize) {
+    pub(crate) fn set_invisible_glyph(&mut self, glyph: Glyph) {
+        unsafe { hb_buffer_set_invisible_glyph(self.as_raw(), glyph) }
+    }
+
+    pub(crate) fn get_invisible_glyph(&self) -> Glyph {
+        unsafe { hb_buffer_get_invisible_glyph(self.as_raw()) }
+    }
+
+    pub(crate) fn set_not_found_glyph(&mut self, glyph: Glyph) {
+        unsafe { hb_buffer_set_not_found_glyph(self.as_raw(), glyph) }
+    }
+
+    pub(crate) fn get_not_found_glyph(&self) -> Glyph {
+        unsafe { hb_buffer_get_not_found_glyph(self.as_raw()) }
+    }
+
+    pub(crate) fn set_replacement_codepoint(&mut self, replacement: u32) {
+        unsafe { hb_buffer_set_replacement_codepoint(self.as_raw(), replacement) }
+    }
+
+    pub(crate) fn get_replacement_codepoint(&self) -> u32 {
+        unsafe { hb_buffer_get_replacement_codepoint(self.as_raw()) }
+    }
+
+    pub(crate) fn set_flags(&mut self, flags: BufferFlags) {
+        unsafe { hb_buffer_set_flags(self.as_raw(), flags.bits()) }
+    }
+
+    pub(crate) fn get_flags(&self) -> BufferFlags {
+        BufferFlags::from_bits_truncate(unsafe { hb_buffer_get_flags(self.as_raw()) })
+    }
+
+    pub(crate) fn pre_allocate(&mut self, size: usize) -> bool {
         let size = size.min(std::os::raw::c_uint::MAX as usize);
-        unsafe { hb_buffer_pre_allocate(self.as_raw(), size as _) };
+        unsafe { hb_buffer_pre_allocate(self.as_raw(), size as _) != 0 }
+    }
+
+    pub(crate) fn allocation_successful(&self) -> bool {
+        unsafe { hb_buffer_allocation_successful(self.as_raw()) != 0 }
+    }
+
+    pub(crate) fn check_allocation(&self) -> Result<(), AllocationError> {
+        if self.allocation_successful() {
+            Ok(())
+        } else {
+            Err(AllocationError)
+        }
+    }
+
+    pub(crate) fn set_length(&mut self, length: usize) -> bool {
+        let length = length.min(c_uint::MAX as usize);
+        let ok = unsafe { hb_buffer_set_length(self.as_raw(), length as c_uint) == 1 };
+        // HarfBuzz clears the post-context and also the pre-context of empty
+        // buffers
+        #[cfg(feature = "unicode-normalization")]
+        if let Some(context) = self.get_user_data(&CONTEXT) {
+            let mut context = context.borrow_mut();
+            if self.is_empty() {
+                context.pre.clear();
+            }
+            context.post.clear();
+        }
+        ok
+    }
+
+    pub(crate) fn truncate(&mut self, length: usize) {
+        if length < self.len() {
+            self.set_length(length);
+        }
     }
 
     pub(crate) fn clear_contents(&mut self) {
         unsafe { hb_buffer_clear_contents(self.as_raw()) };
+        self.reset_length_limit();
+        #[cfg(feature = "unicode-normalization")]
+        self.remove_user_data(&CONTEXT);
+    }
+
+    pub(crate) fn reset(&mut self) {
+        unsafe { hb_buffer_reset(self.as_raw()) };
+        self.reset_length_limit();
+        #[cfg(feature = "unicode-normalization")]
+        self.remove_user_data(&CONTEXT);
     }
 
     pub(crate) fn get_glyph_positions(&self) -> &[GlyphPosition] {
         unsafe {
             let mut length: u32 = 0;
             let glyph_pos = hb_buffer_get_glyph_positions(self.as_raw(), &mut length as *mut u32);
+            if glyph_pos.is_null() {
+                return &[];
+            }
             std::slice::from_raw_parts(glyph_pos as *const _, length as usize)
         }
     }
@@ -307,10 +934,47 @@ impl GenericBuffer {
         unsafe {
             let mut length: u32 = 0;
             let glyph_infos = hb_buffer_get_glyph_infos(self.as_raw(), &mut length as *mut u32);
+            if glyph_infos.is_null() {
+                return &[];
+            }
             std::slice::from_raw_parts(glyph_infos as *const _, length as usize)
         }
     }
 
+    pub(crate) fn get_glyph_positions_mut(&mut self) -> &mut [GlyphPosition] {
+        unsafe {
+            let mut length: u32 = 0;
+            let glyph_pos = hb_buffer_get_glyph_positions(self.as_raw(), &mut length as *mut u32);
+            if glyph_pos.is_null() {
+                return &mut [];
+            }
+            std::slice::from_raw_parts_mut(glyph_pos as *mut _, length as usize)
+        }
+    }
+
+    // Adds a glyph to a buffer containing glyphs and positions.
+    pub(crate) fn push_glyph(&mut self, info: GlyphInfo, position: GlyphPosition) {
+        let len = self.len();
+        if !self.set_length(len + 1) {
+            return;
+        }
+        // truncating a buffer to zero length resets its content type
+        self.set_content_type(HB_BUFFER_CONTENT_TYPE_GLYPHS);
+        self.get_glyph_infos_mut()[len] = info;
+        self.get_glyph_positions_mut()[len] = position;
+    }
+
+    pub(crate) fn get_glyph_infos_mut(&mut self) -> &mut [GlyphInfo] {
+        unsafe {
+            let mut length: u32 = 0;
+            let glyph_infos = hb_buffer_get_glyph_infos(self.as_raw(), &mut length as *mut u32);
+            if glyph_infos.is_null() {
+                return &mut [];
+            }
+            std::slice::from_raw_parts_mut(glyph_infos as *mut _, length as usize)
+        }
+    }
+
     /// Reverse the `Buffer`'s contents.
     pub(crate) fn reverse(&mut self) {
         unsafe { hb_buffer_reverse(self.as_raw()) };
@@ -322,6 +986,48 @@ impl GenericBuffer {
         unsafe { hb_buffer_reverse_range(self.as_raw(), start as u32, end as u32) }
     }
 
+    // Stores `value` under `key` and returns `false` if HarfBuzz failed to
+    // allocate memory for it, in which case `value` is dropped.
+    pub(crate) fn set_user_data<T: Send + 'static>(
+        &mut self,
+        key: &'static UserDataKey<T>,
+        value: T,
+    ) -> bool {
+        extern "C" fn destroy<T>(data: *mut os::raw::c_void) {
+            unsafe { drop(Box::from_raw(data as *mut T)) };
+        }
+        let data = Box::into_raw(Box::new(value));
+        let ok = unsafe {
+            hb_buffer_set_user_data(
+                self.as_raw(),
+                key.as_raw(),
+                data as *mut _,
+                Some(destroy::<T>),
+                1,
+            ) == 1
+        };
+        if !ok {
+            // HarfBuzz does not call `destroy` if it fails to store the data
+            unsafe { drop(Box::from_raw(data)) };
+        }
+        ok
+    }
+
+    pub(crate) fn get_user_data<T: 'static>(&self, key: &'static UserDataKey<T>) -> Option<&T> {
+        let data = unsafe { hb_buffer_get_user_data(self.as_raw(), key.as_raw()) };
+        unsafe { (data as *const T).as_ref() }
+    }
+
+    pub(crate) fn remove_user_data<T>(&mut self, key: &'static UserDataKey<T>) {
+        unsafe {
+            hb_buffer_set_user_data(self.as_raw(), key.as_raw(), std::ptr::null_mut(), None, 1);
+        }
+    }
+
+    pub(crate) fn reverse_clusters(&mut self) {
+        unsafe { hb_buffer_reverse_clusters(self.as_raw()) }
+    }
+
     pub(crate) fn set_content_type(&self, content_type: hb_buffer_content_type_t) {
         unsafe { hb_buffer_set_content_type(self.as_raw(), content_type) }
     }
@@ -329,6 +1035,10 @@ impl GenericBuffer {
     pub(crate) fn content_type(&self) -> hb_buffer_content_type_t {
         unsafe { hb_buffer_get_content_type(self.as_raw()) }
     }
+
+    pub(crate) fn has_positions(&self) -> bool {
+        unsafe { hb_buffer_has_positions(self.as_raw()) != 0 }
+    }
 }
 
 unsafe impl HarfbuzzObject for GenericBuffer {
@@ -353,6 +1063,119 @@ unsafe impl HarfbuzzObject for GenericBuffer {
     }
 }
 
+// A `hb_buffer_t` has no thread affinity, so it can be moved to another thread.
+// It is not `Sync` however because some HarfBuzz functions that take a
+// `&GenericBuffer` mutate the buffer (e.g. `hb_buffer_get_glyph_positions`
+// allocates the positions if necessary).
+unsafe impl Send for GenericBuffer {}
+
+/// The error returned when HarfBuzz failed to allocate memory for a buffer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AllocationError;
+
+impl std::fmt::Display for AllocationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HarfBuzz failed to allocate memory for the buffer")
+    }
+}
+
+impl std::error::Error for AllocationError {}
+
+/// What happens to text that would make a buffer longer than its
+/// [`LengthLimit`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LengthLimitPolicy {
+    /// Add as many codepoints of the text as fit and drop the rest.
+    Truncate,
+    /// Drop the whole text.
+    Reject,
+}
+
+/// A maximum number of codepoints of a [`UnicodeBuffer`].
+///
+/// Set with [`UnicodeBuffer::set_length_limit()`]. Text that would make the
+/// buffer longer than `max_len` is handled according to `policy` and marks
+/// the limit as exceeded, which [`UnicodeBuffer::check_length_limit()`]
+/// reports.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct LengthLimit {
+    /// The maximum number of codepoints in the buffer.
+    pub max_len: usize,
+    /// How text beyond `max_len` is handled.
+    pub policy: LengthLimitPolicy,
+}
+
+impl LengthLimit {
+    /// Creates a limit of `max_len` codepoints with the given policy.
+    pub fn new(max_len: usize, policy: LengthLimitPolicy) -> LengthLimit {
+        LengthLimit { max_len, policy }
+    }
+}
+
+// The length limit of a buffer, stored in its user data so that it stays with
+// the buffer while it is shaped and reused.
+struct LengthLimitState {
+    limit: LengthLimit,
+    exceeded: Cell<bool>,
+}
+
+static LENGTH_LIMIT: UserDataKey<LengthLimitState> = UserDataKey::new();
+
+// The number of codepoints of context HarfBuzz keeps on each side of the
+// contents of a buffer.
+const CONTEXT_LENGTH: usize = 5;
+
+// The pre- and post-context of a buffer in logical order. HarfBuzz offers no
+// way to read the context of a buffer, so the calls that set it are mirrored
+// into the user data of the buffer, which allows restoring the context after
+// normalization replaced the contents. Only normalization needs this, so the
+// context is only tracked with the `unicode-normalization` feature.
+#[cfg(feature = "unicode-normalization")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct BufferContext {
+    pub(crate) pre: Vec<u32>,
+    pub(crate) post: Vec<u32>,
+}
+
+#[cfg(feature = "unicode-normalization")]
+static CONTEXT: UserDataKey<RefCell<BufferContext>> = UserDataKey::new();
+
+/// The error returned when text was truncated or rejected because of the
+/// [`LengthLimit`] of a buffer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LengthLimitExceeded;
+
+impl std::fmt::Display for LengthLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the text exceeded the length limit of the buffer")
+    }
+}
+
+impl std::error::Error for LengthLimitExceeded {}
+
+/// The error returned by [`GlyphBuffer::split_at_cluster()`] if the buffer
+/// cannot be split at the requested cluster.
+///
+/// It contains the unchanged buffer, which can be retrieved with
+/// [`into_buffer()`](SplitError::into_buffer).
+#[derive(Debug)]
+pub struct SplitError(GlyphBuffer);
+
+impl SplitError {
+    /// Returns the buffer that could not be split.
+    pub fn into_buffer(self) -> GlyphBuffer {
+        self.0
+    }
+}
+
+impl std::fmt::Display for SplitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the buffer cannot be split safely at the given cluster")
+    }
+}
+
+impl std::error::Error for SplitError {}
+
 /// The serialization format used in `BufferSerializer`.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum SerializeFormat {
@@ -371,10 +1194,90 @@ impl From<SerializeFormat> for hb_buffer_serialize_format_t {
     }
 }
 
-bitflags! {
-    /// Flags used for serialization with a `BufferSerializer`.
-    #[derive(Default)]
-    pub struct SerializeFlags: u32 {
+impl SerializeFormat {
+    /// Returns the tag HarfBuzz uses to identify the format (`TEXT` or
+    /// `JSON`).
+    pub fn to_tag(self) -> Tag {
+        Tag(hb_buffer_serialize_format_t::from(self))
+    }
+
+    /// Returns the format identified by `tag` or `None` if the tag does not
+    /// name a format supported by this crate.
+    pub fn from_tag(tag: Tag) -> Option<SerializeFormat> {
+        match tag.0 {
+            HB_BUFFER_SERIALIZE_FORMAT_TEXT => Some(SerializeFormat::Text),
+            HB_BUFFER_SERIALIZE_FORMAT_JSON => Some(SerializeFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Returns all serialization formats supported by the linked HarfBuzz
+    /// library.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::SerializeFormat;
+    ///
+    /// let formats = SerializeFormat::list();
+    /// assert!(formats.contains(&SerializeFormat::Text));
+    /// assert!(formats.contains(&SerializeFormat::Json));
+    /// ```
+    pub fn list() -> Vec<SerializeFormat> {
+        let mut formats = Vec::new();
+        unsafe {
+            let mut name = hb_buffer_serialize_list_formats();
+            while !name.is_null() && !(*name).is_null() {
+                let format = hb_buffer_serialize_format_from_string(*name, -1);
+                formats.extend(SerializeFormat::from_tag(Tag(format)));
+                name = name.add(1);
+            }
+        }
+        formats
+    }
+}
+
+/// The error returned when parsing an unknown `SerializeFormat`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidSerializeFormat;
+
+impl std::fmt::Display for InvalidSerializeFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown serialization format")
+    }
+}
+
+impl std::error::Error for InvalidSerializeFormat {}
+
+impl std::str::FromStr for SerializeFormat {
+    type Err = InvalidSerializeFormat;
+
+    /// Parses a format name like `"text"` or `"json"`. Only the first four
+    /// characters are significant and the case is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::SerializeFormat;
+    ///
+    /// assert_eq!("json".parse(), Ok(SerializeFormat::Json));
+    /// assert_eq!("TEXT".parse(), Ok(SerializeFormat::Text));
+    /// assert!("xml".parse::<SerializeFormat>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<SerializeFormat, InvalidSerializeFormat> {
+        let len = std::cmp::min(s.len(), i32::MAX as _) as i32;
+        let format = unsafe { hb_buffer_serialize_format_from_string(s.as_ptr() as *const _, len) };
+        if format == HB_BUFFER_SERIALIZE_FORMAT_INVALID {
+            return Err(InvalidSerializeFormat);
+        }
+        SerializeFormat::from_tag(Tag(format)).ok_or(InvalidSerializeFormat)
+    }
+}
+
+bitflags! {
+    /// Flags used for serialization with a `BufferSerializer`.
+    #[derive(Default)]
+    pub struct SerializeFlags: u32 {
         /// Do not serialize glyph cluster.
         const NO_CLUSTERS = HB_BUFFER_SERIALIZE_FLAG_NO_CLUSTERS;
         /// Do not serialize glyph position information.
@@ -391,6 +1294,33 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Flags that control the behaviour of a buffer during shaping.
+    #[derive(Default)]
+    pub struct BufferFlags: u32 {
+        /// The buffer contains the beginning of a paragraph, which allows
+        /// special handling of the beginning of text.
+        const BOT = HB_BUFFER_FLAG_BOT;
+        /// The buffer contains the end of a paragraph, which allows special
+        /// handling of the end of text.
+        const EOT = HB_BUFFER_FLAG_EOT;
+        /// Characters with the Default_Ignorable Unicode property use the
+        /// corresponding glyph from the font instead of being hidden.
+        const PRESERVE_DEFAULT_IGNORABLES = HB_BUFFER_FLAG_PRESERVE_DEFAULT_IGNORABLES;
+        /// Characters with the Default_Ignorable Unicode property are removed
+        /// from the output instead of being hidden.
+        const REMOVE_DEFAULT_IGNORABLES = HB_BUFFER_FLAG_REMOVE_DEFAULT_IGNORABLES;
+        /// Do not insert a dotted circle for incorrect character sequences.
+        const DO_NOT_INSERT_DOTTED_CIRCLE = HB_BUFFER_FLAG_DO_NOT_INSERT_DOTTED_CIRCLE;
+        /// Verify the shaping results.
+        const VERIFY = HB_BUFFER_FLAG_VERIFY;
+        /// Produce the `unsafe_to_concat` glyph flag.
+        const PRODUCE_UNSAFE_TO_CONCAT = HB_BUFFER_FLAG_PRODUCE_UNSAFE_TO_CONCAT;
+        /// Produce the `safe_to_insert_tatweel` glyph flag.
+        const PRODUCE_SAFE_TO_INSERT_TATWEEL = HB_BUFFER_FLAG_PRODUCE_SAFE_TO_INSERT_TATWEEL;
+    }
+}
+
 /// A type that can be used to serialize a `GlyphBuffer`.
 ///
 /// A `BufferSerializer` is obtained by calling the `GlyphBuffer::serializer`
@@ -404,10 +1334,96 @@ pub struct BufferSerializer<'a> {
     end: usize,
     format: SerializeFormat,
     flags: SerializeFlags,
+    // the end of the cluster span of every glyph
+    cluster_spans: Option<Vec<usize>>,
 
     bytes: io::Cursor<Vec<u8>>,
 }
 
+impl<'a> BufferSerializer<'a> {
+    /// Annotates every glyph with the byte range of its cluster in `text`,
+    /// which has to be the text the buffer was shaped from.
+    ///
+    /// The range of a cluster extends from its cluster value to the next
+    /// larger cluster value in the buffer or the end of `text` (or is empty
+    /// if the cluster value lies beyond the end of `text`). In the text
+    /// format it is appended to each glyph in braces, e.g.
+    /// `[f_i=0+500{0..2}|x=2+400{2..3}]`, in the JSON format it is added to
+    /// each glyph as `"span":[0,2]`. This makes it easy to see which part of
+    /// the text ended up in which glyph, e.g. when debugging cluster issues.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    /// use std::io::Read;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let text = "e\u{301}x";
+    /// let output = shape(&font, UnicodeBuffer::new().add_str(text), &[]);
+    ///
+    /// let mut string = String::new();
+    /// output
+    ///     .serializer(None, SerializeFormat::Text, SerializeFlags::NO_POSITIONS)
+    ///     .with_cluster_spans(text)
+    ///     .read_to_string(&mut string)
+    ///     .unwrap();
+    /// let spans: Vec<&str> = string.matches(|c| c == '{' || c == '}').collect();
+    /// assert_eq!(spans.len(), 2 * output.len());
+    /// assert!(string.ends_with("=3{3..4}]"));
+    /// ```
+    pub fn with_cluster_spans(mut self, text: &str) -> Self {
+        let mut clusters: Vec<u32> = self
+            .buffer
+            .get_glyph_infos()
+            .iter()
+            .map(|info| info.cluster)
+            .collect();
+        clusters.sort_unstable();
+        clusters.dedup();
+        let ends = self
+            .buffer
+            .get_glyph_infos()
+            .iter()
+            .map(|info| {
+                let next = clusters.partition_point(|&c| c <= info.cluster);
+                clusters
+                    .get(next)
+                    .map_or_else(|| text.len().max(info.cluster as usize), |&c| c as usize)
+            })
+            .collect();
+        self.cluster_spans = Some(ends);
+        self
+    }
+
+    // Appends the cluster span to the serialization of the glyph at `index`,
+    // which is the only glyph in `bytes`.
+    fn annotate_cluster_span(&mut self, index: usize) {
+        let end = match &self.cluster_spans {
+            Some(ends) => ends[index],
+            None => return,
+        };
+        let cluster = self.buffer.get_glyph_infos()[index].cluster;
+        let bytes = self.bytes.get_mut();
+        // HarfBuzz closes the list after the last serialized glyph
+        if bytes.last() == Some(&b']') {
+            bytes.pop();
+        }
+        let span = match self.format {
+            SerializeFormat::Text => format!("{{{}..{}}}", cluster, end),
+            SerializeFormat::Json => {
+                bytes.pop();
+                format!(",\"span\":[{},{}]}}", cluster, end)
+            }
+        };
+        bytes.extend_from_slice(span.as_bytes());
+        if index + 1 == self.end {
+            bytes.push(b']');
+        }
+    }
+}
+
 impl<'a> Read for BufferSerializer<'a> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         match self.bytes.read(buf) {
@@ -416,12 +1432,18 @@ impl<'a> Read for BufferSerializer<'a> {
                 if self.start > self.end.saturating_sub(1) {
                     return Ok(0);
                 }
+                // glyphs are serialized one by one to annotate them
+                let end = if self.cluster_spans.is_some() {
+                    self.start + 1
+                } else {
+                    self.end
+                };
                 let mut bytes_written = 0;
                 let num_serialized_items = unsafe {
                     hb_buffer_serialize_glyphs(
                         self.buffer.as_raw(),
                         self.start as u32,
-                        self.end as u32,
+                        end as u32,
                         self.bytes.get_mut().as_mut_ptr() as *mut _,
                         self.bytes.get_ref().capacity() as u32,
                         &mut bytes_written,
@@ -432,9 +1454,16 @@ impl<'a> Read for BufferSerializer<'a> {
                         self.flags.bits(),
                     )
                 };
-                self.start += num_serialized_items as usize;
                 self.bytes.set_position(0);
                 unsafe { self.bytes.get_mut().set_len(bytes_written as usize) };
+                if num_serialized_items == 0 {
+                    // a single glyph does not fit, so make room for it
+                    let capacity = self.bytes.get_ref().capacity();
+                    self.bytes.get_mut().reserve(capacity);
+                } else if self.cluster_spans.is_some() {
+                    self.annotate_cluster_span(self.start);
+                }
+                self.start += num_serialized_items as usize;
 
                 self.read(buf)
             }
@@ -496,7 +1525,28 @@ impl TypedBuffer {
 /// If you want to get a `UnicodeBuffer` from a pointer to a raw harfbuzz
 /// object, you need to use the `from_raw` static method on `TypedBuffer`. This
 /// ensures that a buffer of correct type is created.
+///
+/// # Thread safety
+///
+/// A `UnicodeBuffer` is `Send`, so it can be moved to another thread (e.g.
+/// to shape on a worker thread). It is not `Sync` because HarfBuzz may mutate
+/// a buffer even through functions that only need shared access, so a buffer
+/// can not be shared between threads without synchronization.
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<harfbuzz_rs::UnicodeBuffer>();
+/// ```
 pub struct UnicodeBuffer(pub(crate) Owned<GenericBuffer>);
+
+/// Cloning a `UnicodeBuffer` copies its contents, context, segment properties
+/// and all other settings into a new buffer.
+impl Clone for UnicodeBuffer {
+    fn clone(&self) -> Self {
+        UnicodeBuffer(self.0.deep_copy())
+    }
+}
+
 impl UnicodeBuffer {
     pub(crate) fn from_generic(generic: Owned<GenericBuffer>) -> Self {
         generic.set_content_type(HB_BUFFER_CONTENT_TYPE_UNICODE);
@@ -521,6 +1571,30 @@ impl UnicodeBuffer {
         Owned::into_raw(self.0)
     }
 
+    /// Returns the raw `hb_buffer_t` pointer of the buffer for FFI calls
+    /// that do not take ownership of the buffer.
+    ///
+    /// The pointer must not be used after the buffer is dropped.
+    pub fn as_ptr(&self) -> *mut hb_buffer_t {
+        self.0.as_raw()
+    }
+
+    /// Takes ownership of a raw `hb_buffer_t` pointer, e.g. one returned by
+    /// [`into_raw()`](Self::into_raw).
+    ///
+    /// Unlike the `from_ptr()` methods of the reference counted types (e.g.
+    /// [`Font::from_ptr()`](crate::Font::from_ptr)), this does not increase the
+    /// reference count: buffers are not shared, so the caller gives up its
+    /// reference.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a valid pointer to a `hb_buffer_t` containing Unicode
+    /// text that is not used by anyone else afterwards.
+    pub unsafe fn from_raw_owned(ptr: *mut hb_buffer_t) -> UnicodeBuffer {
+        FromRaw::from_raw(ptr)
+    }
+
     /// Returns the length of the data of the buffer.
     ///
     /// This corresponds to the number of unicode codepoints contained in the
@@ -567,6 +1641,12 @@ impl UnicodeBuffer {
     /// When shaping part of a larger text (e.g. a run of text from a paragraph)
     /// it is preferable to use `add_str_item` instead.
     ///
+    /// # Panics
+    ///
+    /// Panics if more than `c_int::MAX` bytes of `str_slice` are added at
+    /// once, the maximum length HarfBuzz accepts. A length limit (see
+    /// [`Self::set_length_limit()`]) truncates or rejects longer text instead.
+    ///
     /// # Examples
     ///
     /// ```
@@ -582,6 +1662,151 @@ impl UnicodeBuffer {
         self
     }
 
+    /// Add the string slice `str_slice` to the `Buffer` like [`Self::add_str()`]
+    /// but offset the cluster values of the added codepoints by
+    /// `base_cluster`.
+    ///
+    /// This keeps the cluster values relative to a larger text when a buffer
+    /// is assembled from several fragments of it, e.g. from the spans of a
+    /// paragraph with different styles. Pass the byte offset of each fragment
+    /// in the paragraph as `base_cluster`. In contrast to
+    /// [`Self::add_str_item()`] the fragments do not need to be parts of one
+    /// contiguous string, but the text around a fragment is not used as
+    /// context for shaping.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `base_cluster + str_slice.len()` does not fit into a `u32`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// // the spans of "Hello World" without the space
+    /// let buffer = UnicodeBuffer::new()
+    ///     .add_str_with_cluster_offset("Hello", 0)
+    ///     .add_str_with_cluster_offset("World", 6);
+    ///
+    /// let output = shape(&font, buffer, &[]);
+    /// let clusters: Vec<u32> = output.clusters().collect();
+    /// assert_eq!(clusters, vec![0, 1, 2, 3, 4, 6, 7, 8, 9, 10]);
+    /// ```
+    pub fn add_str_with_cluster_offset(
+        mut self,
+        str_slice: &str,
+        base_cluster: u32,
+    ) -> UnicodeBuffer {
+        self.0.add_str_with_cluster_offset(str_slice, base_cluster);
+        self
+    }
+
+    /// Add UTF-16 encoded text to the `Buffer`'s array of codepoints.
+    ///
+    /// The cluster values of the added codepoints are the indices of the
+    /// corresponding UTF-16 code units in `text`. Unpaired surrogates are
+    /// replaced by the buffer's replacement codepoint (see
+    /// [`Self::set_replacement_codepoint()`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `c_int::MAX` elements of `text` are added at once,
+    /// the maximum length HarfBuzz accepts. A length limit (see
+    /// [`Self::set_length_limit()`]) truncates or rejects longer text instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::UnicodeBuffer;
+    ///
+    /// let text: Vec<u16> = "Hello 😍".encode_utf16().collect();
+    /// let buffer = UnicodeBuffer::new().add_utf16(&text);
+    /// assert_eq!(buffer.string_lossy(), "Hello 😍");
+    /// ```
+    pub fn add_utf16(mut self, text: &[u16]) -> UnicodeBuffer {
+        self.0.add_utf16_item(text, 0, text.len());
+        self
+    }
+
+    /// Add UTF-32 encoded text to the `Buffer`'s array of codepoints.
+    ///
+    /// The cluster values of the added codepoints are their indices in
+    /// `text`. Invalid codepoints (surrogates and values above U+10FFFF) are
+    /// replaced by the buffer's replacement codepoint (see
+    /// [`Self::set_replacement_codepoint()`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `c_int::MAX` elements of `text` are added at once,
+    /// the maximum length HarfBuzz accepts. A length limit (see
+    /// [`Self::set_length_limit()`]) truncates or rejects longer text instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::UnicodeBuffer;
+    ///
+    /// let buffer = UnicodeBuffer::new().add_utf32(&[0x48, 0x69, 0x1F60D]);
+    /// assert_eq!(buffer.string_lossy(), "Hi😍");
+    /// ```
+    pub fn add_utf32(mut self, text: &[u32]) -> UnicodeBuffer {
+        self.0.add_utf32_item(text, 0, text.len());
+        self
+    }
+
+    /// Add a slice of `char`s to the `Buffer`'s array of codepoints.
+    ///
+    /// This is a convenience method for text that is stored as `Vec<char>`.
+    /// The cluster values of the added codepoints are their indices in `text`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `c_int::MAX` elements of `text` are added at once,
+    /// the maximum length HarfBuzz accepts. A length limit (see
+    /// [`Self::set_length_limit()`]) truncates or rejects longer text instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::UnicodeBuffer;
+    ///
+    /// let text: Vec<char> = "Hello".chars().collect();
+    /// let buffer = UnicodeBuffer::new().add_chars(&text);
+    /// assert_eq!(buffer.string_lossy(), "Hello");
+    /// ```
+    pub fn add_chars(mut self, text: &[char]) -> UnicodeBuffer {
+        self.edit().add_chars(text);
+        self
+    }
+
+    /// Add Latin-1 (ISO-8859-1) encoded text to the `Buffer`'s array of
+    /// codepoints.
+    ///
+    /// Every byte is interpreted as the Unicode codepoint of the same value.
+    /// The cluster values of the added codepoints are their byte indices in
+    /// `text`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `c_int::MAX` elements of `text` are added at once,
+    /// the maximum length HarfBuzz accepts. A length limit (see
+    /// [`Self::set_length_limit()`]) truncates or rejects longer text instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::UnicodeBuffer;
+    ///
+    /// let buffer = UnicodeBuffer::new().add_latin1(b"Stra\xDFe");
+    /// assert_eq!(buffer.string_lossy(), "Straße");
+    /// ```
+    pub fn add_latin1(mut self, text: &[u8]) -> UnicodeBuffer {
+        self.0.add_latin1_item(text, 0, text.len());
+        self
+    }
+
     /// Add a string item to the buffer, providing context.
     ///
     /// Only the `item` string gets added to the buffer and will be shaped.
@@ -615,11 +1840,38 @@ impl UnicodeBuffer {
     /// assert_eq!(buffer.string_lossy(), "World");
     /// ```
     pub fn add_str_item(mut self, context: &str, item: &str) -> UnicodeBuffer {
-        const PANIC_MSG: &str = "`item` must be a substring of `context`";
-        let offset =
-            usize::checked_sub(item.as_ptr() as _, context.as_ptr() as _).expect(PANIC_MSG);
-        assert!(offset + item.len() <= context.len(), "{}", PANIC_MSG);
-        self.0.add_str_item(context, offset, item.len());
+        self.edit().add_str_item(context, item);
+        self
+    }
+
+    /// Add the UTF-16 code units in the range `item` of `context` to the
+    /// buffer.
+    ///
+    /// This is the UTF-16 version of [`add_str_item()`](Self::add_str_item):
+    /// only the `item` range is shaped while the rest of `context` is used by
+    /// the shaper as surrounding text. The clusters are the indices of the
+    /// code units in `context`, so they map directly back to the source text
+    /// without converting between encodings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `item` is not a valid range of `context`, if more than
+    /// `c_int::MAX` code units of `item` are added at once (see
+    /// [`Self::add_utf16()`]) or if the clusters of `item` do not fit into a
+    /// `u32`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::UnicodeBuffer;
+    ///
+    /// let text: Vec<u16> = "Hello World!".encode_utf16().collect();
+    ///
+    /// let buffer = UnicodeBuffer::new().add_utf16_item(&text, 6..11);
+    /// assert_eq!(buffer.string_lossy(), "World");
+    /// ```
+    pub fn add_utf16_item(mut self, context: &[u16], item: Range<usize>) -> UnicodeBuffer {
+        self.edit().add_utf16_item(context, item);
         self
     }
 
@@ -686,6 +1938,29 @@ impl UnicodeBuffer {
         }
     }
 
+    /// Replace every character in the buffer that has a mirrored counterpart
+    /// (see [`mirror_char`](fn.mirror_char.html)) with that counterpart.
+    ///
+    /// HarfBuzz already mirrors characters when shaping text with direction
+    /// `Direction::Rtl`, so this is only needed for pipelines that shape
+    /// right-to-left runs without running the full bidi algorithm, e.g. when
+    /// the text has been reordered visually and is shaped left-to-right.
+    /// Calling this on a buffer that is shaped right-to-left afterwards undoes
+    /// the mirroring.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::UnicodeBuffer;
+    ///
+    /// let buffer = UnicodeBuffer::new().add_str("(a) [b]").apply_mirroring();
+    /// assert_eq!(buffer.string_lossy(), ")a( ]b[");
+    /// ```
+    pub fn apply_mirroring(mut self) -> UnicodeBuffer {
+        self.edit().apply_mirroring();
+        self
+    }
+
     /// Get the stored codepoints as a `String`.
     ///
     /// Invalid codepoints get replaced by the U+FFFD replacement character.
@@ -708,8 +1983,7 @@ impl UnicodeBuffer {
 
     /// Set the script from an ISO15924 tag.
     pub fn set_script(mut self, script: Tag) -> UnicodeBuffer {
-        self.0
-            .set_script(unsafe { hb_script_from_iso15924_tag(script.0) });
+        self.edit().set_script(script);
         self
     }
 
@@ -736,6 +2010,24 @@ impl UnicodeBuffer {
         self
     }
 
+    /// Set the segment properties (direction, language, script) of the
+    /// buffer all at once.
+    ///
+    /// # Examples
+    /// ```
+    /// use harfbuzz_rs::UnicodeBuffer;
+    ///
+    /// let template = UnicodeBuffer::new().add_str("abc").guess_segment_properties();
+    /// let buffer = UnicodeBuffer::new()
+    ///     .add_str("def")
+    ///     .set_segment_properties(template.get_segment_properties());
+    /// assert_eq!(buffer.get_segment_properties(), template.get_segment_properties());
+    /// ```
+    pub fn set_segment_properties(mut self, props: SegmentProperties) -> UnicodeBuffer {
+        self.0.set_segment_properties(props);
+        self
+    }
+
     /// Get the segment properties (direction, language, script) of the current
     /// buffer.
     pub fn get_segment_properties(&self) -> SegmentProperties {
@@ -753,50 +2045,643 @@ impl UnicodeBuffer {
         self.0.get_cluster_level()
     }
 
-    /// Pre-allocate the buffer to hold a string at least `size` codepoints.
-    pub fn pre_allocate(&mut self, size: usize) {
-        self.0.pre_allocate(size)
+    /// Set the glyph that replaces invisible characters in the shaping result.
+    ///
+    /// By default HarfBuzz replaces default-ignorable characters (like zero
+    /// width joiners) that are not removed from the output with the space
+    /// glyph and sets their advance to zero. If `glyph` is non-zero it will be
+    /// used instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::UnicodeBuffer;
+    ///
+    /// let buffer = UnicodeBuffer::new().set_invisible_glyph(3);
+    /// assert_eq!(buffer.get_invisible_glyph(), 3);
+    /// ```
+    pub fn set_invisible_glyph(mut self, glyph: Glyph) -> UnicodeBuffer {
+        self.0.set_invisible_glyph(glyph);
+        self
     }
 
-    /// Clear the contents of the buffer (i.e. the stored string of unicode
-    /// characters).
+    /// Returns the glyph that replaces invisible characters in the shaping
+    /// result. Zero means the space glyph is used.
+    pub fn get_invisible_glyph(&self) -> Glyph {
+        self.0.get_invisible_glyph()
+    }
+
+    /// Set the glyph that replaces characters not found in the font during
+    /// shaping.
+    ///
+    /// By default such characters are mapped to glyph zero (`.notdef`). This
+    /// allows you to use a custom "tofu" glyph instead.
     ///
     /// # Examples
+    ///
     /// ```
     /// use harfbuzz_rs::UnicodeBuffer;
     ///
-    /// let buffer = UnicodeBuffer::new();
-    /// let buffer = buffer.add_str("Test!");
-    /// assert_eq!(buffer.len(), 5);
-    /// let buffer = buffer.clear_contents();
-    /// assert!(buffer.is_empty());
+    /// let buffer = UnicodeBuffer::new().set_not_found_glyph(5);
+    /// assert_eq!(buffer.get_not_found_glyph(), 5);
     /// ```
-    pub fn clear_contents(mut self) -> UnicodeBuffer {
-        self.0.clear_contents();
+    pub fn set_not_found_glyph(mut self, glyph: Glyph) -> UnicodeBuffer {
+        self.0.set_not_found_glyph(glyph);
         self
     }
-}
 
-impl std::fmt::Debug for UnicodeBuffer {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        fmt.debug_struct("UnicodeBuffer")
-            .field("content", &self.string_lossy())
-            .field("direction", &self.get_direction())
-            .field("language", &self.get_language())
-            .field("script", &self.get_script())
-            .field("cluster_level", &self.get_cluster_level())
-            .finish()
+    /// Returns the glyph that replaces characters not found in the font
+    /// during shaping.
+    pub fn get_not_found_glyph(&self) -> Glyph {
+        self.0.get_not_found_glyph()
     }
-}
 
-impl std::default::Default for UnicodeBuffer {
-    fn default() -> UnicodeBuffer {
-        UnicodeBuffer::new()
+    /// Set the codepoint that replaces invalid entries in the input text.
+    ///
+    /// Invalid input like malformed UTF-16 sequences or codepoints outside of
+    /// the Unicode range is replaced by this codepoint when it is added to the
+    /// buffer. The default is U+FFFD REPLACEMENT CHARACTER. Note that the
+    /// setting only affects text added after calling this method.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::UnicodeBuffer;
+    ///
+    /// let buffer = UnicodeBuffer::new();
+    /// assert_eq!(buffer.get_replacement_codepoint(), 0xFFFD);
+    /// let buffer = buffer.set_replacement_codepoint('?' as u32);
+    /// assert_eq!(buffer.get_replacement_codepoint(), '?' as u32);
+    /// ```
+    pub fn set_replacement_codepoint(mut self, replacement: u32) -> UnicodeBuffer {
+        self.0.set_replacement_codepoint(replacement);
+        self
     }
-}
 
-/// An iterator over the codepoints stored in a `UnicodeBuffer`.
-///
+    /// Returns the codepoint that replaces invalid entries in the input text.
+    pub fn get_replacement_codepoint(&self) -> u32 {
+        self.0.get_replacement_codepoint()
+    }
+
+    /// Set the flags that control the shaping behaviour of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{BufferFlags, UnicodeBuffer};
+    ///
+    /// let buffer = UnicodeBuffer::new()
+    ///     .set_flags(BufferFlags::BOT | BufferFlags::PRODUCE_UNSAFE_TO_CONCAT);
+    /// assert!(buffer.get_flags().contains(BufferFlags::PRODUCE_UNSAFE_TO_CONCAT));
+    /// ```
+    pub fn set_flags(mut self, flags: BufferFlags) -> UnicodeBuffer {
+        self.0.set_flags(flags);
+        self
+    }
+
+    /// Returns the flags that control the shaping behaviour of the buffer.
+    pub fn get_flags(&self) -> BufferFlags {
+        self.0.get_flags()
+    }
+
+    /// Attaches `value` to the buffer under `key`, replacing and dropping a
+    /// previous value.
+    ///
+    /// User data lets application metadata such as a run id or a style index
+    /// travel with the buffer, e.g. through C code that receives the raw
+    /// buffer. The data is kept by [`shape()`](crate::shape) and is available
+    /// from the resulting [`GlyphBuffer`]. It is also kept when the buffer is
+    /// cleared or reset but not copied by `clone()`. The value is dropped
+    /// together with the buffer.
+    ///
+    /// If HarfBuzz fails to allocate memory for the data, `value` is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// static STYLE: UserDataKey<String> = UserDataKey::new();
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let buffer = UnicodeBuffer::new()
+    ///     .add_str("Hello")
+    ///     .set_user_data(&STYLE, "bold".to_string());
+    ///
+    /// let output = shape(&font, buffer, &[]);
+    /// assert_eq!(output.get_user_data(&STYLE).map(String::as_str), Some("bold"));
+    /// ```
+    pub fn set_user_data<T: Send + 'static>(
+        mut self,
+        key: &'static UserDataKey<T>,
+        value: T,
+    ) -> UnicodeBuffer {
+        self.edit().set_user_data(key, value);
+        self
+    }
+
+    /// Returns the user data stored under `key`, if any.
+    pub fn get_user_data<T: 'static>(&self, key: &'static UserDataKey<T>) -> Option<&T> {
+        self.0.get_user_data(key)
+    }
+
+    /// Removes and drops the user data stored under `key`.
+    pub fn remove_user_data<T>(mut self, key: &'static UserDataKey<T>) -> UnicodeBuffer {
+        self.edit().remove_user_data(key);
+        self
+    }
+
+    /// Pre-allocate the buffer to hold a string at least `size` codepoints.
+    pub fn pre_allocate(&mut self, size: usize) {
+        self.0.pre_allocate(size);
+    }
+
+    /// Pre-allocate the buffer to hold a string at least `size` codepoints and
+    /// report whether the allocation succeeded.
+    ///
+    /// # Examples
+    /// ```
+    /// use harfbuzz_rs::UnicodeBuffer;
+    ///
+    /// let mut buffer = UnicodeBuffer::new();
+    /// assert!(buffer.try_pre_allocate(100).is_ok());
+    /// // more than HarfBuzz' maximum buffer length
+    /// assert!(buffer.try_pre_allocate(u32::MAX as usize).is_err());
+    /// ```
+    pub fn try_pre_allocate(&mut self, size: usize) -> Result<(), AllocationError> {
+        if self.0.pre_allocate(size) {
+            Ok(())
+        } else {
+            Err(AllocationError)
+        }
+    }
+
+    /// Returns an error if HarfBuzz failed to allocate memory for the buffer
+    /// at any point since it was created or its contents were last cleared.
+    ///
+    /// After a failed allocation HarfBuzz ignores all further additions to the
+    /// buffer and shaping it produces no output, so check this after adding
+    /// very large texts. [`Self::clear_contents()`] and [`Self::reset()`]
+    /// make the buffer usable again.
+    pub fn check_allocation(&self) -> Result<(), AllocationError> {
+        self.0.check_allocation()
+    }
+
+    /// Limits the number of codepoints in the buffer to protect against
+    /// pathologically long inputs.
+    ///
+    /// Text added afterwards that would make the buffer longer than
+    /// `limit.max_len` is truncated at a codepoint boundary or rejected as a
+    /// whole, depending on `limit.policy`. In both cases the limit is marked
+    /// as exceeded, which [`Self::check_length_limit()`] reports. The
+    /// contents already in the buffer are not changed.
+    ///
+    /// The limit stays with the buffer when it is shaped and cleared, so it
+    /// also applies when the buffer is reused.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{LengthLimit, LengthLimitPolicy, UnicodeBuffer};
+    ///
+    /// let limit = LengthLimit::new(5, LengthLimitPolicy::Truncate);
+    /// let buffer = UnicodeBuffer::new().set_length_limit(limit).add_str("Hello World!");
+    /// assert_eq!(buffer.string_lossy(), "Hello");
+    /// assert!(buffer.check_length_limit().is_err());
+    ///
+    /// let limit = LengthLimit::new(5, LengthLimitPolicy::Reject);
+    /// let buffer = UnicodeBuffer::new()
+    ///     .set_length_limit(limit)
+    ///     .add_str("Hi")
+    ///     .add_str(" World!");
+    /// assert_eq!(buffer.string_lossy(), "Hi");
+    /// assert!(buffer.check_length_limit().is_err());
+    /// ```
+    pub fn set_length_limit(mut self, limit: LengthLimit) -> UnicodeBuffer {
+        self.0.set_length_limit(limit);
+        self
+    }
+
+    /// Returns the length limit of the buffer, if any.
+    pub fn get_length_limit(&self) -> Option<LengthLimit> {
+        self.0.get_length_limit()
+    }
+
+    /// Removes the length limit of the buffer.
+    pub fn remove_length_limit(mut self) -> UnicodeBuffer {
+        self.0.remove_user_data(&LENGTH_LIMIT);
+        self
+    }
+
+    /// Returns an error if text was truncated or rejected because of the
+    /// length limit of the buffer since the limit was set or the contents
+    /// were last cleared.
+    pub fn check_length_limit(&self) -> Result<(), LengthLimitExceeded> {
+        self.0.check_length_limit()
+    }
+
+    /// Shortens the buffer to `len` codepoints, keeping the first ones.
+    ///
+    /// This has no effect if the buffer is not longer than `len`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::UnicodeBuffer;
+    ///
+    /// let buffer = UnicodeBuffer::new().add_str("Hello!").truncate(5);
+    /// assert_eq!(buffer.string_lossy(), "Hello");
+    /// ```
+    pub fn truncate(mut self, len: usize) -> UnicodeBuffer {
+        self.0.truncate(len);
+        self
+    }
+
+    /// Changes the length of the buffer to `len` codepoints.
+    ///
+    /// If the buffer grows, the new entries have a codepoint and cluster value
+    /// of zero.
+    pub fn resize(mut self, len: usize) -> UnicodeBuffer {
+        self.0.set_length(len);
+        self
+    }
+
+    /// Clear the contents of the buffer (i.e. the stored string of unicode
+    /// characters).
+    ///
+    /// This also resets the segment properties, but keeps the other settings
+    /// like flags and cluster level. Use [`Self::reset()`] to reset those as
+    /// well.
+    ///
+    /// # Examples
+    /// ```
+    /// use harfbuzz_rs::UnicodeBuffer;
+    ///
+    /// let buffer = UnicodeBuffer::new();
+    /// let buffer = buffer.add_str("Test!");
+    /// assert_eq!(buffer.len(), 5);
+    /// let buffer = buffer.clear_contents();
+    /// assert!(buffer.is_empty());
+    /// ```
+    pub fn clear_contents(mut self) -> UnicodeBuffer {
+        self.0.clear_contents();
+        self
+    }
+
+    /// Reset the buffer to the state of a freshly created buffer.
+    ///
+    /// [`Self::clear_contents()`] only removes the text and the segment
+    /// properties. `reset` additionally restores the flags, the cluster level,
+    /// the replacement codepoint and the invisible and not-found glyphs to
+    /// their defaults. The allocation is kept, which makes this useful for
+    /// reusing buffers for unrelated text.
+    ///
+    /// # Examples
+    /// ```
+    /// use harfbuzz_rs::{Direction, UnicodeBuffer};
+    ///
+    /// let buffer = UnicodeBuffer::new()
+    ///     .add_str("Test!")
+    ///     .set_replacement_codepoint('?' as u32);
+    /// let buffer = buffer.reset();
+    /// assert!(buffer.is_empty());
+    /// assert_eq!(buffer.get_replacement_codepoint(), 0xFFFD);
+    /// ```
+    pub fn reset(mut self) -> UnicodeBuffer {
+        self.0.reset();
+        self
+    }
+
+    /// Returns a view of the buffer that allows mutating it in place.
+    ///
+    /// The methods of `UnicodeBuffer` that change the buffer take it by value,
+    /// which is convenient for building a buffer but inconvenient if the
+    /// buffer is stored in a struct field. [`UnicodeBufferMut`] provides the
+    /// same methods taking `&mut self` instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Direction, UnicodeBuffer};
+    ///
+    /// struct Engine {
+    ///     buffer: UnicodeBuffer,
+    /// }
+    ///
+    /// let mut engine = Engine { buffer: UnicodeBuffer::new() };
+    /// engine
+    ///     .buffer
+    ///     .edit()
+    ///     .add_str("Hello")
+    ///     .set_direction(Direction::Ltr);
+    /// assert_eq!(engine.buffer.string_lossy(), "Hello");
+    ///
+    /// engine.buffer.edit().clear_contents().add_str("World");
+    /// assert_eq!(engine.buffer.string_lossy(), "World");
+    /// ```
+    pub fn edit(&mut self) -> UnicodeBufferMut<'_> {
+        UnicodeBufferMut(self)
+    }
+}
+
+/// A mutable view of a [`UnicodeBuffer`] obtained by
+/// [`UnicodeBuffer::edit()`].
+///
+/// Every method corresponds to the `UnicodeBuffer` method of the same name,
+/// but takes `&mut self` and returns `&mut Self` so that calls can be chained.
+#[derive(Debug)]
+pub struct UnicodeBufferMut<'a>(&'a mut UnicodeBuffer);
+
+impl<'a> UnicodeBufferMut<'a> {
+    /// See [`UnicodeBuffer::add()`].
+    pub fn add(&mut self, codepoint: u32, cluster: u32) -> &mut Self {
+        self.0 .0.add(codepoint, cluster);
+        self
+    }
+
+    /// See [`UnicodeBuffer::add_str()`].
+    pub fn add_str(&mut self, str_slice: &str) -> &mut Self {
+        self.0 .0.add_str_item(str_slice, 0, str_slice.len());
+        self
+    }
+
+    /// See [`UnicodeBuffer::add_str_with_cluster_offset()`].
+    pub fn add_str_with_cluster_offset(&mut self, str_slice: &str, base_cluster: u32) -> &mut Self {
+        self.0
+             .0
+            .add_str_with_cluster_offset(str_slice, base_cluster);
+        self
+    }
+
+    /// See [`UnicodeBuffer::add_utf16()`].
+    pub fn add_utf16(&mut self, text: &[u16]) -> &mut Self {
+        self.0 .0.add_utf16_item(text, 0, text.len());
+        self
+    }
+
+    /// See [`UnicodeBuffer::add_utf32()`].
+    pub fn add_utf32(&mut self, text: &[u32]) -> &mut Self {
+        self.0 .0.add_utf32_item(text, 0, text.len());
+        self
+    }
+
+    /// See [`UnicodeBuffer::add_chars()`].
+    pub fn add_chars(&mut self, text: &[char]) -> &mut Self {
+        // `char` has the same size and alignment as `u32` and every `char` is
+        // a valid `u32`.
+        let text = unsafe { std::slice::from_raw_parts(text.as_ptr() as *const u32, text.len()) };
+        self.add_utf32(text)
+    }
+
+    /// See [`UnicodeBuffer::add_latin1()`].
+    pub fn add_latin1(&mut self, text: &[u8]) -> &mut Self {
+        self.0 .0.add_latin1_item(text, 0, text.len());
+        self
+    }
+
+    /// See [`UnicodeBuffer::add_str_item()`].
+    pub fn add_str_item(&mut self, context: &str, item: &str) -> &mut Self {
+        const PANIC_MSG: &str = "`item` must be a substring of `context`";
+        let offset =
+            usize::checked_sub(item.as_ptr() as _, context.as_ptr() as _).expect(PANIC_MSG);
+        assert!(offset + item.len() <= context.len(), "{}", PANIC_MSG);
+        self.0 .0.add_str_item(context, offset, item.len());
+        self
+    }
+
+    /// See [`UnicodeBuffer::add_utf16_item()`].
+    pub fn add_utf16_item(&mut self, context: &[u16], item: Range<usize>) -> &mut Self {
+        assert!(
+            item.start <= item.end && item.end <= context.len(),
+            "`item` must be a range of `context`"
+        );
+        self.0
+             .0
+            .add_utf16_item(context, item.start, item.end - item.start);
+        self
+    }
+
+    /// See [`UnicodeBuffer::append()`].
+    pub fn append(&mut self, other: &UnicodeBuffer) -> &mut Self {
+        self.0 .0.append(&other.0, 0, c_uint::MAX);
+        self
+    }
+
+    /// See [`UnicodeBuffer::append_range()`].
+    pub fn append_range(
+        &mut self,
+        other: &UnicodeBuffer,
+        range: impl std::ops::RangeBounds<usize>,
+    ) -> &mut Self {
+        let (start, end) = crate::start_end_range(range);
+        self.0 .0.append(&other.0, start, end);
+        self
+    }
+
+    /// See [`UnicodeBuffer::apply_mirroring()`].
+    pub fn apply_mirroring(&mut self) -> &mut Self {
+        for info in self.0 .0.get_glyph_infos_mut() {
+            let mirrored = std::char::from_u32(info.codepoint).and_then(mirror_char);
+            if let Some(mirrored) = mirrored {
+                info.codepoint = mirrored as u32;
+            }
+        }
+        self
+    }
+
+    /// See [`UnicodeBuffer::set_direction()`].
+    pub fn set_direction(&mut self, direction: Direction) -> &mut Self {
+        self.0 .0.set_direction(direction);
+        self
+    }
+
+    /// See [`UnicodeBuffer::set_script()`].
+    pub fn set_script(&mut self, script: Tag) -> &mut Self {
+        self.0
+             .0
+            .set_script(unsafe { hb_script_from_iso15924_tag(script.0) });
+        self
+    }
+
+    /// See [`UnicodeBuffer::set_language()`].
+    pub fn set_language(&mut self, lang: Language) -> &mut Self {
+        self.0 .0.set_language(lang);
+        self
+    }
+
+    /// See [`UnicodeBuffer::guess_segment_properties()`].
+    pub fn guess_segment_properties(&mut self) -> &mut Self {
+        self.0 .0.guess_segment_properties();
+        self
+    }
+
+    /// See [`UnicodeBuffer::set_segment_properties()`].
+    pub fn set_segment_properties(&mut self, props: SegmentProperties) -> &mut Self {
+        self.0 .0.set_segment_properties(props);
+        self
+    }
+
+    /// See [`UnicodeBuffer::set_cluster_level()`].
+    pub fn set_cluster_level(&mut self, cluster_level: ClusterLevel) -> &mut Self {
+        self.0 .0.set_cluster_level(cluster_level);
+        self
+    }
+
+    /// See [`UnicodeBuffer::set_invisible_glyph()`].
+    pub fn set_invisible_glyph(&mut self, glyph: Glyph) -> &mut Self {
+        self.0 .0.set_invisible_glyph(glyph);
+        self
+    }
+
+    /// See [`UnicodeBuffer::set_not_found_glyph()`].
+    pub fn set_not_found_glyph(&mut self, glyph: Glyph) -> &mut Self {
+        self.0 .0.set_not_found_glyph(glyph);
+        self
+    }
+
+    /// See [`UnicodeBuffer::set_replacement_codepoint()`].
+    pub fn set_replacement_codepoint(&mut self, replacement: u32) -> &mut Self {
+        self.0 .0.set_replacement_codepoint(replacement);
+        self
+    }
+
+    /// See [`UnicodeBuffer::set_flags()`].
+    pub fn set_flags(&mut self, flags: BufferFlags) -> &mut Self {
+        self.0 .0.set_flags(flags);
+        self
+    }
+
+    /// See [`UnicodeBuffer::set_length_limit()`].
+    pub fn set_length_limit(&mut self, limit: LengthLimit) -> &mut Self {
+        self.0 .0.set_length_limit(limit);
+        self
+    }
+
+    /// See [`UnicodeBuffer::remove_length_limit()`].
+    pub fn remove_length_limit(&mut self) -> &mut Self {
+        self.0 .0.remove_user_data(&LENGTH_LIMIT);
+        self
+    }
+
+    /// See [`UnicodeBuffer::set_user_data()`].
+    pub fn set_user_data<T: Send + 'static>(
+        &mut self,
+        key: &'static UserDataKey<T>,
+        value: T,
+    ) -> &mut Self {
+        self.0 .0.set_user_data(key, value);
+        self
+    }
+
+    /// See [`UnicodeBuffer::remove_user_data()`].
+    pub fn remove_user_data<T>(&mut self, key: &'static UserDataKey<T>) -> &mut Self {
+        self.0 .0.remove_user_data(key);
+        self
+    }
+
+    /// See [`UnicodeBuffer::truncate()`].
+    pub fn truncate(&mut self, len: usize) -> &mut Self {
+        self.0 .0.truncate(len);
+        self
+    }
+
+    /// See [`UnicodeBuffer::resize()`].
+    pub fn resize(&mut self, len: usize) -> &mut Self {
+        self.0 .0.set_length(len);
+        self
+    }
+
+    /// See [`UnicodeBuffer::clear_contents()`].
+    pub fn clear_contents(&mut self) -> &mut Self {
+        self.0 .0.clear_contents();
+        self
+    }
+
+    /// See [`UnicodeBuffer::reset()`].
+    pub fn reset(&mut self) -> &mut Self {
+        self.0 .0.reset();
+        self
+    }
+}
+
+impl std::fmt::Debug for UnicodeBuffer {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("UnicodeBuffer")
+            .field("content", &self.string_lossy())
+            .field("direction", &self.get_direction())
+            .field("language", &self.get_language())
+            .field("script", &self.get_script())
+            .field("cluster_level", &self.get_cluster_level())
+            .field("invisible_glyph", &self.get_invisible_glyph())
+            .field("not_found_glyph", &self.get_not_found_glyph())
+            .field("replacement_codepoint", &self.get_replacement_codepoint())
+            .field("flags", &self.get_flags())
+            .finish()
+    }
+}
+
+impl FromRaw<*mut hb_buffer_t> for UnicodeBuffer {
+    unsafe fn from_raw(raw: *mut hb_buffer_t) -> Self {
+        UnicodeBuffer::from_generic(Owned::from_raw(raw))
+    }
+}
+
+impl IntoRaw<*mut hb_buffer_t> for UnicodeBuffer {
+    fn into_raw(self) -> *mut hb_buffer_t {
+        Owned::into_raw(self.0)
+    }
+}
+
+impl std::default::Default for UnicodeBuffer {
+    fn default() -> UnicodeBuffer {
+        UnicodeBuffer::new()
+    }
+}
+
+/// Adds `(codepoint, cluster)` pairs to the buffer.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::UnicodeBuffer;
+///
+/// let mut buffer = UnicodeBuffer::new().add_str("Hello");
+/// buffer.extend(" World".chars().zip(5..).map(|(c, i)| (c as u32, i)));
+/// assert_eq!(buffer.string_lossy(), "Hello World");
+/// ```
+impl Extend<(u32, u32)> for UnicodeBuffer {
+    fn extend<T: IntoIterator<Item = (u32, u32)>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        let (lower_bound, _) = iter.size_hint();
+        let new_len = self.len().saturating_add(lower_bound);
+        self.0.pre_allocate(new_len);
+        for (codepoint, cluster) in iter {
+            self.0.add(codepoint, cluster);
+        }
+    }
+}
+
+/// Creates a buffer from `(codepoint, cluster)` pairs.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::UnicodeBuffer;
+///
+/// let buffer: UnicodeBuffer = "abc".chars().map(|c| (c as u32, 0)).collect();
+/// assert_eq!(buffer.string_lossy(), "abc");
+/// ```
+impl std::iter::FromIterator<(u32, u32)> for UnicodeBuffer {
+    fn from_iter<T: IntoIterator<Item = (u32, u32)>>(iter: T) -> UnicodeBuffer {
+        let mut buffer = UnicodeBuffer::new();
+        buffer.extend(iter);
+        buffer
+    }
+}
+
+/// An iterator over the codepoints stored in a `UnicodeBuffer`.
+///
 /// You get an iterator of this type from the `.codepoints()` method on
 /// `UnicodeBuffer`. It yields `u32`s that should be interpreted as unicode
 /// codepoints stored in the underlying buffer.
@@ -811,42 +2696,509 @@ impl<'a> Iterator for Codepoints<'a> {
     fn next(&mut self) -> Option<u32> {
         self.slice_iter.next().map(|info| info.codepoint)
     }
-}
+}
+
+/// A `GlyphBuffer` contains the resulting output information of the shaping
+/// process.
+///
+/// An object of this type is obtained through the `shape` function.
+///
+/// Like [`UnicodeBuffer`] a `GlyphBuffer` is `Send` but not `Sync`. Clone the
+/// buffer or copy the glyph infos and positions out of it to share the results
+/// between threads.
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<harfbuzz_rs::GlyphBuffer>();
+/// ```
+pub struct GlyphBuffer(pub(crate) Owned<GenericBuffer>);
+
+/// Cloning a `GlyphBuffer` copies the glyph infos and positions into a new
+/// buffer, so the original buffer can be reused for shaping while the clone
+/// keeps the results.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+///
+/// let output = shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]);
+/// let snapshot = output.clone();
+/// let output = shape(&font, output.clear().add_str("World!"), &[]);
+///
+/// assert_eq!(snapshot.len(), 5);
+/// assert_eq!(output.len(), 6);
+/// ```
+impl Clone for GlyphBuffer {
+    fn clone(&self) -> Self {
+        GlyphBuffer(self.0.deep_copy())
+    }
+}
+
+impl GlyphBuffer {
+    /// Creates a new, empty buffer for glyphs that are added manually with
+    /// [`push()`](GlyphBuffer::push).
+    ///
+    /// This is useful for fallback shapers and for tests of layout code. The
+    /// segment properties of the buffer are unset; set them with
+    /// [`set_segment_properties()`](GlyphBuffer::set_segment_properties) if
+    /// the code consuming the buffer relies on them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let mut buffer = GlyphBuffer::new();
+    /// buffer.push(GlyphInfo::new(36, 0), GlyphPosition::new(500, 0, 0, 0));
+    /// buffer.push(GlyphInfo::new(37, 1), GlyphPosition::new(480, 0, 0, 0));
+    ///
+    /// assert_eq!(buffer.len(), 2);
+    /// assert_eq!(buffer.get_glyph_infos()[1].codepoint, 37);
+    /// assert_eq!(buffer.get_glyph_positions()[1].x_advance, 480);
+    /// ```
+    pub fn new() -> GlyphBuffer {
+        let mut buffer = GenericBuffer::new();
+        buffer.set_content_type(HB_BUFFER_CONTENT_TYPE_GLYPHS);
+        // requesting the positions makes HarfBuzz allocate them, so they are
+        // kept when the buffer grows
+        buffer.get_glyph_positions_mut();
+        GlyphBuffer(buffer)
+    }
+
+    /// Appends a glyph with its position to the end of the buffer.
+    ///
+    /// This can also be used to add synthesized glyphs, such as a hyphen, to
+    /// the output of [`shape()`](crate::shape).
+    pub fn push(&mut self, info: GlyphInfo, position: GlyphPosition) {
+        self.0.push_glyph(info, position);
+    }
+
+    /// Sets the segment properties of the buffer.
+    pub fn set_segment_properties(&mut self, props: SegmentProperties) {
+        self.0.set_segment_properties(props);
+    }
+
+    /// Returns the segment properties of the buffer.
+    pub fn get_segment_properties(&self) -> SegmentProperties {
+        self.0.get_segment_properties()
+    }
+
+    /// Attaches `value` to the buffer under `key`, replacing and dropping a
+    /// previous value.
+    ///
+    /// See [`UnicodeBuffer::set_user_data()`] for details.
+    pub fn set_user_data<T: Send + 'static>(&mut self, key: &'static UserDataKey<T>, value: T) {
+        self.0.set_user_data(key, value);
+    }
+
+    /// Returns the user data stored under `key`, if any.
+    pub fn get_user_data<T: 'static>(&self, key: &'static UserDataKey<T>) -> Option<&T> {
+        self.0.get_user_data(key)
+    }
+
+    /// Removes and drops the user data stored under `key`.
+    pub fn remove_user_data<T>(&mut self, key: &'static UserDataKey<T>) {
+        self.0.remove_user_data(key);
+    }
+
+    /// Returns the length of the data of the buffer.
+    ///
+    /// When called before shaping this is the number of unicode codepoints
+    /// contained in the buffer. When called after shaping it returns the number
+    /// of glyphs stored.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Converts this buffer to a raw harfbuzz object pointer.
+    pub fn into_raw(self) -> *mut hb_buffer_t {
+        Owned::into_raw(self.0)
+    }
+
+    /// Returns the raw `hb_buffer_t` pointer of the buffer for FFI calls
+    /// that do not take ownership of the buffer.
+    ///
+    /// The pointer must not be used after the buffer is dropped.
+    pub fn as_ptr(&self) -> *mut hb_buffer_t {
+        self.0.as_raw()
+    }
+
+    /// Takes ownership of a raw `hb_buffer_t` pointer, e.g. one returned by
+    /// [`into_raw()`](Self::into_raw).
+    ///
+    /// Unlike the `from_ptr()` methods of the reference counted types (e.g.
+    /// [`Font::from_ptr()`](crate::Font::from_ptr)), this does not increase the
+    /// reference count: buffers are not shared, so the caller gives up its
+    /// reference.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a valid pointer to a `hb_buffer_t` containing glyphs
+    /// that is not used by anyone else afterwards.
+    pub unsafe fn from_raw_owned(ptr: *mut hb_buffer_t) -> GlyphBuffer {
+        FromRaw::from_raw(ptr)
+    }
+
+    /// Returns `true` if the buffer contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the kind of content of the buffer, which is
+    /// [`ContentType::Glyphs`] for buffers returned by shaping.
+    pub fn content_type(&self) -> ContentType {
+        ContentType::from_raw(self.0.content_type())
+    }
+
+    /// Returns `true` if the buffer has glyph positions.
+    ///
+    /// Buffers returned by shaping and created with
+    /// [`GlyphBuffer::new()`] always have positions. A buffer received from
+    /// foreign code with [`TypedBuffer::take_from_raw()`] may only contain
+    /// glyphs that were not positioned yet, so check this before relying on
+    /// the positions of such a buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let output = shape(&font, UnicodeBuffer::new().add_str("a"), &[]);
+    /// assert_eq!(output.content_type(), ContentType::Glyphs);
+    /// assert!(output.has_positions());
+    /// ```
+    pub fn has_positions(&self) -> bool {
+        self.0.has_positions()
+    }
+
+    /// Get the glyph positions.
+    ///
+    /// If the buffer has no positions (see
+    /// [`has_positions()`](Self::has_positions)), HarfBuzz initializes them
+    /// with zeros.
+    pub fn get_glyph_positions(&self) -> &[GlyphPosition] {
+        self.0.get_glyph_positions()
+    }
+
+    /// Get the glyph infos.
+    pub fn get_glyph_infos(&self) -> &[GlyphInfo] {
+        self.0.get_glyph_infos()
+    }
+
+    /// Returns an error if HarfBuzz failed to allocate memory for the buffer
+    /// during shaping. The buffer is empty in that case.
+    pub fn check_allocation(&self) -> Result<(), AllocationError> {
+        self.0.check_allocation()
+    }
+
+    /// Returns an error if the text of the buffer was truncated or rejected
+    /// because of its length limit before shaping.
+    ///
+    /// See [`UnicodeBuffer::set_length_limit()`].
+    pub fn check_length_limit(&self) -> Result<(), LengthLimitExceeded> {
+        self.0.check_length_limit()
+    }
+
+    /// Get the glyph infos for modification, e.g. to change their masks.
+    pub fn get_glyph_infos_mut(&mut self) -> &mut [GlyphInfo] {
+        self.0.get_glyph_infos_mut()
+    }
+
+    /// Returns an iterator over the clusters of the buffer.
+    ///
+    /// Each item is a tuple of the cluster value and the infos and positions
+    /// of the consecutive glyphs belonging to that cluster, in the order of
+    /// the buffer (i.e. visual order after shaping). This is the grouping
+    /// needed for hit testing and selection rendering.
+    ///
+    /// With the monotone cluster levels (the default) all glyphs of a cluster
+    /// are adjacent, so each cluster is returned exactly once. With
+    /// [`ClusterLevel::Characters`] HarfBuzz does not merge clusters and the
+    /// glyphs of one cluster may be separated by glyphs of other clusters; each
+    /// adjacent group is then returned separately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// // the combining accent belongs to the cluster of its base character
+    /// let output = shape(&font, UnicodeBuffer::new().add_str("e\u{301}x"), &[]);
+    ///
+    /// let clusters: Vec<u32> = output
+    ///     .cluster_runs()
+    ///     .map(|(cluster, _infos, _positions)| cluster)
+    ///     .collect();
+    /// assert_eq!(clusters, vec![0, 3]);
+    /// ```
+    pub fn cluster_runs(&self) -> ClusterRuns<'_> {
+        ClusterRuns {
+            infos: self.get_glyph_infos(),
+            positions: self.get_glyph_positions(),
+        }
+    }
+
+    /// Returns an iterator over the glyphs of the buffer that combines the
+    /// glyph infos with the corresponding glyph positions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let output = shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]);
+    ///
+    /// let mut pen_x = 0;
+    /// for glyph in &output {
+    ///     println!("glyph {} of cluster {} at {}", glyph.gid, glyph.cluster, pen_x + glyph.x_offset);
+    ///     pen_x += glyph.x_advance;
+    /// }
+    /// assert_eq!(output.iter().count(), 5);
+    /// ```
+    pub fn iter(&self) -> ShapedGlyphs<'_> {
+        ShapedGlyphs {
+            infos: self.get_glyph_infos().iter(),
+            positions: self.get_glyph_positions().iter(),
+        }
+    }
+
+    /// Returns an iterator over the glyph ids of the buffer.
+    ///
+    /// HarfBuzz stores the glyph infos and positions as arrays of structs, so
+    /// this and the other per-field accessors (e.g.
+    /// [`x_advances()`](Self::x_advances)) read the field from each glyph. Use
+    /// [`to_arrays()`](Self::to_arrays) to get all fields as contiguous
+    /// arrays, e.g. to upload them as GPU instance data.
+    pub fn glyph_ids(&self) -> impl ExactSizeIterator<Item = Glyph> + '_ {
+        self.get_glyph_infos().iter().map(|info| info.codepoint)
+    }
+
+    /// Returns an iterator over the clusters of the glyphs.
+    pub fn clusters(&self) -> impl ExactSizeIterator<Item = u32> + '_ {
+        self.get_glyph_infos().iter().map(|info| info.cluster)
+    }
+
+    /// Returns an iterator over the horizontal advances of the glyphs.
+    pub fn x_advances(&self) -> impl ExactSizeIterator<Item = Position> + '_ {
+        self.get_glyph_positions().iter().map(|pos| pos.x_advance)
+    }
+
+    /// Returns an iterator over the vertical advances of the glyphs.
+    pub fn y_advances(&self) -> impl ExactSizeIterator<Item = Position> + '_ {
+        self.get_glyph_positions().iter().map(|pos| pos.y_advance)
+    }
+
+    /// Returns an iterator over the horizontal offsets of the glyphs.
+    pub fn x_offsets(&self) -> impl ExactSizeIterator<Item = Position> + '_ {
+        self.get_glyph_positions().iter().map(|pos| pos.x_offset)
+    }
+
+    /// Returns an iterator over the vertical offsets of the glyphs.
+    pub fn y_offsets(&self) -> impl ExactSizeIterator<Item = Position> + '_ {
+        self.get_glyph_positions().iter().map(|pos| pos.y_offset)
+    }
 
-/// A `GlyphBuffer` contains the resulting output information of the shaping
-/// process.
-///
-/// An object of this type is obtained through the `shape` function.
-pub struct GlyphBuffer(pub(crate) Owned<GenericBuffer>);
+    /// Copies the glyphs into a new [`GlyphArrays`] with one array per field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let output = shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]);
+    ///
+    /// let arrays = output.to_arrays();
+    /// assert_eq!(arrays.len(), 5);
+    /// assert_eq!(arrays.glyph_ids, output.glyph_ids().collect::<Vec<_>>());
+    /// assert_eq!(arrays.x_advances[1], output.get_glyph_positions()[1].x_advance);
+    /// ```
+    pub fn to_arrays(&self) -> GlyphArrays {
+        let mut arrays = GlyphArrays::default();
+        self.write_arrays(&mut arrays);
+        arrays
+    }
 
-impl GlyphBuffer {
-    /// Returns the length of the data of the buffer.
+    /// Copies the glyphs into `arrays`, replacing their previous contents.
     ///
-    /// When called before shaping this is the number of unicode codepoints
-    /// contained in the buffer. When called after shaping it returns the number
-    /// of glyphs stored.
-    pub fn len(&self) -> usize {
-        self.0.len()
+    /// This reuses the allocations of `arrays`, so a renderer can keep one
+    /// `GlyphArrays` around instead of allocating new arrays for every buffer.
+    pub fn write_arrays(&self, arrays: &mut GlyphArrays) {
+        arrays.clear();
+        let infos = self.get_glyph_infos();
+        let positions = self.get_glyph_positions();
+        arrays
+            .glyph_ids
+            .extend(infos.iter().map(|info| info.codepoint));
+        arrays
+            .clusters
+            .extend(infos.iter().map(|info| info.cluster));
+        arrays
+            .x_advances
+            .extend(positions.iter().map(|pos| pos.x_advance));
+        arrays
+            .y_advances
+            .extend(positions.iter().map(|pos| pos.y_advance));
+        arrays
+            .x_offsets
+            .extend(positions.iter().map(|pos| pos.x_offset));
+        arrays
+            .y_offsets
+            .extend(positions.iter().map(|pos| pos.y_offset));
     }
 
-    /// Converts this buffer to a raw harfbuzz object pointer.
-    pub fn into_raw(self) -> *mut hb_buffer_t {
-        Owned::into_raw(self.0)
+    /// Shortens the buffer to `len` glyphs, keeping the first ones (e.g. to
+    /// drop the glyphs after the position of an ellipsis).
+    ///
+    /// This has no effect if the buffer is not longer than `len`.
+    pub fn truncate(&mut self, len: usize) {
+        self.0.truncate(len)
     }
 
-    /// Returns `true` if the buffer contains no elements.
-    pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+    /// Changes the length of the buffer to `len` glyphs.
+    ///
+    /// If the buffer grows, the new glyph infos and positions are zeroed.
+    pub fn resize(&mut self, len: usize) {
+        self.0.set_length(len);
     }
 
-    /// Get the glyph positions.
-    pub fn get_glyph_positions(&self) -> &[GlyphPosition] {
-        self.0.get_glyph_positions()
+    /// Splits the buffer before the glyphs of `cluster` without shaping
+    /// again.
+    ///
+    /// Returns the glyphs of the clusters before `cluster` and the glyphs of
+    /// `cluster` and the following clusters, both in the visual order of the
+    /// buffer and with the segment properties of the buffer. The glyphs keep
+    /// their positions, so the halves are what shaping the two parts of the
+    /// text separately would produce. One of the halves reuses the allocation
+    /// of `self`.
+    ///
+    /// Splitting fails if no glyph belongs to `cluster`, if the glyphs are not
+    /// grouped into the two halves (e.g. with [`ClusterLevel::Characters`])
+    /// or if the glyphs of `cluster` are marked as
+    /// [`unsafe_to_break`](GlyphFlags::unsafe_to_break), e.g. because of
+    /// kerning or a ligature across the boundary. The error contains the
+    /// unchanged buffer, so it can be shaped again in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let output = shape(&font, UnicodeBuffer::new().add_str("Hello World"), &[]);
+    ///
+    /// let (hello, world) = output.split_at_cluster(6).unwrap();
+    /// assert_eq!(hello.len(), 6);
+    /// assert_eq!(world.len(), 5);
+    /// assert_eq!(world.get_glyph_infos()[0].cluster, 6);
+    /// ```
+    pub fn split_at_cluster(
+        mut self,
+        cluster: u32,
+    ) -> Result<(GlyphBuffer, GlyphBuffer), SplitError> {
+        let direction = self.get_segment_properties().direction;
+        let backward = matches!(direction, Direction::Rtl | Direction::Btt);
+        let infos = self.get_glyph_infos();
+        // in backward directions the later clusters come first
+        let in_front = |info: &GlyphInfo| (info.cluster < cluster) != backward;
+        let split = infos
+            .iter()
+            .position(|info| !in_front(info))
+            .unwrap_or(infos.len());
+        let boundary = if backward {
+            split.checked_sub(1)
+        } else {
+            Some(split)
+        };
+        let valid = infos[split..].iter().all(|info| !in_front(info))
+            && boundary
+                .and_then(|index| infos.get(index))
+                .is_some_and(|info| {
+                    info.cluster == cluster && !info.glyph_flags().unsafe_to_break()
+                });
+        if !valid {
+            return Err(SplitError(self));
+        }
+
+        let mut back = self.0.create_similar();
+        back.append(&self.0, split as c_uint, c_uint::MAX);
+        back.set_content_type(self.0.content_type());
+        let back = GlyphBuffer(back);
+        self.truncate(split);
+        if backward {
+            Ok((back, self))
+        } else {
+            Ok((self, back))
+        }
     }
 
-    /// Get the glyph infos.
-    pub fn get_glyph_infos(&self) -> &[GlyphInfo] {
-        self.0.get_glyph_infos()
+    /// Returns a copy of the buffer that only contains the glyphs for which
+    /// `keep` returns `true`, e.g. to drop default ignorables or marks for a
+    /// renderer that does not need them.
+    ///
+    /// The copy has the same segment properties and the kept glyphs keep
+    /// their clusters and glyph flags. The advances of dropped glyphs are
+    /// added to the preceding kept glyph, so the kept glyphs stay at their
+    /// positions and the total advance does not change unless glyphs at the
+    /// start are dropped. A cluster whose glyphs are all dropped disappears,
+    /// so its text becomes part of the neighbouring cluster, just as if
+    /// HarfBuzz had merged the clusters. Since the kept glyphs stay in their
+    /// order, the clusters of the copy are monotonic whenever the clusters of
+    /// this buffer are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let output = shape(&font, UnicodeBuffer::new().add_str("e\u{301}x"), &[]);
+    ///
+    /// // drop the combining accent
+    /// let accent = font.get_nominal_glyph('\u{301}').unwrap();
+    /// let filtered = output.filtered_copy(|info, _| info.codepoint != accent);
+    /// assert_eq!(filtered.len(), 2);
+    /// assert_eq!(filtered.clusters().collect::<Vec<_>>(), vec![0, 3]);
+    /// assert_eq!(
+    ///     filtered.x_advances().sum::<Position>(),
+    ///     output.x_advances().sum::<Position>()
+    /// );
+    /// ```
+    pub fn filtered_copy(&self, keep: impl Fn(&GlyphInfo, &GlyphPosition) -> bool) -> GlyphBuffer {
+        let mut copy = self.0.create_similar();
+        copy.set_content_type(HB_BUFFER_CONTENT_TYPE_GLYPHS);
+        // requesting the positions makes HarfBuzz allocate them, so the copy
+        // has positions even if no glyph is kept
+        copy.get_glyph_positions_mut();
+        let mut copy = GlyphBuffer(copy);
+        // The kept glyphs are a subsequence of the glyphs of `self`, so their
+        // clusters are ordered like those of `self` and need no checks.
+        for (info, position) in self
+            .get_glyph_infos()
+            .iter()
+            .zip(self.get_glyph_positions())
+        {
+            if keep(info, position) {
+                copy.push(*info, *position);
+            } else if let Some(previous) = copy.0.get_glyph_positions_mut().last_mut() {
+                previous.x_advance += position.x_advance;
+                previous.y_advance += position.y_advance;
+            }
+        }
+        copy
     }
 
     /// Reverse the `Buffer`'s contents.
@@ -859,6 +3211,196 @@ impl GlyphBuffer {
         self.0.reverse_range(start, end)
     }
 
+    /// Reverse the order of the clusters in the `Buffer` while keeping the
+    /// order of the glyphs within each cluster.
+    ///
+    /// This turns the visual order of right-to-left output into the logical
+    /// order and vice versa without reordering the glyphs inside clusters such
+    /// as a base glyph and its marks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let mut output = shape(&font, UnicodeBuffer::new().add_str("x\u{301}y"), &[]);
+    /// let glyphs: Vec<Glyph> = output.iter().map(|glyph| glyph.gid).collect();
+    ///
+    /// output.reverse_clusters();
+    /// let reversed: Vec<Glyph> = output.iter().map(|glyph| glyph.gid).collect();
+    /// assert_eq!(reversed, vec![glyphs[2], glyphs[0], glyphs[1]]);
+    /// ```
+    pub fn reverse_clusters(&mut self) {
+        self.0.reverse_clusters()
+    }
+
+    /// Returns a hash of the glyph infos and positions contained in the buffer.
+    ///
+    /// Two buffers with the same glyphs, clusters, glyph flags and positions
+    /// have the same hash. The hash function is fixed (64-bit FNV-1a), so the
+    /// result is stable across runs and platforms and can be used to check
+    /// whether reshaping changed the output, e.g. to avoid uploading the same
+    /// glyph data to the GPU again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    /// # use std::path::PathBuf;
+    /// # let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    /// # path.push("testfiles/SourceSansVariable-Roman.ttf");
+    /// let face = Face::from_file(path, 0).expect("Error reading font file.");
+    /// let font = Font::new(face);
+    ///
+    /// let first = shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]);
+    /// let second = shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]);
+    /// assert_eq!(first.content_hash(), second.content_hash());
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut write = |value: u32| hash = fnv1a(hash, &value.to_le_bytes());
+        for (info, pos) in self
+            .get_glyph_infos()
+            .iter()
+            .zip(self.get_glyph_positions())
+        {
+            write(info.codepoint);
+            write(info.cluster);
+            write(info.glyph_flags().0);
+            write(pos.x_advance as u32);
+            write(pos.y_advance as u32);
+            write(pos.x_offset as u32);
+            write(pos.y_offset as u32);
+        }
+        hash
+    }
+
+    /// Returns the union of the ink extents of all glyphs in the buffer.
+    ///
+    /// The extents of every glyph are obtained with
+    /// [`Font::get_glyph_extents()`] and placed at the glyph origin, which is
+    /// computed by accumulating the advances of the preceding glyphs starting
+    /// at `(0, 0)` and adding the glyph offsets. `font` should be the font the
+    /// buffer was shaped with. If no glyph has any ink, an empty `Rect` is
+    /// returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    /// # use std::path::PathBuf;
+    /// # let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    /// # path.push("testfiles/SourceSansVariable-Roman.ttf");
+    /// let face = Face::from_file(path, 0).expect("Error reading font file.");
+    /// let font = Font::new(face);
+    ///
+    /// let buffer = shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]);
+    /// let ink = buffer.ink_extents(&font);
+    /// assert!(ink.x_min >= 0);
+    /// assert!(ink.y_max > 0);
+    /// ```
+    pub fn ink_extents(&self, font: &(impl GlyphMetricsSource + ?Sized)) -> Rect {
+        self.union_glyph_bounds(|glyph| {
+            font.glyph_extents(glyph)
+                .map(|extents| Rect::from_glyph_extents(&extents))
+        })
+    }
+
+    /// Returns the union of the outline bounds of all glyphs in the buffer.
+    ///
+    /// This works like [`Self::ink_extents()`] but uses
+    /// [`Font::get_glyph_outline_extents()`], which draws every glyph to
+    /// compute the exact bounds of its outline.
+    pub fn outline_ink_extents(&self, font: &Font<'_>) -> Rect {
+        self.union_glyph_bounds(|glyph| font.get_glyph_outline_extents(glyph))
+    }
+
+    fn union_glyph_bounds(&self, mut glyph_bounds: impl FnMut(Glyph) -> Option<Rect>) -> Rect {
+        let mut ink = Rect::default();
+        let (mut pen_x, mut pen_y) = (0, 0);
+        for (info, pos) in self
+            .get_glyph_infos()
+            .iter()
+            .zip(self.get_glyph_positions())
+        {
+            if let Some(bounds) = glyph_bounds(info.codepoint) {
+                let bounds = bounds.translate(pen_x + pos.x_offset, pen_y + pos.y_offset);
+                ink = ink.union(&bounds);
+            }
+            pen_x += pos.x_advance;
+            pen_y += pos.y_advance;
+        }
+        ink
+    }
+
+    /// Partitions the glyphs of the buffer into draw batches.
+    ///
+    /// `batch_key` is called for every glyph and returns the key of the batch
+    /// the glyph belongs to, e.g. the texture atlas page it was rasterized to
+    /// or whether it has to be drawn by the color or the monochrome glyph
+    /// pipeline. Every batch contains the glyphs with the same key.
+    ///
+    /// The batches are ordered by the first appearance of their key in the
+    /// buffer and the glyphs in each batch keep the visual order of the buffer.
+    /// Every glyph records its index into the buffer (and therefore into
+    /// [`Self::get_glyph_infos()`]) and its cluster, so it can still be mapped
+    /// back to the text after batching. The glyph origins are computed by
+    /// accumulating the advances of the preceding glyphs starting at `(0, 0)`
+    /// and adding the glyph offsets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    /// # use std::path::PathBuf;
+    /// # let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    /// # path.push("testfiles/SourceSansVariable-Roman.ttf");
+    /// let face = Face::from_file(path, 0).expect("Error reading font file.");
+    /// let font = Font::new(face);
+    /// let space = font.get_nominal_glyph(' ').unwrap();
+    ///
+    /// let buffer = shape(&font, UnicodeBuffer::new().add_str("a b c"), &[]);
+    /// let batches = buffer.batch_by(|glyph| glyph == space);
+    ///
+    /// assert_eq!(batches.len(), 2);
+    /// assert_eq!(batches[0].key, false);
+    /// assert_eq!(batches[0].glyphs.len(), 3);
+    /// assert_eq!(batches[1].glyphs.len(), 2);
+    /// ```
+    pub fn batch_by<K, F>(&self, mut batch_key: F) -> Vec<GlyphBatch<K>>
+    where
+        K: PartialEq,
+        F: FnMut(Glyph) -> K,
+    {
+        let mut batches: Vec<GlyphBatch<K>> = Vec::new();
+        let (mut pen_x, mut pen_y) = (0, 0);
+        let infos = self.get_glyph_infos();
+        let positions = self.get_glyph_positions();
+        for (index, (info, pos)) in infos.iter().zip(positions).enumerate() {
+            let glyph = BatchedGlyph {
+                index,
+                glyph: info.codepoint,
+                cluster: info.cluster,
+                x: pen_x + pos.x_offset,
+                y: pen_y + pos.y_offset,
+            };
+            pen_x += pos.x_advance;
+            pen_y += pos.y_advance;
+
+            let key = batch_key(info.codepoint);
+            match batches.iter_mut().find(|batch| batch.key == key) {
+                Some(batch) => batch.glyphs.push(glyph),
+                None => batches.push(GlyphBatch {
+                    key,
+                    glyphs: vec![glyph],
+                }),
+            }
+        }
+        batches
+    }
+
     /// Clears the contents of the glyph buffer and returns an empty
     /// `UnicodeBuffer` reusing the existing allocation.
     pub fn clear(mut self) -> UnicodeBuffer {
@@ -866,6 +3408,14 @@ impl GlyphBuffer {
         UnicodeBuffer::from_generic(self.0)
     }
 
+    /// Resets the glyph buffer to the state of a freshly created buffer (see
+    /// [`UnicodeBuffer::reset()`]) and returns it as an empty `UnicodeBuffer`
+    /// reusing the existing allocation.
+    pub fn reset(mut self) -> UnicodeBuffer {
+        self.0.reset();
+        UnicodeBuffer::from_generic(self.0)
+    }
+
     /// Returns a serializer that allows the contents of the buffer to be
     /// converted into a human or machine readable representation.
     ///
@@ -917,9 +3467,306 @@ impl GlyphBuffer {
             end: self.len(),
             format,
             flags,
+            cluster_spans: None,
             bytes: io::Cursor::new(Vec::with_capacity(128)),
         }
     }
+
+    /// Returns an object that implements `Display` by serializing the buffer
+    /// contents with the given `font`, `format` and `flags`.
+    ///
+    /// This is handy in logging and assertion messages. The `Display`
+    /// implementation of `GlyphBuffer` itself uses the text format without a
+    /// font, so it cannot show glyph names.
+    ///
+    /// See [`Self::serializer()`] for a description of the arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    /// # use std::path::PathBuf;
+    /// # let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    /// # path.push("testfiles/SourceSansVariable-Roman.ttf");
+    /// let face = Face::from_file(path, 0).expect("Error reading font file.");
+    /// let font = Font::new(face);
+    ///
+    /// let buffer = shape(&font, UnicodeBuffer::new().add_str("AB"), &[]);
+    /// let display = buffer.display_with(
+    ///     Some(&font),
+    ///     SerializeFormat::Text,
+    ///     SerializeFlags::NO_POSITIONS,
+    /// );
+    /// assert_eq!(buffer.len(), 2, "unexpected shaping result {}", display);
+    /// ```
+    pub fn display_with<'a>(
+        &'a self,
+        font: Option<&'a crate::Font<'a>>,
+        format: SerializeFormat,
+        flags: SerializeFlags,
+    ) -> GlyphBufferDisplay<'a> {
+        GlyphBufferDisplay {
+            buffer: self,
+            font,
+            format,
+            flags,
+        }
+    }
+}
+
+/// Helper struct for displaying a `GlyphBuffer` with a custom serialization
+/// format.
+///
+/// This is obtained through the `GlyphBuffer::display_with` method.
+#[derive(Debug, Copy, Clone)]
+pub struct GlyphBufferDisplay<'a> {
+    buffer: &'a GlyphBuffer,
+    font: Option<&'a crate::Font<'a>>,
+    format: SerializeFormat,
+    flags: SerializeFlags,
+}
+
+impl<'a> fmt::Display for GlyphBufferDisplay<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut serializer = self.buffer.serializer(self.font, self.format, self.flags);
+        let mut string = String::new();
+        serializer
+            .read_to_string(&mut string)
+            .map_err(|_| fmt::Error)?;
+        write!(fmt, "{}", string)
+    }
+}
+
+/// A group of glyphs that can be drawn together.
+///
+/// This is obtained through the `GlyphBuffer::batch_by` method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlyphBatch<K> {
+    /// The key that all glyphs in this batch share.
+    pub key: K,
+    /// The glyphs of the batch in visual order.
+    pub glyphs: Vec<BatchedGlyph>,
+}
+
+/// A positioned glyph that is part of a `GlyphBatch`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BatchedGlyph {
+    /// The index of the glyph in the `GlyphBuffer`.
+    pub index: usize,
+    /// The glyph id.
+    pub glyph: Glyph,
+    /// The cluster the glyph belongs to.
+    pub cluster: u32,
+    /// The x coordinate of the glyph origin.
+    pub x: Position,
+    /// The y coordinate of the glyph origin.
+    pub y: Position,
+}
+
+/// An iterator over the clusters of a `GlyphBuffer`.
+///
+/// This is obtained through the `GlyphBuffer::cluster_runs` method.
+#[derive(Debug, Clone)]
+pub struct ClusterRuns<'a> {
+    infos: &'a [GlyphInfo],
+    positions: &'a [GlyphPosition],
+}
+
+impl<'a> Iterator for ClusterRuns<'a> {
+    type Item = (u32, &'a [GlyphInfo], &'a [GlyphPosition]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cluster = self.infos.first()?.cluster;
+        let len = self
+            .infos
+            .iter()
+            .position(|info| info.cluster != cluster)
+            .unwrap_or(self.infos.len());
+        let (infos, rest_infos) = self.infos.split_at(len);
+        let (positions, rest_positions) = self.positions.split_at(len.min(self.positions.len()));
+        self.infos = rest_infos;
+        self.positions = rest_positions;
+        Some((cluster, infos, positions))
+    }
+}
+
+impl<'a> std::iter::FusedIterator for ClusterRuns<'a> {}
+
+/// A shaped glyph combining the `GlyphInfo` and `GlyphPosition` of a glyph.
+///
+/// This is obtained by iterating over a `GlyphBuffer`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ShapedGlyph {
+    /// The glyph id.
+    pub gid: Glyph,
+    /// The cluster the glyph belongs to.
+    pub cluster: u32,
+    /// How much the line advances after drawing this glyph in horizontal
+    /// direction.
+    pub x_advance: Position,
+    /// How much the line advances after drawing this glyph in vertical
+    /// direction.
+    pub y_advance: Position,
+    /// How much the glyph moves on the X-axis before drawing it.
+    pub x_offset: Position,
+    /// How much the glyph moves on the Y-axis before drawing it.
+    pub y_offset: Position,
+    /// The glyph flags set during shaping.
+    pub flags: GlyphFlags,
+}
+
+/// The glyphs of a [`GlyphBuffer`] as a structure of arrays.
+///
+/// All arrays have the same length and the entries with the same index belong
+/// to the same glyph. This is obtained through the `GlyphBuffer::to_arrays`
+/// and `GlyphBuffer::write_arrays` methods.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GlyphArrays {
+    /// The glyph ids.
+    pub glyph_ids: Vec<Glyph>,
+    /// The clusters of the glyphs.
+    pub clusters: Vec<u32>,
+    /// The horizontal advances of the glyphs.
+    pub x_advances: Vec<Position>,
+    /// The vertical advances of the glyphs.
+    pub y_advances: Vec<Position>,
+    /// The horizontal offsets of the glyphs.
+    pub x_offsets: Vec<Position>,
+    /// The vertical offsets of the glyphs.
+    pub y_offsets: Vec<Position>,
+}
+
+impl GlyphArrays {
+    /// Returns the number of glyphs.
+    pub fn len(&self) -> usize {
+        self.glyph_ids.len()
+    }
+
+    /// Returns `true` if there are no glyphs.
+    pub fn is_empty(&self) -> bool {
+        self.glyph_ids.is_empty()
+    }
+
+    /// Removes all glyphs, keeping the allocated memory.
+    pub fn clear(&mut self) {
+        self.glyph_ids.clear();
+        self.clusters.clear();
+        self.x_advances.clear();
+        self.y_advances.clear();
+        self.x_offsets.clear();
+        self.y_offsets.clear();
+    }
+}
+
+/// An iterator over the glyphs of a `GlyphBuffer` yielding `ShapedGlyph`s.
+///
+/// This is obtained through the `GlyphBuffer::iter` method.
+#[derive(Debug, Clone)]
+pub struct ShapedGlyphs<'a> {
+    infos: std::slice::Iter<'a, GlyphInfo>,
+    positions: std::slice::Iter<'a, GlyphPosition>,
+}
+
+fn shaped_glyph(info: &GlyphInfo, pos: &GlyphPosition) -> ShapedGlyph {
+    ShapedGlyph {
+        gid: info.codepoint,
+        cluster: info.cluster,
+        x_advance: pos.x_advance,
+        y_advance: pos.y_advance,
+        x_offset: pos.x_offset,
+        y_offset: pos.y_offset,
+        flags: info.glyph_flags(),
+    }
+}
+
+impl<'a> Iterator for ShapedGlyphs<'a> {
+    type Item = ShapedGlyph;
+
+    fn next(&mut self) -> Option<ShapedGlyph> {
+        Some(shaped_glyph(self.infos.next()?, self.positions.next()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a> DoubleEndedIterator for ShapedGlyphs<'a> {
+    fn next_back(&mut self) -> Option<ShapedGlyph> {
+        Some(shaped_glyph(
+            self.infos.next_back()?,
+            self.positions.next_back()?,
+        ))
+    }
+}
+
+impl<'a> ExactSizeIterator for ShapedGlyphs<'a> {
+    fn len(&self) -> usize {
+        self.infos.len().min(self.positions.len())
+    }
+}
+
+impl<'a> IntoIterator for &'a GlyphBuffer {
+    type Item = ShapedGlyph;
+    type IntoIter = ShapedGlyphs<'a>;
+
+    fn into_iter(self) -> ShapedGlyphs<'a> {
+        self.iter()
+    }
+}
+
+/// The raw buffer is expected to contain glyphs, e.g. because it was shaped
+/// with `hb_shape`.
+impl FromRaw<*mut hb_buffer_t> for GlyphBuffer {
+    unsafe fn from_raw(raw: *mut hb_buffer_t) -> Self {
+        GlyphBuffer(Owned::from_raw(raw))
+    }
+}
+
+impl IntoRaw<*mut hb_buffer_t> for GlyphBuffer {
+    fn into_raw(self) -> *mut hb_buffer_t {
+        Owned::into_raw(self.0)
+    }
+}
+
+impl Default for GlyphBuffer {
+    fn default() -> GlyphBuffer {
+        GlyphBuffer::new()
+    }
+}
+
+/// Adds `(info, position)` pairs to the buffer.
+impl Extend<(GlyphInfo, GlyphPosition)> for GlyphBuffer {
+    fn extend<T: IntoIterator<Item = (GlyphInfo, GlyphPosition)>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        let (lower_bound, _) = iter.size_hint();
+        let new_len = self.len().saturating_add(lower_bound);
+        self.0.pre_allocate(new_len);
+        for (info, position) in iter {
+            self.push(info, position);
+        }
+    }
+}
+
+/// Creates a buffer from `(info, position)` pairs.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let buffer: GlyphBuffer = (0..3)
+///     .map(|i| (GlyphInfo::new(10 + i, i), GlyphPosition::new(500, 0, 0, 0)))
+///     .collect();
+/// assert_eq!(buffer.iter().map(|glyph| glyph.x_advance).sum::<i32>(), 1500);
+/// ```
+impl std::iter::FromIterator<(GlyphInfo, GlyphPosition)> for GlyphBuffer {
+    fn from_iter<T: IntoIterator<Item = (GlyphInfo, GlyphPosition)>>(iter: T) -> GlyphBuffer {
+        let mut buffer = GlyphBuffer::new();
+        buffer.extend(iter);
+        buffer
+    }
 }
 
 impl fmt::Debug for GlyphBuffer {
@@ -933,12 +3780,8 @@ impl fmt::Debug for GlyphBuffer {
 
 impl fmt::Display for GlyphBuffer {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut serializer =
-            self.serializer(None, SerializeFormat::Text, SerializeFlags::default());
-        let mut string = String::new();
-        serializer.read_to_string(&mut string).unwrap();
-        write!(fmt, "{}", string)?;
-        Ok(())
+        self.display_with(None, SerializeFormat::Text, SerializeFlags::default())
+            .fmt(fmt)
     }
 }
 
@@ -1037,4 +3880,878 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_serialization_with_cluster_spans() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = Face::from_file(path, 0).unwrap();
+        let font = Font::new(face);
+        let text = "a\u{301}bc";
+        let buffer = UnicodeBuffer::new()
+            .add_str(text)
+            .set_direction(Direction::Rtl);
+        let glyph_buffer = shape(&font, buffer, &[]);
+
+        let serialize = |format| {
+            let mut string = String::new();
+            glyph_buffer
+                .serializer(Some(&font), format, SerializeFlags::NO_POSITIONS)
+                .with_cluster_spans(text)
+                .read_to_string(&mut string)
+                .unwrap();
+            string
+        };
+        let spans: Vec<String> = serialize(SerializeFormat::Text)
+            .split('{')
+            .skip(1)
+            .map(|item| item[..item.find('}').unwrap()].to_string())
+            .collect();
+        let expected = if glyph_buffer.len() == 4 {
+            vec!["4..5", "3..4", "0..3", "0..3"]
+        } else {
+            vec!["4..5", "3..4", "0..3"]
+        };
+        assert_eq!(spans, expected);
+
+        let json = serialize(SerializeFormat::Json);
+        assert!(json.starts_with("[{") && json.ends_with(",\"span\":[0,3]}]"));
+        assert_eq!(json.matches("\"span\"").count(), glyph_buffer.len());
+
+        // clusters beyond the end of the text get empty spans
+        let mut string = String::new();
+        glyph_buffer
+            .serializer(None, SerializeFormat::Text, SerializeFlags::NO_POSITIONS)
+            .with_cluster_spans("a")
+            .read_to_string(&mut string)
+            .unwrap();
+        let spans: Vec<&str> = string
+            .split('{')
+            .skip(1)
+            .map(|item| &item[..item.find('}').unwrap()])
+            .collect();
+        assert_eq!(&spans[..3], &["4..4", "3..4", "0..3"]);
+    }
+
+    #[test]
+    fn test_invisible_glyph() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = Face::from_file(path, 0).unwrap();
+        let font = Font::new(face);
+        let invisible = font.get_nominal_glyph('X').unwrap();
+
+        // U+2060 WORD JOINER is default-ignorable and not removed from the output
+        let buffer = UnicodeBuffer::new().add_str("A\u{2060}B");
+        let glyph_buffer = shape(&font, buffer, &[]);
+        assert_ne!(glyph_buffer.get_glyph_infos()[1].codepoint, invisible);
+
+        let buffer = glyph_buffer
+            .clear()
+            .add_str("A\u{2060}B")
+            .set_invisible_glyph(invisible);
+        let glyph_buffer = shape(&font, buffer, &[]);
+        assert_eq!(glyph_buffer.get_glyph_infos()[1].codepoint, invisible);
+        assert_eq!(glyph_buffer.get_glyph_positions()[1].x_advance, 0);
+    }
+
+    #[test]
+    fn test_not_found_glyph() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = Face::from_file(path, 0).unwrap();
+        let font = Font::new(face);
+        let tofu = font.get_nominal_glyph('X').unwrap();
+
+        // U+E000 is a private use character which the font does not contain
+        let buffer = UnicodeBuffer::new().add_str("A\u{E000}");
+        let glyph_buffer = shape(&font, buffer, &[]);
+        assert_eq!(glyph_buffer.get_glyph_infos()[1].codepoint, 0);
+
+        let buffer = glyph_buffer
+            .clear()
+            .add_str("A\u{E000}")
+            .set_not_found_glyph(tofu);
+        let glyph_buffer = shape(&font, buffer, &[]);
+        assert_eq!(glyph_buffer.get_glyph_infos()[1].codepoint, tofu);
+    }
+
+    #[test]
+    fn test_replacement_codepoint() {
+        let invalid_utf8 = b"a\xFFb";
+        let add_invalid_utf8 = |buffer: &mut UnicodeBuffer| unsafe {
+            crate::bindings::hb_buffer_add_utf8(
+                buffer.0.as_raw(),
+                invalid_utf8.as_ptr() as *const _,
+                invalid_utf8.len() as i32,
+                0,
+                -1,
+            )
+        };
+
+        let mut buffer = UnicodeBuffer::new();
+        add_invalid_utf8(&mut buffer);
+        assert_eq!(buffer.string_lossy(), "a\u{FFFD}b");
+
+        let mut buffer = UnicodeBuffer::new().set_replacement_codepoint('?' as u32);
+        add_invalid_utf8(&mut buffer);
+        assert_eq!(buffer.string_lossy(), "a?b");
+    }
+
+    #[test]
+    fn test_add_utf16_clusters() {
+        let text: Vec<u16> = "a😍b".encode_utf16().collect();
+        let buffer = UnicodeBuffer::new().add_utf16(&text);
+        let clusters: Vec<u32> = buffer
+            .0
+            .get_glyph_infos()
+            .iter()
+            .map(|i| i.cluster)
+            .collect();
+        assert_eq!(buffer.string_lossy(), "a😍b");
+        assert_eq!(clusters, vec![0, 1, 3]);
+
+        // unpaired surrogate
+        let buffer = UnicodeBuffer::new()
+            .set_replacement_codepoint('?' as u32)
+            .add_utf16(&[0x61, 0xD800, 0x62]);
+        assert_eq!(buffer.string_lossy(), "a?b");
+    }
+
+    #[test]
+    fn test_display_with() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = Face::from_file(path, 0).unwrap();
+        let font = Font::new(face);
+        let glyph_buffer = shape(&font, UnicodeBuffer::new().add_str("AB"), &[]);
+
+        let flags = SerializeFlags::NO_POSITIONS | SerializeFlags::NO_GLYPH_NAMES;
+        let infos = glyph_buffer.get_glyph_infos();
+        assert_eq!(
+            glyph_buffer
+                .display_with(Some(&font), SerializeFormat::Text, flags)
+                .to_string(),
+            format!("[{}=0|{}=1]", infos[0].codepoint, infos[1].codepoint)
+        );
+        assert_eq!(
+            glyph_buffer.to_string(),
+            glyph_buffer
+                .display_with(None, SerializeFormat::Text, SerializeFlags::default())
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_add_utf32_clusters() {
+        let text: Vec<char> = "a😍b".chars().collect();
+        let buffer = UnicodeBuffer::new().add_chars(&text);
+        let clusters: Vec<u32> = buffer
+            .0
+            .get_glyph_infos()
+            .iter()
+            .map(|i| i.cluster)
+            .collect();
+        assert_eq!(buffer.string_lossy(), "a😍b");
+        assert_eq!(clusters, vec![0, 1, 2]);
+
+        // surrogates and values beyond U+10FFFF are invalid
+        let buffer = UnicodeBuffer::new()
+            .set_replacement_codepoint('?' as u32)
+            .add_utf32(&[0x61, 0xD800, 0x110000, 0x62]);
+        assert_eq!(buffer.string_lossy(), "a??b");
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = Face::from_file(path, 0).unwrap();
+        let mut font = Font::new(face);
+
+        let hello = shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]);
+        let world = shape(&font, UnicodeBuffer::new().add_str("World"), &[]);
+        assert_ne!(hello.content_hash(), world.content_hash());
+
+        // only positions changed
+        font.set_scale(2000, 2000);
+        let scaled = shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]);
+        assert_ne!(hello.content_hash(), scaled.content_hash());
+
+        let empty = shape(&font, UnicodeBuffer::new(), &[]);
+        assert_eq!(empty.content_hash(), 0xcbf2_9ce4_8422_2325);
+    }
+
+    #[test]
+    fn test_batch_by() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = Face::from_file(path, 0).unwrap();
+        let font = Font::new(face);
+        let glyph_buffer = shape(&font, UnicodeBuffer::new().add_str("abab"), &[]);
+        let a = font.get_nominal_glyph('a').unwrap();
+
+        let batches = glyph_buffer.batch_by(|glyph| glyph == a);
+        assert_eq!(batches.len(), 2);
+        assert!(batches[0].key);
+        assert!(!batches[1].key);
+
+        let indices: Vec<Vec<usize>> = batches
+            .iter()
+            .map(|batch| batch.glyphs.iter().map(|glyph| glyph.index).collect())
+            .collect();
+        assert_eq!(indices, vec![vec![0, 2], vec![1, 3]]);
+
+        let positions = glyph_buffer.get_glyph_positions();
+        let b = batches[1].glyphs[0];
+        assert_eq!(b.cluster, 1);
+        assert_eq!(b.x, positions[0].x_advance + positions[1].x_offset);
+
+        // right-to-left runs are batched in visual order as well
+        let rtl = shape(
+            &font,
+            UnicodeBuffer::new()
+                .add_str("abab")
+                .set_direction(Direction::Rtl),
+            &[],
+        );
+        let batches = rtl.batch_by(|glyph| glyph == a);
+        assert!(!batches[0].key);
+        let clusters: Vec<u32> = batches[0].glyphs.iter().map(|g| g.cluster).collect();
+        assert_eq!(clusters, vec![3, 1]);
+    }
+
+    #[test]
+    fn test_ink_extents() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = Face::from_file(path, 0).unwrap();
+        let font = Font::new(face);
+
+        let o = font.get_nominal_glyph('o').unwrap();
+        let o_extents = Rect::from_glyph_extents(&font.get_glyph_extents(o).unwrap());
+        let o_advance = font.get_glyph_h_advance(o);
+        let buffer = shape(&font, UnicodeBuffer::new().add_str("o o"), &[]);
+        let space_advance = buffer.get_glyph_positions()[1].x_advance;
+
+        let ink = buffer.ink_extents(&font);
+        assert_eq!(ink.x_min, o_extents.x_min);
+        assert_eq!(ink.x_max, o_extents.x_max + o_advance + space_advance);
+        assert_eq!((ink.y_min, ink.y_max), (o_extents.y_min, o_extents.y_max));
+
+        // the outline bounds of an `o` touch the extrema of its curves
+        let outline = buffer.outline_ink_extents(&font);
+        assert!((outline.x_min - ink.x_min).abs() <= 1);
+        assert!((outline.x_max - ink.x_max).abs() <= 1);
+        assert!((outline.y_min - ink.y_min).abs() <= 1);
+        assert!((outline.y_max - ink.y_max).abs() <= 1);
+
+        let spaces = shape(&font, UnicodeBuffer::new().add_str("  "), &[]);
+        assert!(spaces.ink_extents(&font).is_empty());
+        assert!(spaces.outline_ink_extents(&font).is_empty());
+    }
+
+    #[test]
+    fn test_glyph_masks() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = Face::from_file(path, 0).unwrap();
+        let font = Font::new(face);
+        let mut glyph_buffer = shape(&font, UnicodeBuffer::new().add_str("ab"), &[]);
+
+        const CUSTOM_BIT: hb_mask_t = 1 << 31;
+        for info in glyph_buffer.get_glyph_infos_mut() {
+            info.set_mask_bits(CUSTOM_BIT, false);
+        }
+        let info = &mut glyph_buffer.get_glyph_infos_mut()[1];
+        let flags = info.glyph_flags();
+        info.set_mask_bits(CUSTOM_BIT, true);
+        assert!(info.has_mask_bits(CUSTOM_BIT));
+        assert_eq!(info.glyph_flags(), flags);
+
+        let marked: Vec<usize> = (0..glyph_buffer.len())
+            .filter(|&i| glyph_buffer.get_glyph_infos()[i].has_mask_bits(CUSTOM_BIT))
+            .collect();
+        assert_eq!(marked, vec![1]);
+
+        let info = &mut glyph_buffer.get_glyph_infos_mut()[1];
+        info.set_mask_bits(CUSTOM_BIT, false);
+        assert_eq!(info.mask() & CUSTOM_BIT, 0);
+        info.set_mask(HB_GLYPH_FLAG_UNSAFE_TO_BREAK);
+        assert!(info.glyph_flags().unsafe_to_break());
+    }
+
+    #[test]
+    fn test_glyph_flags() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = Face::from_file(path, 0).unwrap();
+        let font = Font::new(face);
+
+        // the kerning between `A` and `V` makes the boundary unsafe to break
+        let buffer = UnicodeBuffer::new()
+            .add_str("AV")
+            .set_flags(BufferFlags::PRODUCE_UNSAFE_TO_CONCAT);
+        let glyph_buffer = shape(&font, buffer, &[]);
+        let flags = glyph_buffer.get_glyph_infos()[1].glyph_flags();
+        assert!(flags.unsafe_to_break());
+        assert!(flags.unsafe_to_concat());
+        assert!(!flags.safe_to_insert_tatweel());
+        assert_eq!(
+            flags.bits(),
+            HB_GLYPH_FLAG_UNSAFE_TO_BREAK | HB_GLYPH_FLAG_UNSAFE_TO_CONCAT
+        );
+    }
+
+    #[test]
+    fn test_truncate_and_resize() {
+        let buffer = UnicodeBuffer::new().add_str("abc").truncate(5);
+        assert_eq!(buffer.string_lossy(), "abc");
+        let buffer = buffer.resize(4);
+        assert_eq!(
+            buffer.codepoints().collect::<Vec<_>>(),
+            vec![0x61, 0x62, 0x63, 0]
+        );
+
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = Face::from_file(path, 0).unwrap();
+        let font = Font::new(face);
+        let mut glyph_buffer = shape(&font, UnicodeBuffer::new().add_str("abc"), &[]);
+        let first_two: Vec<u32> = glyph_buffer.get_glyph_infos()[..2]
+            .iter()
+            .map(|info| info.codepoint)
+            .collect();
+        glyph_buffer.truncate(2);
+        assert_eq!(glyph_buffer.len(), 2);
+        assert_eq!(glyph_buffer.get_glyph_positions().len(), 2);
+        let glyphs: Vec<u32> = glyph_buffer
+            .get_glyph_infos()
+            .iter()
+            .map(|info| info.codepoint)
+            .collect();
+        assert_eq!(glyphs, first_two);
+
+        glyph_buffer.resize(3);
+        assert_eq!(glyph_buffer.get_glyph_infos()[2].codepoint, 0);
+        assert_eq!(glyph_buffer.get_glyph_positions()[2].x_advance, 0);
+    }
+
+    #[test]
+    fn test_cluster_runs() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+
+        let output = shape(&font, UnicodeBuffer::new().add_str("ae\u{301}b"), &[]);
+        let runs: Vec<_> = output.cluster_runs().collect();
+        let clusters: Vec<u32> = runs.iter().map(|run| run.0).collect();
+        assert_eq!(clusters, vec![0, 1, 4]);
+        let glyph_count: usize = runs.iter().map(|run| run.1.len()).sum();
+        assert_eq!(glyph_count, output.len());
+        assert!(runs.iter().all(|run| run.1.len() == run.2.len()));
+
+        // right-to-left text is returned in visual order
+        let output = shape(
+            &font,
+            UnicodeBuffer::new()
+                .add_str("abc")
+                .set_direction(Direction::Rtl),
+            &[],
+        );
+        let clusters: Vec<u32> = output.cluster_runs().map(|run| run.0).collect();
+        assert_eq!(clusters, vec![2, 1, 0]);
+
+        let empty = shape(&font, UnicodeBuffer::new(), &[]);
+        assert_eq!(empty.cluster_runs().count(), 0);
+    }
+
+    #[test]
+    fn test_shaped_glyphs() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let output = shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]);
+
+        let glyphs: Vec<ShapedGlyph> = output.iter().collect();
+        assert_eq!(glyphs.len(), output.len());
+        for ((glyph, info), pos) in glyphs
+            .iter()
+            .zip(output.get_glyph_infos())
+            .zip(output.get_glyph_positions())
+        {
+            assert_eq!(glyph.gid, info.codepoint);
+            assert_eq!(glyph.cluster, info.cluster);
+            assert_eq!(glyph.x_advance, pos.x_advance);
+            assert_eq!(glyph.flags, info.glyph_flags());
+        }
+        let reversed: Vec<ShapedGlyph> = output.iter().rev().collect();
+        assert_eq!(reversed.first(), glyphs.last());
+        assert_eq!(output.iter().len(), 5);
+    }
+
+    #[test]
+    fn test_buffers_are_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<UnicodeBuffer>();
+        assert_send::<GlyphBuffer>();
+        assert_send::<TypedBuffer>();
+
+        let buffer = UnicodeBuffer::new().add_str("Hello");
+        let glyphs = std::thread::spawn(move || {
+            let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+            let font = Font::new(face);
+            shape(&font, buffer, &[])
+        })
+        .join()
+        .unwrap();
+        assert_eq!(glyphs.len(), 5);
+
+        let buffer = std::thread::spawn(move || glyphs.clear()).join().unwrap();
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_segment_properties_hash() {
+        use std::collections::HashSet;
+
+        let latin = UnicodeBuffer::new()
+            .add_str("abc")
+            .guess_segment_properties()
+            .get_segment_properties();
+        let arabic = UnicodeBuffer::new()
+            .add_str("\u{0627}")
+            .guess_segment_properties()
+            .get_segment_properties();
+        let set: HashSet<SegmentProperties> = vec![latin, arabic, latin].into_iter().collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&latin) && set.contains(&arabic));
+    }
+
+    #[test]
+    fn test_clone() {
+        let buffer = UnicodeBuffer::new()
+            .add_str("Hello")
+            .guess_segment_properties()
+            .set_cluster_level(ClusterLevel::Characters)
+            .set_flags(BufferFlags::EOT);
+        let copy = buffer.clone();
+        let buffer = buffer.add_str("!");
+        assert_eq!(copy.string_lossy(), "Hello");
+        assert_eq!(buffer.string_lossy(), "Hello!");
+        assert_eq!(
+            copy.get_segment_properties(),
+            buffer.get_segment_properties()
+        );
+        assert_eq!(copy.get_cluster_level(), ClusterLevel::Characters);
+        assert_eq!(copy.get_flags(), BufferFlags::EOT);
+
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let glyphs = shape(&font, copy, &[]);
+        let glyphs_copy = glyphs.clone();
+        assert_eq!(glyphs_copy.len(), 5);
+        assert_eq!(glyphs_copy.content_hash(), glyphs.content_hash());
+    }
+
+    #[test]
+    fn test_allocation_failure() {
+        let mut buffer = UnicodeBuffer::new().add_str("abc");
+        assert!(buffer.check_allocation().is_ok());
+        assert!(buffer.try_pre_allocate(u32::MAX as usize).is_err());
+        let buffer = buffer.add_str("def");
+        assert_eq!(buffer.check_allocation(), Err(AllocationError));
+
+        let buffer = buffer.clear_contents().add_str("def");
+        assert!(buffer.check_allocation().is_ok());
+        assert_eq!(buffer.string_lossy(), "def");
+    }
+
+    #[test]
+    fn test_reset_and_clear_contents() {
+        let setup = || {
+            UnicodeBuffer::new()
+                .add_str("abc")
+                .set_script(Tag::new('A', 'r', 'a', 'b'))
+                .set_language("ar".parse::<Language>().unwrap())
+                .set_cluster_level(ClusterLevel::Characters)
+                .set_flags(BufferFlags::BOT)
+        };
+
+        let fresh = UnicodeBuffer::new();
+        let cleared = setup().clear_contents();
+        assert!(cleared.is_empty());
+        assert_eq!(cleared.get_script(), fresh.get_script());
+        assert_eq!(cleared.get_cluster_level(), ClusterLevel::Characters);
+        assert_eq!(cleared.get_flags(), BufferFlags::BOT);
+
+        let reset = setup().reset();
+        assert!(reset.is_empty());
+        assert_eq!(reset.get_script(), fresh.get_script());
+        assert_eq!(reset.get_language(), fresh.get_language());
+        assert_eq!(reset.get_cluster_level(), ClusterLevel::MonotoneGraphemes);
+        assert_eq!(reset.get_flags(), BufferFlags::empty());
+    }
+
+    #[test]
+    fn test_glyph_arrays() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = crate::Face::from_file(path, 0).unwrap();
+        let font = Font::new(face);
+        let output = crate::shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]);
+
+        let mut arrays =
+            crate::shape(&font, UnicodeBuffer::new().add_str("World!"), &[]).to_arrays();
+        output.write_arrays(&mut arrays);
+        assert_eq!(arrays.len(), output.len());
+        for (i, glyph) in output.iter().enumerate() {
+            assert_eq!(arrays.glyph_ids[i], glyph.gid);
+            assert_eq!(arrays.clusters[i], glyph.cluster);
+            assert_eq!(arrays.x_advances[i], glyph.x_advance);
+            assert_eq!(arrays.y_advances[i], glyph.y_advance);
+            assert_eq!(arrays.x_offsets[i], glyph.x_offset);
+            assert_eq!(arrays.y_offsets[i], glyph.y_offset);
+        }
+        assert!(output.clusters().eq(0..5));
+        assert_eq!(output.y_offsets().len(), 5);
+        assert!(GlyphBuffer::new().to_arrays().is_empty());
+    }
+
+    #[test]
+    fn test_manual_glyph_buffer() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = crate::Face::from_file(path, 0).unwrap();
+        let font = Font::new(face);
+        let mut output = crate::shape(&font, UnicodeBuffer::new().add_str("ab"), &[]);
+        let hyphen = font.get_nominal_glyph('-').unwrap();
+        let advance = font.get_glyph_h_advance(hyphen);
+
+        output.push(
+            GlyphInfo::new(hyphen, 2),
+            GlyphPosition::new(advance, 0, 0, 0),
+        );
+        assert_eq!(output.len(), 3);
+        let glyph = output.iter().next_back().unwrap();
+        assert_eq!(
+            (glyph.gid, glyph.cluster, glyph.x_advance),
+            (hyphen, 2, advance)
+        );
+        // the positions of the shaped glyphs are kept
+        assert!(output.get_glyph_positions()[0].x_advance > 0);
+        let text = output.to_string();
+        assert!(text.ends_with(&format!("|gid{}=2+{}]", hyphen, advance)));
+
+        let mut manual: GlyphBuffer = output
+            .get_glyph_infos()
+            .iter()
+            .copied()
+            .zip(output.get_glyph_positions().iter().copied())
+            .collect();
+        manual.set_segment_properties(output.get_segment_properties());
+        assert_eq!(manual.content_hash(), output.content_hash());
+        assert_eq!(manual.to_string(), output.to_string());
+
+        manual.truncate(0);
+        manual.push(GlyphInfo::new(1, 0), GlyphPosition::new(10, 0, 0, 0));
+        assert_eq!(manual.get_glyph_positions()[0].x_advance, 10);
+        assert!(GlyphBuffer::default().is_empty());
+    }
+
+    #[test]
+    fn test_has_positions() {
+        let empty = GenericBuffer::new();
+        assert_eq!(
+            ContentType::from_raw(empty.content_type()),
+            ContentType::Invalid
+        );
+        assert!(!empty.has_positions());
+        assert!(GlyphBuffer::new().has_positions());
+
+        // glyphs from foreign code that were not positioned
+        let raw = UnicodeBuffer::new().add_str("ab").into_raw();
+        unsafe { hb_buffer_set_content_type(raw, HB_BUFFER_CONTENT_TYPE_GLYPHS) };
+        let glyphs = match unsafe { TypedBuffer::take_from_raw(raw) } {
+            Some(TypedBuffer::Glyphs(glyphs)) => glyphs,
+            _ => panic!("expected a glyph buffer"),
+        };
+        assert_eq!(glyphs.content_type(), ContentType::Glyphs);
+        assert!(!glyphs.has_positions());
+    }
+
+    #[test]
+    fn test_split_at_cluster() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let text = "ab cd";
+
+        for &direction in &[Direction::Ltr, Direction::Rtl] {
+            let shape_text = |text: &str| {
+                let buffer = UnicodeBuffer::new().add_str(text).set_direction(direction);
+                shape(&font, buffer, &[])
+            };
+            let output = shape_text(text);
+            let (first, second) = output.clone().split_at_cluster(3).unwrap();
+            assert_eq!(first.to_string(), shape_text("ab ").to_string());
+            assert_eq!(first.get_segment_properties().direction, direction);
+            let mut clusters: Vec<u32> = second.clusters().collect();
+            clusters.sort_unstable();
+            assert_eq!(clusters, vec![3, 4]);
+            assert_eq!(
+                second.x_advances().collect::<Vec<_>>(),
+                shape_text("cd").x_advances().collect::<Vec<_>>()
+            );
+
+            let (empty, all) = output.clone().split_at_cluster(0).unwrap();
+            assert!(empty.is_empty());
+            assert_eq!(all.to_string(), output.to_string());
+
+            // there is no cluster 10
+            let output = output.split_at_cluster(10).unwrap_err().into_buffer();
+            assert_eq!(output.len(), 5);
+        }
+
+        // e.g. kerning between the glyphs makes splitting unsafe
+        let mut output = shape(&font, UnicodeBuffer::new().add_str("ab"), &[]);
+        output.get_glyph_infos_mut()[1].set_mask(HB_GLYPH_FLAG_UNSAFE_TO_BREAK);
+        assert!(output.split_at_cluster(1).is_err());
+    }
+
+    #[test]
+    fn test_filtered_copy() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let buffer = UnicodeBuffer::new()
+            .add_str("a\u{200B}b")
+            .set_direction(Direction::Rtl);
+        let output = shape(&font, buffer, &[]);
+        assert_eq!(output.len(), 3);
+
+        // drop the zero width space in the middle
+        let space = output.get_glyph_infos()[1];
+        let filtered = output.filtered_copy(|info, _| info.cluster != space.cluster);
+        assert_eq!(
+            filtered.get_segment_properties(),
+            output.get_segment_properties()
+        );
+        let infos = output.get_glyph_infos();
+        let kept = [infos[0], infos[2]];
+        for (info, expected) in filtered.get_glyph_infos().iter().zip(&kept) {
+            assert_eq!(info.codepoint, expected.codepoint);
+            assert_eq!(info.cluster, expected.cluster);
+            assert_eq!(info.glyph_flags(), expected.glyph_flags());
+        }
+        let advances: Vec<_> = output.x_advances().collect();
+        assert_eq!(
+            filtered.x_advances().collect::<Vec<_>>(),
+            vec![advances[0] + advances[1], advances[2]]
+        );
+
+        let empty = output.filtered_copy(|_, _| false);
+        assert!(empty.is_empty());
+        assert!(empty.has_positions());
+    }
+
+    #[test]
+    fn test_add_str_with_cluster_offset() {
+        let mut buffer = UnicodeBuffer::new().add_str_with_cluster_offset("añ", 10);
+        buffer
+            .edit()
+            .add_str_with_cluster_offset("b", 20)
+            .add_str("c");
+        let clusters: Vec<u32> = buffer
+            .0
+            .get_glyph_infos()
+            .iter()
+            .map(|info| info.cluster)
+            .collect();
+        assert_eq!(clusters, vec![10, 11, 20, 0]);
+        assert_eq!(buffer.string_lossy(), "añbc");
+
+        let buffer = UnicodeBuffer::new().add_str_with_cluster_offset("ab", u32::MAX - 2);
+        assert_eq!(buffer.0.get_glyph_infos()[1].cluster, u32::MAX - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "too long")]
+    fn test_text_len_overflow() {
+        text_len(os::raw::c_int::MAX as usize + 1);
+    }
+
+    #[test]
+    fn test_context_window() {
+        // only the item and its context are passed to HarfBuzz, even if the
+        // whole text is longer than HarfBuzz accepts
+        let len = 3 << 30;
+        let item = (2 << 30)..(2 << 30) + 10;
+        let window = context_window(len, item.clone(), 4);
+        assert_eq!(window, item.start - 20..item.end + 20);
+        assert_eq!(text_len(window.len()), 50);
+        assert_eq!(context_window(len, 0..10, 2), 0..20);
+        assert_eq!(context_window(len, len - 1..len, 1), len - 6..len);
+        assert_eq!(context_window(8, 3..4, 1), 0..8);
+    }
+
+    #[test]
+    fn test_add_item_with_long_context() {
+        let padding = "\u{1F600}".repeat(20);
+        let text = format!("{}abcé\u{1F600}xyz{}", padding, padding);
+        let item_start = padding.len() + 3;
+        let item = &text[item_start..item_start + 6];
+        assert_eq!(item, "é\u{1F600}");
+        // the context is only tracked for normalization
+        #[cfg(feature = "unicode-normalization")]
+        let expected_pre: Vec<u32> = "\u{1F600}\u{1F600}abc".chars().map(u32::from).collect();
+        #[cfg(feature = "unicode-normalization")]
+        let expected_post: Vec<u32> = "xyz\u{1F600}\u{1F600}".chars().map(u32::from).collect();
+
+        let check = |buffer: &UnicodeBuffer, clusters: &[u32]| {
+            assert_eq!(buffer.string_lossy(), item);
+            let actual: Vec<u32> = buffer
+                .0
+                .get_glyph_infos()
+                .iter()
+                .map(|i| i.cluster)
+                .collect();
+            assert_eq!(actual, clusters);
+            #[cfg(feature = "unicode-normalization")]
+            {
+                let context = buffer.0.context();
+                assert_eq!(context.pre, expected_pre);
+                assert_eq!(context.post, expected_post);
+            }
+        };
+        let buffer = UnicodeBuffer::new().add_str_item(&text, item);
+        check(&buffer, &[item_start as u32, item_start as u32 + 2]);
+
+        let utf16: Vec<u16> = text.encode_utf16().collect();
+        let start = 2 * 20 + 3;
+        let buffer = UnicodeBuffer::new().add_utf16_item(&utf16, start..start + 3);
+        check(&buffer, &[start as u32, start as u32 + 1]);
+
+        let utf32: Vec<u32> = text.chars().map(u32::from).collect();
+        let mut buffer = UnicodeBuffer::new();
+        buffer.0.add_utf32_item(&utf32, 23, 2);
+        check(&buffer, &[23, 24]);
+
+        let latin1 = b"0123456789abcdefghij";
+        let mut buffer = UnicodeBuffer::new();
+        buffer.0.add_latin1_item(latin1, 10, 2);
+        assert_eq!(buffer.string_lossy(), "ab");
+        assert_eq!(buffer.0.get_glyph_infos()[1].cluster, 11);
+        #[cfg(feature = "unicode-normalization")]
+        {
+            assert_eq!(buffer.0.context().pre, b"56789".map(u32::from));
+            assert_eq!(buffer.0.context().post, b"cdefg".map(u32::from));
+        }
+
+        // the length limit applies before the context is chosen
+        let limit = LengthLimit::new(1, LengthLimitPolicy::Truncate);
+        let buffer = UnicodeBuffer::new()
+            .set_length_limit(limit)
+            .add_str_item(&text, item);
+        assert_eq!(buffer.string_lossy(), "é");
+        assert_eq!(buffer.0.get_glyph_infos()[0].cluster, item_start as u32);
+        #[cfg(feature = "unicode-normalization")]
+        {
+            let post: Vec<u32> = "\u{1F600}xyz\u{1F600}".chars().map(u32::from).collect();
+            assert_eq!(buffer.0.context().post, post);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow")]
+    fn test_add_str_with_cluster_offset_overflow() {
+        let _ = UnicodeBuffer::new().add_str_with_cluster_offset("ab", u32::MAX - 1);
+    }
+
+    #[test]
+    fn test_length_limit() {
+        let limit = LengthLimit::new(3, LengthLimitPolicy::Truncate);
+        let mut buffer = UnicodeBuffer::new().set_length_limit(limit);
+        assert_eq!(buffer.get_length_limit(), Some(limit));
+
+        // truncation happens at codepoint boundaries in every encoding
+        buffer.edit().add_str("añ\u{1F600}b");
+        assert_eq!(buffer.string_lossy(), "añ\u{1F600}");
+        assert_eq!(buffer.check_length_limit(), Err(LengthLimitExceeded));
+        let text: Vec<u16> = "a\u{1F600}b".encode_utf16().collect();
+        buffer.edit().clear_contents().add_utf16(&text);
+        assert_eq!(buffer.string_lossy(), "a\u{1F600}b");
+        assert_eq!(buffer.check_length_limit(), Ok(()));
+        buffer.edit().add('c' as u32, 3).add_latin1(b"de");
+        assert_eq!(buffer.len(), 3);
+        assert!(buffer.check_length_limit().is_err());
+
+        // the limit survives shaping and applies to appended text
+        let face = crate::Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = crate::Font::new(face);
+        let glyphs = crate::shape(&font, buffer, &[]);
+        assert!(glyphs.check_length_limit().is_err());
+        let other = UnicodeBuffer::new().add_str("xyz");
+        let buffer = glyphs.clear().append(&other).append(&other);
+        assert_eq!(buffer.string_lossy(), "xyz");
+        assert!(buffer.check_length_limit().is_err());
+
+        let limit = LengthLimit::new(4, LengthLimitPolicy::Reject);
+        let buffer = buffer.set_length_limit(limit).add_utf32(&[1, 2]);
+        assert_eq!(buffer.string_lossy(), "xyz");
+        let copy = buffer.clone().add_utf32(&[1]);
+        assert_eq!(copy.len(), 4);
+        assert!(copy.check_length_limit().is_err());
+        let buffer = buffer.remove_length_limit().add_utf32(&[1, 2]);
+        assert_eq!(buffer.len(), 5);
+        assert_eq!(buffer.check_length_limit(), Ok(()));
+    }
+
+    #[test]
+    fn test_add_utf16_item() {
+        let text: Vec<u16> = "a\u{1F600}b c".encode_utf16().collect();
+        let buffer = UnicodeBuffer::new().add_utf16_item(&text, 1..4);
+        assert_eq!(buffer.string_lossy(), "\u{1F600}b");
+        let clusters: Vec<u32> = buffer
+            .0
+            .get_glyph_infos()
+            .iter()
+            .map(|i| i.cluster)
+            .collect();
+        assert_eq!(clusters, vec![1, 3]);
+
+        // the shaped glyphs keep the indices into the context as clusters
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let font = Font::new(crate::Face::from_file(path, 0).unwrap());
+        let output = crate::shape(&font, UnicodeBuffer::new().add_utf16_item(&text, 4..6), &[]);
+        assert_eq!(
+            output.iter().map(|g| g.cluster).collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_add_utf16_item_out_of_bounds() {
+        let text: Vec<u16> = "abc".encode_utf16().collect();
+        let _ = UnicodeBuffer::new().add_utf16_item(&text, 2..4);
+    }
+
+    #[test]
+    fn test_user_data() {
+        use std::sync::Arc;
+
+        static RUN_ID: UserDataKey<u32> = UserDataKey::new();
+        static SHARED: UserDataKey<Arc<()>> = UserDataKey::new();
+
+        let tracker = Arc::new(());
+        let buffer = UnicodeBuffer::new()
+            .add_str("abc")
+            .set_user_data(&RUN_ID, 7)
+            .set_user_data(&SHARED, tracker.clone());
+        assert_eq!(buffer.get_user_data(&RUN_ID), Some(&7));
+        assert_eq!(Arc::strong_count(&tracker), 2);
+
+        // the data survives shaping and clearing but not cloning
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let font = Font::new(crate::Face::from_file(path, 0).unwrap());
+        let mut output = crate::shape(&font, buffer, &[]);
+        assert_eq!(output.get_user_data(&RUN_ID), Some(&7));
+        assert_eq!(output.clone().get_user_data(&RUN_ID), None);
+        output.set_user_data(&RUN_ID, 8);
+        let buffer = output.clear();
+        assert_eq!(buffer.get_user_data(&RUN_ID), Some(&8));
+
+        let buffer = buffer.remove_user_data(&SHARED);
+        assert_eq!(buffer.get_user_data(&SHARED), None);
+        assert_eq!(Arc::strong_count(&tracker), 1);
+
+        let buffer = buffer.set_user_data(&SHARED, tracker.clone());
+        drop(buffer);
+        assert_eq!(Arc::strong_count(&tracker), 1);
+    }
 }
@@ -1,4 +1,6 @@
 use std::borrow::Borrow;
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
 /// A type to represent 4-byte SFNT tags.
@@ -185,6 +187,11 @@ impl Direction {
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Language(pub hb_language_t);
 
+// A language is a pointer to an interned string that HarfBuzz never modifies
+// or frees.
+unsafe impl Send for Language {}
+unsafe impl Sync for Language {}
+
 impl Default for Language {
     fn default() -> Language {
         Language(unsafe { hb_language_get_default() })
@@ -199,10 +206,13 @@ impl Debug for Language {
 
 use std::ffi::CStr;
 
+use crate::scripts::SCRIPT_NAMES;
+
 use crate::bindings::{
     hb_direction_t, hb_language_from_string, hb_language_get_default, hb_language_t,
     hb_language_to_string, hb_script_from_iso15924_tag, hb_script_get_horizontal_direction,
     hb_script_t, hb_script_to_iso15924_tag, hb_tag_from_string, hb_tag_t, hb_tag_to_string,
+    hb_unicode_funcs_get_default, hb_unicode_mirroring, hb_unicode_script, hb_user_data_key_t,
     HB_DIRECTION_BTT, HB_DIRECTION_INVALID, HB_DIRECTION_LTR, HB_DIRECTION_RTL, HB_DIRECTION_TTB,
 };
 impl Display for Language {
@@ -251,6 +261,110 @@ impl Script {
     pub fn horizontal_direction(self) -> Direction {
         Direction::from_raw(unsafe { hb_script_get_horizontal_direction(self.0) })
     }
+
+    /// Returns the script of the character `c` according to the Unicode
+    /// Script property.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Script, Tag};
+    ///
+    /// assert_eq!(Script::from_char('a').to_iso15924_tag(), Tag::new('L', 'a', 't', 'n'));
+    /// assert_eq!(Script::from_char('\u{0627}').to_iso15924_tag(), Tag::new('A', 'r', 'a', 'b'));
+    /// ```
+    pub fn from_char(c: char) -> Self {
+        Script(unsafe { hb_unicode_script(hb_unicode_funcs_get_default(), c as u32) })
+    }
+
+    /// Returns the Unicode name of the script (e.g. `"Old_Italic"`) or `None`
+    /// if the script is unknown to HarfBuzz.
+    pub fn name(self) -> Option<&'static str> {
+        SCRIPT_NAMES
+            .iter()
+            .find(|&&(script, _)| script == self)
+            .map(|&(_, name)| name)
+    }
+}
+
+/// Formats the script as its ISO 15924 tag, e.g. `Latn`.
+impl Display for Script {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_iso15924_tag())
+    }
+}
+
+/// An error returned when a `Script` fails to parse from a `&str`, i.e. if the
+/// string is neither the ISO 15924 tag nor the name of a script known to
+/// HarfBuzz.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidScript;
+
+impl Display for InvalidScript {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid script name or tag")
+    }
+}
+
+impl std::error::Error for InvalidScript {}
+
+impl FromStr for Script {
+    type Err = InvalidScript;
+    /// Parses a script from its ISO 15924 tag (e.g. `"Latn"`) or its Unicode
+    /// name (e.g. `"Latin"`), ignoring case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::Script;
+    ///
+    /// assert_eq!("Arab".parse(), Ok(Script::ARABIC));
+    /// assert_eq!("old_italic".parse(), Ok(Script::OLD_ITALIC));
+    /// assert_eq!(Script::LATIN.to_string(), "Latn");
+    /// assert!("Xyzw".parse::<Script>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Script, InvalidScript> {
+        if let Some(&(script, _)) = SCRIPT_NAMES
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(s))
+        {
+            return Ok(script);
+        }
+        if s.len() != 4 {
+            return Err(InvalidScript);
+        }
+        let tag = Tag::from_str(s).map_err(|_| InvalidScript)?;
+        let script = Script::from_iso15924_tag(tag);
+        match script.name() {
+            Some(_) => Ok(script),
+            None => Err(InvalidScript),
+        }
+    }
+}
+
+/// Returns the mirrored counterpart of `c` (e.g. `)` for `(`) according to the
+/// Unicode Bidi_Mirroring_Glyph property or `None` if `c` has no mirror.
+///
+/// HarfBuzz applies mirroring automatically to text shaped in right-to-left
+/// direction. This function is useful for custom pipelines that handle
+/// bidirectional text themselves (see also `UnicodeBuffer::apply_mirroring`).
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::mirror_char;
+///
+/// assert_eq!(mirror_char('('), Some(')'));
+/// assert_eq!(mirror_char('«'), Some('»'));
+/// assert_eq!(mirror_char('a'), None);
+/// ```
+pub fn mirror_char(c: char) -> Option<char> {
+    let mirrored = unsafe { hb_unicode_mirroring(hb_unicode_funcs_get_default(), c as u32) };
+    if mirrored == c as u32 {
+        None
+    } else {
+        std::char::from_u32(mirrored)
+    }
 }
 
 /// A trait which is implemented for all harffbuzz wrapper structs. It exposes
@@ -496,6 +610,283 @@ impl<T: HarfbuzzObject> DerefMut for Owned<T> {
     }
 }
 
+/// Conversion from a raw HarfBuzz value of type `R`.
+///
+/// This trait is implemented uniformly for the wrappers of this crate, so FFI
+/// code can convert between raw and wrapped values generically. For
+/// reference-counted objects `R` is the object pointer and the conversion
+/// takes over one reference, like [`Owned::from_raw()`] and
+/// [`Shared::from_raw_owned()`]. Plain values like [`Tag`] or
+/// [`Feature`](crate::Feature) are converted from their C struct or integer.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::{FromRaw, IntoRaw, Tag, UnicodeBuffer};
+///
+/// fn round_trip<T: IntoRaw<R> + FromRaw<R>, R>(value: T) -> T {
+///     unsafe { T::from_raw(value.into_raw()) }
+/// }
+///
+/// let buffer = round_trip(UnicodeBuffer::new().add_str("abc"));
+/// assert_eq!(buffer.len(), 3);
+/// assert_eq!(round_trip(Tag::new('w', 'g', 'h', 't')), Tag::new('w', 'g', 'h', 't'));
+/// ```
+pub trait FromRaw<R>: Sized {
+    /// Creates the wrapper from the raw value.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be a valid value of the raw type. For pointers ownership of
+    /// one reference is transferred to the wrapper, so the pointer must not be
+    /// destroyed by the caller afterwards.
+    unsafe fn from_raw(raw: R) -> Self;
+}
+
+/// Conversion into a raw HarfBuzz value of type `R`.
+///
+/// This is the counterpart of [`FromRaw`]. For reference-counted objects the
+/// reference owned by the wrapper is handed to the caller, who has to release
+/// it with `hb_TYPE_destroy` or convert it back with [`FromRaw::from_raw()`].
+pub trait IntoRaw<R> {
+    /// Converts the wrapper into the raw value.
+    fn into_raw(self) -> R;
+}
+
+impl<T: HarfbuzzObject> FromRaw<*mut T::Raw> for Owned<T> {
+    unsafe fn from_raw(raw: *mut T::Raw) -> Self {
+        Owned::from_raw(raw)
+    }
+}
+
+impl<T: HarfbuzzObject> IntoRaw<*mut T::Raw> for Owned<T> {
+    fn into_raw(self) -> *mut T::Raw {
+        Owned::into_raw(self)
+    }
+}
+
+impl<T: HarfbuzzObject> FromRaw<*mut T::Raw> for Shared<T> {
+    unsafe fn from_raw(raw: *mut T::Raw) -> Self {
+        Shared::from_raw_owned(raw)
+    }
+}
+
+impl<T: HarfbuzzObject> IntoRaw<*mut T::Raw> for Shared<T> {
+    fn into_raw(self) -> *mut T::Raw {
+        Shared::into_raw(self)
+    }
+}
+
+// Implements `FromRaw` and `IntoRaw` for a newtype around a plain raw value.
+macro_rules! impl_raw_newtype {
+    ($type:ident, $raw:ty) => {
+        impl FromRaw<$raw> for $type {
+            unsafe fn from_raw(raw: $raw) -> Self {
+                $type(raw)
+            }
+        }
+
+        impl IntoRaw<$raw> for $type {
+            fn into_raw(self) -> $raw {
+                self.0
+            }
+        }
+    };
+}
+
+impl_raw_newtype!(Tag, hb_tag_t);
+impl_raw_newtype!(Language, hb_language_t);
+impl_raw_newtype!(Script, hb_script_t);
+
+impl FromRaw<hb_direction_t> for Direction {
+    unsafe fn from_raw(raw: hb_direction_t) -> Self {
+        Direction::from_raw(raw)
+    }
+}
+
+impl IntoRaw<hb_direction_t> for Direction {
+    fn into_raw(self) -> hb_direction_t {
+        self.to_raw()
+    }
+}
+
+/// A key for attaching user data of type `T` to HarfBuzz objects, e.g. with
+/// [`UnicodeBuffer::set_user_data()`](crate::UnicodeBuffer::set_user_data).
+///
+/// HarfBuzz identifies keys by their address, so keys have to be declared as
+/// `static`s. The type parameter ensures that the data stored under a key is
+/// always read back with the same type.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::{UnicodeBuffer, UserDataKey};
+///
+/// static RUN_ID: UserDataKey<u32> = UserDataKey::new();
+///
+/// let buffer = UnicodeBuffer::new().set_user_data(&RUN_ID, 42);
+/// assert_eq!(buffer.get_user_data(&RUN_ID), Some(&42));
+/// ```
+///
+/// Keys declared as `const` are rejected, because the compiler could merge
+/// the constants of different keys into a single address:
+///
+/// ```compile_fail
+/// use harfbuzz_rs::{UnicodeBuffer, UserDataKey};
+///
+/// const RUN_ID: UserDataKey<u32> = UserDataKey::new();
+///
+/// let buffer = UnicodeBuffer::new().set_user_data(&RUN_ID, 42);
+/// ```
+pub struct UserDataKey<T> {
+    // The cell is never written to, but it prevents references to `const`
+    // keys from being promoted to `&'static`, which would allow keys of
+    // different types to share an address.
+    raw: UnsafeCell<hb_user_data_key_t>,
+    marker: PhantomData<fn() -> T>,
+}
+
+// The key is only ever used for its address.
+unsafe impl<T> Sync for UserDataKey<T> {}
+
+impl<T> UserDataKey<T> {
+    /// Creates a new key.
+    pub const fn new() -> UserDataKey<T> {
+        UserDataKey {
+            raw: UnsafeCell::new(hb_user_data_key_t { unused: 0 }),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the raw key, e.g. for passing it to C code that reads the user
+    /// data with `hb_TYPE_get_user_data()`.
+    pub fn as_raw(&'static self) -> *mut hb_user_data_key_t {
+        self.raw.get()
+    }
+}
+
+impl<T> Default for UserDataKey<T> {
+    fn default() -> Self {
+        UserDataKey::new()
+    }
+}
+
+impl<T> Debug for UserDataKey<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("UserDataKey")
+            .field(&(self.raw.get() as *const hb_user_data_key_t))
+            .finish()
+    }
+}
+
+/// Determines what happens when a Rust callback that was handed to HarfBuzz
+/// (e.g. a [`FontFuncs`](crate::font_funcs::FontFuncs) or
+/// [`DrawFuncs`](crate::draw_funcs::DrawFuncs) implementation) panics.
+///
+/// Unwinding into HarfBuzz' C code is undefined behaviour, so panics are
+/// always caught at the callback boundary. The policy decides how the caught
+/// panic is handled afterwards.
+#[derive(Debug, Clone, Default)]
+pub enum PanicPolicy {
+    /// Return the default value of the callback's return type (e.g. `None` or
+    /// `0`) to HarfBuzz and continue.
+    #[default]
+    ReturnDefault,
+    /// Abort the process.
+    Abort,
+    /// Return the default value like `ReturnDefault` and record the panic
+    /// message in the given sink so it can be inspected after the shaping or
+    /// drawing call returned.
+    Record(PanicSink),
+}
+
+impl PanicPolicy {
+    // Calls `f` and handles a panic in it according to the policy.
+    pub(crate) fn call<R: Default>(&self, f: impl FnOnce() -> R) -> R {
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+            Ok(val) => val,
+            Err(payload) => match self {
+                PanicPolicy::ReturnDefault => R::default(),
+                PanicPolicy::Abort => std::process::abort(),
+                PanicPolicy::Record(sink) => {
+                    sink.push(panic_message(&*payload));
+                    R::default()
+                }
+            },
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+/// Collects the messages of panics caught in callbacks using
+/// [`PanicPolicy::Record`].
+///
+/// A `PanicSink` is cheap to clone and all clones share the same messages,
+/// so one clone can be given to the policy and another kept to retrieve the
+/// messages afterwards.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+/// use harfbuzz_rs::font_funcs::FontFuncs;
+///
+/// struct Broken;
+/// impl FontFuncs for Broken {
+///     fn get_glyph_h_advance(&self, _font: &Font, _glyph: Glyph) -> Position {
+///         panic!("advance not implemented")
+///     }
+/// }
+///
+/// # std::panic::set_hook(Box::new(|_| {}));
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let mut font = Font::new(face);
+/// let sink = PanicSink::new();
+/// font.set_font_funcs_with_panic_policy(Broken, PanicPolicy::Record(sink.clone()));
+///
+/// shape(&font, UnicodeBuffer::new().add_str("a"), &[]);
+/// assert_eq!(sink.take(), vec!["advance not implemented".to_string()]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PanicSink(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+impl PanicSink {
+    /// Creates an empty sink.
+    pub fn new() -> PanicSink {
+        PanicSink::default()
+    }
+
+    fn push(&self, message: String) {
+        self.lock().push(message);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<String>> {
+        // the lock is never held while user code runs, so poisoning can be ignored
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns `true` if no panics were recorded since the last call to
+    /// [`Self::take()`].
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Removes and returns the messages of all recorded panics in the order
+    /// they occurred.
+    pub fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.lock())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -590,4 +981,47 @@ mod tests {
         // ensure there are no dangling references
         assert_eq!(Rc::strong_count(&object.share_count), 1);
     }
+
+    fn round_trip<T: IntoRaw<R> + FromRaw<R>, R>(value: T) -> T {
+        unsafe { T::from_raw(value.into_raw()) }
+    }
+
+    #[test]
+    fn test_raw_conversions() {
+        use crate::{Face, Feature, Font, Variation};
+
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let glyph_count = face.glyph_count();
+        let face: Shared<Face<'_>> = round_trip(Shared::from(face));
+        assert_eq!(face.glyph_count(), glyph_count);
+        let font = round_trip(Font::new(face));
+        assert_eq!(font.face().glyph_count(), glyph_count);
+
+        let feature = round_trip(Feature::new(b"liga", 0, 2..5));
+        assert_eq!(
+            (feature.tag(), feature.start(), feature.end()),
+            (b"liga".into(), 2, 5)
+        );
+        assert_eq!(round_trip(Variation::new(b"wght", 300.0)).value(), 300.0);
+        assert_eq!(round_trip(Direction::Rtl), Direction::Rtl);
+        let lang = Language::from_str("de").unwrap();
+        assert_eq!(round_trip(lang), lang);
+    }
+
+    #[test]
+    fn test_script_parsing() {
+        for &(script, name) in SCRIPT_NAMES {
+            assert_eq!(Script::from_str(name), Ok(script));
+            assert_eq!(Script::from_str(&script.to_string()), Ok(script));
+            assert_eq!(script.to_iso15924_tag().to_string(), script.to_string());
+        }
+        assert_eq!(Script::from_str("LATN"), Ok(Script::LATIN));
+        assert_eq!(Script::from_char('\u{05D0}'), Script::HEBREW);
+        assert_eq!(Script::from_str(""), Err(InvalidScript));
+        assert_eq!(Script::from_str("Latin1"), Err(InvalidScript));
+        assert_eq!(Script(0).name(), None);
+        assert_eq!(Script::SIGNWRITING.name(), Some("SignWriting"));
+        assert_eq!(Script::from_str("mathematical notation"), Ok(Script::MATH));
+        assert_eq!(InvalidScript.to_string(), "invalid script name or tag");
+    }
 }
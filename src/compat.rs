@@ -0,0 +1,9 @@
+//! Compatibility layers that mirror the APIs of other shaping crates.
+//!
+//! The modules in this namespace are thin wrappers around the types of this
+//! crate. They allow downstream projects to switch between this crate and an
+//! alternative with minimal changes, e.g. while evaluating both. They are only
+//! available with the corresponding cargo feature.
+
+#[cfg(feature = "rustybuzz-compat")]
+pub mod rustybuzz;
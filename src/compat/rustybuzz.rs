@@ -0,0 +1,430 @@
+//! Aliases that follow the naming of the [`rustybuzz`] API.
+//!
+//! `rustybuzz` is a pure Rust port of HarfBuzz. Its API combines a face and
+//! its scaling parameters in a single [`Face`] type and names several methods
+//! differently from this crate (e.g. [`UnicodeBuffer::push_str()`] instead of
+//! [`add_str()`](crate::UnicodeBuffer::add_str)). This module provides the
+//! `rustybuzz` names on top of the HarfBuzz C library, so code written against
+//! either API can be ported to the other by mostly changing imports.
+//!
+//! Only the commonly used subset of the API is covered. Types that are the
+//! same in both crates, like [`Feature`] and [`GlyphPosition`], are
+//! re-exported from this crate. Note that [`GlyphInfo`] calls the glyph id
+//! `codepoint` like HarfBuzz, while `rustybuzz` calls it `glyph_id`.
+//!
+//! This module requires the `rustybuzz-compat` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use harfbuzz_rs::compat::rustybuzz::{shape, Face, UnicodeBuffer};
+//!
+//! let data = std::fs::read("testfiles/SourceSansVariable-Roman.ttf").unwrap();
+//! let face = Face::from_slice(&data, 0).unwrap();
+//!
+//! let mut buffer = UnicodeBuffer::new();
+//! buffer.push_str("Hello");
+//! let output = shape(&face, &[], buffer);
+//! assert_eq!(output.len(), 5);
+//! assert_eq!(output.glyph_infos()[0].cluster, 0);
+//! ```
+//!
+//! [`rustybuzz`]: https://crates.io/crates/rustybuzz
+
+use crate::bindings::hb_face_count;
+use crate::blob::Blob;
+use crate::buffer::{ClusterLevel, SegmentProperties};
+use crate::common::{Direction as HbDirection, HarfbuzzObject, Owned, Tag};
+use crate::font::Font;
+
+pub use crate::{Feature, GlyphInfo, GlyphPosition, Language, Script, Variation};
+
+/// A font face together with its scaling parameters.
+///
+/// This corresponds to `rustybuzz::Face` and wraps a [`Font`] of this crate,
+/// which also holds the face and its size.
+#[derive(Debug)]
+pub struct Face<'a>(Owned<Font<'a>>);
+
+impl<'a> Face<'a> {
+    /// Creates a face from the face at `index` in the font file `data`.
+    ///
+    /// Returns `None` if `data` does not contain a face at `index`.
+    pub fn from_slice(data: &'a [u8], index: u32) -> Option<Face<'a>> {
+        let blob = Blob::with_bytes(data);
+        if index >= unsafe { hb_face_count(blob.as_raw()) } {
+            return None;
+        }
+        Some(Face(Font::new(crate::Face::new(blob, index))))
+    }
+
+    /// Returns the units per em of the face.
+    pub fn units_per_em(&self) -> i32 {
+        self.0.face().upem() as i32
+    }
+
+    /// Sets the pixels per em used for hinting and bitmap selection, or
+    /// resets them if `ppem` is `None`.
+    pub fn set_pixels_per_em(&mut self, ppem: Option<(u16, u16)>) {
+        let (x, y) = ppem.unwrap_or((0, 0));
+        self.0.set_ppem(x.into(), y.into());
+    }
+
+    /// Sets the variation axis values of a variable font.
+    pub fn set_variations(&mut self, variations: &[Variation]) {
+        self.0.set_variations(variations);
+    }
+
+    /// Returns the underlying font of this crate.
+    pub fn font(&self) -> &Font<'a> {
+        &self.0
+    }
+
+    /// Returns the underlying font of this crate mutably.
+    pub fn font_mut(&mut self) -> &mut Font<'a> {
+        &mut self.0
+    }
+}
+
+impl<'a> From<Owned<Font<'a>>> for Face<'a> {
+    fn from(font: Owned<Font<'a>>) -> Face<'a> {
+        Face(font)
+    }
+}
+
+/// The text direction, with the variant names of `rustybuzz::Direction`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Initial, unset direction.
+    #[default]
+    Invalid,
+    /// Text is set horizontally from left to right.
+    LeftToRight,
+    /// Text is set horizontally from right to left.
+    RightToLeft,
+    /// Text is set vertically from top to bottom.
+    TopToBottom,
+    /// Text is set vertically from bottom to top.
+    BottomToTop,
+}
+
+impl From<HbDirection> for Direction {
+    fn from(direction: HbDirection) -> Direction {
+        match direction {
+            HbDirection::Invalid => Direction::Invalid,
+            HbDirection::Ltr => Direction::LeftToRight,
+            HbDirection::Rtl => Direction::RightToLeft,
+            HbDirection::Ttb => Direction::TopToBottom,
+            HbDirection::Btt => Direction::BottomToTop,
+        }
+    }
+}
+
+impl From<Direction> for HbDirection {
+    fn from(direction: Direction) -> HbDirection {
+        match direction {
+            Direction::Invalid => HbDirection::Invalid,
+            Direction::LeftToRight => HbDirection::Ltr,
+            Direction::RightToLeft => HbDirection::Rtl,
+            Direction::TopToBottom => HbDirection::Ttb,
+            Direction::BottomToTop => HbDirection::Btt,
+        }
+    }
+}
+
+/// The cluster level of a buffer, with the variant names of
+/// `rustybuzz::BufferClusterLevel`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BufferClusterLevel {
+    /// See [`ClusterLevel::MonotoneGraphemes`].
+    #[default]
+    MonotoneGraphemes,
+    /// See [`ClusterLevel::MonotoneCharacters`].
+    MonotoneCharacters,
+    /// See [`ClusterLevel::Characters`].
+    Characters,
+}
+
+impl From<BufferClusterLevel> for ClusterLevel {
+    fn from(level: BufferClusterLevel) -> ClusterLevel {
+        match level {
+            BufferClusterLevel::MonotoneGraphemes => ClusterLevel::MonotoneGraphemes,
+            BufferClusterLevel::MonotoneCharacters => ClusterLevel::MonotoneCharacters,
+            BufferClusterLevel::Characters => ClusterLevel::Characters,
+        }
+    }
+}
+
+/// A buffer of text to shape, mirroring `rustybuzz::UnicodeBuffer`.
+///
+/// Unlike [`crate::UnicodeBuffer`] its methods take `&mut self`.
+#[derive(Debug, Default, Clone)]
+pub struct UnicodeBuffer(crate::UnicodeBuffer);
+
+impl UnicodeBuffer {
+    /// Creates a new empty buffer.
+    pub fn new() -> UnicodeBuffer {
+        UnicodeBuffer(crate::UnicodeBuffer::new())
+    }
+
+    /// Returns the number of characters in the buffer.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the buffer contains no characters.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Pre-allocates space for `size` characters.
+    pub fn reserve(&mut self, size: usize) {
+        self.0.pre_allocate(size);
+    }
+
+    /// Appends `text` to the buffer. See
+    /// [`crate::UnicodeBuffer::add_str()`].
+    pub fn push_str(&mut self, text: &str) {
+        self.0.edit().add_str(text);
+    }
+
+    /// Appends a single character with the given cluster value.
+    pub fn add(&mut self, codepoint: char, cluster: u32) {
+        self.0.edit().add(codepoint as u32, cluster);
+    }
+
+    /// Sets the text direction.
+    pub fn set_direction(&mut self, direction: Direction) {
+        self.0.edit().set_direction(direction.into());
+    }
+
+    /// Returns the text direction.
+    pub fn direction(&self) -> Direction {
+        self.0.get_direction().into()
+    }
+
+    /// Sets the script of the text.
+    pub fn set_script(&mut self, script: Script) {
+        self.0.edit().set_script(Tag(script.0));
+    }
+
+    /// Returns the script of the text.
+    pub fn script(&self) -> Script {
+        Script(self.0.get_script().0)
+    }
+
+    /// Sets the language of the text.
+    pub fn set_language(&mut self, language: Language) {
+        self.0.edit().set_language(language);
+    }
+
+    /// Returns the language of the text, if it is set.
+    pub fn language(&self) -> Option<Language> {
+        self.0.get_language()
+    }
+
+    /// Guesses the direction, script and language of the text from its
+    /// contents if they are not set.
+    pub fn guess_segment_properties(&mut self) {
+        self.0.edit().guess_segment_properties();
+    }
+
+    /// Sets the cluster level.
+    pub fn set_cluster_level(&mut self, cluster_level: BufferClusterLevel) {
+        self.0.edit().set_cluster_level(cluster_level.into());
+    }
+
+    /// Removes all text from the buffer, keeping its properties.
+    pub fn clear(&mut self) {
+        self.0.edit().clear_contents();
+    }
+
+    /// Returns the underlying buffer of this crate.
+    pub fn into_inner(self) -> crate::UnicodeBuffer {
+        self.0
+    }
+}
+
+impl From<crate::UnicodeBuffer> for UnicodeBuffer {
+    fn from(buffer: crate::UnicodeBuffer) -> UnicodeBuffer {
+        UnicodeBuffer(buffer)
+    }
+}
+
+/// The result of shaping, mirroring `rustybuzz::GlyphBuffer`.
+#[derive(Debug, Default, Clone)]
+pub struct GlyphBuffer(crate::GlyphBuffer);
+
+impl GlyphBuffer {
+    /// Returns the number of glyphs.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the buffer contains no glyphs.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the glyph infos.
+    pub fn glyph_infos(&self) -> &[GlyphInfo] {
+        self.0.get_glyph_infos()
+    }
+
+    /// Returns the glyph positions.
+    pub fn glyph_positions(&self) -> &[GlyphPosition] {
+        self.0.get_glyph_positions()
+    }
+
+    /// Clears the contents of the buffer and returns it as a
+    /// [`UnicodeBuffer`], so its allocation can be reused.
+    pub fn clear(self) -> UnicodeBuffer {
+        UnicodeBuffer(self.0.clear())
+    }
+
+    /// Returns the underlying buffer of this crate.
+    pub fn into_inner(self) -> crate::GlyphBuffer {
+        self.0
+    }
+}
+
+impl From<crate::GlyphBuffer> for GlyphBuffer {
+    fn from(buffer: crate::GlyphBuffer) -> GlyphBuffer {
+        GlyphBuffer(buffer)
+    }
+}
+
+/// A shape plan, mirroring `rustybuzz::ShapePlan`.
+///
+/// Unlike [`crate::ShapePlan`] the plan stores its features, which are
+/// applied by [`shape_with_plan()`].
+#[derive(Debug)]
+pub struct ShapePlan {
+    plan: Owned<crate::ShapePlan>,
+    direction: Direction,
+    script: Option<Script>,
+    language: Option<Language>,
+    features: Vec<Feature>,
+}
+
+impl ShapePlan {
+    /// Creates a plan for shaping text with the given properties and
+    /// features with `face`.
+    pub fn new(
+        face: &Face<'_>,
+        direction: Direction,
+        script: Option<Script>,
+        language: Option<&Language>,
+        features: &[Feature],
+    ) -> ShapePlan {
+        let props = SegmentProperties {
+            direction: direction.into(),
+            script: script.unwrap_or(Script(0)),
+            language: language.copied().unwrap_or(Language(std::ptr::null())),
+        };
+        ShapePlan {
+            plan: crate::ShapePlan::new(&face.0, &props, features),
+            direction,
+            script,
+            language: language.copied(),
+            features: features.to_vec(),
+        }
+    }
+
+    /// Returns the direction the plan was created for.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns the script the plan was created for.
+    pub fn script(&self) -> Option<Script> {
+        self.script
+    }
+
+    /// Returns the language the plan was created for.
+    pub fn language(&self) -> Option<Language> {
+        self.language
+    }
+}
+
+/// Shapes `buffer` with `face` and `features`. See [`crate::shape()`].
+pub fn shape(face: &Face<'_>, features: &[Feature], buffer: UnicodeBuffer) -> GlyphBuffer {
+    GlyphBuffer(crate::shape(&face.0, buffer.0, features))
+}
+
+/// Shapes `buffer` with a previously created `plan`.
+///
+/// The buffer is shaped with the language of the plan.
+///
+/// # Panics
+///
+/// Panics if the direction or script of `buffer` (after guessing the unset
+/// properties) differ from those `plan` was created for, like `rustybuzz`
+/// does, or if `plan` was created for another face.
+pub fn shape_with_plan(face: &Face<'_>, plan: &ShapePlan, buffer: UnicodeBuffer) -> GlyphBuffer {
+    let buffer = buffer.0.guess_segment_properties();
+    let mut props = buffer.get_segment_properties();
+    assert_eq!(Direction::from(props.direction), plan.direction);
+    assert_eq!(props.script, plan.script.unwrap_or(Script(0)));
+    // HarfBuzz requires the segment properties to match the plan exactly
+    props.language = plan.language.unwrap_or(Language(std::ptr::null()));
+    let buffer = buffer.set_segment_properties(props);
+    GlyphBuffer(plan.plan.execute(&face.0, buffer, &plan.features))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rustybuzz_compat() {
+        let data = std::fs::read("testfiles/SourceSansVariable-Roman.ttf").unwrap();
+        assert!(Face::from_slice(&data, 1).is_none());
+        let face = Face::from_slice(&data, 0).unwrap();
+        assert_eq!(face.units_per_em(), 1000);
+
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str("Hello");
+        buffer.add('!', 5);
+        buffer.guess_segment_properties();
+        assert_eq!(buffer.direction(), Direction::LeftToRight);
+        assert_eq!(buffer.script(), Script::LATIN);
+
+        let plan = ShapePlan::new(
+            &face,
+            Direction::LeftToRight,
+            Some(Script::LATIN),
+            None,
+            &[],
+        );
+        let with_plan = shape_with_plan(&face, &plan, buffer.clone());
+        let output = shape(&face, &[], buffer);
+        assert_eq!(
+            output.clone().into_inner().to_string(),
+            with_plan.into_inner().to_string()
+        );
+
+        let mut buffer = output.clear();
+        assert!(buffer.is_empty());
+        buffer.push_str("a");
+        assert_eq!(shape(&face, &[], buffer).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "face the shape plan was created for")]
+    fn test_shape_with_plan_of_other_face() {
+        let data = std::fs::read("testfiles/SourceSansVariable-Roman.ttf").unwrap();
+        let face = Face::from_slice(&data, 0).unwrap();
+        let other_face = Face::from_slice(&data, 0).unwrap();
+        let plan = ShapePlan::new(
+            &face,
+            Direction::LeftToRight,
+            Some(Script::LATIN),
+            None,
+            &[],
+        );
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str("a");
+        shape_with_plan(&other_face, &plan, buffer);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::buffer::{GlyphBuffer, UnicodeBuffer};
+use crate::common::Direction;
+use crate::font::{Font, Position};
+use crate::{shape, Feature};
+
+use std::collections::HashMap;
+
+/// What [`shape_with_control_policy()`] does with a character.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ControlCharAction {
+    /// Shape the given character instead, e.g. a visible symbol for a zero
+    /// width space in a "show invisibles" mode.
+    Replace(char),
+    /// Shape the given character instead and give it a fixed horizontal
+    /// advance.
+    Fixed(char, Position),
+    /// Shape the given character instead and advance to the next tab stop.
+    Tab(char),
+}
+
+impl ControlCharAction {
+    fn replacement(self) -> char {
+        match self {
+            ControlCharAction::Replace(c)
+            | ControlCharAction::Fixed(c, _)
+            | ControlCharAction::Tab(c) => c,
+        }
+    }
+}
+
+/// Describes how tabs and other control characters are treated by
+/// [`shape_with_control_policy()`].
+///
+/// By default tabs are shaped as spaces that advance to the next tab stop and
+/// all other characters are left alone. Further rules are added with
+/// [`set()`](ControlCharPolicy::set).
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// // tab stops every 2000 units and a visible, zero width marker for zero
+/// // width spaces
+/// let policy = ControlCharPolicy::new(2000)
+///     .set('\u{200B}', ControlCharAction::Fixed('\u{B7}', 0));
+/// assert_eq!(policy.get('\t'), Some(ControlCharAction::Tab(' ')));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlCharPolicy {
+    tab_interval: Position,
+    actions: HashMap<char, ControlCharAction>,
+}
+
+impl ControlCharPolicy {
+    /// Creates a policy with tab stops every `tab_interval` font units that
+    /// replaces tabs by spaces.
+    pub fn new(tab_interval: Position) -> ControlCharPolicy {
+        let mut actions = HashMap::new();
+        actions.insert('\t', ControlCharAction::Tab(' '));
+        ControlCharPolicy {
+            tab_interval,
+            actions,
+        }
+    }
+
+    /// Sets the action for the character `c`, replacing any previous action.
+    pub fn set(mut self, c: char, action: ControlCharAction) -> ControlCharPolicy {
+        self.actions.insert(c, action);
+        self
+    }
+
+    /// Removes the action for the character `c`, so it is shaped as is.
+    pub fn unset(mut self, c: char) -> ControlCharPolicy {
+        self.actions.remove(&c);
+        self
+    }
+
+    /// Returns the action for the character `c`.
+    pub fn get(&self, c: char) -> Option<ControlCharAction> {
+        self.actions.get(&c).copied()
+    }
+
+    /// Returns the distance between tab stops in font units.
+    pub fn tab_interval(&self) -> Position {
+        self.tab_interval
+    }
+}
+
+/// Shape the contents of `buffer` like [`shape()`] after applying the
+/// control character `policy`.
+///
+/// Characters with an action are replaced by their replacement character
+/// before shaping. Their glyphs keep the cluster of the original character,
+/// so cluster based hit testing and selection work on the original text.
+/// After shaping the advances of fixed width characters and tabs are set.
+/// Tab stops are measured from the logical start of the buffer.
+///
+/// Only the horizontal advances are adjusted, so the policy has no effect on
+/// the widths in vertical text.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let policy = ControlCharPolicy::new(2000);
+///
+/// let buffer = UnicodeBuffer::new().add_str("a\tb");
+/// let output = shape_with_control_policy(&font, buffer, &[], &policy);
+///
+/// let positions = output.get_glyph_positions();
+/// assert_eq!(positions[0].x_advance + positions[1].x_advance, 2000);
+/// assert_eq!(output.get_glyph_infos()[1].cluster, 1);
+/// ```
+pub fn shape_with_control_policy(
+    font: &Font<'_>,
+    mut buffer: UnicodeBuffer,
+    features: &[Feature],
+    policy: &ControlCharPolicy,
+) -> GlyphBuffer {
+    let mut actions = HashMap::new();
+    for info in buffer.0.get_glyph_infos_mut() {
+        let action = std::char::from_u32(info.codepoint).and_then(|c| policy.get(c));
+        if let Some(action) = action {
+            info.codepoint = action.replacement() as u32;
+            actions.insert(info.cluster, action);
+        }
+    }
+    let mut output = shape(font, buffer, features);
+    if actions.is_empty() {
+        return output;
+    }
+
+    let clusters: Vec<u32> = output
+        .get_glyph_infos()
+        .iter()
+        .map(|info| info.cluster)
+        .collect();
+    // visit the glyphs in logical order to measure the tab stops from the
+    // start of the text
+    let order: Box<dyn Iterator<Item = usize>> =
+        if output.get_segment_properties().direction == Direction::Rtl {
+            Box::new((0..clusters.len()).rev())
+        } else {
+            Box::new(0..clusters.len())
+        };
+    let positions = output.0.get_glyph_positions_mut();
+    let mut pen: Position = 0;
+    let mut prev_cluster = None;
+    for i in order {
+        let cluster = clusters[i];
+        let first_in_cluster = prev_cluster != Some(cluster);
+        prev_cluster = Some(cluster);
+        let advance = match actions.get(&cluster) {
+            // only the first glyph of a cluster gets the advance
+            Some(_) if !first_in_cluster => Some(0),
+            Some(ControlCharAction::Fixed(_, width)) => Some(*width),
+            Some(ControlCharAction::Tab(_)) if policy.tab_interval > 0 => {
+                let interval = policy.tab_interval;
+                Some((pen.div_euclid(interval) + 1) * interval - pen)
+            }
+            _ => None,
+        };
+        if let Some(advance) = advance {
+            positions[i].x_advance = advance;
+        }
+        pen += positions[i].x_advance;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Face;
+
+    #[test]
+    fn test_control_policy() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let middle_dot = font.get_nominal_glyph('\u{B7}').unwrap();
+        let policy =
+            ControlCharPolicy::new(2000).set('\u{200B}', ControlCharAction::Fixed('\u{B7}', 0));
+
+        let buffer = UnicodeBuffer::new().add_str("a\tbcd\t\u{200B}e");
+        let output = shape_with_control_policy(&font, buffer, &[], &policy);
+        let glyphs: Vec<_> = output.iter().collect();
+        assert_eq!(glyphs.len(), 8);
+        let mut pen = 0;
+        let mut stops = vec![];
+        for glyph in &glyphs {
+            pen += glyph.x_advance;
+            if glyph.cluster == 1 || glyph.cluster == 5 {
+                stops.push(pen);
+            }
+        }
+        assert_eq!(stops, vec![2000, 4000]);
+        assert_eq!(glyphs[6].gid, middle_dot);
+        assert_eq!((glyphs[6].cluster, glyphs[6].x_advance), (6, 0));
+
+        // right-to-left text measures the tab stops from the right
+        let buffer = UnicodeBuffer::new()
+            .add_str("a\tb")
+            .set_direction(Direction::Rtl);
+        let output = shape_with_control_policy(&font, buffer, &[], &policy);
+        let positions = output.get_glyph_positions();
+        assert_eq!(positions[1].x_advance + positions[2].x_advance, 2000);
+
+        // without a policy entry the tab is shaped as is
+        let buffer = UnicodeBuffer::new().add_str("a\tb");
+        let plain = shape(&font, buffer.clone(), &[]);
+        let unset = ControlCharPolicy::new(1000).unset('\t');
+        let output = shape_with_control_policy(&font, buffer, &[], &unset);
+        assert_eq!(output.content_hash(), plain.content_hash());
+    }
+}
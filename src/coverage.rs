@@ -0,0 +1,250 @@
+// Copyright (c) 2018 Manuel Reinhardt
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+use crate::face::Face;
+
+/// A set of Unicode codepoints stored as sorted ranges.
+///
+/// Fonts usually cover a few large blocks of consecutive codepoints, so storing
+/// the ranges is much more compact than a bitmap or a hash set. Lookups are a
+/// binary search over the ranges and do not call into HarfBuzz, which makes a
+/// `Coverage` well suited for font fallback code that checks many characters
+/// against many fonts.
+///
+/// A `Coverage` is obtained by [`Face::coverage_bitmap()`] or built from
+/// ranges and can be combined with the usual set operations.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::{Coverage, Face};
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let coverage = face.coverage_bitmap();
+/// assert!(coverage.contains('a'));
+/// assert!(!coverage.contains('😀'));
+///
+/// let emoji = Coverage::from_ranges(vec![(0x1F600, 0x1F64F)]);
+/// assert!(coverage.union(&emoji).contains('😀'));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Coverage {
+    // sorted, non-overlapping and non-adjacent inclusive ranges
+    ranges: Vec<(u32, u32)>,
+}
+
+impl Coverage {
+    /// Creates an empty coverage.
+    pub fn new() -> Coverage {
+        Coverage::default()
+    }
+
+    /// Creates a coverage containing all codepoints in the inclusive `ranges`.
+    ///
+    /// The ranges may be unsorted and overlap each other. Ranges whose start
+    /// is larger than their end are ignored.
+    pub fn from_ranges(ranges: impl IntoIterator<Item = (u32, u32)>) -> Coverage {
+        let mut ranges: Vec<(u32, u32)> = ranges
+            .into_iter()
+            .filter(|&(first, last)| first <= last)
+            .collect();
+        ranges.sort_unstable();
+        Coverage {
+            ranges: merge_sorted(ranges),
+        }
+    }
+
+    /// Returns `true` if `c` is contained in the coverage.
+    pub fn contains(&self, c: char) -> bool {
+        self.contains_codepoint(c as u32)
+    }
+
+    /// Returns `true` if `codepoint` is contained in the coverage.
+    pub fn contains_codepoint(&self, codepoint: u32) -> bool {
+        let index = self.ranges.partition_point(|&(_, last)| last < codepoint);
+        self.ranges
+            .get(index)
+            .map(|&(first, _)| first <= codepoint)
+            .unwrap_or(false)
+    }
+
+    /// Returns `true` if the coverage contains no codepoints.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Returns the number of codepoints in the coverage.
+    pub fn len(&self) -> usize {
+        self.ranges
+            .iter()
+            .map(|&(first, last)| (last - first) as usize + 1)
+            .sum()
+    }
+
+    /// Returns the sorted inclusive ranges of codepoints in the coverage.
+    ///
+    /// Adjacent ranges are always merged.
+    pub fn ranges(&self) -> &[(u32, u32)] {
+        &self.ranges
+    }
+
+    /// Returns the codepoints contained in `self` or `other`.
+    pub fn union(&self, other: &Coverage) -> Coverage {
+        let mut ranges = Vec::with_capacity(self.ranges.len() + other.ranges.len());
+        let (mut a, mut b) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+        // merge the two sorted lists
+        loop {
+            let next = match (a.peek(), b.peek()) {
+                (Some(x), Some(y)) if x <= y => a.next(),
+                (Some(_), Some(_)) => b.next(),
+                (Some(_), None) => a.next(),
+                (None, _) => b.next(),
+            };
+            match next {
+                Some(&range) => ranges.push(range),
+                None => break,
+            }
+        }
+        Coverage {
+            ranges: merge_sorted(ranges),
+        }
+    }
+
+    /// Returns the codepoints contained in both `self` and `other`.
+    pub fn intersection(&self, other: &Coverage) -> Coverage {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a_first, a_last) = self.ranges[i];
+            let (b_first, b_last) = other.ranges[j];
+            let first = a_first.max(b_first);
+            let last = a_last.min(b_last);
+            if first <= last {
+                ranges.push((first, last));
+            }
+            if a_last < b_last {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Coverage { ranges }
+    }
+
+    /// Returns the codepoints contained in `self` but not in `other`.
+    pub fn difference(&self, other: &Coverage) -> Coverage {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for &(first, last) in &self.ranges {
+            // `u64` so that the position after `u32::MAX` can be represented
+            let (mut start, last) = (first as u64, last as u64);
+            // skip the ranges of `other` that end before this range
+            while j < other.ranges.len() && (other.ranges[j].1 as u64) < start {
+                j += 1;
+            }
+            for &(cut_first, cut_last) in &other.ranges[j..] {
+                if cut_first as u64 > last {
+                    break;
+                }
+                if cut_first as u64 > start {
+                    ranges.push((start as u32, cut_first - 1));
+                }
+                start = cut_last as u64 + 1;
+                if start > last {
+                    break;
+                }
+            }
+            if start <= last {
+                ranges.push((start as u32, last as u32));
+            }
+        }
+        Coverage { ranges }
+    }
+}
+
+// Merges overlapping and adjacent ranges of a sorted list.
+fn merge_sorted(ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for (first, last) in ranges {
+        match merged.last_mut() {
+            Some(prev) if first <= prev.1.saturating_add(1) => prev.1 = prev.1.max(last),
+            _ => merged.push((first, last)),
+        }
+    }
+    merged
+}
+
+impl std::iter::FromIterator<char> for Coverage {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        Coverage::from_ranges(iter.into_iter().map(|c| (c as u32, c as u32)))
+    }
+}
+
+impl<'a> Face<'a> {
+    /// Returns the set of codepoints that are mapped to glyphs by the `cmap`
+    /// table of the face.
+    ///
+    /// The result is a compact, range-based [`Coverage`] that can be queried
+    /// without calling into HarfBuzz.
+    pub fn coverage_bitmap(&self) -> Coverage {
+        Coverage {
+            ranges: self.unicode_ranges(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_ranges() {
+        let coverage = Coverage::from_ranges(vec![(10, 20), (5, 9), (15, 30), (40, 41), (3, 1)]);
+        assert_eq!(coverage.ranges(), &[(5, 30), (40, 41)]);
+        assert_eq!(coverage.len(), 28);
+        assert!(coverage.contains_codepoint(5));
+        assert!(coverage.contains_codepoint(30));
+        assert!(!coverage.contains_codepoint(31));
+        assert!(!coverage.contains_codepoint(4));
+        assert!(!coverage.contains_codepoint(u32::MAX));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = Coverage::from_ranges(vec![(0, 10), (20, 30), (50, 60)]);
+        let b = Coverage::from_ranges(vec![(5, 25), (30, 40), (60, 60)]);
+
+        assert_eq!(a.union(&b).ranges(), &[(0, 40), (50, 60)]);
+        assert_eq!(
+            a.intersection(&b).ranges(),
+            &[(5, 10), (20, 25), (30, 30), (60, 60)]
+        );
+        assert_eq!(a.difference(&b).ranges(), &[(0, 4), (26, 29), (50, 59)]);
+        assert_eq!(b.difference(&a).ranges(), &[(11, 19), (31, 40)]);
+        assert!(a.difference(&a).is_empty());
+
+        let all = Coverage::from_ranges(vec![(0, u32::MAX)]);
+        assert_eq!(all.difference(&a).ranges()[2], (61, u32::MAX));
+        assert!(all.difference(&all).is_empty());
+        assert_eq!(a.union(&Coverage::new()), a);
+    }
+
+    #[test]
+    fn test_face_coverage() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let coverage = face.coverage_bitmap();
+        for c in "Hello, world!".chars() {
+            assert!(coverage.contains(c));
+        }
+        assert!(!coverage.contains('\u{0627}'));
+
+        let glyph_count = face.glyph_count() as usize;
+        assert!(coverage.len() > 100 && coverage.len() < 2 * glyph_count);
+        assert!(Face::empty().coverage_bitmap().is_empty());
+    }
+}
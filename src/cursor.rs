@@ -0,0 +1,220 @@
+use crate::bindings::{
+    hb_unicode_funcs_get_default, hb_unicode_general_category,
+    HB_UNICODE_GENERAL_CATEGORY_ENCLOSING_MARK, HB_UNICODE_GENERAL_CATEGORY_NON_SPACING_MARK,
+    HB_UNICODE_GENERAL_CATEGORY_SPACING_MARK,
+};
+use crate::buffer::GlyphBuffer;
+use crate::font::Font;
+
+use std::collections::HashMap;
+
+fn is_mark(c: char) -> bool {
+    let category = unsafe { hb_unicode_general_category(hb_unicode_funcs_get_default(), c as u32) };
+    matches!(
+        category,
+        HB_UNICODE_GENERAL_CATEGORY_SPACING_MARK
+            | HB_UNICODE_GENERAL_CATEGORY_ENCLOSING_MARK
+            | HB_UNICODE_GENERAL_CATEGORY_NON_SPACING_MARK
+    )
+}
+
+// Returns the sorted byte offsets into `text` at which the cursor may be
+// placed.
+fn cursor_stops(buffer: &GlyphBuffer, font: &Font<'_>, text: &str) -> Vec<usize> {
+    let direction = buffer.get_segment_properties().direction;
+    // maps each cluster to its glyph if it consists of a single glyph, since
+    // only clusters of a single ligature glyph can be split at its carets
+    let mut single_glyphs: HashMap<usize, Option<u32>> = HashMap::new();
+    for info in buffer.get_glyph_infos() {
+        single_glyphs
+            .entry(info.cluster as usize)
+            .and_modify(|glyph| *glyph = None)
+            .or_insert(Some(info.codepoint));
+    }
+    let mut clusters: Vec<usize> = single_glyphs.keys().copied().collect();
+    clusters.sort_unstable();
+
+    let mut stops = clusters.clone();
+    stops.push(text.len());
+    for (i, &start) in clusters.iter().enumerate() {
+        let end = clusters.get(i + 1).copied().unwrap_or(text.len());
+        let glyph = match single_glyphs[&start] {
+            Some(glyph) => glyph,
+            None => continue,
+        };
+        let carets = font.get_ligature_carets(direction, glyph).len();
+        if carets == 0 {
+            continue;
+        }
+        // each caret separates two components, which start at a character
+        // that is not a mark
+        let components = text[start..end]
+            .char_indices()
+            .skip(1)
+            .filter(|&(_, c)| !is_mark(c))
+            .map(|(offset, _)| start + offset)
+            .take(carets);
+        stops.extend(components);
+    }
+    stops.sort_unstable();
+    stops.dedup();
+    stops
+}
+
+/// Returns the byte offset of the cursor position in `text` that follows
+/// `current` in logical order.
+///
+/// `buffer` must be the result of shaping `text` with `font` (e.g. added with
+/// [`UnicodeBuffer::add_str()`](crate::UnicodeBuffer::add_str)), so that its
+/// clusters are byte offsets into `text`. The cursor only stops at cluster
+/// boundaries, so it never lands between a base character and its marks or
+/// inside a syllable of a complex script. Clusters that consist of a single
+/// ligature glyph are split at the ligature carets of the font, so the cursor
+/// can be placed between the components of e.g. an "fi" ligature.
+///
+/// Returns `text.len()` if there is no following cursor position.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let text = "x\u{301}y";
+/// let output = shape(&font, UnicodeBuffer::new().add_str(text), &[]);
+///
+/// // the combining accent is skipped
+/// assert_eq!(next_cursor_position(&output, &font, text, 0), 3);
+/// assert_eq!(next_cursor_position(&output, &font, text, 3), 4);
+/// assert_eq!(next_cursor_position(&output, &font, text, 4), 4);
+/// ```
+pub fn next_cursor_position(
+    buffer: &GlyphBuffer,
+    font: &Font<'_>,
+    text: &str,
+    current: usize,
+) -> usize {
+    cursor_stops(buffer, font, text)
+        .into_iter()
+        .find(|&stop| stop > current)
+        .unwrap_or(text.len())
+}
+
+/// Returns the byte offset of the cursor position in `text` that precedes
+/// `current` in logical order.
+///
+/// This is the counterpart of [`next_cursor_position()`] and has the same
+/// requirements. Returns `0` if there is no preceding cursor position.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let text = "x\u{301}y";
+/// let output = shape(&font, UnicodeBuffer::new().add_str(text), &[]);
+///
+/// assert_eq!(previous_cursor_position(&output, &font, text, 4), 3);
+/// assert_eq!(previous_cursor_position(&output, &font, text, 3), 0);
+/// ```
+pub fn previous_cursor_position(
+    buffer: &GlyphBuffer,
+    font: &Font<'_>,
+    text: &str,
+    current: usize,
+) -> usize {
+    cursor_stops(buffer, font, text)
+        .into_iter()
+        .rev()
+        .find(|&stop| stop < current)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Direction, Tag};
+    use crate::{shape, Face, UnicodeBuffer};
+
+    #[test]
+    fn test_cursor_movement() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let text = "ae\u{301}x\u{301}\u{302}b";
+        let expected = vec![0, 1, 4, 9, 10];
+
+        for &direction in &[Direction::Ltr, Direction::Rtl] {
+            let buffer = UnicodeBuffer::new().add_str(text).set_direction(direction);
+            let output = shape(&font, buffer, &[]);
+
+            let mut forward = vec![0];
+            while *forward.last().unwrap() < text.len() {
+                let current = *forward.last().unwrap();
+                forward.push(next_cursor_position(&output, &font, text, current));
+            }
+            assert_eq!(forward, expected);
+
+            let mut backward = vec![text.len()];
+            while *backward.last().unwrap() > 0 {
+                let current = *backward.last().unwrap();
+                backward.push(previous_cursor_position(&output, &font, text, current));
+            }
+            backward.reverse();
+            assert_eq!(backward, expected);
+
+            // positions inside a cluster move to its boundaries
+            assert_eq!(next_cursor_position(&output, &font, text, 6), 9);
+            assert_eq!(previous_cursor_position(&output, &font, text, 6), 4);
+        }
+        assert!(font.get_ligature_carets(Direction::Ltr, 1).is_empty());
+    }
+
+    // A `GDEF` table whose `LigCaretList` gives `glyph` one caret at 250.
+    fn ligature_caret_table(glyph: u16) -> Vec<u8> {
+        let mut table = vec![0, 1, 0, 0, 0, 0, 0, 0, 0, 12, 0, 0];
+        // `LigCaretList` with the offsets of its coverage and `LigGlyph`
+        table.extend_from_slice(&[0, 14, 0, 1, 0, 6]);
+        // `LigGlyph` with one `CaretValue` in format 1
+        table.extend_from_slice(&[0, 1, 0, 4, 0, 1, 0, 250]);
+        // coverage in format 1
+        table.extend_from_slice(&[0, 1, 0, 1]);
+        table.extend_from_slice(&glyph.to_be_bytes());
+        table
+    }
+
+    #[test]
+    fn test_ligature_carets() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let font = Font::new(Face::from_file(path, 0).unwrap());
+        let text = "off";
+        let output = shape(&font, UnicodeBuffer::new().add_str(text), &[]);
+        // "ff" is shaped as a single ligature glyph without carets
+        assert_eq!(output.len(), 2);
+        let ligature = output.get_glyph_infos()[1].codepoint;
+        assert_eq!(next_cursor_position(&output, &font, text, 1), 3);
+
+        let original = font.face();
+        let face = Face::from_table_func(move |tag| {
+            if tag == Tag::new('G', 'D', 'E', 'F') {
+                Some(ligature_caret_table(ligature as u16).into())
+            } else {
+                original.table_with_tag(tag)
+            }
+        });
+        let font = Font::new(face);
+        assert_eq!(
+            font.get_ligature_carets(Direction::Ltr, ligature),
+            vec![250]
+        );
+        let output = shape(&font, UnicodeBuffer::new().add_str(text), &[]);
+        assert_eq!(output.get_glyph_infos()[1].codepoint, ligature);
+
+        assert_eq!(next_cursor_position(&output, &font, text, 1), 2);
+        assert_eq!(next_cursor_position(&output, &font, text, 2), 3);
+        assert_eq!(previous_cursor_position(&output, &font, text, 3), 2);
+        assert_eq!(previous_cursor_position(&output, &font, text, 2), 1);
+    }
+}
@@ -0,0 +1,46 @@
+//! Integration with DirectWrite fonts on Windows.
+//!
+//! This module is only available on Windows with the `directwrite` feature.
+
+use std::os::raw::c_void;
+
+use crate::bindings::hb_face_t;
+use crate::common::Owned;
+use crate::face::Face;
+use crate::font::Font;
+
+extern "C" {
+    fn hb_directwrite_face_create(font_face: *mut c_void) -> *mut hb_face_t;
+}
+
+impl Face<'static> {
+    /// Creates a face that reads its tables from a DirectWrite font face.
+    ///
+    /// The tables are requested from DirectWrite when HarfBuzz needs them, so
+    /// the font data is not copied into memory owned by HarfBuzz. This makes
+    /// it cheap to shape with fonts found through DirectWrite's font
+    /// enumeration. The face holds a COM reference to `font_face`, so the
+    /// caller may release its own reference afterwards.
+    ///
+    /// # Safety
+    ///
+    /// `font_face` must be a valid pointer to an `IDWriteFontFace`, e.g. the
+    /// result of `as_raw()` on the `IDWriteFontFace` of the `windows` crate.
+    pub unsafe fn from_dwrite_face(font_face: *mut c_void) -> Owned<Face<'static>> {
+        Owned::from_raw(hb_directwrite_face_create(font_face))
+    }
+}
+
+impl Font<'static> {
+    /// Creates a font for a DirectWrite font face.
+    ///
+    /// This is a shortcut for creating the face with
+    /// [`Face::from_dwrite_face()`] and calling [`Font::new()`].
+    ///
+    /// # Safety
+    ///
+    /// `font_face` must be a valid pointer to an `IDWriteFontFace`.
+    pub unsafe fn from_dwrite_face(font_face: *mut c_void) -> Owned<Font<'static>> {
+        Font::new(Face::from_dwrite_face(font_face))
+    }
+}
@@ -3,7 +3,14 @@
 // This software is released under the MIT License.
 // https://opensource.org/licenses/MIT
 
-//! Contains the `DrawFuncs` trait.
+//! Drawing of glyph outlines.
+//!
+//! Outlines are drawn with [`Font::draw_glyph()`](crate::Font::draw_glyph),
+//! which calls the methods of a [`DrawFuncs`] implementation for every
+//! segment of the outline. For closure based drawing, e.g. to collect
+//! outlines into an existing path type without a wrapper type, set up a
+//! [`DrawFuncsImpl`] and pass it to
+//! [`Font::draw_glyph_with_funcs()`](crate::Font::draw_glyph_with_funcs).
 
 use crate::bindings::{
     hb_draw_funcs_create, hb_draw_funcs_destroy, hb_draw_funcs_get_empty, hb_draw_funcs_reference,
@@ -11,28 +18,41 @@ use crate::bindings::{
     hb_draw_funcs_set_line_to_func, hb_draw_funcs_set_move_to_func,
     hb_draw_funcs_set_quadratic_to_func, hb_draw_funcs_t, hb_draw_state_t,
 };
-use crate::common::{HarfbuzzObject, Owned, Shared};
+use crate::common::{HarfbuzzObject, Owned, PanicPolicy, Shared};
 use crate::font::destroy_box;
 
 use std::os::raw::c_void;
 
 use std::{self, fmt, marker::PhantomData, panic, ptr::NonNull};
 
+/// The state of the outline being drawn before the current drawing operation.
 #[derive(Copy, Clone, Debug)]
 pub struct DrawState {
+    /// Whether a path has been started with `move_to` and not yet closed.
     pub path_open: bool,
+    /// The x coordinate of the start point of the current path.
     pub path_start_x: f32,
+    /// The y coordinate of the start point of the current path.
     pub path_start_y: f32,
+    /// The x coordinate of the current point.
     pub current_x: f32,
+    /// The y coordinate of the current point.
     pub current_y: f32,
 }
 
 /// This Trait specifies the font callbacks that harfbuzz uses when asked
 /// to draw a glyph.
+///
+/// Coordinates are in the scale of the font, with the y axis pointing
+/// upwards. Every path starts with `move_to` and ends with `close_path`.
 #[allow(unused_variables)]
 pub trait DrawFuncs {
+    /// Starts a new path at the given point.
     fn move_to(&mut self, st: &DrawState, to_x: f32, to_y: f32);
+    /// Draws a straight line from the current point to the given point.
     fn line_to(&mut self, st: &DrawState, to_x: f32, to_y: f32);
+    /// Draws a quadratic Bézier curve from the current point to `to_x`,
+    /// `to_y`.
     fn quadratic_to(
         &mut self,
         st: &DrawState,
@@ -41,6 +61,7 @@ pub trait DrawFuncs {
         to_x: f32,
         to_y: f32,
     );
+    /// Draws a cubic Bézier curve from the current point to `to_x`, `to_y`.
     #[allow(clippy::too_many_arguments)]
     fn cubic_to(
         &mut self,
@@ -52,6 +73,8 @@ pub trait DrawFuncs {
         to_x: f32,
         to_y: f32,
     );
+    /// Closes the current path with a straight line to its start point if
+    /// necessary.
     fn close_path(&mut self, st: &DrawState);
 }
 
@@ -157,32 +180,36 @@ hb_callback!(
     }
 );
 
-/// A `DrawFuncsImpl` contains implementations of the font callbacks that
-/// harfbuzz uses to draw a glyph.
+/// A `DrawFuncsImpl` contains implementations of the callbacks that harfbuzz
+/// uses to draw a glyph, which get mutable access to drawing data of type
+/// `T`.
 ///
-/// To use this, set the font funcs from a type that implements the `DrawFuncs`
-/// trait using the `from_trait_impl` constructor.
+/// It is created either from the [`DrawFuncs`] implementation of `T` with
+/// [`from_trait_impl()`](Self::from_trait_impl) or empty with
+/// [`new()`](Self::new) and filled with closures. Callbacks that are not set
+/// do nothing. A `DrawFuncsImpl` can be reused for drawing many glyphs with
+/// [`Font::draw_glyph_with_funcs()`](crate::Font::draw_glyph_with_funcs).
 ///
 /// # Example
 ///
-/// ```ignore
+/// ```
 /// use harfbuzz_rs::*;
 /// use harfbuzz_rs::draw_funcs::DrawFuncsImpl;
 ///
-/// // Dummy struct implementing DrawFuncs
-/// struct MyDrawFuncs {
-///    points: Vec<(f32,f32)>,
-/// }
-/// impl DrawFuncs for MyDrawFuncs {
-///     fn move_to(&self, _: &DrawState, x: f32, y: f32) {
-///         // ...
-///     }
-///     // implementations of other functions...
-/// }
+/// // count the points of the outline
+/// let mut funcs = DrawFuncsImpl::<usize>::new();
+/// funcs.set_move_to_func(|points, _, _, _| *points += 1);
+/// funcs.set_line_to_func(|points, _, _, _| *points += 1);
+/// funcs.set_quadratic_to_func(|points, _, _, _, _, _| *points += 2);
+/// funcs.set_cubic_to_func(|points, _, _, _, _, _, _, _| *points += 3);
 ///
-/// let draw_funcs: Owned<DrawFuncsImpl<MyFontData>> = DrawFuncsImpl::from_trait_impl();
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let mut points = 0;
+/// font.draw_glyph_with_funcs(2, &funcs, &mut points);
+/// assert!(points > 0);
 /// ```
-pub(crate) struct DrawFuncsImpl<T> {
+pub struct DrawFuncsImpl<T> {
     raw: NonNull<hb_draw_funcs_t>,
     marker: PhantomData<T>,
 }
@@ -190,7 +217,6 @@ pub(crate) struct DrawFuncsImpl<T> {
 impl<T> DrawFuncsImpl<T> {
     /// Returns an empty `DrawFuncsImpl`. Every font callback of the returned
     /// `DrawFuncsImpl` gives a null value regardless of its input.
-    #[allow(unused)]
     pub fn empty() -> Shared<DrawFuncsImpl<T>> {
         let raw = unsafe { hb_draw_funcs_get_empty() };
         unsafe { Shared::from_raw_ref(raw) }
@@ -200,52 +226,46 @@ impl<T> DrawFuncsImpl<T> {
 impl<T: DrawFuncs> DrawFuncsImpl<T> {
     /// Create a new `DrawFuncsImpl` from the `DrawFuncs` trait implementation
     /// of `T`.
-    ///
-    /// # Examples
-    ///
-    /// ```ignore
-    /// use harfbuzz_rs::*;
-    /// use harfbuzz_rs::draw_funcs::DrawFuncsImpl;
-    ///
-    /// // Dummy struct implementing DrawFuncs
-    /// struct MyDrawFuncs {
-    ///    points: Vec<(f32,f32)>,
-    /// }
-    /// impl DrawFuncs for MyDrawFuncs {
-    ///     fn move_to(&self, _: &DrawState, x: f32, y: f32) {
-    ///         // ...
-    ///     }
-    ///     // implementations of other functions...
-    /// }
-    ///
-    /// let draw_funcs: Owned<DrawFuncsImpl<MyFontData>> = DrawFuncsImpl::from_trait_impl();
-    /// ```
-    ///
     pub fn from_trait_impl() -> Owned<DrawFuncsImpl<T>> {
+        DrawFuncsImpl::from_trait_impl_with_panic_policy(PanicPolicy::default())
+    }
+
+    /// Like `from_trait_impl` but panics in the trait methods are handled
+    /// according to `policy`.
+    pub fn from_trait_impl_with_panic_policy(policy: PanicPolicy) -> Owned<DrawFuncsImpl<T>> {
         let mut ffuncs = DrawFuncsImpl::new();
-        ffuncs.set_trait_impl();
+        ffuncs.set_trait_impl(&policy);
         ffuncs
     }
 
-    fn set_trait_impl(&mut self) {
-        self.set_move_to_func(|data, st, x, y| data.move_to(&st, x, y));
-        self.set_line_to_func(|data, st, x, y| data.line_to(&st, x, y));
-        self.set_quadratic_to_func(|data, st, cx, cy, x, y| data.quadratic_to(&st, cx, cy, x, y));
-        self.set_cubic_to_func(|data, st, c1x, c1y, c2x, c2y, x, y| {
-            data.cubic_to(&st, c1x, c1y, c2x, c2y, x, y)
+    fn set_trait_impl(&mut self, policy: &PanicPolicy) {
+        let p = policy.clone();
+        self.set_move_to_func(move |data, st, x, y| p.call(|| data.move_to(&st, x, y)));
+        let p = policy.clone();
+        self.set_line_to_func(move |data, st, x, y| p.call(|| data.line_to(&st, x, y)));
+        let p = policy.clone();
+        self.set_quadratic_to_func(move |data, st, cx, cy, x, y| {
+            p.call(|| data.quadratic_to(&st, cx, cy, x, y))
+        });
+        let p = policy.clone();
+        self.set_cubic_to_func(move |data, st, c1x, c1y, c2x, c2y, x, y| {
+            p.call(|| data.cubic_to(&st, c1x, c1y, c2x, c2y, x, y))
         });
-        self.set_close_path_func(|data, st| data.close_path(&st));
+        let p = policy.clone();
+        self.set_close_path_func(move |data, st| p.call(|| data.close_path(&st)));
     }
 }
 
 impl<T> DrawFuncsImpl<T> {
+    /// Creates a `DrawFuncsImpl` without callbacks.
     pub fn new() -> Owned<DrawFuncsImpl<T>> {
         unsafe { Owned::from_raw(hb_draw_funcs_create()) }
     }
 
+    /// Sets the callback for starting a path, see [`DrawFuncs::move_to()`].
     pub fn set_move_to_func<F>(&mut self, func: F)
     where
-        F: Fn(&mut T, DrawState, f32, f32),
+        F: Fn(&mut T, DrawState, f32, f32) + Send + Sync + 'static,
     {
         let user_data = Box::new(func);
         unsafe {
@@ -258,9 +278,10 @@ impl<T> DrawFuncsImpl<T> {
         }
     }
 
+    /// Sets the callback for drawing a quadratic curve, see [`DrawFuncs::quadratic_to()`].
     pub fn set_quadratic_to_func<F>(&mut self, func: F)
     where
-        F: Fn(&mut T, DrawState, f32, f32, f32, f32),
+        F: Fn(&mut T, DrawState, f32, f32, f32, f32) + Send + Sync + 'static,
     {
         let user_data = Box::new(func);
         unsafe {
@@ -273,9 +294,10 @@ impl<T> DrawFuncsImpl<T> {
         }
     }
 
+    /// Sets the callback for drawing a line, see [`DrawFuncs::line_to()`].
     pub fn set_line_to_func<F>(&mut self, func: F)
     where
-        F: Fn(&mut T, DrawState, f32, f32),
+        F: Fn(&mut T, DrawState, f32, f32) + Send + Sync + 'static,
     {
         let user_data = Box::new(func);
         unsafe {
@@ -288,9 +310,10 @@ impl<T> DrawFuncsImpl<T> {
         }
     }
 
+    /// Sets the callback for drawing a cubic curve, see [`DrawFuncs::cubic_to()`].
     pub fn set_cubic_to_func<F>(&mut self, func: F)
     where
-        F: Fn(&mut T, DrawState, f32, f32, f32, f32, f32, f32),
+        F: Fn(&mut T, DrawState, f32, f32, f32, f32, f32, f32) + Send + Sync + 'static,
     {
         let user_data = Box::new(func);
         unsafe {
@@ -303,9 +326,10 @@ impl<T> DrawFuncsImpl<T> {
         }
     }
 
+    /// Sets the callback for closing a path, see [`DrawFuncs::close_path()`].
     pub fn set_close_path_func<F>(&mut self, func: F)
     where
-        F: Fn(&mut T, DrawState),
+        F: Fn(&mut T, DrawState) + Send + Sync + 'static,
     {
         let user_data = Box::new(func);
         unsafe {
@@ -415,11 +439,10 @@ mod tests {
         path.push("testfiles/SourceSansVariable-Roman.ttf");
         let face = Face::from_file(path, 0).expect("Error reading font file.");
         let font = Font::new(face);
-        let shape = TestDrawFuncs {
+        let mut shape = TestDrawFuncs {
             output: String::new(),
         };
-        font.draw_glyph(2, &shape);
-        println!("After");
+        font.draw_glyph(2, &mut shape);
         assert_eq!(shape.output, "M 10 0 L 246 660 L 274 660 L 510 0 L 476 0 L 338 396 Q 317 456, 298.5 510 Q 280 564, 262 626 L 258 626 Q 240 564, 221.5 510 Q 203 456, 182 396 L 42 0 L 10 0 ZM 112 236 L 112 264 L 405 264 L 405 236 L 112 236 Z");
     }
 }
@@ -0,0 +1,227 @@
+use crate::bindings::HB_BUFFER_CONTENT_TYPE_GLYPHS;
+use crate::buffer::{GlyphBuffer, GlyphPosition, SegmentProperties, UnicodeBuffer};
+use crate::common::Direction;
+use crate::font::{Font, Position};
+use crate::shape;
+
+use std::os::raw::c_uint;
+
+/// Where [`ellipsize()`] removes text to make room for the ellipsis.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum EllipsisMode {
+    /// Keep the start of the text and put the ellipsis at its end.
+    #[default]
+    End,
+    /// Keep the start and the end of the text and put the ellipsis in the
+    /// middle.
+    Middle,
+    /// Keep the end of the text and put the ellipsis at its start.
+    Start,
+}
+
+// Shapes the ellipsis character with the segment properties of the text,
+// falling back to three periods if the font does not support it.
+fn shape_ellipsis(font: &Font<'_>, props: SegmentProperties) -> GlyphBuffer {
+    let shape_str = |text| {
+        let buffer = UnicodeBuffer::new()
+            .add_str(text)
+            .set_segment_properties(props);
+        shape(font, buffer, &[])
+    };
+    let ellipsis = shape_str("\u{2026}");
+    if ellipsis
+        .get_glyph_infos()
+        .iter()
+        .any(|info| info.codepoint == 0)
+    {
+        shape_str("...")
+    } else {
+        ellipsis
+    }
+}
+
+/// Shortens the shaped text in `buffer` so that it fits into `max_width`
+/// by removing glyphs and inserting an ellipsis ("…").
+///
+/// Glyphs are only removed in whole clusters and only at positions where
+/// HarfBuzz reports that breaking is safe (see
+/// [`GlyphFlags::unsafe_to_break()`](crate::GlyphFlags::unsafe_to_break)), so
+/// the remaining glyphs are shaped correctly without shaping the text again.
+/// The ellipsis is shaped with `font` and the segment properties of `buffer`.
+/// If the font has no glyph for "…", three periods are used instead. The
+/// ellipsis glyphs get the cluster of the first removed character.
+///
+/// `mode` refers to the logical order of the text, so for right-to-left text
+/// `EllipsisMode::End` keeps the rightmost glyphs. `max_width` is measured
+/// along the direction of the text. If the text fits into `max_width`, a copy
+/// of `buffer` is returned. If not even the ellipsis fits, only the ellipsis is
+/// returned.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let output = shape(&font, UnicodeBuffer::new().add_str("Hello World"), &[]);
+/// let width: Position = output.iter().map(|glyph| glyph.x_advance).sum();
+///
+/// let shortened = ellipsize(&output, &font, width / 2, EllipsisMode::End);
+/// assert!(shortened.len() < output.len());
+/// assert!(shortened.iter().map(|glyph| glyph.x_advance).sum::<Position>() <= width / 2);
+/// assert_eq!(shortened.get_glyph_infos()[0].cluster, 0);
+/// ```
+pub fn ellipsize(
+    buffer: &GlyphBuffer,
+    font: &Font<'_>,
+    max_width: Position,
+    mode: EllipsisMode,
+) -> GlyphBuffer {
+    let props = buffer.0.get_segment_properties();
+    let vertical = matches!(props.direction, Direction::Ttb | Direction::Btt);
+    // glyphs are stored in visual order, which is the reverse of the logical
+    // order for these directions
+    let backwards = matches!(props.direction, Direction::Rtl | Direction::Btt);
+    let advance = |pos: &GlyphPosition| {
+        if vertical {
+            -pos.y_advance
+        } else {
+            pos.x_advance
+        }
+    };
+
+    let infos = buffer.get_glyph_infos();
+    let len = infos.len();
+    // `offsets[i]` is the width of the first `i` glyphs
+    let mut offsets = Vec::with_capacity(len + 1);
+    offsets.push(0);
+    for pos in buffer.get_glyph_positions() {
+        offsets.push(offsets[offsets.len() - 1] + advance(pos));
+    }
+    let total = offsets[len];
+    if total <= max_width {
+        return buffer.clone();
+    }
+
+    let ellipsis = shape_ellipsis(font, props);
+    let ellipsis_width: Position = ellipsis.get_glyph_positions().iter().map(advance).sum();
+    let available = max_width - ellipsis_width;
+
+    // the glyph indices at which the glyphs may be split
+    let safe: Vec<usize> = (0..=len)
+        .filter(|&i| {
+            if i == 0 || i == len {
+                return true;
+            }
+            // the flag is set on the glyphs following the break in logical order
+            let next = if backwards { &infos[i - 1] } else { &infos[i] };
+            infos[i - 1].cluster != infos[i].cluster && !next.glyph_flags().unsafe_to_break()
+        })
+        .collect();
+    let leading_end = |width: Position| {
+        safe.iter()
+            .rev()
+            .copied()
+            .find(|&i| offsets[i] <= width)
+            .unwrap_or(0)
+    };
+    let trailing_start = |width: Position, min: usize| {
+        safe.iter()
+            .copied()
+            .find(|&i| i >= min && total - offsets[i] <= width)
+            .unwrap_or(len)
+    };
+
+    let keep_leading = (mode == EllipsisMode::End) != backwards;
+    let (left_end, right_start) = match mode {
+        EllipsisMode::Middle => {
+            let left_end = leading_end(available / 2);
+            let right_start = trailing_start(available - offsets[left_end], left_end);
+            (left_end, right_start)
+        }
+        _ if keep_leading => (leading_end(available), len),
+        _ => (0, trailing_start(available, 0)),
+    };
+    let cluster = infos[left_end..right_start]
+        .iter()
+        .map(|info| info.cluster)
+        .min()
+        .unwrap_or(0);
+
+    let mut output = buffer.0.create_similar();
+    output.append(&buffer.0, 0, left_end as c_uint);
+    output.append(&ellipsis.0, 0, c_uint::MAX);
+    output.append(&buffer.0, right_start as c_uint, len as c_uint);
+    output.set_content_type(HB_BUFFER_CONTENT_TYPE_GLYPHS);
+    for info in &mut output.get_glyph_infos_mut()[left_end..left_end + ellipsis.len()] {
+        info.cluster = cluster;
+    }
+    GlyphBuffer(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Face;
+
+    fn width(buffer: &GlyphBuffer) -> Position {
+        buffer.iter().map(|glyph| glyph.x_advance).sum()
+    }
+
+    #[test]
+    fn test_ellipsize() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let output = shape(&font, UnicodeBuffer::new().add_str("Hello World"), &[]);
+        let total = width(&output);
+        let ellipsis = shape_ellipsis(&font, output.0.get_segment_properties());
+        let ellipsis_gid = ellipsis.get_glyph_infos()[0].codepoint;
+
+        let same = ellipsize(&output, &font, total, EllipsisMode::End);
+        assert_eq!(same.content_hash(), output.content_hash());
+
+        let max_width = total * 2 / 3;
+        let end = ellipsize(&output, &font, max_width, EllipsisMode::End);
+        assert!(width(&end) <= max_width);
+        let last = end.get_glyph_infos().last().unwrap();
+        assert_eq!(last.codepoint, ellipsis_gid);
+        assert_eq!(last.cluster as usize, end.len() - 1);
+        for (a, b) in end.iter().zip(output.iter()).take(end.len() - 1) {
+            assert_eq!(a, b);
+        }
+
+        let start = ellipsize(&output, &font, max_width, EllipsisMode::Start);
+        assert!(width(&start) <= max_width);
+        assert_eq!(start.get_glyph_infos()[0].codepoint, ellipsis_gid);
+        assert_eq!(start.get_glyph_infos()[0].cluster, 0);
+        assert_eq!(start.get_glyph_infos().last().unwrap().cluster, 10);
+
+        let middle = ellipsize(&output, &font, max_width, EllipsisMode::Middle);
+        assert!(width(&middle) <= max_width);
+        assert_eq!(middle.get_glyph_infos()[0].cluster, 0);
+        assert_eq!(middle.get_glyph_infos().last().unwrap().cluster, 10);
+        assert!(middle.iter().any(|glyph| glyph.gid == ellipsis_gid));
+
+        let tiny = ellipsize(&output, &font, 1, EllipsisMode::End);
+        assert_eq!(tiny.content_hash(), ellipsis.content_hash());
+    }
+
+    #[test]
+    fn test_ellipsize_rtl() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let buffer = UnicodeBuffer::new()
+            .add_str("abcdefghijkl")
+            .set_direction(Direction::Rtl);
+        let output = shape(&font, buffer, &[]);
+        let max_width = width(&output) * 2 / 3;
+
+        // the logical start is on the right
+        let end = ellipsize(&output, &font, max_width, EllipsisMode::End);
+        assert!(width(&end) <= max_width);
+        assert_eq!(end.get_glyph_infos().last().unwrap().cluster, 0);
+        let first = end.get_glyph_infos()[0];
+        assert_eq!(first.cluster, end.get_glyph_infos()[1].cluster + 1);
+    }
+}
@@ -0,0 +1,201 @@
+//! Segmentation of text into emoji and regular text.
+//!
+//! This module is only available with the `emoji` feature.
+
+use std::ops::Range;
+
+use unicode_properties::emoji::{is_regional_indicator, is_tag_character, EmojiStatus};
+use unicode_properties::UnicodeEmoji;
+
+const ZWJ: char = '\u{200D}';
+const TEXT_PRESENTATION_SELECTOR: char = '\u{FE0E}';
+const EMOJI_PRESENTATION_SELECTOR: char = '\u{FE0F}';
+const COMBINING_ENCLOSING_KEYCAP: char = '\u{20E3}';
+const CANCEL_TAG: char = '\u{E007F}';
+
+/// A run of text that is either presented as emoji or as regular text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EmojiRun {
+    /// The byte range of the run in the segmented text.
+    pub range: Range<usize>,
+    /// `true` if the run consists of emoji that should be shaped with a color
+    /// emoji font.
+    pub is_emoji: bool,
+}
+
+fn has_emoji_presentation(c: char) -> bool {
+    matches!(
+        c.emoji_status(),
+        EmojiStatus::EmojiPresentation
+            | EmojiStatus::EmojiPresentationAndModifierBase
+            | EmojiStatus::EmojiPresentationAndEmojiComponent
+            | EmojiStatus::EmojiPresentationAndModifierAndEmojiComponent
+    )
+}
+
+fn is_emoji_modifier(c: char) -> bool {
+    c.emoji_status() == EmojiStatus::EmojiPresentationAndModifierAndEmojiComponent
+}
+
+// Returns the number of chars of the emoji element (a flag, keycap, tag
+// sequence or single emoji with its presentation selector or modifier) at
+// the start of `chars` and whether it is presented as emoji.
+fn emoji_element(chars: &[char]) -> Option<(usize, bool)> {
+    let c = *chars.first()?;
+    if is_regional_indicator(c) {
+        let len = if matches!(chars.get(1), Some(&c) if is_regional_indicator(c)) {
+            2
+        } else {
+            1
+        };
+        return Some((len, true));
+    }
+    if !c.is_emoji_char() {
+        return None;
+    }
+
+    let mut len = 1;
+    let mut emoji = has_emoji_presentation(c);
+    match chars.get(1) {
+        Some(&EMOJI_PRESENTATION_SELECTOR) => {
+            len += 1;
+            emoji = true;
+        }
+        Some(&TEXT_PRESENTATION_SELECTOR) => {
+            len += 1;
+            emoji = false;
+        }
+        Some(&modifier) if is_emoji_modifier(modifier) => {
+            len += 1;
+            emoji = true;
+        }
+        _ => {}
+    }
+    if matches!(c, '0'..='9' | '#' | '*') && chars.get(len) == Some(&COMBINING_ENCLOSING_KEYCAP) {
+        len += 1;
+        emoji = true;
+    }
+    let tags = chars[len..]
+        .iter()
+        .take_while(|&&c| is_tag_character(c))
+        .count();
+    if tags > 0 && chars[len + tags - 1] == CANCEL_TAG {
+        len += tags;
+        emoji = true;
+    }
+    Some((len, emoji))
+}
+
+// Like `emoji_element()`, but includes the elements joined to the first one
+// with zero width joiners.
+fn emoji_sequence(chars: &[char]) -> Option<(usize, bool)> {
+    let (mut len, mut emoji) = emoji_element(chars)?;
+    while chars.get(len) == Some(&ZWJ) {
+        match emoji_element(&chars[len + 1..]) {
+            Some((next, _)) => {
+                len += 1 + next;
+                emoji = true;
+            }
+            None => break,
+        }
+    }
+    Some((len, emoji))
+}
+
+/// Splits `text` into runs of emoji and runs of regular text, e.g. to shape
+/// the emoji with a color emoji font.
+///
+/// Emoji ZWJ sequences, flags, keycaps, tag sequences and emoji with
+/// modifiers are never split. Characters that have emoji and text
+/// presentations, like digits or `©`, are emoji only if they are followed by
+/// the emoji presentation selector U+FE0F or are part of such a sequence.
+/// Emoji followed by the text presentation selector U+FE0E are text.
+/// Adjacent runs are never of the same kind and together they cover all of
+/// `text`.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let text = "Hi 👋🏽! 1\u{FE0F}\u{20E3} 🇩🇪";
+/// let runs = segment_emoji(text);
+/// let kinds: Vec<(&str, bool)> = runs
+///     .iter()
+///     .map(|run| (&text[run.range.clone()], run.is_emoji))
+///     .collect();
+/// assert_eq!(
+///     kinds,
+///     vec![
+///         ("Hi ", false),
+///         ("👋🏽", true),
+///         ("! ", false),
+///         ("1\u{FE0F}\u{20E3}", true),
+///         (" ", false),
+///         ("🇩🇪", true),
+///     ]
+/// );
+/// ```
+pub fn segment_emoji(text: &str) -> Vec<EmojiRun> {
+    let chars: Vec<char> = text.chars().collect();
+    let offsets: Vec<usize> = text
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain(Some(text.len()))
+        .collect();
+
+    let mut runs: Vec<EmojiRun> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (len, is_emoji) = emoji_sequence(&chars[i..]).unwrap_or((1, false));
+        let range = offsets[i]..offsets[i + len];
+        match runs.last_mut() {
+            Some(run) if run.is_emoji == is_emoji => run.range.end = range.end,
+            _ => runs.push(EmojiRun { range, is_emoji }),
+        }
+        i += len;
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(text: &str) -> Vec<(&str, bool)> {
+        segment_emoji(text)
+            .into_iter()
+            .map(|run| (&text[run.range], run.is_emoji))
+            .collect()
+    }
+
+    #[test]
+    fn test_segment_emoji() {
+        assert!(segment_emoji("").is_empty());
+        assert_eq!(segments("abc"), vec![("abc", false)]);
+        // digits and © default to text presentation
+        assert_eq!(segments("1 © 2"), vec![("1 © 2", false)]);
+        assert_eq!(segments("©\u{FE0F}"), vec![("©\u{FE0F}", true)]);
+        // a text presentation selector turns emoji into text
+        assert_eq!(segments("a😀\u{FE0E}"), vec![("a😀\u{FE0E}", false)]);
+
+        // family: man, woman, girl
+        let family = "👨\u{200D}👩\u{200D}👧";
+        assert_eq!(
+            segments(&format!("x{}y", family)),
+            vec![("x", false), (family, true), ("y", false)]
+        );
+        // adjacent emoji form a single run
+        let flags = "🇫🇷🇯🇵";
+        assert_eq!(segments(flags), vec![(flags, true)]);
+        // England
+        let england = "🏴\u{E0067}\u{E0062}\u{E0065}\u{E006E}\u{E0067}\u{E007F}";
+        assert_eq!(segments(england), vec![(england, true)]);
+        assert_eq!(segments("#\u{20E3}"), vec![("#\u{20E3}", true)]);
+        // a dangling joiner stays with the following text
+        assert_eq!(
+            segments("😀\u{200D}a"),
+            vec![("😀", true), ("\u{200D}a", false)]
+        );
+    }
+}
@@ -1,17 +1,33 @@
-use std::os::raw::c_void;
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::{c_uint, c_void};
 use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use std::marker::PhantomData;
 use std::path::Path;
 
 use crate::bindings::{
-    hb_blob_t, hb_face_create, hb_face_create_for_tables, hb_face_destroy, hb_face_get_empty,
-    hb_face_get_glyph_count, hb_face_get_index, hb_face_get_upem, hb_face_reference,
-    hb_face_reference_blob, hb_face_reference_table, hb_face_set_glyph_count, hb_face_set_upem,
-    hb_face_t, hb_tag_t,
+    hb_blob_t, hb_face_collect_unicodes, hb_face_create, hb_face_create_for_tables,
+    hb_face_destroy, hb_face_get_empty, hb_face_get_glyph_count, hb_face_get_index,
+    hb_face_get_table_tags, hb_face_get_upem, hb_face_get_user_data, hb_face_reference,
+    hb_face_reference_blob, hb_face_reference_table, hb_face_set_get_table_tags_func,
+    hb_face_set_glyph_count, hb_face_set_upem, hb_face_set_user_data, hb_face_t,
+    hb_ot_color_has_png, hb_ot_layout_collect_lookups, hb_ot_layout_has_positioning,
+    hb_ot_layout_has_substitution, hb_ot_layout_language_get_feature_indexes,
+    hb_ot_layout_language_get_required_feature_index, hb_ot_layout_lookups_substitute_closure,
+    hb_ot_layout_script_select_language, hb_ot_layout_table_select_script,
+    hb_ot_var_get_named_instance_count, hb_set_create, hb_set_destroy, hb_set_next_range, hb_tag_t,
 };
-use crate::blob::Blob;
-use crate::common::{HarfbuzzObject, Owned, Shared, Tag};
+use crate::blob::{fnv1a, Blob, FNV_OFFSET_BASIS};
+use crate::common::{HarfbuzzObject, Language, Owned, Script, Shared, Tag, UserDataKey};
+use crate::ot;
+use crate::set::Set;
+
+// The key of the memoized digest of a face.
+static DIGEST_KEY: UserDataKey<u64> = UserDataKey::new();
+static ID_KEY: UserDataKey<u64> = UserDataKey::new();
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
 
 /// A wrapper around `hb_face_t`.
 ///
@@ -20,7 +36,7 @@ use crate::common::{HarfbuzzObject, Owned, Shared, Tag};
 /// > exactly, a font face represents a single face in a binary font file. Font
 /// > faces are typically built from a binary blob and a face index. Font faces
 /// > are used to create fonts.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Face<'a> {
     raw: NonNull<hb_face_t>,
     marker: PhantomData<&'a [u8]>,
@@ -36,6 +52,28 @@ impl<'a> Face<'a> {
         unsafe { Owned::from_raw(hb_face) }
     }
 
+    /// Returns the raw `hb_face_t` pointer of the face for FFI calls.
+    ///
+    /// This is the same as [`HarfbuzzObject::as_raw()`] without the need to
+    /// import the trait. The reference count is not changed, so the pointer
+    /// must not be used after the face is destroyed.
+    pub fn as_ptr(&self) -> *mut hb_face_t {
+        self.as_raw()
+    }
+
+    /// Wraps a raw `hb_face_t` pointer, e.g. one created by a C library.
+    ///
+    /// The reference count is increased, so the caller still has to release
+    /// its own reference.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a valid pointer to a `hb_face_t` whose data lives at least
+    /// as long as `'a`.
+    pub unsafe fn from_ptr(ptr: *mut hb_face_t) -> Shared<Face<'a>> {
+        Shared::from_raw_ref(ptr)
+    }
+
     /// Returns a "null" face.
     pub fn empty() -> Owned<Face<'static>> {
         let hb_face = unsafe { hb_face_get_empty() };
@@ -96,6 +134,60 @@ impl<'a> Face<'a> {
         }
     }
 
+    /// Creates a face from the data of its tables and checks that they
+    /// form a usable font.
+    ///
+    /// This is useful for assembling fonts programmatically, e.g. in a font
+    /// build pipeline. The table data is borrowed, not copied. The `head`,
+    /// `maxp` and `cmap` tables are required, `head` and `maxp` must be well
+    /// formed and the `loca` and `hmtx` tables, if present, must cover all
+    /// glyphs counted in `maxp`. An `hmtx` table also requires an `hhea`
+    /// table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Face, FaceTablesError, Tag};
+    ///
+    /// let font = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let tables: Vec<_> = font
+    ///     .table_tags()
+    ///     .into_iter()
+    ///     .map(|tag| (tag, font.table_with_tag(tag).unwrap()))
+    ///     .collect();
+    ///
+    /// let face = Face::from_tables(tables.iter().map(|(tag, blob)| (*tag, blob.as_ref()))).unwrap();
+    /// assert_eq!(face.glyph_count(), font.glyph_count());
+    ///
+    /// let without_cmap = tables
+    ///     .iter()
+    ///     .filter(|(tag, _)| *tag != Tag::from(b"cmap"))
+    ///     .map(|(tag, blob)| (*tag, blob.as_ref()));
+    /// assert_eq!(
+    ///     Face::from_tables(without_cmap).unwrap_err(),
+    ///     FaceTablesError::MissingTable(Tag::from(b"cmap"))
+    /// );
+    /// ```
+    pub fn from_tables<I>(tables: I) -> Result<Owned<Face<'a>>, FaceTablesError>
+    where
+        I: IntoIterator<Item = (Tag, &'a [u8])>,
+    {
+        let mut map = HashMap::new();
+        for (tag, data) in tables {
+            if map.insert(tag, data).is_some() {
+                return Err(FaceTablesError::DuplicateTable(tag));
+            }
+        }
+        validate_tables(&map)?;
+        let mut tags: Vec<hb_tag_t> = map.keys().map(|tag| tag.0).collect();
+        tags.sort_unstable();
+        let face = Face::from_table_func(move |tag| {
+            map.get(&tag).map(|&data| Blob::with_bytes(data).into())
+        });
+        set_table_tags(&face, tags);
+        Ok(face)
+    }
+
     pub fn face_data(&self) -> Shared<Blob<'a>> {
         unsafe {
             let raw_blob = hb_face_reference_blob(self.as_raw());
@@ -103,6 +195,109 @@ impl<'a> Face<'a> {
         }
     }
 
+    /// Returns a hash that identifies the face by its font data and index.
+    ///
+    /// This is the [`digest`](Blob::digest) of the face data combined with the
+    /// face index, so the same face of the same font file has the same digest
+    /// no matter where it was loaded from. Faces created with
+    /// [`Face::from_tables()`] have no face data and are hashed by their tables
+    /// instead. Faces created with [`Face::from_table_func()`] can not list
+    /// their tables (see [`Face::table_tags()`]) and have no digest, so `None`
+    /// is returned for them. The digest is computed on the first call and
+    /// stored with the face, so later calls are cheap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::Face;
+    ///
+    /// let path = "testfiles/SourceSansVariable-Roman.ttf";
+    /// let data = std::fs::read(path).unwrap();
+    /// let face = Face::from_file(path, 0).unwrap();
+    /// assert!(face.digest().is_some());
+    /// assert_eq!(face.digest(), Face::from_bytes(&data, 0).digest());
+    /// ```
+    pub fn digest(&self) -> Option<u64> {
+        let stored = unsafe { hb_face_get_user_data(self.as_raw(), DIGEST_KEY.as_raw()) };
+        if let Some(digest) = unsafe { (stored as *const u64).as_ref() } {
+            return Some(*digest);
+        }
+        let digest = self.compute_digest()?;
+        extern "C" fn destroy(data: *mut c_void) {
+            unsafe { drop(Box::from_raw(data as *mut u64)) };
+        }
+        let data = Box::into_raw(Box::new(digest));
+        let stored = unsafe {
+            hb_face_set_user_data(
+                self.as_raw(),
+                DIGEST_KEY.as_raw(),
+                data as *mut _,
+                Some(destroy),
+                0,
+            ) == 1
+        };
+        if !stored {
+            // e.g. another thread stored the digest first
+            unsafe { drop(Box::from_raw(data)) };
+        }
+        Some(digest)
+    }
+
+    // Returns a number that identifies the face for as long as the process
+    // runs.
+    //
+    // Unlike addresses, the numbers are never reused after a face is
+    // destroyed, so data cached for a face can not be mistaken for data of a
+    // later face.
+    pub(crate) fn id(&self) -> u64 {
+        let stored = unsafe { hb_face_get_user_data(self.as_raw(), ID_KEY.as_raw()) };
+        if let Some(id) = unsafe { (stored as *const u64).as_ref() } {
+            return *id;
+        }
+        extern "C" fn destroy(data: *mut c_void) {
+            unsafe { drop(Box::from_raw(data as *mut u64)) };
+        }
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let data = Box::into_raw(Box::new(id));
+        let stored = unsafe {
+            hb_face_set_user_data(
+                self.as_raw(),
+                ID_KEY.as_raw(),
+                data as *mut _,
+                Some(destroy),
+                0,
+            ) == 1
+        };
+        if stored {
+            return id;
+        }
+        unsafe { drop(Box::from_raw(data)) };
+        // another thread stored an id first, or the face is the immutable empty
+        // face, which gets a new id every time
+        let stored = unsafe { hb_face_get_user_data(self.as_raw(), ID_KEY.as_raw()) };
+        unsafe { (stored as *const u64).as_ref() }.map_or(id, |id| *id)
+    }
+
+    fn compute_digest(&self) -> Option<u64> {
+        let data = self.face_data();
+        let hash = if data.is_empty() {
+            let tags = self.table_tags();
+            if tags.is_empty() {
+                return None;
+            }
+            tags.into_iter().fold(FNV_OFFSET_BASIS, |hash, tag| {
+                let hash = fnv1a(hash, &tag.0.to_be_bytes());
+                match self.table_with_tag(tag) {
+                    Some(table) => fnv1a(hash, table.get_data()),
+                    None => hash,
+                }
+            })
+        } else {
+            data.digest()
+        };
+        Some(fnv1a(hash, &self.index().to_le_bytes()))
+    }
+
     /// Returns the slice of bytes for the table named `tag` or None if there is
     /// no table with `tag`.
     pub fn table_with_tag(&self, tag: impl Into<Tag>) -> Option<Shared<Blob<'a>>> {
@@ -142,6 +337,288 @@ impl<'a> Face<'a> {
         unsafe { hb_face_get_glyph_count(self.as_raw()) }
     }
 
+    /// Returns the number of named instances (e.g. "SemiBold Condensed") of
+    /// a variable font defined in its `fvar` table.
+    ///
+    /// See [`Font::set_var_named_instance()`](crate::Font::set_var_named_instance).
+    pub fn named_instance_count(&self) -> u32 {
+        unsafe { hb_ot_var_get_named_instance_count(self.as_raw()) }
+    }
+
+    /// Returns the tags of all tables contained in the face.
+    ///
+    /// Faces created with `Face::from_table_func` cannot enumerate their tables
+    /// and return an empty `Vec`, while faces created with
+    /// [`Face::from_tables()`] return the tags of their tables in ascending
+    /// order.
+    pub fn table_tags(&self) -> Vec<Tag> {
+        let total = unsafe {
+            hb_face_get_table_tags(self.as_raw(), 0, std::ptr::null_mut(), std::ptr::null_mut())
+        };
+        let mut count = total;
+        let mut tags: Vec<Tag> = Vec::with_capacity(total as usize);
+        unsafe {
+            hb_face_get_table_tags(self.as_raw(), 0, &mut count, tags.as_mut_ptr() as *mut _);
+            tags.set_len(count as usize);
+        }
+        tags
+    }
+
+    /// Returns an estimate of the number of bytes of font data referenced by
+    /// this face.
+    ///
+    /// For faces backed by a single blob (e.g. created by `Face::new` or
+    /// `Face::from_file`) this is the size of that blob, since all tables are
+    /// sub-blobs of it. For faces created with [`Face::from_tables()`] the
+    /// sizes of their tables are summed up. Faces created with
+    /// `Face::from_table_func` can not list their tables (see
+    /// [`Face::table_tags()`]), so their size is unknown and `0` is returned.
+    /// HarfBuzz' own bookkeeping structures are not included, so the result
+    /// is only meant as a rough measure for cache budgeting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::Face;
+    ///
+    /// let path = "testfiles/SourceSansVariable-Roman.ttf";
+    /// let face = Face::from_file(path, 0).expect("could not load face");
+    /// let file_size = std::fs::metadata(path).unwrap().len() as usize;
+    /// assert_eq!(face.approximate_memory_usage(), file_size);
+    /// ```
+    pub fn approximate_memory_usage(&self) -> usize {
+        let face_data_len = self.face_data().len();
+        if face_data_len > 0 {
+            return face_data_len;
+        }
+        self.table_tags()
+            .into_iter()
+            .filter_map(|tag| self.table_with_tag(tag))
+            .map(|blob| blob.len())
+            .sum()
+    }
+
+    // Returns the inclusive ranges of codepoints mapped by the `cmap` table.
+    pub(crate) fn unicode_ranges(&self) -> Vec<(u32, u32)> {
+        const SET_VALUE_INVALID: u32 = u32::MAX;
+
+        let mut ranges = Vec::new();
+        unsafe {
+            let set = hb_set_create();
+            hb_face_collect_unicodes(self.as_raw(), set);
+            let mut first = SET_VALUE_INVALID;
+            let mut last = SET_VALUE_INVALID;
+            while hb_set_next_range(set, &mut first, &mut last) == 1 {
+                ranges.push((first, last));
+            }
+            hb_set_destroy(set);
+        }
+        ranges
+    }
+
+    /// Returns `true` if the face has a `GSUB` table with glyph substitution
+    /// rules.
+    pub fn has_substitution(&self) -> bool {
+        unsafe { hb_ot_layout_has_substitution(self.as_raw()) == 1 }
+    }
+
+    /// Returns `true` if the face has a `GPOS` table with glyph positioning
+    /// rules.
+    pub fn has_positioning(&self) -> bool {
+        unsafe { hb_ot_layout_has_positioning(self.as_raw()) == 1 }
+    }
+
+    /// Returns the indices of the lookups in the layout table `table` (`GSUB`
+    /// or `GPOS`) that belong to the given `features` in any script and
+    /// language system.
+    ///
+    /// If `features` is empty, the lookups of all features are returned.
+    pub fn collect_lookups(&self, table: impl Into<Tag>, features: &[Tag]) -> Owned<Set> {
+        let lookups = Set::new();
+        // the features are passed as a zero terminated array
+        let features: Option<Vec<hb_tag_t>> = if features.is_empty() {
+            None
+        } else {
+            Some(features.iter().map(|tag| tag.0).chain(Some(0)).collect())
+        };
+        unsafe {
+            hb_ot_layout_collect_lookups(
+                self.as_raw(),
+                table.into().0,
+                std::ptr::null(),
+                std::ptr::null(),
+                features
+                    .as_ref()
+                    .map_or(std::ptr::null(), |tags| tags.as_ptr()),
+                lookups.as_raw(),
+            )
+        };
+        lookups
+    }
+
+    /// Returns all glyphs that can result from substituting the glyphs in
+    /// `initial` by the `GSUB` lookups of `features`, including the glyphs of
+    /// `initial` themselves.
+    ///
+    /// The closure is transitive, so glyphs produced by a substitution are
+    /// substituted again. This is the set of glyphs a subsetter has to keep or
+    /// a glyph atlas has to preload to render any text using the glyphs of
+    /// `initial`. If `features` is empty, the lookups of all features are
+    /// used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Face, Font, Owned, Set, Tag};
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let initial: Owned<Set> = "abc".chars().filter_map(|c| font.get_nominal_glyph(c)).collect();
+    ///
+    /// let closure = font.face().closure_glyphs(&initial, &[Tag::from(b"smcp")]);
+    /// assert!(initial.iter().all(|glyph| closure.contains(glyph)));
+    /// assert!(closure.len() >= initial.len());
+    /// ```
+    pub fn closure_glyphs(&self, initial: &Set, features: &[Tag]) -> Owned<Set> {
+        let lookups = self.collect_lookups(b"GSUB", features);
+        let glyphs = initial.copy();
+        unsafe {
+            hb_ot_layout_lookups_substitute_closure(
+                self.as_raw(),
+                lookups.as_raw(),
+                glyphs.as_raw(),
+            )
+        };
+        glyphs
+    }
+
+    /// Returns `true` if the `GSUB` table of the face contains substitution
+    /// features that apply to text in `script` and `language`.
+    ///
+    /// The script and language system is selected the same way as during
+    /// shaping. So if the font has no rules specifically for `script`, the
+    /// rules of its default script (`DFLT`) are considered, because those are
+    /// what the shaper would apply. If `language` is `None` or not supported
+    /// by the font, the default language system of the script is used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Face, Script, Tag};
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let latin = Script::from_iso15924_tag(Tag::new('L', 'a', 't', 'n'));
+    /// assert!(face.has_substitution_for(latin, None));
+    /// ```
+    pub fn has_substitution_for(&self, script: Script, language: Option<Language>) -> bool {
+        self.has_layout_features(Tag::new('G', 'S', 'U', 'B'), script, language, false)
+    }
+
+    /// Returns `true` if the `GPOS` table of the face contains positioning
+    /// features that apply to text in `script` and `language`.
+    ///
+    /// See [`Self::has_substitution_for()`] for how the script and language
+    /// are matched.
+    pub fn has_positioning_for(&self, script: Script, language: Option<Language>) -> bool {
+        self.has_layout_features(Tag::new('G', 'P', 'O', 'S'), script, language, false)
+    }
+
+    // If `exact` is `true` only rules for `script` itself are considered and
+    // the default script is ignored.
+    pub(crate) fn has_layout_features(
+        &self,
+        table: Tag,
+        script: Script,
+        language: Option<Language>,
+        exact: bool,
+    ) -> bool {
+        // Constants from `hb-ot-layout.h` which are not part of the bindings.
+        const NO_SCRIPT_INDEX: u32 = 0xFFFF;
+        const NO_FEATURE_INDEX: u32 = 0xFFFF;
+
+        let (script_tags, language_tags) = ot::tags_from_script_and_language(script, language);
+        let mut script_index = NO_SCRIPT_INDEX;
+        let mut language_index = 0;
+        let mut required_feature_index = NO_FEATURE_INDEX;
+        unsafe {
+            // This falls back to the default script if none of the tags is
+            // found, like the shaper does.
+            let found = hb_ot_layout_table_select_script(
+                self.as_raw(),
+                table.0,
+                script_tags.len() as u32,
+                script_tags.as_ptr() as *const hb_tag_t,
+                &mut script_index,
+                std::ptr::null_mut(),
+            );
+            if script_index == NO_SCRIPT_INDEX || (exact && found == 0) {
+                return false;
+            }
+            hb_ot_layout_script_select_language(
+                self.as_raw(),
+                table.0,
+                script_index,
+                language_tags.len() as u32,
+                language_tags.as_ptr() as *const hb_tag_t,
+                &mut language_index,
+            );
+            hb_ot_layout_language_get_required_feature_index(
+                self.as_raw(),
+                table.0,
+                script_index,
+                language_index,
+                &mut required_feature_index,
+            );
+            let feature_count = hb_ot_layout_language_get_feature_indexes(
+                self.as_raw(),
+                table.0,
+                script_index,
+                language_index,
+                0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            );
+            feature_count > 0 || required_feature_index != NO_FEATURE_INDEX
+        }
+    }
+
+    /// Returns `true` if the face contains color bitmap glyphs in PNG format
+    /// (`CBDT` or `sbix` tables).
+    pub fn has_png_glyphs(&self) -> bool {
+        unsafe { hb_ot_color_has_png(self.as_raw()) == 1 }
+    }
+
+    /// Returns the embedded bitmap strikes of the face.
+    ///
+    /// The strikes are read from the `CBLC` table (color bitmaps) or, if that
+    /// does not exist, from the `EBLC` table (monochrome and grayscale
+    /// bitmaps). If the face contains no embedded bitmaps an empty `Vec` is
+    /// returned.
+    pub fn bitmap_strikes(&self) -> Vec<BitmapStrike> {
+        self.table_with_tag(b"CBLC")
+            .or_else(|| self.table_with_tag(b"EBLC"))
+            .map(|table| BitmapStrike::parse_location_table(&table))
+            .unwrap_or_default()
+    }
+
+    /// Chooses the color bitmap strike that HarfBuzz would use to render
+    /// glyphs at `ppem` pixels per em.
+    ///
+    /// This is the smallest strike whose larger ppem value is at least `ppem`
+    /// or, if there is no such strike, the largest strike. A `ppem` of zero
+    /// selects the largest strike. HarfBuzz only renders color bitmaps, so
+    /// only the strikes of the `CBLC` table are considered. Returns `None` if
+    /// the face contains no such strikes.
+    ///
+    /// See also [`Font::bitmap_strike()`](struct.Font.html#method.bitmap_strike).
+    pub fn choose_bitmap_strike(&self, ppem: u32) -> Option<BitmapStrike> {
+        let strikes = self
+            .table_with_tag(b"CBLC")
+            .map(|table| BitmapStrike::parse_location_table(&table))
+            .unwrap_or_default();
+        BitmapStrike::choose(&strikes, ppem)
+    }
+
     #[cfg(variation_support)]
     pub fn get_variation_axis_infos(&self) -> Vec<VariationAxisInfo> {
         let mut count = unsafe { hb_ot_var_get_axis_count(self.as_raw()) };
@@ -159,6 +636,78 @@ impl<'a> Face<'a> {
 #[repr(transparent)]
 pub struct VariationAxisInfo(pub hb_ot_var_axis_info_t);
 
+/// An embedded bitmap strike, i.e. a set of bitmap glyphs for one specific
+/// size, as found in the `CBLC` and `EBLC` tables.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BitmapStrike {
+    /// Horizontal pixels per em for which the strike was designed.
+    pub ppem_x: u8,
+    /// Vertical pixels per em for which the strike was designed.
+    pub ppem_y: u8,
+    /// Bit depth of the bitmaps (1, 2, 4 or 8 for `EBLC` and 32 for `CBLC`).
+    pub bit_depth: u8,
+    /// Lowest glyph index covered by the strike.
+    pub start_glyph: u32,
+    /// Highest glyph index covered by the strike.
+    pub end_glyph: u32,
+}
+
+impl BitmapStrike {
+    // Size of the table header (version and `numSizes`).
+    const HEADER_SIZE: usize = 8;
+    // Size of a `BitmapSize` record.
+    const RECORD_SIZE: usize = 48;
+
+    fn parse_location_table(data: &[u8]) -> Vec<BitmapStrike> {
+        let read_u16 = |offset: usize| u16::from_be_bytes([data[offset], data[offset + 1]]);
+        if data.len() < Self::HEADER_SIZE {
+            return Vec::new();
+        }
+        let num_sizes = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let available = (data.len() - Self::HEADER_SIZE) / Self::RECORD_SIZE;
+        (0..num_sizes.min(available))
+            .map(|i| {
+                let record = Self::HEADER_SIZE + i * Self::RECORD_SIZE;
+                BitmapStrike {
+                    start_glyph: read_u16(record + 40) as u32,
+                    end_glyph: read_u16(record + 42) as u32,
+                    ppem_x: data[record + 44],
+                    ppem_y: data[record + 45],
+                    bit_depth: data[record + 46],
+                }
+            })
+            .collect()
+    }
+
+    /// Chooses a strike out of `strikes` for rendering at `ppem` pixels per em
+    /// in the same way HarfBuzz chooses a strike of the `CBLC` table.
+    ///
+    /// Strikes are compared by the larger of their horizontal and vertical
+    /// ppem values. See [`Face::choose_bitmap_strike()`] for details.
+    pub fn choose(strikes: &[BitmapStrike], ppem: u32) -> Option<BitmapStrike> {
+        let ppem = if ppem == 0 { u32::MAX } else { ppem };
+        let size = |strike: &BitmapStrike| strike.ppem_x.max(strike.ppem_y) as u32;
+        let mut best: Option<BitmapStrike> = None;
+        for &strike in strikes {
+            let strike_ppem = size(&strike);
+            best = match best {
+                Some(best_strike) => {
+                    let best_ppem = size(&best_strike);
+                    if (ppem > best_ppem && strike_ppem > best_ppem)
+                        || (ppem <= strike_ppem && strike_ppem < best_ppem)
+                    {
+                        Some(strike)
+                    } else {
+                        Some(best_strike)
+                    }
+                }
+                None => Some(strike),
+            };
+        }
+        best
+    }
+}
+
 unsafe impl<'a> HarfbuzzObject for Face<'a> {
     type Raw = hb_face_t;
 
@@ -182,6 +731,144 @@ unsafe impl<'a> HarfbuzzObject for Face<'a> {
     }
 }
 
+/// The error returned by [`Face::from_tables()`] if the tables do not form a
+/// usable font.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FaceTablesError {
+    /// The table with this tag was given more than once.
+    DuplicateTable(Tag),
+    /// A required table is missing.
+    MissingTable(Tag),
+    /// The table with this tag is too short or has an invalid header.
+    MalformedTable(Tag),
+    /// The table with this tag has fewer entries than the number of glyphs
+    /// in the `maxp` table.
+    GlyphCountMismatch {
+        /// The tag of the inconsistent table.
+        table: Tag,
+        /// The number of glyphs in the `maxp` table.
+        glyph_count: u16,
+    },
+}
+
+impl fmt::Display for FaceTablesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaceTablesError::DuplicateTable(tag) => write!(f, "duplicate `{}` table", tag),
+            FaceTablesError::MissingTable(tag) => write!(f, "missing `{}` table", tag),
+            FaceTablesError::MalformedTable(tag) => write!(f, "malformed `{}` table", tag),
+            FaceTablesError::GlyphCountMismatch { table, glyph_count } => write!(
+                f,
+                "`{}` table does not cover the {} glyphs of the `maxp` table",
+                table, glyph_count
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FaceTablesError {}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+// Checks the tables given to `Face::from_tables`.
+// Lets HarfBuzz list the tables of a face created for tables, which it can not
+// do by itself, so that `Face::table_tags()` and everything based on it see
+// them.
+fn set_table_tags(face: &Face<'_>, tags: Vec<hb_tag_t>) {
+    extern "C" fn destroy(data: *mut c_void) {
+        unsafe { drop(Box::from_raw(data as *mut Vec<hb_tag_t>)) };
+    }
+    unsafe extern "C" fn table_tags(
+        _: *const hb_face_t,
+        start_offset: c_uint,
+        table_count: *mut c_uint,
+        table_tags: *mut hb_tag_t,
+        user_data: *mut c_void,
+    ) -> c_uint {
+        let tags = &*(user_data as *const Vec<hb_tag_t>);
+        if !table_count.is_null() {
+            let tail = tags.get(start_offset as usize..).unwrap_or_default();
+            let count = tail.len().min(*table_count as usize);
+            std::ptr::copy_nonoverlapping(tail.as_ptr(), table_tags, count);
+            *table_count = count as c_uint;
+        }
+        tags.len() as c_uint
+    }
+    let data = Box::into_raw(Box::new(tags));
+    unsafe {
+        hb_face_set_get_table_tags_func(
+            face.as_raw(),
+            Some(table_tags),
+            data as *mut _,
+            Some(destroy),
+        )
+    };
+}
+
+fn validate_tables(tables: &HashMap<Tag, &[u8]>) -> Result<(), FaceTablesError> {
+    let table = |tag: &[u8; 4]| {
+        let tag = Tag::from(tag);
+        tables
+            .get(&tag)
+            .copied()
+            .ok_or(FaceTablesError::MissingTable(tag))
+    };
+    let head = table(b"head")?;
+    let maxp = table(b"maxp")?;
+    table(b"cmap")?;
+
+    let malformed_head = FaceTablesError::MalformedTable(Tag::from(b"head"));
+    const HEAD_MAGIC_NUMBER: u32 = 0x5F0F_3CF5;
+    if head.len() < 54 || read_u32(head, 12) != Some(HEAD_MAGIC_NUMBER) {
+        return Err(malformed_head);
+    }
+    let upem = read_u16(head, 18).ok_or(malformed_head)?;
+    if !(16..=16384).contains(&upem) {
+        return Err(malformed_head);
+    }
+    let glyph_count = match read_u32(maxp, 0) {
+        Some(0x0000_5000) | Some(0x0001_0000) => read_u16(maxp, 4),
+        _ => None,
+    }
+    .ok_or(FaceTablesError::MalformedTable(Tag::from(b"maxp")))?;
+    let mismatch = |tag: &[u8; 4]| FaceTablesError::GlyphCountMismatch {
+        table: Tag::from(tag),
+        glyph_count,
+    };
+
+    if let Ok(loca) = table(b"loca") {
+        // `indexToLocFormat` selects 16 or 32 bit offsets
+        let offset_size = match read_u16(head, 50) {
+            Some(0) => 2,
+            Some(1) => 4,
+            _ => return Err(malformed_head),
+        };
+        if loca.len() < (glyph_count as usize + 1) * offset_size {
+            return Err(mismatch(b"loca"));
+        }
+    }
+    if let Ok(hmtx) = table(b"hmtx") {
+        let metric_count = read_u16(table(b"hhea")?, 34)
+            .ok_or(FaceTablesError::MalformedTable(Tag::from(b"hhea")))?;
+        // the last advance applies to the remaining glyphs, which only have
+        // a left side bearing
+        let len = 4 * metric_count as usize
+            + 2 * (glyph_count as usize).saturating_sub(metric_count as usize);
+        if metric_count == 0 || metric_count > glyph_count || hmtx.len() < len {
+            return Err(mismatch(b"hmtx"));
+        }
+    }
+    Ok(())
+}
+
 unsafe impl<'a> Send for Face<'a> {}
 unsafe impl<'a> Sync for Face<'a> {}
 
@@ -202,4 +889,233 @@ mod tests {
         let maxp_table = face.table_with_tag(b"hhea").unwrap();
         assert_eq!(&maxp_table.as_ref(), b"hhea-table");
     }
+
+    #[test]
+    fn test_face_from_tables() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let font = Face::from_file(path, 0).unwrap();
+        let tables: Vec<(Tag, Shared<Blob>)> = font
+            .table_tags()
+            .into_iter()
+            .map(|tag| (tag, font.table_with_tag(tag).unwrap()))
+            .collect();
+        let with_table = |tag: &[u8; 4], data: &'static [u8]| {
+            let tag = Tag::from(tag);
+            tables
+                .iter()
+                .filter(move |(t, _)| *t != tag)
+                .map(|(tag, blob)| (*tag, blob.as_ref()))
+                .chain(std::iter::once((tag, data)))
+        };
+
+        let all = tables.iter().map(|(tag, blob)| (*tag, blob.as_ref()));
+        let face = Face::from_tables(all.clone()).unwrap();
+        assert_eq!(face.glyph_count(), font.glyph_count());
+        assert_eq!(face.upem(), font.upem());
+        let mut tags = font.table_tags();
+        tags.sort_by_key(|tag| tag.0);
+        assert_eq!(face.table_tags(), tags);
+
+        // the digest identifies the faces by their tables
+        let digest = face.digest().unwrap();
+        assert_eq!(
+            Face::from_tables(all.clone()).unwrap().digest(),
+            Some(digest)
+        );
+        let renamed = Face::from_tables(with_table(b"name", b"other name")).unwrap();
+        assert_ne!(renamed.digest().unwrap(), digest);
+        let original: Shared<Face<'static>> = Face::from_file(path, 0).unwrap().into();
+        let func_face = Face::from_table_func(move |tag| original.table_with_tag(tag));
+        assert_eq!(func_face.digest(), None);
+
+        // the tables are borrowed, so they are what the face holds on to
+        let table_sum: usize = tables.iter().map(|(_, blob)| blob.len()).sum();
+        assert_eq!(face.approximate_memory_usage(), table_sum);
+        assert_eq!(func_face.approximate_memory_usage(), 0);
+
+        let loca = Tag::from(b"loca");
+        assert_eq!(
+            Face::from_tables(all.chain(std::iter::once((loca, &[][..])))).unwrap_err(),
+            FaceTablesError::DuplicateTable(loca)
+        );
+        assert_eq!(
+            Face::from_tables(with_table(b"loca", &[0; 4])).unwrap_err(),
+            FaceTablesError::GlyphCountMismatch {
+                table: loca,
+                glyph_count: font.glyph_count() as u16
+            }
+        );
+        assert_eq!(
+            Face::from_tables(with_table(b"head", &[0; 54])).unwrap_err(),
+            FaceTablesError::MalformedTable(Tag::from(b"head"))
+        );
+        assert_eq!(
+            Face::from_tables(with_table(b"maxp", &[0, 0, 0x50, 0])).unwrap_err(),
+            FaceTablesError::MalformedTable(Tag::from(b"maxp"))
+        );
+
+        let hhea = Tag::from(b"hhea");
+        let without_hhea = tables
+            .iter()
+            .filter(|(tag, _)| *tag != hhea)
+            .map(|(tag, blob)| (*tag, blob.as_ref()));
+        assert_eq!(
+            Face::from_tables(without_hhea).unwrap_err(),
+            FaceTablesError::MissingTable(hhea)
+        );
+        assert_eq!(
+            Face::from_tables(with_table(b"hhea", &[0; 8])).unwrap_err(),
+            FaceTablesError::MalformedTable(hhea)
+        );
+    }
+
+    #[test]
+    fn test_face_table_tags() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = Face::from_file(path, 0).unwrap();
+        let tags = face.table_tags();
+        assert!(tags.contains(&Tag::new('G', 'S', 'U', 'B')));
+        let table_sum: usize = tags
+            .iter()
+            .filter_map(|&tag| face.table_with_tag(tag))
+            .map(|blob| blob.len())
+            .sum();
+        assert!(table_sum <= face.approximate_memory_usage());
+
+        assert!(Face::empty().table_tags().is_empty());
+        assert_eq!(Face::empty().approximate_memory_usage(), 0);
+    }
+
+    fn bitmap_location_table(ppems: &[(u8, u8)]) -> Vec<u8> {
+        let mut table = vec![0, 3, 0, 0];
+        table.extend_from_slice(&(ppems.len() as u32).to_be_bytes());
+        for &(ppem_x, ppem_y) in ppems {
+            let mut record = [0u8; 48];
+            record[40..42].copy_from_slice(&1u16.to_be_bytes());
+            record[42..44].copy_from_slice(&10u16.to_be_bytes());
+            record[44] = ppem_x;
+            record[45] = ppem_y;
+            record[46] = 32;
+            table.extend_from_slice(&record);
+        }
+        table
+    }
+
+    #[test]
+    fn test_bitmap_strikes() {
+        let face = Face::from_table_func(|tag| {
+            if tag == Tag::new('C', 'B', 'L', 'C') {
+                Some(bitmap_location_table(&[(20, 20), (109, 109), (40, 40)]).into())
+            } else {
+                None
+            }
+        });
+        let strikes = face.bitmap_strikes();
+        assert_eq!(strikes.len(), 3);
+        assert_eq!(
+            strikes[0],
+            BitmapStrike {
+                ppem_x: 20,
+                ppem_y: 20,
+                bit_depth: 32,
+                start_glyph: 1,
+                end_glyph: 10,
+            }
+        );
+
+        let chosen_ppem = |ppem| face.choose_bitmap_strike(ppem).unwrap().ppem_y;
+        assert_eq!(chosen_ppem(10), 20);
+        assert_eq!(chosen_ppem(20), 20);
+        assert_eq!(chosen_ppem(21), 40);
+        assert_eq!(chosen_ppem(41), 109);
+        assert_eq!(chosen_ppem(200), 109);
+        assert_eq!(chosen_ppem(0), 109);
+    }
+
+    #[test]
+    fn test_choose_bitmap_strike() {
+        // strikes are compared by their larger ppem value
+        let face = Face::from_table_func(|tag| {
+            if tag == Tag::new('C', 'B', 'L', 'C') {
+                Some(bitmap_location_table(&[(30, 10), (20, 20)]).into())
+            } else {
+                None
+            }
+        });
+        assert_eq!(face.choose_bitmap_strike(20).unwrap().ppem_x, 20);
+        assert_eq!(face.choose_bitmap_strike(25).unwrap().ppem_x, 30);
+
+        let mut font = crate::Font::new(face);
+        font.set_ppem(25, 15);
+        assert_eq!(font.bitmap_strike().unwrap().ppem_x, 30);
+        font.set_ppem(15, 15);
+        assert_eq!(font.bitmap_strike().unwrap().ppem_x, 20);
+
+        // HarfBuzz does not render monochrome or grayscale bitmaps
+        let face = Face::from_table_func(|tag| {
+            if tag == Tag::new('E', 'B', 'L', 'C') {
+                Some(bitmap_location_table(&[(12, 12)]).into())
+            } else {
+                None
+            }
+        });
+        assert_eq!(face.bitmap_strikes().len(), 1);
+        assert_eq!(face.choose_bitmap_strike(12), None);
+        assert_eq!(crate::Font::new(face).bitmap_strike(), None);
+    }
+
+    #[test]
+    fn test_layout_predicates() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        assert!(face.has_substitution());
+        assert!(face.has_positioning());
+
+        let latin = Script::from_iso15924_tag(Tag::new('L', 'a', 't', 'n'));
+        let turkish = "tr".parse().ok();
+        assert!(face.has_substitution_for(latin, turkish));
+        assert!(face.has_positioning_for(latin, None));
+
+        let empty = Face::empty();
+        assert!(!empty.has_substitution());
+        assert!(!empty.has_positioning());
+        assert!(!empty.has_substitution_for(latin, None));
+        assert!(!empty.has_positioning_for(latin, None));
+    }
+
+    #[test]
+    fn test_closure_glyphs() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = crate::Font::new(face);
+        let face = font.face();
+        let mut initial = Set::new();
+        initial.insert(font.get_nominal_glyph('a').unwrap());
+
+        let all = face.closure_glyphs(&initial, &[]);
+        assert!(all.len() > 1);
+        assert!(all.contains(font.get_nominal_glyph('a').unwrap()));
+        let none = face.closure_glyphs(&initial, &[Tag::new('x', 'x', 'x', 'x')]);
+        assert_eq!(*none, *initial);
+
+        assert!(!face.collect_lookups(b"GSUB", &[]).is_empty());
+        assert!(Face::empty().collect_lookups(b"GPOS", &[]).is_empty());
+    }
+
+    #[test]
+    fn test_no_bitmap_strikes() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let face = Face::from_file(path, 0).unwrap();
+        assert!(!face.has_png_glyphs());
+        assert!(face.bitmap_strikes().is_empty());
+        assert_eq!(face.choose_bitmap_strike(16), None);
+
+        // truncated tables are ignored
+        let face = Face::from_table_func(|tag| {
+            if tag == Tag::new('E', 'B', 'L', 'C') {
+                Some(bitmap_location_table(&[(12, 12)])[..30].to_vec().into())
+            } else {
+                None
+            }
+        });
+        assert!(face.bitmap_strikes().is_empty());
+    }
 }
@@ -0,0 +1,139 @@
+use crate::common::Tag;
+use crate::Feature;
+
+use std::ops::Range;
+use std::os::raw::c_uint;
+
+/// A span of a run whose text uses different feature values than the rest,
+/// e.g. because it is styled differently in a rich-text document.
+#[derive(Debug, Clone)]
+pub struct FeatureSpan<'a> {
+    /// The cluster range of the span (byte offsets when the text was added
+    /// with [`UnicodeBuffer::add_str()`](crate::UnicodeBuffer::add_str)).
+    pub range: Range<usize>,
+    /// The features of the span. Their own ranges are ignored and replaced by
+    /// `range`.
+    pub features: &'a [Feature],
+}
+
+// Returns the value of `tag` that the global features of `base` set, if any.
+fn global_value(base: &[Feature], tag: Tag) -> Option<u32> {
+    base.iter()
+        .rev()
+        .find(|feature| {
+            feature.tag() == tag && feature.start() == 0 && feature.end() == c_uint::MAX as usize
+        })
+        .map(|feature| feature.value())
+}
+
+/// Computes the features for shaping a run with `base` features and
+/// per-span overrides in a single call to [`shape()`](crate::shape).
+///
+/// The result contains `base` followed by the overrides of the spans,
+/// restricted to their ranges. Overrides that set a feature to the value
+/// `base` already applies to the whole run are left out, and overrides of
+/// adjacent spans with the same value are merged into a single feature, so
+/// the list stays short even for runs with many spans. Like HarfBuzz, later
+/// spans take precedence over earlier spans where they overlap.
+///
+/// This allows rich-text engines to shape runs that only differ in their
+/// features together instead of splitting them at every style change.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let base = [Feature::new(b"liga", 1, ..)];
+/// let small_caps = [Feature::new(b"smcp", 1, ..)];
+/// let spans = [
+///     FeatureSpan { range: 0..5, features: &small_caps },
+///     FeatureSpan { range: 5..8, features: &small_caps },
+///     // `liga` is already enabled for the whole run
+///     FeatureSpan { range: 8..12, features: &base },
+/// ];
+///
+/// let features = merge_span_features(&base, &spans);
+/// assert_eq!(features.len(), 2);
+/// assert_eq!(features[1].to_string(), "smcp[:8]");
+/// ```
+pub fn merge_span_features(base: &[Feature], spans: &[FeatureSpan<'_>]) -> Vec<Feature> {
+    // the overrides of every tag in the order of the spans
+    let mut overrides: Vec<(Tag, Vec<Feature>)> = Vec::new();
+    for span in spans {
+        for feature in span.features {
+            let tag = feature.tag();
+            let index = match overrides.iter().position(|(t, _)| *t == tag) {
+                Some(index) => index,
+                None => {
+                    overrides.push((tag, Vec::new()));
+                    overrides.len() - 1
+                }
+            };
+            let features = &mut overrides[index].1;
+            let value = feature.value();
+            let start = span.range.start;
+            let end = span.range.end;
+            // a previous override of the range has to be undone even if the
+            // value is the one of `base`
+            let overlaps = features.iter().any(|f| f.start() < end && start < f.end());
+            if !overlaps && global_value(base, tag) == Some(value) {
+                continue;
+            }
+            match features.last_mut() {
+                Some(last) if last.value() == value && last.end() == start => {
+                    *last = Feature::new(tag, value, last.start()..end);
+                }
+                _ => features.push(Feature::new(tag, value, start..end)),
+            }
+        }
+    }
+
+    let mut features = base.to_vec();
+    features.extend(overrides.into_iter().flat_map(|(_, features)| features));
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{shape, Face, Font, UnicodeBuffer};
+
+    #[test]
+    fn test_merge_span_features() {
+        let kern_off = [Feature::new(b"kern", 0, ..)];
+        let kern_on = [Feature::new(b"kern", 1, ..)];
+        let spans = [
+            FeatureSpan {
+                range: 0..2,
+                features: &kern_on,
+            },
+            FeatureSpan {
+                range: 2..4,
+                features: &kern_off,
+            },
+            FeatureSpan {
+                range: 1..3,
+                features: &kern_on,
+            },
+        ];
+        let features = merge_span_features(&kern_on, &spans);
+        let strings: Vec<String> = features.iter().map(|f| f.to_string()).collect();
+        // the first span is redundant, the last one undoes part of the second
+        assert_eq!(strings, vec!["kern", "-kern[2:4]", "kern[1:3]"]);
+
+        // shaping with the merged features matches shaping the spans separately
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let shape_with = |text: &str, features: &[Feature]| {
+            shape(&font, UnicodeBuffer::new().add_str(text), features)
+        };
+        let merged = shape_with("AVAV", &features);
+        let advances: Vec<_> = merged.x_advances().collect();
+        let first = shape_with("AV", &kern_on);
+        let second = shape_with("AV", &kern_off);
+        assert_eq!(advances[0], first.x_advances().next().unwrap());
+        assert_eq!(advances[3], second.x_advances().nth(1).unwrap());
+        assert!(merge_span_features(&[], &[]).is_empty());
+    }
+}
@@ -6,16 +6,22 @@ use crate::bindings::{
     hb_font_create, hb_font_create_sub_font, hb_font_destroy, hb_font_draw_glyph,
     hb_font_extents_t, hb_font_get_empty, hb_font_get_face, hb_font_get_glyph_contour_point,
     hb_font_get_glyph_extents, hb_font_get_glyph_from_name, hb_font_get_glyph_h_advance,
-    hb_font_get_glyph_h_origin, hb_font_get_glyph_name, hb_font_get_glyph_v_advance,
-    hb_font_get_glyph_v_origin, hb_font_get_h_extents, hb_font_get_nominal_glyph,
-    hb_font_get_parent, hb_font_get_ppem, hb_font_get_scale, hb_font_get_v_extents,
-    hb_font_get_variation_glyph, hb_font_reference, hb_font_set_funcs, hb_font_set_ppem,
-    hb_font_set_scale, hb_font_set_variations, hb_font_t, hb_glyph_extents_t, hb_position_t,
+    hb_font_get_glyph_h_advances, hb_font_get_glyph_h_origin, hb_font_get_glyph_name,
+    hb_font_get_glyph_v_advance, hb_font_get_glyph_v_advances, hb_font_get_glyph_v_origin,
+    hb_font_get_h_extents, hb_font_get_nominal_glyph, hb_font_get_parent, hb_font_get_ppem,
+    hb_font_get_ptem, hb_font_get_scale, hb_font_get_v_extents, hb_font_get_var_coords_design,
+    hb_font_get_var_coords_normalized, hb_font_get_var_named_instance, hb_font_get_variation_glyph,
+    hb_font_reference, hb_font_set_funcs, hb_font_set_ppem, hb_font_set_ptem, hb_font_set_scale,
+    hb_font_set_var_coords_design, hb_font_set_var_coords_normalized,
+    hb_font_set_var_named_instance, hb_font_set_variations, hb_font_t, hb_glyph_extents_t,
+    hb_ot_layout_get_ligature_carets, hb_ot_var_axis_info_t, hb_ot_var_get_axis_count,
+    hb_ot_var_get_axis_infos, hb_position_t, hb_style_get_value, hb_unicode_funcs_get_default,
+    hb_unicode_script,
 };
-use crate::common::{HarfbuzzObject, Owned, Shared};
+use crate::common::{Direction, HarfbuzzObject, Language, Owned, PanicPolicy, Script, Shared, Tag};
 pub use crate::draw_funcs::DrawFuncs;
-use crate::draw_funcs::DrawFuncsImpl;
-use crate::face::Face;
+use crate::draw_funcs::{DrawFuncsImpl, DrawState};
+use crate::face::{BitmapStrike, Face};
 pub use crate::font_funcs::FontFuncs;
 use crate::font_funcs::FontFuncsImpl;
 use crate::Variation;
@@ -56,10 +62,194 @@ impl FontExtents {
 
 pub type GlyphExtents = hb_glyph_extents_t;
 
+/// An axis-aligned rectangle in font units with the y axis pointing up.
+///
+/// A rectangle whose minimum is not smaller than its maximum in both
+/// directions is empty.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x_min: Position,
+    pub y_min: Position,
+    pub x_max: Position,
+    pub y_max: Position,
+}
+
+impl Rect {
+    pub fn new(x_min: Position, y_min: Position, x_max: Position, y_max: Position) -> Rect {
+        Rect {
+            x_min,
+            y_min,
+            x_max,
+            y_max,
+        }
+    }
+
+    /// Converts the extents of a glyph drawn at the origin into a rectangle.
+    pub fn from_glyph_extents(extents: &GlyphExtents) -> Rect {
+        let (x0, x1) = (extents.x_bearing, extents.x_bearing + extents.width);
+        let (y0, y1) = (extents.y_bearing, extents.y_bearing + extents.height);
+        Rect::new(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1))
+    }
+
+    /// Returns `true` if the rectangle has no area.
+    pub fn is_empty(&self) -> bool {
+        self.x_min >= self.x_max || self.y_min >= self.y_max
+    }
+
+    pub fn width(&self) -> Position {
+        self.x_max - self.x_min
+    }
+
+    pub fn height(&self) -> Position {
+        self.y_max - self.y_min
+    }
+
+    /// Returns the rectangle moved by `(dx, dy)`.
+    pub fn translate(&self, dx: Position, dy: Position) -> Rect {
+        Rect::new(
+            self.x_min + dx,
+            self.y_min + dy,
+            self.x_max + dx,
+            self.y_max + dy,
+        )
+    }
+
+    /// Returns the smallest rectangle containing both `self` and `other`.
+    /// Empty rectangles are ignored.
+    pub fn union(&self, other: &Rect) -> Rect {
+        if other.is_empty() {
+            *self
+        } else if self.is_empty() {
+            *other
+        } else {
+            Rect::new(
+                self.x_min.min(other.x_min),
+                self.y_min.min(other.y_min),
+                self.x_max.max(other.x_max),
+                self.y_max.max(other.y_max),
+            )
+        }
+    }
+}
+
+// Collects the exact bounding box of a glyph outline.
+#[derive(Debug, Default)]
+struct OutlineBounds {
+    bounds: Option<[f32; 4]>,
+}
+
+impl OutlineBounds {
+    fn add_point(&mut self, x: f32, y: f32) {
+        let bounds = self.bounds.get_or_insert([x, y, x, y]);
+        bounds[0] = bounds[0].min(x);
+        bounds[1] = bounds[1].min(y);
+        bounds[2] = bounds[2].max(x);
+        bounds[3] = bounds[3].max(y);
+    }
+
+    fn add_quadratic(&mut self, p0: (f32, f32), p1: (f32, f32), p2: (f32, f32)) {
+        let eval = |t: f32, a: f32, b: f32, c: f32| {
+            let mt = 1.0 - t;
+            mt * mt * a + 2.0 * mt * t * b + t * t * c
+        };
+        // the curve has an extremum where the derivative is zero
+        let extremum = |a: f32, b: f32, c: f32| {
+            let denom = a - 2.0 * b + c;
+            if denom != 0.0 {
+                Some((a - b) / denom).filter(|t| *t > 0.0 && *t < 1.0)
+            } else {
+                None
+            }
+        };
+        let ts = [extremum(p0.0, p1.0, p2.0), extremum(p0.1, p1.1, p2.1)];
+        for t in ts.iter().flatten() {
+            self.add_point(eval(*t, p0.0, p1.0, p2.0), eval(*t, p0.1, p1.1, p2.1));
+        }
+        self.add_point(p2.0, p2.1);
+    }
+
+    fn add_cubic(&mut self, p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32)) {
+        let eval = |t: f32, a: f32, b: f32, c: f32, d: f32| {
+            let mt = 1.0 - t;
+            mt * mt * mt * a + 3.0 * mt * mt * t * b + 3.0 * mt * t * t * c + t * t * t * d
+        };
+        // roots of the derivative divided by three
+        let extrema = |a: f32, b: f32, c: f32, d: f32| {
+            let qa = -a + 3.0 * b - 3.0 * c + d;
+            let qb = 2.0 * (a - 2.0 * b + c);
+            let qc = b - a;
+            let mut roots = [None, None];
+            if qa.abs() < f32::EPSILON {
+                if qb != 0.0 {
+                    roots[0] = Some(-qc / qb);
+                }
+            } else {
+                let discriminant = qb * qb - 4.0 * qa * qc;
+                if discriminant >= 0.0 {
+                    let sqrt = discriminant.sqrt();
+                    roots[0] = Some((-qb + sqrt) / (2.0 * qa));
+                    roots[1] = Some((-qb - sqrt) / (2.0 * qa));
+                }
+            }
+            roots
+        };
+        let x_extrema = extrema(p0.0, p1.0, p2.0, p3.0);
+        let y_extrema = extrema(p0.1, p1.1, p2.1, p3.1);
+        let ts = x_extrema.iter().chain(y_extrema.iter()).copied();
+        for t in ts.flatten().filter(|t| *t > 0.0 && *t < 1.0) {
+            self.add_point(
+                eval(t, p0.0, p1.0, p2.0, p3.0),
+                eval(t, p0.1, p1.1, p2.1, p3.1),
+            );
+        }
+        self.add_point(p3.0, p3.1);
+    }
+}
+
+impl DrawFuncs for OutlineBounds {
+    fn move_to(&mut self, _: &DrawState, x: f32, y: f32) {
+        self.add_point(x, y);
+    }
+
+    fn line_to(&mut self, _: &DrawState, x: f32, y: f32) {
+        self.add_point(x, y);
+    }
+
+    fn quadratic_to(&mut self, st: &DrawState, cx: f32, cy: f32, x: f32, y: f32) {
+        self.add_quadratic((st.current_x, st.current_y), (cx, cy), (x, y));
+    }
+
+    fn cubic_to(&mut self, st: &DrawState, c1x: f32, c1y: f32, c2x: f32, c2y: f32, x: f32, y: f32) {
+        let p0 = (st.current_x, st.current_y);
+        self.add_cubic(p0, (c1x, c1y), (c2x, c2y), (x, y));
+    }
+
+    fn close_path(&mut self, _: &DrawState) {}
+}
+
 pub(crate) extern "C" fn destroy_box<U>(ptr: *mut c_void) {
     _ = unsafe { Box::from_raw(ptr as *mut U) };
 }
 
+/// How well a font supports a script. See [`Font::supports_script()`].
+///
+/// The levels are ordered, so the better supported of two fonts can be found
+/// by comparing the levels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SupportLevel {
+    /// The font has no characters of the script.
+    None,
+    /// The font has characters of the script, but no OpenType layout rules
+    /// specific to the script.
+    ///
+    /// This is enough for simple scripts but means that complex scripts will
+    /// not be rendered correctly.
+    BasicCmapOnly,
+    /// The font has characters of the script and `GSUB` or `GPOS` rules for
+    /// it.
+    FullShaping,
+}
+
 /// A type representing a single font (i.e. a specific combination of typeface,
 /// font-size and font-variation settings)
 ///
@@ -93,9 +283,9 @@ pub(crate) extern "C" fn destroy_box<U>(ptr: *mut c_void) {
 ///
 /// # Font Variations
 ///
-/// Using OpenType font variations is supported (but variation axis enumeration
-/// is not supported yet unfortunately). See an example for how to use font
-/// variations in [`Self::set_variations()`].
+/// Using OpenType font variations is supported. See an example for how to use
+/// font variations in [`Self::set_variations()`]. The current values of the
+/// variation axes are returned by [`Self::variations()`].
 ///
 /// # Examples
 ///
@@ -218,11 +408,43 @@ impl<'a> Font<'a> {
         }
     }
 
+    /// Returns the raw `hb_font_t` pointer of the font for FFI calls.
+    ///
+    /// This is the same as [`HarfbuzzObject::as_raw()`] without the need to
+    /// import the trait. The reference count is not changed, so the pointer
+    /// must not be used after the font is destroyed.
+    pub fn as_ptr(&self) -> *mut hb_font_t {
+        self.as_raw()
+    }
+
+    /// Wraps a raw `hb_font_t` pointer, e.g. one created by a C library.
+    ///
+    /// The reference count is increased, so the caller still has to release
+    /// its own reference.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a valid pointer to a `hb_font_t` whose data lives at least
+    /// as long as `'a`.
+    pub unsafe fn from_ptr(ptr: *mut hb_font_t) -> Shared<Font<'a>> {
+        Shared::from_raw_ref(ptr)
+    }
+
     /// Returns a shared pointer to the face from which this font was created.
     pub fn face(&self) -> Shared<Face<'a>> {
         unsafe { Shared::from_raw_ref(hb_font_get_face(self.as_raw())) }
     }
 
+    /// Returns an estimate of the number of bytes of font data referenced by
+    /// this font.
+    ///
+    /// Fonts share the data of their face, so this is the same value as
+    /// returned by [`Face::approximate_memory_usage()`] on [`Self::face()`].
+    /// Several fonts created from the same face should only be counted once.
+    pub fn approximate_memory_usage(&self) -> usize {
+        self.face().approximate_memory_usage()
+    }
+
     /// Returns the EM scale of the font.
     pub fn scale(&self) -> (i32, i32) {
         let mut result = (0i32, 0i32);
@@ -235,6 +457,44 @@ impl<'a> Font<'a> {
         unsafe { hb_font_set_scale(self.as_raw_mut(), x, y) };
     }
 
+    /// Sets the EM scale of the font to the units per em of its face.
+    ///
+    /// All positions returned by the font (and by shaping with it) are then
+    /// given in font design units. This is also the default scale of a font
+    /// created by `Font::new`.
+    pub fn set_scale_to_upem(&mut self) {
+        let upem = self.face().upem() as i32;
+        self.set_scale(upem, upem);
+    }
+
+    /// Sets the scale and ppem of the font for rendering at `px` pixels per
+    /// em.
+    ///
+    /// Positions are given in 26.6 fixed point format afterwards, i.e. in units
+    /// of 1/64 pixel. The ppem is set to `px` rounded to the nearest integer so
+    /// that e.g. the right bitmap strike is chosen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    /// # use std::path::PathBuf;
+    /// # let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    /// # path.push("testfiles/SourceSansVariable-Roman.ttf");
+    /// let face = Face::from_file(path, 0).expect("Error reading font file.");
+    /// let mut font = Font::new(face);
+    ///
+    /// font.set_pixel_size(20.0);
+    /// assert_eq!(font.scale(), (20 * 64, 20 * 64));
+    /// assert_eq!(font.ppem(), (20, 20));
+    /// ```
+    pub fn set_pixel_size(&mut self, px: f32) {
+        let scale = (px * 64.0).round() as i32;
+        let ppem = px.round().max(0.0) as u32;
+        self.set_scale(scale, scale);
+        self.set_ppem(ppem, ppem);
+    }
+
     pub fn ppem(&self) -> (u32, u32) {
         let mut result = (0u32, 0u32);
         unsafe { hb_font_get_ppem(self.as_raw(), &mut result.0, &mut result.1) };
@@ -245,13 +505,105 @@ impl<'a> Font<'a> {
         unsafe { hb_font_set_ppem(self.as_raw_mut(), x, y) };
     }
 
+    /// Returns the point size of the font, or 0 if it is not set.
+    pub fn ptem(&self) -> f32 {
+        unsafe { hb_font_get_ptem(self.as_raw()) }
+    }
+
+    /// Sets the point size of the font, i.e. the size of the em in points.
+    ///
+    /// HarfBuzz uses the point size for the size-dependent tracking of the
+    /// `trak` table and passes it to CoreText fonts. It does not change the
+    /// scale of the font or select a value of the optical size (`opsz`) axis.
+    /// A point size of 0 means unset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Face, Font};
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let mut font = Font::new(face);
+    /// assert_eq!(font.ptem(), 0.0);
+    ///
+    /// let scale = font.scale();
+    /// font.set_ptem(12.0);
+    /// assert_eq!(font.ptem(), 12.0);
+    /// assert_eq!(font.scale(), scale);
+    /// ```
+    pub fn set_ptem(&mut self, ptem: f32) {
+        unsafe { hb_font_set_ptem(self.as_raw_mut(), ptem) };
+    }
+
+    /// Returns the value of a style attribute of the font, e.g. its weight
+    /// (`wght`), width (`wdth`) or whether it is italic (`ital`).
+    ///
+    /// The value is taken from the current variation settings of the font if
+    /// applicable and otherwise from the `STAT`, `OS/2` or `head` tables. See
+    /// the [OpenType design-variation axis tag registry](https://docs.microsoft.com/en-us/typography/opentype/spec/dvaraxisreg)
+    /// for the meaning of the values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Face, Font};
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// assert_eq!(font.style_value(b"ital"), 0.0);
+    /// ```
+    pub fn style_value(&self, tag: impl Into<Tag>) -> f32 {
+        unsafe { hb_style_get_value(self.as_raw(), tag.into().0) }
+    }
+
+    /// Returns the embedded color bitmap strike HarfBuzz uses for this font.
+    ///
+    /// The strike is chosen based on the larger of the horizontal and
+    /// vertical ppem of the font (see [`Self::set_ppem()`]) as described in
+    /// [`Face::choose_bitmap_strike()`]. Returns `None` if the font's face
+    /// contains no `CBLC` strikes.
+    pub fn bitmap_strike(&self) -> Option<BitmapStrike> {
+        let (x_ppem, y_ppem) = self.ppem();
+        self.face().choose_bitmap_strike(x_ppem.max(y_ppem))
+    }
+
     /// Sets the font functions that this font will have from a value that
     /// implements [`FontFuncs`].
+    ///
+    /// Panics in the callbacks are caught and the callback returns a default
+    /// value. Use [`Self::set_font_funcs_with_panic_policy()`] to handle them
+    /// differently.
     pub fn set_font_funcs<FuncsType>(&mut self, funcs: FuncsType)
     where
         FuncsType: 'a + Send + Sync + FontFuncs,
     {
-        let funcs_impl: Owned<FontFuncsImpl<FuncsType>> = FontFuncsImpl::from_trait_impl();
+        self.install_font_funcs(funcs, FontFuncsImpl::from_trait_impl())
+    }
+
+    /// Sets the font functions like [`Self::set_font_funcs()`] but handles
+    /// panics in the callbacks according to `policy`.
+    ///
+    /// See [`PanicSink`](crate::PanicSink) for an example.
+    pub fn set_font_funcs_with_panic_policy<FuncsType>(
+        &mut self,
+        funcs: FuncsType,
+        policy: PanicPolicy,
+    ) where
+        FuncsType: 'a + Send + Sync + FontFuncs,
+    {
+        self.install_font_funcs(
+            funcs,
+            FontFuncsImpl::from_trait_impl_with_panic_policy(policy),
+        )
+    }
+
+    fn install_font_funcs<FuncsType>(
+        &mut self,
+        funcs: FuncsType,
+        funcs_impl: Owned<FontFuncsImpl<FuncsType>>,
+    ) where
+        FuncsType: 'a + Send + Sync + FontFuncs,
+    {
         let font_data = Box::new(funcs);
         unsafe {
             hb_font_set_funcs(
@@ -303,6 +655,50 @@ impl<'a> Font<'a> {
         )
     }
 
+    /// Returns how well the font supports text in `script` and `language`.
+    ///
+    /// The `cmap` table is searched for characters of `script` and the `GSUB`
+    /// and `GPOS` tables for rules specific to the script (rules of the
+    /// default script, which the shaper falls back to, are not counted). This
+    /// helps to decide on a fallback font before shaping, which per-character
+    /// coverage alone cannot do for complex scripts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Face, Font, Script, SupportLevel, Tag};
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let latin = Script::from_iso15924_tag(Tag::new('L', 'a', 't', 'n'));
+    /// let arabic = Script::from_iso15924_tag(Tag::new('A', 'r', 'a', 'b'));
+    /// let english = "en".parse().unwrap();
+    ///
+    /// assert_eq!(font.supports_script(latin, english), SupportLevel::FullShaping);
+    /// assert_eq!(font.supports_script(arabic, english), SupportLevel::None);
+    /// ```
+    pub fn supports_script(&self, script: Script, language: Language) -> SupportLevel {
+        let face = self.face();
+        let unicode_funcs = unsafe { hb_unicode_funcs_get_default() };
+        let has_characters = face.unicode_ranges().into_iter().any(|(first, last)| {
+            (first..=last).any(|c| unsafe { hb_unicode_script(unicode_funcs, c) } == script.0)
+        });
+        if !has_characters {
+            return SupportLevel::None;
+        }
+
+        let language = Some(language);
+        let gsub = Tag::new('G', 'S', 'U', 'B');
+        let gpos = Tag::new('G', 'P', 'O', 'S');
+        if face.has_layout_features(gsub, script, language, true)
+            || face.has_layout_features(gpos, script, language, true)
+        {
+            SupportLevel::FullShaping
+        } else {
+            SupportLevel::BasicCmapOnly
+        }
+    }
+
     pub fn get_font_h_extents(&self) -> Option<FontExtents> {
         unsafe {
             let mut extents = FontExtents::default();
@@ -363,6 +759,64 @@ impl<'a> Font<'a> {
         unsafe { hb_font_get_glyph_v_advance(self.as_raw(), glyph) }
     }
 
+    /// Get the horizontal advance widths of `glyphs` and write them to
+    /// `advances`.
+    ///
+    /// This is faster than calling
+    /// [`get_glyph_h_advance()`](Self::get_glyph_h_advance) for each glyph
+    /// of a long run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `glyphs` and `advances` have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    ///
+    /// let glyphs = [1, 2, 3];
+    /// let mut advances = [0; 3];
+    /// font.glyph_h_advances(&glyphs, &mut advances);
+    /// assert_eq!(advances[1], font.get_glyph_h_advance(2));
+    /// ```
+    pub fn glyph_h_advances(&self, glyphs: &[Glyph], advances: &mut [Position]) {
+        assert_eq!(glyphs.len(), advances.len());
+        unsafe {
+            hb_font_get_glyph_h_advances(
+                self.as_raw(),
+                glyphs.len() as u32,
+                glyphs.as_ptr(),
+                std::mem::size_of::<Glyph>() as u32,
+                advances.as_mut_ptr(),
+                std::mem::size_of::<Position>() as u32,
+            )
+        }
+    }
+
+    /// Get the vertical advance widths of `glyphs` and write them to
+    /// `advances`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `glyphs` and `advances` have different lengths.
+    pub fn glyph_v_advances(&self, glyphs: &[Glyph], advances: &mut [Position]) {
+        assert_eq!(glyphs.len(), advances.len());
+        unsafe {
+            hb_font_get_glyph_v_advances(
+                self.as_raw(),
+                glyphs.len() as u32,
+                glyphs.as_ptr(),
+                std::mem::size_of::<Glyph>() as u32,
+                advances.as_mut_ptr(),
+                std::mem::size_of::<Position>() as u32,
+            )
+        }
+    }
+
     pub fn get_glyph_h_origin(&self, glyph: Glyph) -> Option<(Position, Position)> {
         unsafe {
             let mut pos = (0, 0);
@@ -399,6 +853,32 @@ impl<'a> Font<'a> {
         }
     }
 
+    /// Returns the exact bounding box of the outline of `glyph` or `None` if
+    /// the glyph has no outline.
+    ///
+    /// In contrast to `get_glyph_extents` this draws the glyph and computes
+    /// the bounds of the actual curves, which can be tighter than the extents
+    /// stored in the font (e.g. for variable fonts) but is also slower.
+    pub fn get_glyph_outline_extents(&self, glyph: Glyph) -> Option<Rect> {
+        let funcs_impl: Owned<DrawFuncsImpl<OutlineBounds>> = DrawFuncsImpl::from_trait_impl();
+        let mut outline = OutlineBounds::default();
+        unsafe {
+            hb_font_draw_glyph(
+                self.as_raw(),
+                glyph,
+                funcs_impl.as_raw(),
+                &mut outline as *mut _ as *mut c_void,
+            )
+        };
+        let [x_min, y_min, x_max, y_max] = outline.bounds?;
+        Some(Rect::new(
+            x_min.floor() as Position,
+            y_min.floor() as Position,
+            x_max.ceil() as Position,
+            y_max.ceil() as Position,
+        ))
+    }
+
     pub fn get_glyph_contour_point(
         &self,
         glyph: Glyph,
@@ -421,6 +901,72 @@ impl<'a> Font<'a> {
         }
     }
 
+    /// Returns the positions of the ligature carets of `glyph` from the
+    /// `GDEF` table of the font.
+    ///
+    /// Ligature carets mark the boundaries between the components of a
+    /// ligature glyph, so a ligature of three characters usually has two
+    /// carets. The positions are relative to the origin of the glyph along the
+    /// `direction` of the text. The result is empty if `glyph` is not a
+    /// ligature or the font has no carets for it.
+    pub fn get_ligature_carets(&self, direction: Direction, glyph: Glyph) -> Vec<Position> {
+        let count = unsafe {
+            hb_ot_layout_get_ligature_carets(
+                self.as_raw(),
+                direction.to_raw(),
+                glyph,
+                0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        let mut carets = vec![0; count as usize];
+        let mut len = count;
+        unsafe {
+            hb_ot_layout_get_ligature_carets(
+                self.as_raw(),
+                direction.to_raw(),
+                glyph,
+                0,
+                &mut len,
+                carets.as_mut_ptr(),
+            )
+        };
+        carets.truncate(len as usize);
+        carets
+    }
+
+    /// Returns the optical bound of `glyph` defined by the `GPOS` lookup with
+    /// index `lookup_index` (`hb_ot_layout_lookup_get_optical_bound`).
+    ///
+    /// **Unstable:** this wraps an experimental HarfBuzz function, which may
+    /// change or disappear in any HarfBuzz release. It requires the
+    /// `experimental-api` feature and is not covered by the semver guarantees
+    /// of this crate.
+    ///
+    /// Optical bounds are the adjustments of the `lfbd` and `rtbd` features
+    /// that let glyphs protrude into the margin for optical margin alignment.
+    /// The result is the adjustment of the glyph's position along `direction`
+    /// in font units of the font's scale, or 0 if the lookup does not adjust
+    /// the glyph. The lookups of the features can be found with
+    /// [`Face::collect_lookups()`].
+    #[cfg(feature = "experimental-api")]
+    pub fn lookup_optical_bound(
+        &self,
+        lookup_index: u32,
+        direction: Direction,
+        glyph: Glyph,
+    ) -> Position {
+        unsafe {
+            crate::bindings::hb_ot_layout_lookup_get_optical_bound(
+                self.as_raw(),
+                lookup_index,
+                direction.to_raw(),
+                glyph,
+            )
+        }
+    }
+
     pub fn get_glyph_name(&self, glyph: Glyph) -> Option<String> {
         let mut buffer = [0; 256];
         let result = unsafe {
@@ -456,17 +1002,87 @@ impl<'a> Font<'a> {
         }
     }
 
-    pub fn draw_glyph<FuncsType>(&self, glyph: Glyph, drawfuncs: &FuncsType)
-    where
-        FuncsType: 'a + Send + Sync + DrawFuncs + std::fmt::Debug,
-    {
-        let funcs_impl: Owned<DrawFuncsImpl<FuncsType>> = DrawFuncsImpl::from_trait_impl();
+    /// Draws the outline of `glyph` by calling the methods of `drawfuncs`.
+    ///
+    /// Outlines with cubic curves in the `glyf` table and variable composite
+    /// glyphs are only supported with the `experimental-api` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::draw_funcs::{DrawFuncs, DrawState};
+    /// use harfbuzz_rs::{Face, Font};
+    ///
+    /// // collects the paths of the outline as lists of points
+    /// #[derive(Default)]
+    /// struct Points(Vec<Vec<(f32, f32)>>);
+    ///
+    /// impl DrawFuncs for Points {
+    ///     fn move_to(&mut self, _: &DrawState, x: f32, y: f32) {
+    ///         self.0.push(vec![(x, y)]);
+    ///     }
+    ///     fn line_to(&mut self, _: &DrawState, x: f32, y: f32) {
+    ///         self.0.last_mut().unwrap().push((x, y));
+    ///     }
+    ///     fn quadratic_to(&mut self, _: &DrawState, _: f32, _: f32, x: f32, y: f32) {
+    ///         self.0.last_mut().unwrap().push((x, y));
+    ///     }
+    ///     fn cubic_to(
+    ///         &mut self,
+    ///         _: &DrawState,
+    ///         _: f32,
+    ///         _: f32,
+    ///         _: f32,
+    ///         _: f32,
+    ///         x: f32,
+    ///         y: f32,
+    ///     ) {
+    ///         self.0.last_mut().unwrap().push((x, y));
+    ///     }
+    ///     fn close_path(&mut self, _: &DrawState) {}
+    /// }
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let glyph = font.get_nominal_glyph('o').unwrap();
+    ///
+    /// let mut points = Points::default();
+    /// font.draw_glyph(glyph, &mut points);
+    /// // the outer and the inner contour
+    /// assert_eq!(points.0.len(), 2);
+    /// ```
+    pub fn draw_glyph(&self, glyph: Glyph, drawfuncs: &mut impl DrawFuncs) {
+        self.draw_glyph_with_panic_policy(glyph, drawfuncs, PanicPolicy::default())
+    }
+
+    /// Draws `glyph` like [`Self::draw_glyph()`] but handles panics in the
+    /// callbacks of `drawfuncs` according to `policy`.
+    pub fn draw_glyph_with_panic_policy<FuncsType: DrawFuncs>(
+        &self,
+        glyph: Glyph,
+        drawfuncs: &mut FuncsType,
+        policy: PanicPolicy,
+    ) {
+        let funcs_impl: Owned<DrawFuncsImpl<FuncsType>> =
+            DrawFuncsImpl::from_trait_impl_with_panic_policy(policy);
+        self.draw_glyph_with_funcs(glyph, &funcs_impl, drawfuncs);
+    }
+
+    /// Draws the outline of `glyph` by calling the callbacks of `funcs` with
+    /// `data`.
+    ///
+    /// This allows drawing with closures instead of a [`DrawFuncs`]
+    /// implementation and reusing the callbacks for many glyphs. See
+    /// [`DrawFuncsImpl`] for an example.
+    ///
+    /// [`DrawFuncsImpl`]: crate::draw_funcs::DrawFuncsImpl
+    pub fn draw_glyph_with_funcs<T>(&self, glyph: Glyph, funcs: &DrawFuncsImpl<T>, data: &mut T) {
         unsafe {
             hb_font_draw_glyph(
                 self.as_raw(),
                 glyph,
-                funcs_impl.as_raw(),
-                drawfuncs as *const _ as *mut std::ffi::c_void,
+                funcs.as_raw(),
+                data as *mut T as *mut c_void,
             )
         };
     }
@@ -497,6 +1113,211 @@ impl<'a> Font<'a> {
             )
         };
     }
+
+    /// Sets the value of the variation axis `tag` and keeps the values of all
+    /// other axes.
+    ///
+    /// In contrast to this, [`Self::set_variations()`] resets axes that are
+    /// not given to their default values.
+    ///
+    /// Returns `false` and leaves the font unchanged if the font has no axis
+    /// `tag`.
+    pub fn set_variation(&mut self, tag: impl Into<Tag>, value: f32) -> bool {
+        let tag = tag.into();
+        let mut variations = self.variations();
+        let axis = match variations.iter_mut().find(|var| var.tag() == tag) {
+            Some(axis) => axis,
+            None => return false,
+        };
+        *axis = Variation::new(tag, value);
+        self.set_variations(&variations);
+        true
+    }
+
+    /// Returns the current value of every variation axis of the font in
+    /// design units, in the order of the axes in the `fvar` table.
+    ///
+    /// Axes that were not set have their default value. The result is empty
+    /// for fonts without variations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Face, Font, Tag, Variation};
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let mut font = Font::new(face);
+    /// assert_eq!(font.variation(b"wght"), Some(200.0));
+    ///
+    /// font.set_variations(&[Variation::new(b"wght", 700.0)]);
+    /// let variations = font.variations();
+    /// assert_eq!(variations.len(), 1);
+    /// assert_eq!(variations[0].tag(), Tag::from(b"wght"));
+    /// assert_eq!(variations[0].value(), 700.0);
+    /// assert_eq!(font.variation(b"wdth"), None);
+    /// ```
+    pub fn variations(&self) -> Vec<Variation> {
+        let face = self.face();
+        let mut count = unsafe { hb_ot_var_get_axis_count(face.as_raw()) };
+        let mut axes: Vec<hb_ot_var_axis_info_t> = Vec::with_capacity(count as usize);
+        unsafe {
+            hb_ot_var_get_axis_infos(face.as_raw(), 0, &mut count, axes.as_mut_ptr());
+            axes.set_len(count as usize);
+        }
+        let coords = self.var_coords_design();
+        axes.iter()
+            .enumerate()
+            .map(|(i, axis)| {
+                let value = coords.get(i).copied().unwrap_or(axis.default_value);
+                Variation::new(Tag(axis.tag), value)
+            })
+            .collect()
+    }
+
+    /// Returns the current value of the variation axis `tag` in design units
+    /// or `None` if the font has no such axis.
+    ///
+    /// See [`Self::variations()`].
+    pub fn variation(&self, tag: impl Into<Tag>) -> Option<f32> {
+        let tag = tag.into();
+        self.variations()
+            .into_iter()
+            .find(|variation| variation.tag() == tag)
+            .map(|variation| variation.value())
+    }
+
+    /// Sets the variation coordinates of the font in design units, one per
+    /// axis in the order of the axes in the `fvar` table.
+    ///
+    /// This is the same as [`Self::set_variations()`] without the lookup of
+    /// the axes by tag, which makes it cheap enough to update the coordinates
+    /// e.g. in every frame of an animation. Axes missing at the end of
+    /// `coords` get their default values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Face, Font};
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let mut font = Font::new(face);
+    /// font.set_var_coords_design(&[650.0]);
+    /// assert_eq!(font.var_coords_design(), &[650.0]);
+    /// assert_eq!(font.variation(b"wght"), Some(650.0));
+    /// ```
+    pub fn set_var_coords_design(&mut self, coords: &[f32]) {
+        unsafe {
+            hb_font_set_var_coords_design(self.as_raw_mut(), coords.as_ptr(), coords.len() as u32)
+        };
+    }
+
+    /// Applies the variation coordinates of the named instance with `index`
+    /// from the `fvar` table of the font, e.g. "SemiBold Condensed".
+    ///
+    /// Valid indices are smaller than
+    /// [`Face::named_instance_count()`]. Other indices are ignored and leave
+    /// the variations of the font unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Face, Font};
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let mut font = Font::new(face);
+    /// assert_eq!(font.var_named_instance(), None);
+    ///
+    /// let last = font.face().named_instance_count() - 1;
+    /// font.set_var_named_instance(last);
+    /// assert_eq!(font.var_named_instance(), Some(last));
+    /// assert_eq!(font.variation(b"wght"), Some(900.0));
+    ///
+    /// font.set_var_named_instance(last + 1);
+    /// assert_eq!(font.var_named_instance(), Some(last));
+    /// assert_eq!(font.variation(b"wght"), Some(900.0));
+    /// ```
+    pub fn set_var_named_instance(&mut self, index: u32) {
+        // HarfBuzz resets the variations for invalid indices
+        if index >= self.face().named_instance_count() {
+            return;
+        }
+        unsafe { hb_font_set_var_named_instance(self.as_raw_mut(), index) };
+    }
+
+    /// Returns the index of the named instance that was applied with
+    /// [`Self::set_var_named_instance()`] or `None` if there is none.
+    pub fn var_named_instance(&self) -> Option<u32> {
+        // `HB_FONT_NO_VAR_NAMED_INSTANCE`
+        const NO_VAR_NAMED_INSTANCE: u32 = 0xFFFF_FFFF;
+        match unsafe { hb_font_get_var_named_instance(self.as_raw()) } {
+            NO_VAR_NAMED_INSTANCE => None,
+            index => Some(index),
+        }
+    }
+
+    /// Sets the normalized variation coordinates of the font, one per axis
+    /// in the order of the axes in the `fvar` table.
+    ///
+    /// Normalized coordinates are 2.14 fixed point numbers in the range from
+    /// -1 (`-16384`) to 1 (`16384`), where 0 is the default value of an axis,
+    /// after the `avar` mapping has been applied. This is useful for engines
+    /// that normalize the coordinates themselves. Axes missing at the end of
+    /// `coords` get their default values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Face, Font, Variation};
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let mut font = Font::new(face);
+    /// font.set_var_coords_normalized(&[16384]);
+    /// assert_eq!(font.var_coords_normalized(), &[16384]);
+    ///
+    /// let mut other = Font::new(font.face());
+    /// other.set_variations(&[Variation::new(b"wght", 900.0)]);
+    /// assert_eq!(other.var_coords_normalized(), font.var_coords_normalized());
+    /// ```
+    pub fn set_var_coords_normalized(&mut self, coords: &[i32]) {
+        unsafe {
+            hb_font_set_var_coords_normalized(
+                self.as_raw_mut(),
+                coords.as_ptr(),
+                coords.len() as u32,
+            )
+        };
+    }
+
+    /// Returns the normalized variation coordinates of the font as 2.14
+    /// fixed point numbers.
+    ///
+    /// The slice is empty if no variations were set and may be shorter than
+    /// the number of axes; the remaining axes have their default values.
+    pub fn var_coords_normalized(&self) -> &[i32] {
+        let mut len = 0;
+        let coords = unsafe { hb_font_get_var_coords_normalized(self.as_raw(), &mut len) };
+        if coords.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(coords, len as usize) }
+        }
+    }
+
+    /// Returns the variation coordinates of the font in design units.
+    ///
+    /// The coordinates are in the order of the axes in the `fvar` table. The
+    /// slice is empty if no variations were set and may be shorter than the
+    /// number of axes; the remaining axes have their default values. Use
+    /// [`Self::variations()`] to get the values of all axes.
+    pub fn var_coords_design(&self) -> &[f32] {
+        let mut len = 0;
+        let coords = unsafe { hb_font_get_var_coords_design(self.as_raw(), &mut len) };
+        if coords.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(coords, len as usize) }
+        }
+    }
 }
 
 unsafe impl<'a> Send for Font<'a> {}
@@ -546,4 +1367,145 @@ mod test {
     fn test_font_extents_layout() {
         assert_memory_layout_equal::<FontExtents, hb_font_extents_t>()
     }
+
+    #[test]
+    fn test_raw_pointers() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let mut font = Font::new(Face::from_file(path, 0).unwrap());
+        font.set_scale(100, 100);
+        let ptr = font.as_ptr();
+        let shared = unsafe { Font::from_ptr(ptr) };
+        assert_eq!(shared.as_ptr(), ptr);
+        assert_eq!(shared.scale(), (100, 100));
+        drop(shared);
+        // the font is still alive
+        assert_eq!(font.scale(), (100, 100));
+
+        let face = font.face();
+        let face_from_ptr = unsafe { Face::from_ptr(face.as_ptr()) };
+        assert_eq!(face_from_ptr.upem(), face.upem());
+        let blob = face.face_data();
+        let blob_from_ptr = unsafe { crate::Blob::from_ptr(blob.as_ptr()) };
+        assert_eq!(blob_from_ptr.as_ptr(), blob.as_ptr());
+        assert_eq!(blob_from_ptr.len(), blob.len());
+
+        let ptr = crate::UnicodeBuffer::new().add_str("ab").into_raw();
+        let buffer = unsafe { crate::UnicodeBuffer::from_raw_owned(ptr) };
+        assert_eq!(buffer.as_ptr(), ptr);
+        assert_eq!(buffer.len(), 2);
+
+        let ptr = crate::shape(&font, buffer, &[]).into_raw();
+        let glyphs = unsafe { crate::GlyphBuffer::from_raw_owned(ptr) };
+        assert_eq!(glyphs.as_ptr(), ptr);
+        assert_eq!(glyphs.len(), 2);
+        assert!(glyphs.has_positions());
+    }
+
+    #[test]
+    #[cfg(feature = "experimental-api")]
+    fn test_lookup_optical_bound() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let glyph = font.get_nominal_glyph('A').unwrap();
+        // the font has no optical bounds, so neither its kerning lookups nor
+        // nonexistent lookups adjust the glyph
+        let kern = font
+            .face()
+            .collect_lookups(b"GPOS", &[Tag::new('k', 'e', 'r', 'n')]);
+        assert!(!kern.is_empty());
+        for lookup in kern.iter().chain(Some(u32::MAX)) {
+            assert_eq!(font.lookup_optical_bound(lookup, Direction::Ltr, glyph), 0);
+        }
+    }
+
+    #[test]
+    fn test_glyph_advances() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let glyphs: Vec<Glyph> = (0..20).collect();
+        let mut advances = vec![0; glyphs.len()];
+        font.glyph_h_advances(&glyphs, &mut advances);
+        for (&glyph, &advance) in glyphs.iter().zip(&advances) {
+            assert_eq!(advance, font.get_glyph_h_advance(glyph));
+        }
+        font.glyph_v_advances(&glyphs, &mut advances);
+        for (&glyph, &advance) in glyphs.iter().zip(&advances) {
+            assert_eq!(advance, font.get_glyph_v_advance(glyph));
+        }
+        font.glyph_h_advances(&[], &mut []);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_glyph_advances_length_mismatch() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        Font::new(face).glyph_h_advances(&[1, 2], &mut [0]);
+    }
+
+    #[test]
+    fn test_variations() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let mut font = Font::new(face);
+        let default = font.variations();
+        assert!(font.set_variation(b"wght", 250.0));
+        assert_eq!(font.variation(b"wght"), Some(250.0));
+        // unknown axes are ignored
+        assert!(!font.set_variation(b"wdth", 50.0));
+        assert_eq!(font.variations().len(), default.len());
+        assert_eq!(font.variation(b"wght"), Some(250.0));
+        font.set_variations(&[]);
+        assert_eq!(font.variation(b"wght"), default[0].value().into());
+        assert!(Font::empty().variations().is_empty());
+    }
+
+    #[test]
+    fn test_scale_helpers() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let upem = face.upem() as i32;
+        let mut font = Font::new(face);
+        let glyph = font.get_nominal_glyph('A').unwrap();
+        let advance_in_units = font.get_glyph_h_advance(glyph);
+
+        font.set_pixel_size(12.5);
+        assert_eq!(font.scale(), (800, 800));
+        assert_eq!(font.ppem(), (13, 13));
+        let expected = advance_in_units as i64 * 800 / upem as i64;
+        assert!((font.get_glyph_h_advance(glyph) as i64 - expected).abs() <= 1);
+
+        font.set_scale_to_upem();
+        assert_eq!(font.scale(), (upem, upem));
+        assert_eq!(font.get_glyph_h_advance(glyph), advance_in_units);
+    }
+
+    #[derive(Debug)]
+    struct PanickingDraw;
+
+    impl DrawFuncs for PanickingDraw {
+        fn move_to(&mut self, _: &DrawState, _: f32, _: f32) {}
+        fn line_to(&mut self, _: &DrawState, _: f32, _: f32) {}
+        fn quadratic_to(&mut self, _: &DrawState, _: f32, _: f32, _: f32, _: f32) {}
+        fn cubic_to(&mut self, _: &DrawState, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {}
+        fn close_path(&mut self, _: &DrawState) {
+            panic!("close_path failed");
+        }
+    }
+
+    #[test]
+    fn test_draw_panic_policy() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let glyph = font.get_nominal_glyph('o').unwrap();
+
+        let sink = crate::PanicSink::new();
+        font.draw_glyph_with_panic_policy(
+            glyph,
+            &mut PanickingDraw,
+            PanicPolicy::Record(sink.clone()),
+        );
+        let messages = sink.take();
+        // one panic per contour
+        assert_eq!(messages.len(), 2);
+        assert!(messages.iter().all(|msg| msg == "close_path failed"));
+        assert!(sink.is_empty());
+    }
 }
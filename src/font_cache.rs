@@ -0,0 +1,419 @@
+// Copyright (c) 2018 Manuel Reinhardt
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Contains the `FontCache` type.
+//!
+//! Applications that display text from many different fonts usually keep the
+//! loaded fonts around in some kind of cache. `FontCache` provides such a cache
+//! for `Font`s keyed by their source (e.g. a file path), face index and
+//! variation settings.
+
+use crate::blob::Blob;
+use crate::common::{Shared, Tag};
+use crate::face::Face;
+use crate::font::Font;
+use crate::Variation;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FaceKey<S> {
+    source: S,
+    index: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FontKey<S> {
+    face: FaceKey<S>,
+    // `f32` is not `Hash`, so the values are stored as their bit patterns.
+    variations: Vec<(Tag, u32)>,
+}
+
+impl<S> FontKey<S> {
+    fn new(source: S, index: u32, variations: &[Variation]) -> Self {
+        FontKey {
+            face: FaceKey { source, index },
+            variations: variations
+                .iter()
+                .map(|var| (var.tag(), var.value().to_bits()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedFace {
+    face: Shared<Face<'static>>,
+    size: usize,
+    users: usize,
+}
+
+#[derive(Debug)]
+struct CachedFont {
+    font: Shared<Font<'static>>,
+    last_used: u64,
+}
+
+/// A least-recently-used cache of `Font`s.
+///
+/// Fonts are identified by a source `S` (a file path by default), the index of
+/// the face inside the font file and the variation settings applied to the
+/// font. Fonts which only differ in their variation settings share a single
+/// `Face`. A face is only kept alive by the cache as long as at least one of
+/// the cached fonts refers to it.
+///
+/// The size of the cache is limited by the approximate amount of font data it
+/// holds on to (see [`Face::approximate_memory_usage()`]). When that limit is
+/// exceeded, the least recently used fonts are evicted. The most recently
+/// inserted font is never evicted, so a single font larger than the limit can
+/// still be cached.
+///
+/// Fonts handed out by the cache are reference counted, so evicting a font from
+/// the cache never invalidates a font that is still in use somewhere else. Its
+/// memory is released once the last reference to it is dropped.
+///
+/// The cache holds strong references to its fonts: HarfBuzz objects have no
+/// weak references, and the cache could not tell whether a font is still
+/// alive without them. A font therefore stays in memory until it is evicted
+/// or removed, even if it is not used anywhere else. Besides the size limit,
+/// [`Self::remove()`], [`Self::remove_least_recently_used()`],
+/// [`Self::shrink_to()`] and [`Self::clear()`] allow releasing fonts early,
+/// e.g. when the application is notified about memory pressure.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::font_cache::FontCache;
+/// use harfbuzz_rs::Variation;
+///
+/// let path = "testfiles/SourceSansVariable-Roman.ttf";
+/// let mut cache = FontCache::new(64 * 1024 * 1024);
+///
+/// let regular = cache.load(path, 0, &[]).expect("could not load font");
+/// let bold = cache
+///     .load(path, 0, &[Variation::new(b"wght", 700.0)])
+///     .expect("could not load font");
+///
+/// // Both fonts share the same face...
+/// assert_eq!(regular.face(), bold.face());
+/// // ...and loading the same font again returns the cached font.
+/// assert_eq!(cache.load(path, 0, &[]).unwrap(), regular);
+/// assert_eq!(cache.len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct FontCache<S = PathBuf> {
+    max_bytes: usize,
+    used_bytes: usize,
+    clock: u64,
+    faces: HashMap<FaceKey<S>, CachedFace>,
+    fonts: HashMap<FontKey<S>, CachedFont>,
+}
+
+impl<S: Clone + Eq + Hash> FontCache<S> {
+    /// Creates an empty cache that holds on to at most (approximately)
+    /// `max_bytes` of font data.
+    pub fn new(max_bytes: usize) -> Self {
+        FontCache {
+            max_bytes,
+            used_bytes: 0,
+            clock: 0,
+            faces: HashMap::new(),
+            fonts: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of fonts in the cache.
+    pub fn len(&self) -> usize {
+        self.fonts.len()
+    }
+
+    /// Returns `true` if the cache contains no fonts.
+    pub fn is_empty(&self) -> bool {
+        self.fonts.is_empty()
+    }
+
+    /// Returns the approximate number of bytes of font data held by the cache.
+    pub fn memory_usage(&self) -> usize {
+        self.used_bytes
+    }
+
+    /// Returns the maximum number of bytes of font data the cache holds on to.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Changes the size limit of the cache, evicting fonts if necessary.
+    pub fn set_max_bytes(&mut self, max_bytes: usize) {
+        self.max_bytes = max_bytes;
+        self.evict(None);
+    }
+
+    /// Returns the cached font for the given key or `None` if no such font is
+    /// in the cache.
+    ///
+    /// This marks the font as the most recently used one.
+    pub fn get(
+        &mut self,
+        source: &S,
+        index: u32,
+        variations: &[Variation],
+    ) -> Option<Shared<Font<'static>>> {
+        let key = FontKey::new(source.clone(), index, variations);
+        self.clock += 1;
+        let entry = self.fonts.get_mut(&key)?;
+        entry.last_used = self.clock;
+        Some(entry.font.clone())
+    }
+
+    /// Returns the cached font for the given key or creates it if it is not in
+    /// the cache yet.
+    ///
+    /// If no font with the same `source` and `index` is cached, `load` is called
+    /// to obtain the font data. Errors returned by `load` are passed on to the
+    /// caller and nothing is inserted into the cache.
+    pub fn get_or_insert_with<F, E>(
+        &mut self,
+        source: S,
+        index: u32,
+        variations: &[Variation],
+        load: F,
+    ) -> Result<Shared<Font<'static>>, E>
+    where
+        F: FnOnce(&S) -> Result<Shared<Blob<'static>>, E>,
+    {
+        if let Some(font) = self.get(&source, index, variations) {
+            return Ok(font);
+        }
+
+        let key = FontKey::new(source, index, variations);
+        let face = match self.faces.get_mut(&key.face) {
+            Some(cached) => {
+                cached.users += 1;
+                cached.face.clone()
+            }
+            None => {
+                let blob = load(&key.face.source)?;
+                let face: Shared<Face<'static>> = Face::new(blob, index).into();
+                let size = face.approximate_memory_usage();
+                self.used_bytes += size;
+                self.faces.insert(
+                    key.face.clone(),
+                    CachedFace {
+                        face: face.clone(),
+                        size,
+                        users: 1,
+                    },
+                );
+                face
+            }
+        };
+
+        let mut font = Font::new(face);
+        if !variations.is_empty() {
+            font.set_variations(variations);
+        }
+        let font: Shared<Font<'static>> = font.into();
+
+        self.fonts.insert(
+            key.clone(),
+            CachedFont {
+                font: font.clone(),
+                last_used: self.clock,
+            },
+        );
+        self.evict(Some(&key));
+        Ok(font)
+    }
+
+    /// Removes the font with the given key from the cache and returns it.
+    pub fn remove(
+        &mut self,
+        source: &S,
+        index: u32,
+        variations: &[Variation],
+    ) -> Option<Shared<Font<'static>>> {
+        let key = FontKey::new(source.clone(), index, variations);
+        self.remove_key(&key)
+    }
+
+    /// Removes the least recently used font from the cache and returns it.
+    ///
+    /// Returns `None` if the cache is empty.
+    pub fn remove_least_recently_used(&mut self) -> Option<Shared<Font<'static>>> {
+        let key = self.least_recently_used(None)?;
+        self.remove_key(&key)
+    }
+
+    /// Evicts least recently used fonts until the cache holds on to at most
+    /// (approximately) `bytes` of font data.
+    ///
+    /// Unlike [`Self::set_max_bytes()`] this does not change the size limit of
+    /// the cache and it may evict all fonts.
+    pub fn shrink_to(&mut self, bytes: usize) {
+        while self.used_bytes > bytes && self.remove_least_recently_used().is_some() {}
+    }
+
+    /// Removes all fonts from the cache.
+    pub fn clear(&mut self) {
+        self.fonts.clear();
+        self.faces.clear();
+        self.used_bytes = 0;
+    }
+
+    fn remove_key(&mut self, key: &FontKey<S>) -> Option<Shared<Font<'static>>> {
+        let entry = self.fonts.remove(key)?;
+        let cached_face = self
+            .faces
+            .get_mut(&key.face)
+            .expect("every cached font has a cached face");
+        cached_face.users -= 1;
+        if cached_face.users == 0 {
+            self.used_bytes -= cached_face.size;
+            self.faces.remove(&key.face);
+        }
+        Some(entry.font)
+    }
+
+    fn least_recently_used(&self, keep: Option<&FontKey<S>>) -> Option<FontKey<S>> {
+        self.fonts
+            .iter()
+            .filter(|(key, _)| Some(*key) != keep)
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone())
+    }
+
+    // Evicts least recently used fonts until the cache fits into its limit.
+    fn evict(&mut self, keep: Option<&FontKey<S>>) {
+        while self.used_bytes > self.max_bytes {
+            match self.least_recently_used(keep) {
+                Some(key) => {
+                    self.remove_key(&key);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+impl FontCache<PathBuf> {
+    /// Returns the cached font for the font file at `path` or loads it if it is
+    /// not in the cache yet.
+    ///
+    /// See also [`Self::get_or_insert_with()`].
+    pub fn load<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        index: u32,
+        variations: &[Variation],
+    ) -> io::Result<Shared<Font<'static>>> {
+        self.get_or_insert_with(path.as_ref().to_owned(), index, variations, |path| {
+            Blob::from_file(path)
+        })
+    }
+}
+
+impl<S: Clone + Eq + Hash> Default for FontCache<S> {
+    /// Creates a cache with a limit of 256 MiB of font data.
+    fn default() -> Self {
+        FontCache::new(256 * 1024 * 1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATH: &str = "testfiles/SourceSansVariable-Roman.ttf";
+
+    fn font_data() -> Shared<Blob<'static>> {
+        std::fs::read(PATH).unwrap().into()
+    }
+
+    #[test]
+    fn test_faces_are_shared_between_variations() {
+        let mut cache = FontCache::new(usize::MAX);
+        let light = cache.load(PATH, 0, &[Variation::new(b"wght", 300.0)]);
+        let bold = cache.load(PATH, 0, &[Variation::new(b"wght", 700.0)]);
+        assert_eq!(light.unwrap().face(), bold.unwrap().face());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.faces.len(), 1);
+
+        let face_size = cache.memory_usage();
+        assert!(face_size > 0);
+
+        cache.remove(&PATH.into(), 0, &[Variation::new(b"wght", 300.0)]);
+        assert_eq!(cache.memory_usage(), face_size);
+        cache.remove(&PATH.into(), 0, &[Variation::new(b"wght", 700.0)]);
+        assert_eq!(cache.memory_usage(), 0);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = FontCache::new(usize::MAX);
+        let load = |_: &&str| Ok::<_, ()>(font_data());
+        let first = cache.get_or_insert_with("first", 0, &[], load).unwrap();
+        cache.get_or_insert_with("second", 0, &[], load).unwrap();
+        let face_size = first.face().approximate_memory_usage();
+
+        // touch "first" so that "second" becomes the least recently used font
+        assert!(cache.get(&"first", 0, &[]).is_some());
+        cache.set_max_bytes(2 * face_size);
+        cache.get_or_insert_with("third", 0, &[], load).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&"second", 0, &[]).is_none());
+        assert!(cache.get(&"first", 0, &[]).is_some());
+        assert!(cache.get(&"third", 0, &[]).is_some());
+
+        // evicted fonts stay valid as long as they are referenced
+        cache.clear();
+        assert!(first.get_nominal_glyph('A').is_some());
+    }
+
+    #[test]
+    fn test_explicit_eviction() {
+        let mut cache = FontCache::new(usize::MAX);
+        let load = |_: &&str| Ok::<_, ()>(font_data());
+        let first = cache.get_or_insert_with("first", 0, &[], load).unwrap();
+        let second = cache.get_or_insert_with("second", 0, &[], load).unwrap();
+        cache.get_or_insert_with("third", 0, &[], load).unwrap();
+        let face_size = first.face().approximate_memory_usage();
+
+        assert!(cache.get(&"first", 0, &[]).is_some());
+        assert_eq!(cache.remove_least_recently_used(), Some(second));
+        assert_eq!(cache.memory_usage(), 2 * face_size);
+
+        cache.shrink_to(face_size);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&"first", 0, &[]).is_some());
+        assert_eq!(cache.max_bytes(), usize::MAX);
+
+        cache.shrink_to(0);
+        assert!(cache.is_empty());
+        assert_eq!(cache.remove_least_recently_used(), None);
+    }
+
+    #[test]
+    fn test_newest_font_is_never_evicted() {
+        let mut cache = FontCache::new(0);
+        let font = cache
+            .get_or_insert_with("font", 0, &[], |_| Ok::<_, ()>(font_data()))
+            .unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&"font", 0, &[]), Some(font));
+    }
+
+    #[test]
+    fn test_load_error() {
+        let mut cache = FontCache::default();
+        assert!(cache.load("does/not/exist.ttf", 0, &[]).is_err());
+        assert!(cache.is_empty());
+        assert_eq!(cache.memory_usage(), 0);
+    }
+}
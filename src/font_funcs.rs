@@ -18,7 +18,9 @@ use crate::bindings::{
     hb_font_funcs_set_variation_glyph_func, hb_font_funcs_t, hb_font_t, hb_glyph_extents_t,
 };
 use crate::font::destroy_box;
-use crate::{Font, FontExtents, Glyph, GlyphExtents, HarfbuzzObject, Owned, Position, Shared};
+use crate::{
+    Font, FontExtents, Glyph, GlyphExtents, HarfbuzzObject, Owned, PanicPolicy, Position, Shared,
+};
 
 use std::os::raw::c_void;
 
@@ -427,28 +429,62 @@ impl<T: FontFuncs> FontFuncsImpl<T> {
     /// ```
     ///
     pub fn from_trait_impl() -> Owned<FontFuncsImpl<T>> {
+        FontFuncsImpl::from_trait_impl_with_panic_policy(PanicPolicy::default())
+    }
+
+    /// Like `from_trait_impl` but panics in the trait methods are handled
+    /// according to `policy`.
+    pub fn from_trait_impl_with_panic_policy(policy: PanicPolicy) -> Owned<FontFuncsImpl<T>> {
         let mut ffuncs = FontFuncsImpl::new();
-        ffuncs.set_trait_impl();
+        ffuncs.set_trait_impl(&policy);
         ffuncs
     }
 
-    fn set_trait_impl(&mut self) {
-        self.set_font_h_extents_func(|font, data| data.get_font_h_extents(font));
-        self.set_font_v_extents_func(|font, data| data.get_font_v_extents(font));
-        self.set_nominal_glyph_func(|font, data, chr| data.get_nominal_glyph(font, chr));
-        self.set_variation_glyph_func(|font, data, chr, var| {
-            data.get_variation_glyph(font, chr, var)
+    fn set_trait_impl(&mut self, policy: &PanicPolicy) {
+        let p = policy.clone();
+        self.set_font_h_extents_func(move |font, data| p.call(|| data.get_font_h_extents(font)));
+        let p = policy.clone();
+        self.set_font_v_extents_func(move |font, data| p.call(|| data.get_font_v_extents(font)));
+        let p = policy.clone();
+        self.set_nominal_glyph_func(move |font, data, chr| {
+            p.call(|| data.get_nominal_glyph(font, chr))
+        });
+        let p = policy.clone();
+        self.set_variation_glyph_func(move |font, data, chr, var| {
+            p.call(|| data.get_variation_glyph(font, chr, var))
+        });
+        let p = policy.clone();
+        self.set_glyph_h_advance_func(move |font, data, glyph| {
+            p.call(|| data.get_glyph_h_advance(font, glyph))
+        });
+        let p = policy.clone();
+        self.set_glyph_v_advance_func(move |font, data, glyph| {
+            p.call(|| data.get_glyph_v_advance(font, glyph))
+        });
+        let p = policy.clone();
+        self.set_glyph_h_origin_func(move |font, data, glyph| {
+            p.call(|| data.get_glyph_h_origin(font, glyph))
+        });
+        let p = policy.clone();
+        self.set_glyph_v_origin_func(move |font, data, glyph| {
+            p.call(|| data.get_glyph_v_origin(font, glyph))
+        });
+        let p = policy.clone();
+        self.set_glyph_extents_func(move |font, data, glyph| {
+            p.call(|| data.get_glyph_extents(font, glyph))
+        });
+        let p = policy.clone();
+        self.set_glyph_contour_point_func(move |font, data, glyph, index| {
+            p.call(|| data.get_glyph_contour_point(font, glyph, index))
+        });
+        let p = policy.clone();
+        self.set_glyph_name_func(move |font, data, glyph| {
+            p.call(|| data.get_glyph_name(font, glyph))
         });
-        self.set_glyph_h_advance_func(|font, data, glyph| data.get_glyph_h_advance(font, glyph));
-        self.set_glyph_v_advance_func(|font, data, glyph| data.get_glyph_v_advance(font, glyph));
-        self.set_glyph_h_origin_func(|font, data, glyph| data.get_glyph_h_origin(font, glyph));
-        self.set_glyph_v_origin_func(|font, data, glyph| data.get_glyph_v_origin(font, glyph));
-        self.set_glyph_extents_func(|font, data, glyph| data.get_glyph_extents(font, glyph));
-        self.set_glyph_contour_point_func(|font, data, glyph, index| {
-            data.get_glyph_contour_point(font, glyph, index)
+        let p = policy.clone();
+        self.set_glyph_from_name_func(move |font, data, name| {
+            p.call(|| data.get_glyph_from_name(font, name))
         });
-        self.set_glyph_name_func(|font, data, glyph| data.get_glyph_name(font, glyph));
-        self.set_glyph_from_name_func(|font, data, name| data.get_glyph_from_name(font, name));
     }
 }
 
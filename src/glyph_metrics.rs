@@ -0,0 +1,160 @@
+#[cfg(feature = "ttf-parser")]
+use std::convert::TryFrom;
+
+use crate::common::{HarfbuzzObject, Owned, Shared};
+use crate::font::{Font, FontExtents, Glyph, GlyphExtents, Position};
+
+/// A source of font and glyph metrics.
+///
+/// The measurement and layout helpers of this crate that do not shape, like
+/// [`LineMetrics::from_font()`](crate::LineMetrics::from_font),
+/// [`GlyphBuffer::ink_extents()`](crate::GlyphBuffer::ink_extents) or
+/// [`hex_boxes()`](crate::hex_boxes), only need metrics and accept any
+/// source. This allows e.g. measuring glyphs that were shaped elsewhere
+/// with a pure Rust font parser.
+///
+/// The trait is implemented for [`Font`], which returns metrics in its
+/// scale, and with the `ttf-parser` feature for `ttf_parser::Face`, which
+/// returns metrics in font units. Values of different sources are only
+/// comparable if they use the same scale.
+pub trait GlyphMetricsSource {
+    /// Returns the extents of the font for horizontal text.
+    fn font_h_extents(&self) -> Option<FontExtents>;
+
+    /// Returns the advance of `glyph` for horizontal text.
+    fn glyph_h_advance(&self, glyph: Glyph) -> Position;
+
+    /// Returns the extents of `glyph` or `None` if the glyph does not exist.
+    fn glyph_extents(&self, glyph: Glyph) -> Option<GlyphExtents>;
+}
+
+impl<'a> GlyphMetricsSource for Font<'a> {
+    fn font_h_extents(&self) -> Option<FontExtents> {
+        self.get_font_h_extents()
+    }
+
+    fn glyph_h_advance(&self, glyph: Glyph) -> Position {
+        self.get_glyph_h_advance(glyph)
+    }
+
+    fn glyph_extents(&self, glyph: Glyph) -> Option<GlyphExtents> {
+        self.get_glyph_extents(glyph)
+    }
+}
+
+impl<T: HarfbuzzObject + GlyphMetricsSource> GlyphMetricsSource for Owned<T> {
+    fn font_h_extents(&self) -> Option<FontExtents> {
+        (**self).font_h_extents()
+    }
+
+    fn glyph_h_advance(&self, glyph: Glyph) -> Position {
+        (**self).glyph_h_advance(glyph)
+    }
+
+    fn glyph_extents(&self, glyph: Glyph) -> Option<GlyphExtents> {
+        (**self).glyph_extents(glyph)
+    }
+}
+
+impl<T: HarfbuzzObject + GlyphMetricsSource> GlyphMetricsSource for Shared<T> {
+    fn font_h_extents(&self) -> Option<FontExtents> {
+        (**self).font_h_extents()
+    }
+
+    fn glyph_h_advance(&self, glyph: Glyph) -> Position {
+        (**self).glyph_h_advance(glyph)
+    }
+
+    fn glyph_extents(&self, glyph: Glyph) -> Option<GlyphExtents> {
+        (**self).glyph_extents(glyph)
+    }
+}
+
+#[cfg(feature = "ttf-parser")]
+impl<'a> GlyphMetricsSource for ttf_parser::Face<'a> {
+    fn font_h_extents(&self) -> Option<FontExtents> {
+        Some(FontExtents::new(
+            self.ascender().into(),
+            self.descender().into(),
+            self.line_gap().into(),
+        ))
+    }
+
+    fn glyph_h_advance(&self, glyph: Glyph) -> Position {
+        u16::try_from(glyph)
+            .ok()
+            .and_then(|glyph| self.glyph_hor_advance(ttf_parser::GlyphId(glyph)))
+            .map_or(0, Position::from)
+    }
+
+    fn glyph_extents(&self, glyph: Glyph) -> Option<GlyphExtents> {
+        let glyph = ttf_parser::GlyphId(u16::try_from(glyph).ok()?);
+        if glyph.0 >= self.number_of_glyphs() {
+            return None;
+        }
+        // glyphs without outline are empty, like in HarfBuzz
+        let bounds = self.glyph_bounding_box(glyph).unwrap_or(ttf_parser::Rect {
+            x_min: 0,
+            y_min: 0,
+            x_max: 0,
+            y_max: 0,
+        });
+        Some(GlyphExtents {
+            x_bearing: bounds.x_min.into(),
+            y_bearing: bounds.y_max.into(),
+            width: Position::from(bounds.x_max) - Position::from(bounds.x_min),
+            height: Position::from(bounds.y_min) - Position::from(bounds.y_max),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Face;
+
+    #[test]
+    #[cfg(feature = "ttf-parser")]
+    fn test_ttf_parser_metrics() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let data = std::fs::read(path).unwrap();
+        let parsed = ttf_parser::Face::parse(&data, 0).unwrap();
+        let font = Font::new(Face::from_file(path, 0).unwrap());
+
+        let (extents, parsed_extents) = (
+            font.font_h_extents().unwrap(),
+            parsed.font_h_extents().unwrap(),
+        );
+        assert_eq!(extents.ascender, parsed_extents.ascender);
+        assert_eq!(extents.descender, parsed_extents.descender);
+        for glyph in 0..10 {
+            assert_eq!(font.glyph_h_advance(glyph), parsed.glyph_h_advance(glyph));
+            let (a, b) = (
+                font.glyph_extents(glyph).unwrap(),
+                parsed.glyph_extents(glyph).unwrap(),
+            );
+            assert_eq!(
+                (a.x_bearing, a.y_bearing, a.width, a.height),
+                (b.x_bearing, b.y_bearing, b.width, b.height)
+            );
+        }
+        let missing = u32::from(parsed.number_of_glyphs());
+        assert!(parsed.glyph_extents(missing).is_none());
+        assert_eq!(parsed.glyph_h_advance(missing), 0);
+    }
+
+    #[test]
+    fn test_font_metrics_source() {
+        fn ascender(source: &impl GlyphMetricsSource) -> Position {
+            source.font_h_extents().unwrap().ascender
+        }
+
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let font = Font::new(Face::from_file(path, 0).unwrap());
+        let expected = font.get_font_h_extents().unwrap().ascender;
+        assert_eq!(ascender(&font), expected);
+        assert_eq!(ascender(&*font), expected);
+        let shared: Shared<Font<'_>> = font.into();
+        assert_eq!(ascender(&shared), expected);
+    }
+}
@@ -0,0 +1,256 @@
+use crate::buffer::GlyphBuffer;
+use crate::draw_funcs::{DrawFuncs, DrawState};
+use crate::font::Position;
+use crate::glyph_metrics::GlyphMetricsSource;
+
+// The hex digits as bitmaps of 5 rows of 3 pixels, from top to bottom.
+const DIGITS: [[u8; 5]; 16] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    [0b010, 0b101, 0b111, 0b101, 0b101],
+    [0b110, 0b101, 0b110, 0b101, 0b110],
+    [0b011, 0b100, 0b100, 0b100, 0b011],
+    [0b110, 0b101, 0b101, 0b101, 0b110],
+    [0b111, 0b100, 0b111, 0b100, 0b111],
+    [0b111, 0b100, 0b111, 0b100, 0b100],
+];
+
+// The height of a box in pixels: the frame, a gap, two rows of digits
+// separated by a gap, another gap and the frame.
+const BOX_HEIGHT: f32 = 15.0;
+
+/// A "hex box" that stands in for a character the font has no glyph for.
+///
+/// Hex boxes show the codepoint of the missing character in hexadecimal
+/// digits inside a frame, like browsers do, so missing characters can be
+/// identified instead of showing identical `.notdef` glyphs. They are found
+/// with [`hex_boxes()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HexBox {
+    /// The index of the `.notdef` glyph in the buffer.
+    pub index: usize,
+    /// The codepoint of the missing character.
+    pub codepoint: u32,
+    /// The width of the box, which is the advance of the glyph.
+    pub width: Position,
+    /// The height of the box above the baseline.
+    pub height: Position,
+}
+
+impl HexBox {
+    /// Returns the digits shown in the box: four for characters of the Basic
+    /// Multilingual Plane and six for all others.
+    pub fn digits(&self) -> String {
+        if self.codepoint <= 0xFFFF {
+            format!("{:04X}", self.codepoint)
+        } else {
+            format!("{:06X}", self.codepoint)
+        }
+    }
+
+    /// Draws the box by calling the methods of `funcs`.
+    ///
+    /// The box is drawn like the outline of a glyph at the origin, i.e. it
+    /// has to be placed at the position of the glyph with index
+    /// [`index`](Self::index). The digits are arranged in two rows and drawn
+    /// as filled squares, so the outline should be filled with the non-zero
+    /// winding rule.
+    pub fn draw(&self, funcs: &mut impl DrawFuncs) {
+        let digits: Vec<usize> = self
+            .digits()
+            .chars()
+            .map(|c| c.to_digit(16).unwrap() as usize)
+            .collect();
+        let columns = digits.len() / 2;
+        // the frame, a gap, the columns of digits separated by gaps, another
+        // gap and the frame
+        let box_width = (columns * 4 + 3) as f32;
+        let pixel = (self.width as f32 / (box_width + 2.0)).min(self.height as f32 / BOX_HEIGHT);
+        let left = (self.width as f32 - box_width * pixel) / 2.0;
+
+        let mut pen = Pen::new(funcs);
+        let (right, top) = (left + box_width * pixel, BOX_HEIGHT * pixel);
+        pen.rect(left, 0.0, right, top, false);
+        pen.rect(left + pixel, pixel, right - pixel, top - pixel, true);
+        for (i, &digit) in digits.iter().enumerate() {
+            let x = left + (2 + (i % columns) * 4) as f32 * pixel;
+            let y = top - (2 + (i / columns) * 6) as f32 * pixel;
+            for (row, bits) in DIGITS[digit].iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) != 0 {
+                        let x = x + column as f32 * pixel;
+                        let y = y - row as f32 * pixel;
+                        pen.rect(x, y - pixel, x + pixel, y, false);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Draws closed paths of straight lines and keeps track of the draw state.
+struct Pen<'a, D> {
+    funcs: &'a mut D,
+    state: DrawState,
+}
+
+impl<'a, D: DrawFuncs> Pen<'a, D> {
+    fn new(funcs: &'a mut D) -> Self {
+        Pen {
+            funcs,
+            state: DrawState {
+                path_open: false,
+                path_start_x: 0.0,
+                path_start_y: 0.0,
+                current_x: 0.0,
+                current_y: 0.0,
+            },
+        }
+    }
+
+    // Draws a rectangle counter-clockwise or, if `clockwise`, clockwise.
+    fn rect(&mut self, x_min: f32, y_min: f32, x_max: f32, y_max: f32, clockwise: bool) {
+        let mut corners = [
+            (x_min, y_min),
+            (x_max, y_min),
+            (x_max, y_max),
+            (x_min, y_max),
+        ];
+        if clockwise {
+            corners.reverse();
+        }
+        let (x, y) = corners[0];
+        self.funcs.move_to(&self.state, x, y);
+        self.state = DrawState {
+            path_open: true,
+            path_start_x: x,
+            path_start_y: y,
+            current_x: x,
+            current_y: y,
+        };
+        for &(x, y) in &corners[1..] {
+            self.funcs.line_to(&self.state, x, y);
+            self.state.current_x = x;
+            self.state.current_y = y;
+        }
+        self.funcs.line_to(&self.state, corners[0].0, corners[0].1);
+        self.funcs.close_path(&self.state);
+        self.state.path_open = false;
+    }
+}
+
+/// Finds the `.notdef` glyphs in `buffer` and returns the hex boxes to draw
+/// instead of them.
+///
+/// `buffer` must be the result of shaping `text` with `font`, so that its
+/// clusters are byte offsets into `text`. Each box shows the first character
+/// of the cluster of its glyph. The boxes are as wide as the advance of the
+/// `.notdef` glyph and as high as three quarters of the ascender of `font`.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let text = "a\u{E000}";
+/// let output = shape(&font, UnicodeBuffer::new().add_str(text), &[]);
+///
+/// let boxes = hex_boxes(&output, text, &font);
+/// assert_eq!(boxes.len(), 1);
+/// assert_eq!(boxes[0].index, 1);
+/// assert_eq!(boxes[0].digits(), "E000");
+/// ```
+pub fn hex_boxes(
+    buffer: &GlyphBuffer,
+    text: &str,
+    font: &(impl GlyphMetricsSource + ?Sized),
+) -> Vec<HexBox> {
+    let ascender = font.font_h_extents().map_or(0, |extents| extents.ascender);
+    buffer
+        .get_glyph_infos()
+        .iter()
+        .zip(buffer.get_glyph_positions())
+        .enumerate()
+        .filter(|(_, (info, _))| info.codepoint == 0)
+        .filter_map(|(index, (info, position))| {
+            let c = text.get(info.cluster as usize..)?.chars().next()?;
+            Some(HexBox {
+                index,
+                codepoint: c as u32,
+                width: position.x_advance,
+                height: ascender * 3 / 4,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{shape, Face, Font, UnicodeBuffer};
+
+    #[derive(Default)]
+    struct Recorder {
+        paths: Vec<Vec<(f32, f32)>>,
+    }
+
+    impl DrawFuncs for Recorder {
+        fn move_to(&mut self, st: &DrawState, to_x: f32, to_y: f32) {
+            assert!(!st.path_open);
+            self.paths.push(vec![(to_x, to_y)]);
+        }
+
+        fn line_to(&mut self, st: &DrawState, to_x: f32, to_y: f32) {
+            assert!(st.path_open);
+            self.paths.last_mut().unwrap().push((to_x, to_y));
+        }
+
+        fn quadratic_to(&mut self, _: &DrawState, _: f32, _: f32, _: f32, _: f32) {
+            unreachable!()
+        }
+
+        fn cubic_to(&mut self, _: &DrawState, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {
+            unreachable!()
+        }
+
+        fn close_path(&mut self, st: &DrawState) {
+            let path = self.paths.last().unwrap();
+            assert_eq!(path.first(), path.last());
+            assert_eq!((st.current_x, st.current_y), path[path.len() - 2]);
+        }
+    }
+
+    #[test]
+    fn test_hex_box_drawing() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let text = "\u{10FFFF}x\u{1}";
+        let output = shape(&font, UnicodeBuffer::new().add_str(text), &[]);
+        let boxes = hex_boxes(&output, text, &font);
+        let digits: Vec<String> = boxes.iter().map(HexBox::digits).collect();
+        assert_eq!(digits, vec!["10FFFF", "0001"]);
+        assert_eq!(boxes[1].index, 2);
+
+        let mut recorder = Recorder::default();
+        boxes[1].draw(&mut recorder);
+        // the frame, the hole and a square per pixel of "0001"
+        let pixels = 3 * 12 + 8;
+        assert_eq!(recorder.paths.len(), 2 + pixels);
+        for path in &recorder.paths {
+            for &(x, y) in path {
+                assert!(x >= 0.0 && x <= boxes[1].width as f32);
+                assert!(y >= 0.0 && y <= boxes[1].height as f32);
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! JSON export of shaped glyphs.
+//!
+//! This module is only available with the `json` feature.
+
+use serde::Serialize;
+
+use crate::buffer::GlyphBuffer;
+use crate::common::Direction;
+use crate::font::{Font, Position};
+
+/// Options that select the optional data of [`GlyphBuffer::to_json()`].
+///
+/// The default includes glyph names, extents and flags and produces compact
+/// output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct JsonOptions {
+    /// Include the name of every glyph. Glyphs without a name in the font
+    /// get a name of the form `gid<glyph id>`.
+    pub glyph_names: bool,
+    /// Include the extents of every glyph.
+    pub extents: bool,
+    /// Include the glyph flags of every glyph.
+    pub flags: bool,
+    /// Indent the output for human readers.
+    pub pretty: bool,
+}
+
+impl Default for JsonOptions {
+    fn default() -> JsonOptions {
+        JsonOptions {
+            glyph_names: true,
+            extents: true,
+            flags: true,
+            pretty: false,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonBuffer<'a> {
+    direction: &'static str,
+    script: String,
+    language: Option<String>,
+    glyphs: &'a [JsonGlyph],
+}
+
+#[derive(Serialize)]
+struct JsonGlyph {
+    glyph: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    cluster: u32,
+    x_advance: Position,
+    y_advance: Position,
+    x_offset: Position,
+    y_offset: Position,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extents: Option<JsonExtents>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flags: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct JsonExtents {
+    x_bearing: Position,
+    y_bearing: Position,
+    width: Position,
+    height: Position,
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Invalid => "invalid",
+        Direction::Ltr => "ltr",
+        Direction::Rtl => "rtl",
+        Direction::Ttb => "ttb",
+        Direction::Btt => "btt",
+    }
+}
+
+impl GlyphBuffer {
+    /// Exports the shaped glyphs as JSON, resolving glyph names and extents
+    /// with `font`.
+    ///
+    /// `font` should be the font the buffer was shaped with. In contrast to
+    /// [`GlyphBuffer::serializer()`], which uses HarfBuzz' own format, the
+    /// output has a fixed schema that does not depend on the HarfBuzz
+    /// version, which makes it suitable for tooling and test expectations:
+    ///
+    /// ```json
+    /// {"direction":"ltr","script":"Latn","language":"en","glyphs":[
+    ///   {"glyph":1,"name":"a","cluster":0,"x_advance":500,"y_advance":0,
+    ///    "x_offset":0,"y_offset":0,
+    ///    "extents":{"x_bearing":40,"y_bearing":500,"width":420,"height":-510},
+    ///    "flags":0}
+    /// ]}
+    /// ```
+    ///
+    /// `language` is `null` if the buffer has no language. The `name`,
+    /// `extents` and `flags` members are omitted if they are disabled in
+    /// `options`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    /// let output = shape(&font, UnicodeBuffer::new().add_str("a"), &[]);
+    ///
+    /// let options = JsonOptions {
+    ///     glyph_names: false,
+    ///     extents: false,
+    ///     flags: false,
+    ///     pretty: false,
+    /// };
+    /// let json = output.to_json(&font, options);
+    /// let position = output.get_glyph_positions()[0];
+    /// assert_eq!(
+    ///     json,
+    ///     format!(
+    ///         r#"{{"direction":"ltr","script":"Latn","language":"{}","glyphs":[{{"glyph":{},"cluster":0,"x_advance":{},"y_advance":0,"x_offset":0,"y_offset":0}}]}}"#,
+    ///         output.get_segment_properties().language,
+    ///         output.get_glyph_infos()[0].codepoint,
+    ///         position.x_advance,
+    ///     )
+    /// );
+    /// ```
+    pub fn to_json(&self, font: &Font<'_>, options: JsonOptions) -> String {
+        let glyphs: Vec<JsonGlyph> = self
+            .get_glyph_infos()
+            .iter()
+            .zip(self.get_glyph_positions())
+            .map(|(info, position)| {
+                let glyph = info.codepoint;
+                let name = if options.glyph_names {
+                    let name = font.get_glyph_name(glyph);
+                    Some(name.unwrap_or_else(|| format!("gid{}", glyph)))
+                } else {
+                    None
+                };
+                let extents = if options.extents {
+                    // glyphs without extents are empty, like in HarfBuzz'
+                    // own serialization
+                    let extents = font.get_glyph_extents(glyph);
+                    Some(extents.map_or(
+                        JsonExtents {
+                            x_bearing: 0,
+                            y_bearing: 0,
+                            width: 0,
+                            height: 0,
+                        },
+                        |extents| JsonExtents {
+                            x_bearing: extents.x_bearing,
+                            y_bearing: extents.y_bearing,
+                            width: extents.width,
+                            height: extents.height,
+                        },
+                    ))
+                } else {
+                    None
+                };
+                JsonGlyph {
+                    glyph,
+                    name,
+                    cluster: info.cluster,
+                    x_advance: position.x_advance,
+                    y_advance: position.y_advance,
+                    x_offset: position.x_offset,
+                    y_offset: position.y_offset,
+                    extents,
+                    flags: if options.flags {
+                        Some(info.glyph_flags().bits())
+                    } else {
+                        None
+                    },
+                }
+            })
+            .collect();
+
+        let properties = self.get_segment_properties();
+        let language = properties.language;
+        let buffer = JsonBuffer {
+            direction: direction_name(properties.direction),
+            script: properties.script.to_string(),
+            language: if language.0.is_null() {
+                None
+            } else {
+                Some(language.to_string())
+            },
+            glyphs: &glyphs,
+        };
+        let json = if options.pretty {
+            serde_json::to_string_pretty(&buffer)
+        } else {
+            serde_json::to_string(&buffer)
+        };
+        json.expect("serializing glyphs to JSON cannot fail")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{shape, Face, UnicodeBuffer};
+
+    #[test]
+    fn test_to_json() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let buffer = UnicodeBuffer::new()
+            .add_str("ab")
+            .set_direction(Direction::Rtl);
+        let output = shape(&font, buffer, &[]);
+
+        let json = output.to_json(&font, JsonOptions::default());
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["direction"], "rtl");
+        assert_eq!(value["script"], "Latn");
+        let glyphs = value["glyphs"].as_array().unwrap();
+        assert_eq!(glyphs.len(), 2);
+        // the font has no glyph names
+        let glyph = output.get_glyph_infos()[0].codepoint;
+        assert_eq!(glyphs[0]["name"], format!("gid{}", glyph));
+        assert_eq!(glyphs[0]["cluster"], 1);
+        let extents = font.get_glyph_extents(glyph).unwrap();
+        assert_eq!(glyphs[0]["extents"]["width"], extents.width);
+        let flags = output.get_glyph_infos()[0].glyph_flags();
+        assert_eq!(glyphs[0]["flags"], flags.bits());
+
+        let pretty = JsonOptions {
+            pretty: true,
+            ..JsonOptions::default()
+        };
+        let pretty_json = output.to_json(&font, pretty);
+        assert!(pretty_json.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&pretty_json).unwrap(),
+            value
+        );
+    }
+}
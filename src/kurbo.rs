@@ -0,0 +1,143 @@
+//! This module allows you to draw glyph outlines into kurbo paths, e.g. for
+//! rendering with piet or vello.
+//!
+//! This module is only available with the `kurbo` feature.
+
+use kurbo::{BezPath, Point};
+
+use crate::draw_funcs::{DrawFuncs, DrawState};
+use crate::font::{Font, Glyph};
+
+/// A [`DrawFuncs`] implementation that accumulates the drawn outlines in a
+/// [`BezPath`].
+///
+/// The coordinates are the coordinates of the font, so the y axis points up.
+/// Apply `kurbo::Affine::FLIP_Y` to the path for renderers where it points
+/// down. Several glyphs can be drawn into the same path.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::kurbo::BezPathBuilder;
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+///
+/// let mut builder = BezPathBuilder::new();
+/// for c in "ab".chars() {
+///     font.draw_glyph(font.get_nominal_glyph(c).unwrap(), &mut builder);
+/// }
+/// let path = builder.into_path();
+/// assert!(!path.elements().is_empty());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct BezPathBuilder {
+    path: BezPath,
+}
+
+impl BezPathBuilder {
+    /// Creates a builder with an empty path.
+    pub fn new() -> BezPathBuilder {
+        BezPathBuilder::default()
+    }
+
+    /// Returns the path drawn so far.
+    pub fn path(&self) -> &BezPath {
+        &self.path
+    }
+
+    /// Returns the path, consuming the builder.
+    pub fn into_path(self) -> BezPath {
+        self.path
+    }
+}
+
+fn point(x: f32, y: f32) -> Point {
+    Point::new(x.into(), y.into())
+}
+
+impl DrawFuncs for BezPathBuilder {
+    fn move_to(&mut self, _: &DrawState, to_x: f32, to_y: f32) {
+        self.path.move_to(point(to_x, to_y));
+    }
+
+    fn line_to(&mut self, _: &DrawState, to_x: f32, to_y: f32) {
+        self.path.line_to(point(to_x, to_y));
+    }
+
+    fn quadratic_to(
+        &mut self,
+        _: &DrawState,
+        control_x: f32,
+        control_y: f32,
+        to_x: f32,
+        to_y: f32,
+    ) {
+        self.path
+            .quad_to(point(control_x, control_y), point(to_x, to_y));
+    }
+
+    fn cubic_to(
+        &mut self,
+        _: &DrawState,
+        control1_x: f32,
+        control1_y: f32,
+        control2_x: f32,
+        control2_y: f32,
+        to_x: f32,
+        to_y: f32,
+    ) {
+        self.path.curve_to(
+            point(control1_x, control1_y),
+            point(control2_x, control2_y),
+            point(to_x, to_y),
+        );
+    }
+
+    fn close_path(&mut self, _: &DrawState) {
+        self.path.close_path();
+    }
+}
+
+impl<'a> Font<'a> {
+    /// Returns the outline of `glyph` as a kurbo [`BezPath`] in the
+    /// coordinates of the font.
+    ///
+    /// The path is empty if the glyph has no outline. See [`BezPathBuilder`]
+    /// for drawing several glyphs into one path.
+    pub fn glyph_bez_path(&self, glyph: Glyph) -> BezPath {
+        let mut builder = BezPathBuilder::new();
+        self.draw_glyph(glyph, &mut builder);
+        builder.into_path()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Face;
+    use kurbo::{PathEl, Shape};
+
+    #[test]
+    fn test_glyph_bez_path() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+
+        // glyph 2 has two contours
+        let path = font.glyph_bez_path(2);
+        let elements = path.elements();
+        assert_eq!(elements[0], PathEl::MoveTo(Point::new(10.0, 0.0)));
+        assert_eq!(elements[1], PathEl::LineTo(Point::new(246.0, 660.0)));
+        let closed = elements.iter().filter(|el| **el == PathEl::ClosePath);
+        assert_eq!(closed.count(), 2);
+
+        let bounds = path.bounding_box();
+        let extents = font.get_glyph_extents(2).unwrap();
+        assert_eq!(bounds.x0, f64::from(extents.x_bearing));
+        assert_eq!(bounds.y1, f64::from(extents.y_bearing));
+
+        let space = font.get_nominal_glyph(' ').unwrap();
+        assert!(font.glyph_bez_path(space).elements().is_empty());
+    }
+}
@@ -76,6 +76,10 @@
 #[macro_use]
 extern crate bitflags;
 
+#[cfg(feature = "async")]
+mod async_shape;
+mod baseline_shift;
+mod batch;
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(non_upper_case_globals)]
@@ -85,26 +89,103 @@ mod bindings;
 mod blob;
 mod buffer;
 mod common;
+pub mod compat;
+mod control;
+mod coverage;
+mod cursor;
+#[cfg(all(windows, feature = "directwrite"))]
+mod directwrite;
 pub mod draw_funcs;
+mod ellipsis;
+#[cfg(feature = "emoji")]
+mod emoji;
 mod face;
+mod feature_spans;
 mod font;
+pub mod font_cache;
 pub mod font_funcs;
+mod glyph_metrics;
+mod hex_box;
+#[cfg(feature = "json")]
+mod json;
+mod line_metrics;
+mod matching;
+mod name;
+#[cfg(feature = "unicode-normalization")]
+mod normalization;
+pub mod ot;
+#[cfg(feature = "rayon")]
+mod par_shape;
+pub mod pipeline;
+mod post_shape;
+pub mod prelude;
+mod reshape;
+mod scripts;
+mod set;
+mod shape_options;
+mod shape_plan;
+mod shaper;
+mod shaping_cache;
+mod svg_path;
+pub mod tools;
 
+#[cfg(feature = "kurbo")]
+pub mod kurbo;
 #[cfg(feature = "rusttype")]
 pub mod rusttype;
+#[cfg(feature = "woff2")]
+mod woff2;
 
-use bindings::hb_feature_t;
-use bindings::hb_shape;
+use bindings::hb_shape_full;
 use bindings::hb_variation_t;
+use bindings::{hb_feature_from_string, hb_feature_t, hb_feature_to_string};
+use buffer::GenericBuffer;
 
+#[cfg(feature = "async")]
+pub use crate::async_shape::shape_async;
+pub use crate::baseline_shift::{BaselineShift, ScriptPlacement};
+pub use crate::batch::{shape_runs, BatchRun, BatchShaper, Run, RunInfo, ShapedBatch, ShapedRun};
 pub use crate::blob::*;
 pub use crate::buffer::*;
 pub use crate::common::*;
+pub use crate::control::{shape_with_control_policy, ControlCharAction, ControlCharPolicy};
+pub use crate::coverage::Coverage;
+pub use crate::cursor::{next_cursor_position, previous_cursor_position};
+pub use crate::ellipsis::{ellipsize, EllipsisMode};
+#[cfg(feature = "emoji")]
+pub use crate::emoji::{segment_emoji, EmojiRun};
 pub use crate::face::*;
+pub use crate::feature_spans::{merge_span_features, FeatureSpan};
 pub use crate::font::*;
+pub use crate::glyph_metrics::GlyphMetricsSource;
+pub use crate::hex_box::{hex_boxes, HexBox};
+#[cfg(feature = "json")]
+pub use crate::json::JsonOptions;
+pub use crate::line_metrics::{LineMetrics, Strut};
+pub use crate::matching::{match_font, FontQuery, FontStyle};
+pub use crate::name::{NameEntry, NameId};
+#[cfg(feature = "unicode-normalization")]
+pub use crate::normalization::NormalizationForm;
+#[cfg(feature = "rayon")]
+pub use crate::par_shape::shape_par;
+pub use crate::post_shape::{GlyphRunMut, PostShapePass};
+pub use crate::reshape::reshape_clusters;
+pub use crate::set::{collect_unique_glyphs, Set, SetIter};
+pub use crate::shape_options::ShapeOptions;
+pub use crate::shape_plan::{
+    clear_shape_plan_cache, deterministic_shaping, set_deterministic_shaping,
+    set_shape_plan_cache_capacity, shape_plan_cache_capacity, ShapePlan,
+};
+pub use crate::shaper::{shape_full, Shaper, ShaperError};
+pub use crate::shaping_cache::{segment_cacheable_words, shape_cached, ShapingCache};
+pub use crate::svg_path::SvgPathOptions;
+#[cfg(feature = "woff2")]
+pub use crate::woff2::{decode_woff2, Woff2Error};
 
+use std::fmt;
 use std::ops::{Bound, RangeBounds};
 use std::os::raw::c_uint;
+use std::str::FromStr;
 
 pub(crate) fn start_end_range(range: impl RangeBounds<usize>) -> (c_uint, c_uint) {
     // We have to do careful bounds checking since c_uint may be of
@@ -164,6 +245,18 @@ impl Variation {
     }
 }
 
+impl FromRaw<hb_variation_t> for Variation {
+    unsafe fn from_raw(raw: hb_variation_t) -> Self {
+        Variation(raw)
+    }
+}
+
+impl IntoRaw<hb_variation_t> for Variation {
+    fn into_raw(self) -> hb_variation_t {
+        self.0
+    }
+}
+
 /// A feature tag with an accompanying range specifying on which subslice of
 /// `shape`s input it should be applied.
 ///
@@ -218,23 +311,140 @@ impl Feature {
         })
     }
 
-    pub fn tag(&self) -> Tag {
+    /// Create a new `Feature` that is applied with the given value to the
+    /// whole buffer.
+    ///
+    /// This is equivalent to `Feature::new(tag, value, ..)` but is a `const
+    /// fn`, so it can be used to build feature lists at compile time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Feature, Tag};
+    ///
+    /// static FEATURES: [Feature; 2] = [
+    ///     Feature::global(Tag::new('k', 'e', 'r', 'n'), 0),
+    ///     Feature::global(Tag::new('s', 'm', 'c', 'p'), 1),
+    /// ];
+    ///
+    /// assert_eq!(FEATURES[0].start(), 0);
+    /// assert_eq!(FEATURES[1].end(), Feature::new(Tag::new('s', 'm', 'c', 'p'), 1, ..).end());
+    /// ```
+    pub const fn global(tag: Tag, value: u32) -> Feature {
+        Feature(hb_feature_t {
+            tag: tag.0,
+            value,
+            start: 0,
+            end: c_uint::MAX,
+        })
+    }
+
+    pub const fn tag(&self) -> Tag {
         Tag(self.0.tag)
     }
 
-    pub fn value(&self) -> u32 {
+    pub const fn value(&self) -> u32 {
         self.0.value
     }
 
-    pub fn start(&self) -> usize {
+    pub const fn start(&self) -> usize {
         self.0.start as usize
     }
 
-    pub fn end(&self) -> usize {
+    pub const fn end(&self) -> usize {
         self.0.end as usize
     }
 }
 
+/// An error returned when a `Feature` fails to parse from a `&str`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidFeature;
+
+impl fmt::Display for InvalidFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid feature string")
+    }
+}
+
+impl std::error::Error for InvalidFeature {}
+
+impl FromStr for Feature {
+    type Err = InvalidFeature;
+
+    /// Parses a feature in the syntax of `hb_feature_from_string`, which
+    /// follows the CSS `font-feature-settings` property and extends it with
+    /// ranges.
+    ///
+    /// A feature is enabled with `"tag"` or `"+tag"`, disabled with `"-tag"`
+    /// and set to a value with `"tag=value"` or `"tag value"`. A range of
+    /// cluster values like `"[3:5]"` may follow the tag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Feature, Tag};
+    ///
+    /// let feature: Feature = "kern[3:5]=0".parse().unwrap();
+    /// assert_eq!(feature.tag(), Tag::from(b"kern"));
+    /// assert_eq!((feature.value(), feature.start(), feature.end()), (0, 3, 5));
+    /// assert_eq!(feature.to_string(), "-kern[3:5]");
+    ///
+    /// assert_eq!("+liga".parse::<Feature>().unwrap().value(), 1);
+    /// assert!("=1".parse::<Feature>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Feature, InvalidFeature> {
+        let mut raw = hb_feature_t {
+            tag: 0,
+            value: 0,
+            start: 0,
+            end: 0,
+        };
+        let len = std::cmp::min(s.len(), i32::MAX as usize) as i32;
+        let result = unsafe { hb_feature_from_string(s.as_ptr() as *const _, len, &mut raw) };
+        if result == 1 {
+            Ok(Feature(raw))
+        } else {
+            Err(InvalidFeature)
+        }
+    }
+}
+
+/// Formats the feature in the syntax accepted by its `FromStr`
+/// implementation.
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut raw = self.0;
+        let mut buffer = [0 as std::os::raw::c_char; 128];
+        unsafe { hb_feature_to_string(&mut raw, buffer.as_mut_ptr(), buffer.len() as c_uint) };
+        let string = unsafe { std::ffi::CStr::from_ptr(buffer.as_ptr()) };
+        write!(f, "{}", string.to_string_lossy())
+    }
+}
+
+impl FromRaw<hb_feature_t> for Feature {
+    unsafe fn from_raw(raw: hb_feature_t) -> Self {
+        Feature(raw)
+    }
+}
+
+impl IntoRaw<hb_feature_t> for Feature {
+    fn into_raw(self) -> hb_feature_t {
+        self.0
+    }
+}
+
+// Guesses the unset segment properties of `buffer` the way `shape` does.
+pub(crate) fn resolve_segment_properties(buffer: UnicodeBuffer) -> UnicodeBuffer {
+    if deterministic_shaping() && buffer.get_language().is_none() {
+        // keep the language unset instead of using the one of the locale
+        buffer
+            .guess_segment_properties()
+            .set_language(Language(std::ptr::null()))
+    } else {
+        buffer.guess_segment_properties()
+    }
+}
+
 /// Shape the contents of the buffer using the provided font and activating all
 /// OpenType features given in `features`.
 ///
@@ -254,18 +464,151 @@ impl Feature {
 /// also contains metadata about the text in the form of segment properties.
 /// - `features` – a slice of additional features to activate
 pub fn shape(font: &Font<'_>, buffer: UnicodeBuffer, features: &[Feature]) -> GlyphBuffer {
-    let buffer = buffer.guess_segment_properties();
+    let buffer = resolve_segment_properties(buffer);
+    if let Some(plan) = shape_plan::cached_plan(font, &buffer.get_segment_properties(), features) {
+        return plan.execute(font, buffer, features);
+    }
     unsafe {
-        hb_shape(
+        hb_shape_full(
             font.as_raw(),
             buffer.0.as_raw(),
             features.as_ptr() as *mut _,
             features.len() as u32,
+            shape_plan::shaper_list(),
         )
     };
     GlyphBuffer(buffer.0)
 }
 
+/// Shapes `text` like [`shape()`] and stores the result in `output`, reusing
+/// its allocation.
+///
+/// The previous contents and segment properties of `output` are discarded,
+/// but buffer settings like the flags and the cluster level are kept. Unlike
+/// `shape` this does not move buffers in and out of the call, so a
+/// per-thread `GlyphBuffer` can be used to shape many runs without allocating
+/// once it has grown to the size of the largest run.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+///
+/// let mut output = GlyphBuffer::new();
+/// for word in &["Hello", "World!"] {
+///     shape_into(&font, word, &mut output, &[]);
+///     assert_eq!(output.len(), word.len());
+/// }
+/// ```
+pub fn shape_into(font: &Font<'_>, text: &str, output: &mut GlyphBuffer, features: &[Feature]) {
+    let mut buffer = std::mem::replace(&mut output.0, GenericBuffer::empty());
+    buffer.clear_contents();
+    buffer.add_str_item(text, 0, text.len());
+    *output = shape(font, UnicodeBuffer::from_generic(buffer), features);
+}
+
+/// Shape the contents of the buffer like [`shape()`] but report if HarfBuzz
+/// failed to allocate memory for the buffer.
+///
+/// HarfBuzz does not abort on allocation failures but leaves the buffer
+/// empty, which makes such failures indistinguishable from empty input when
+/// using `shape`.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+///
+/// let output = try_shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]).unwrap();
+/// assert_eq!(output.len(), 5);
+/// ```
+pub fn try_shape(
+    font: &Font<'_>,
+    buffer: UnicodeBuffer,
+    features: &[Feature],
+) -> Result<GlyphBuffer, AllocationError> {
+    buffer.check_allocation()?;
+    let output = shape(font, buffer, features);
+    output.check_allocation()?;
+    Ok(output)
+}
+
+/// The result of [`shape_justify()`].
+#[cfg(feature = "experimental-api")]
+#[derive(Debug, Copy, Clone)]
+pub struct Justification {
+    /// The advance of the shaped buffer along its direction.
+    pub advance: f32,
+    /// The variation that was applied to the font to reach the target
+    /// advance, or `None` if the text was shaped without a variation.
+    pub variation: Option<Variation>,
+}
+
+/// Shapes `buffer` like [`shape()`] while adjusting a variation axis of `font`
+/// so that the advance of the result lies between `min_target_advance` and
+/// `max_target_advance` (`hb_shape_justify`).
+///
+/// HarfBuzz varies the `jstf` axis of the font or, if the font has no such
+/// axis, its `wdth` axis, and searches for the axis value that brings the
+/// advance of the text into the target range. Fonts with neither axis are
+/// shaped without a variation. The variation stays applied to `font`, so
+/// glyphs drawn with it afterwards match the justified advances. The targets
+/// are in font units of the font's scale.
+///
+/// Returns the shaped glyphs and the resulting advance and variation, or
+/// `None` instead of the latter if HarfBuzz failed to justify the text. The
+/// glyphs are shaped in both cases.
+///
+/// This requires the `experimental-api` feature since justification is not
+/// part of the stable HarfBuzz API.
+#[cfg(feature = "experimental-api")]
+pub fn shape_justify(
+    font: &mut Font<'_>,
+    buffer: UnicodeBuffer,
+    features: &[Feature],
+    min_target_advance: f32,
+    max_target_advance: f32,
+) -> (GlyphBuffer, Option<Justification>) {
+    let buffer = resolve_segment_properties(buffer);
+    // an advance of zero lets HarfBuzz compute it
+    let mut advance = 0.0;
+    let mut var_tag = 0;
+    let mut var_value = 0.0;
+    let success = unsafe {
+        bindings::hb_shape_justify(
+            font.as_raw(),
+            buffer.0.as_raw(),
+            features.as_ptr() as *const _,
+            features.len() as c_uint,
+            shape_plan::shaper_list(),
+            min_target_advance,
+            max_target_advance,
+            &mut advance,
+            &mut var_tag,
+            &mut var_value,
+        )
+    };
+    let justification = if success == 1 {
+        Some(Justification {
+            advance,
+            variation: if var_tag == 0 {
+                None
+            } else {
+                Some(Variation::new(Tag(var_tag), var_value))
+            },
+        })
+    } else {
+        None
+    };
+    (GlyphBuffer(buffer.0), justification)
+}
+
 #[cfg(test)]
 mod tests {
     use std::mem::{align_of, size_of};
@@ -309,4 +652,82 @@ mod tests {
         let feature = Feature::new(tag, 100, ..);
         assert_feature(feature, tag, 100, 0, UINT_MAX);
     }
+
+    #[test]
+    fn feature_parsing() {
+        const UINT_MAX: usize = std::os::raw::c_uint::MAX as usize;
+        let tag = b"smcp".into();
+
+        assert_feature("smcp".parse().unwrap(), tag, 1, 0, UINT_MAX);
+        assert_feature("-smcp".parse().unwrap(), tag, 0, 0, UINT_MAX);
+        assert_feature("smcp[2:]=3".parse().unwrap(), tag, 3, 2, UINT_MAX);
+        assert_feature("smcp[5]".parse().unwrap(), tag, 1, 5, 6);
+        assert!("".parse::<Feature>().is_err());
+        assert!("smcp=x".parse::<Feature>().is_err());
+
+        for string in &["smcp", "-liga", "kern[3:5]", "aalt=2", "calt[:7]"] {
+            let feature: Feature = string.parse().unwrap();
+            assert_eq!(feature.to_string(), *string);
+            let again: Feature = feature.to_string().parse().unwrap();
+            let (start, end) = (feature.start(), feature.end());
+            assert_feature(again, feature.tag(), feature.value(), start, end);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "experimental-api")]
+    fn test_shape_justify() {
+        use super::{shape, shape_justify, Face, Font, Shared, UnicodeBuffer};
+
+        let advance = |buffer: &super::GlyphBuffer| -> f32 {
+            buffer
+                .get_glyph_positions()
+                .iter()
+                .map(|pos| pos.x_advance as f32)
+                .sum()
+        };
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let text = || UnicodeBuffer::new().add_str("Hello");
+
+        // without a `jstf` or `wdth` axis the text is shaped normally
+        let mut font = Font::new(Face::from_file(path, 0).unwrap());
+        let (output, justification) = shape_justify(&mut font, text(), &[], 5000.0, 6000.0);
+        let justification = justification.unwrap();
+        assert!(justification.variation.is_none());
+        assert_eq!(justification.advance, advance(&output));
+
+        // turn the weight axis into a width axis, which widens the glyphs
+        // towards its maximum
+        let original: Shared<Face<'static>> = Face::from_file(path, 0).unwrap().into();
+        let mut fvar = original.table_with_tag(b"fvar").unwrap().to_vec();
+        let axes = u16::from_be_bytes([fvar[4], fvar[5]]) as usize;
+        assert_eq!(&fvar[axes..axes + 4], b"wght");
+        fvar[axes..axes + 4].copy_from_slice(b"wdth");
+        let face = Face::from_table_func(move |tag| {
+            if tag == Tag::new('f', 'v', 'a', 'r') {
+                Some(fvar.clone().into())
+            } else {
+                original.table_with_tag(tag)
+            }
+        });
+        let mut font = Font::new(face);
+        let narrow = advance(&shape(&font, text(), &[]));
+        assert!(font.set_variation(b"wdth", 900.0));
+        let wide = advance(&shape(&font, text(), &[]));
+        assert!(wide > narrow + 100.0);
+        font.set_variations(&[]);
+
+        let target = (narrow + wide) / 2.0;
+        let (output, justification) =
+            shape_justify(&mut font, text(), &[], target - 10.0, target + 10.0);
+        let justification = justification.unwrap();
+        let width = advance(&output);
+        assert_eq!(justification.advance, width);
+        assert!((target - 10.0..=target + 10.0).contains(&width));
+        let variation = justification.variation.unwrap();
+        assert_eq!(variation.tag(), Tag::new('w', 'd', 't', 'h'));
+        assert!(variation.value() > 200.0 && variation.value() < 900.0);
+        // the variation stays applied to the font
+        assert_eq!(font.variation(b"wdth"), Some(variation.value()));
+    }
 }
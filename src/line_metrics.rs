@@ -0,0 +1,181 @@
+use crate::buffer::GlyphBuffer;
+use crate::font::Position;
+use crate::glyph_metrics::GlyphMetricsSource;
+
+/// The vertical metrics of a line of horizontal text.
+///
+/// The values follow the conventions of [`FontExtents`](crate::FontExtents):
+/// `ascent` is the distance from the baseline to the top of the line and
+/// usually positive, `descent` is the offset of the bottom of the line from
+/// the baseline and usually negative.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct LineMetrics {
+    /// The extent of the line above the baseline.
+    pub ascent: Position,
+    /// The extent of the line below the baseline (usually negative).
+    pub descent: Position,
+    /// The additional space between this line and the next.
+    pub leading: Position,
+}
+
+/// The minimum metrics of every line, also known as a strut.
+///
+/// A strut makes lines at least as high as the given metrics, so lines keep
+/// a uniform height when the fallback fonts of some runs have smaller
+/// metrics than the primary font.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Strut {
+    /// The minimum metrics of a line.
+    pub metrics: LineMetrics,
+    /// If `true` lines get exactly the metrics of the strut, even if their
+    /// content is larger.
+    pub force: bool,
+}
+
+impl Strut {
+    /// Creates a strut with the metrics of `font` that lines may exceed.
+    pub fn from_font(font: &(impl GlyphMetricsSource + ?Sized)) -> Strut {
+        Strut {
+            metrics: LineMetrics::from_font(font),
+            force: false,
+        }
+    }
+}
+
+impl LineMetrics {
+    /// Returns the metrics of a line set in `font`, taken from its horizontal
+    /// font extents.
+    pub fn from_font(font: &(impl GlyphMetricsSource + ?Sized)) -> LineMetrics {
+        font.font_h_extents()
+            .map(|extents| LineMetrics {
+                ascent: extents.ascender,
+                descent: extents.descender,
+                leading: extents.line_gap,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Computes the metrics of a line consisting of runs shaped with
+    /// different fonts, e.g. because of font fallback.
+    ///
+    /// The line gets the largest ascent, the lowest descent and the largest
+    /// leading of the fonts of all non-empty runs. All fonts should use the
+    /// same scale, so that their metrics are comparable. A line without
+    /// non-empty runs has zero metrics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let path = "testfiles/SourceSansVariable-Roman.ttf";
+    /// let regular = Font::new(Face::from_file(path, 0).unwrap());
+    /// let mut large = Font::new(Face::from_file(path, 0).unwrap());
+    /// let (x_scale, y_scale) = regular.scale();
+    /// large.set_scale(x_scale * 2, y_scale * 2);
+    ///
+    /// let a = shape(&regular, UnicodeBuffer::new().add_str("Hello "), &[]);
+    /// let b = shape(&large, UnicodeBuffer::new().add_str("World"), &[]);
+    ///
+    /// let metrics = LineMetrics::aggregate(vec![(&*regular, &a), (&*large, &b)]);
+    /// assert_eq!(metrics, LineMetrics::from_font(&large));
+    /// assert!(metrics.height() > LineMetrics::from_font(&regular).height());
+    /// ```
+    pub fn aggregate<'a, M: GlyphMetricsSource + ?Sized + 'a>(
+        runs: impl IntoIterator<Item = (&'a M, &'a GlyphBuffer)>,
+    ) -> LineMetrics {
+        runs.into_iter()
+            .filter(|(_, glyphs)| !glyphs.is_empty())
+            .map(|(font, _)| LineMetrics::from_font(font))
+            .reduce(LineMetrics::union)
+            .unwrap_or_default()
+    }
+
+    /// Computes the metrics of a line like [`aggregate()`](Self::aggregate)
+    /// and applies `strut`.
+    ///
+    /// Unless the strut is forced, the line gets the larger metrics of the
+    /// strut and its content, so also empty lines have the height of the
+    /// strut.
+    pub fn aggregate_with_strut<'a, M: GlyphMetricsSource + ?Sized + 'a>(
+        runs: impl IntoIterator<Item = (&'a M, &'a GlyphBuffer)>,
+        strut: &Strut,
+    ) -> LineMetrics {
+        if strut.force {
+            strut.metrics
+        } else {
+            LineMetrics::aggregate(runs).union(strut.metrics)
+        }
+    }
+
+    /// Returns the metrics of a line containing the content of both `self`
+    /// and `other`.
+    pub fn union(self, other: LineMetrics) -> LineMetrics {
+        LineMetrics {
+            ascent: self.ascent.max(other.ascent),
+            descent: self.descent.min(other.descent),
+            leading: self.leading.max(other.leading),
+        }
+    }
+
+    /// Returns the distance between the baseline of this line and the next,
+    /// i.e. `ascent - descent + leading`.
+    pub fn height(&self) -> Position {
+        self.ascent - self.descent + self.leading
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{shape, Face, Font, UnicodeBuffer};
+
+    #[test]
+    fn test_line_metrics() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let font = Font::new(Face::from_file(path, 0).unwrap());
+        let mut small = Font::new(Face::from_file(path, 0).unwrap());
+        let (x_scale, y_scale) = font.scale();
+        small.set_scale(x_scale / 2, y_scale / 2);
+        let glyphs = shape(&font, UnicodeBuffer::new().add_str("a"), &[]);
+        let small_glyphs = shape(&small, UnicodeBuffer::new().add_str("b"), &[]);
+        let empty = shape(&font, UnicodeBuffer::new(), &[]);
+
+        let metrics = LineMetrics::from_font(&font);
+        assert!(metrics.ascent > 0 && metrics.descent < 0);
+        assert_eq!(
+            LineMetrics::aggregate(vec![(&*small, &small_glyphs), (&*font, &glyphs)]),
+            metrics
+        );
+        // empty runs do not contribute
+        let small_metrics = LineMetrics::from_font(&small);
+        assert_eq!(
+            LineMetrics::aggregate(vec![(&*small, &small_glyphs), (&*font, &empty)]),
+            small_metrics
+        );
+        assert_eq!(
+            LineMetrics::aggregate(Vec::<(&Font<'_>, &GlyphBuffer)>::new()),
+            LineMetrics::default()
+        );
+
+        let strut = Strut::from_font(&font);
+        let runs = vec![(&*small, &small_glyphs)];
+        assert_eq!(
+            LineMetrics::aggregate_with_strut(runs.clone(), &strut),
+            metrics
+        );
+        let forced = Strut {
+            metrics: small_metrics,
+            force: true,
+        };
+        let runs = vec![(&*font, &glyphs)];
+        assert_eq!(
+            LineMetrics::aggregate_with_strut(runs, &forced),
+            small_metrics
+        );
+        assert_eq!(
+            metrics.height(),
+            metrics.ascent - metrics.descent + metrics.leading
+        );
+    }
+}
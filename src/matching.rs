@@ -0,0 +1,284 @@
+use crate::bindings::{hb_ot_var_axis_info_t, hb_ot_var_find_axis_info};
+use crate::common::{HarfbuzzObject, Language, Shared, Tag};
+use crate::coverage::Coverage;
+use crate::face::Face;
+use crate::font::Font;
+use crate::name::NameId;
+
+/// The slope of a font as used in a [`FontQuery`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum FontStyle {
+    /// An upright font.
+    #[default]
+    Normal,
+    /// A cursive, italic font.
+    Italic,
+    /// A slanted version of an upright font.
+    Oblique,
+}
+
+/// The properties of the font to find with [`match_font()`].
+#[derive(Debug, Clone)]
+pub struct FontQuery<'q> {
+    /// The family name. If `Some`, only faces of this family are matched.
+    /// The comparison ignores ASCII case and considers the localized names
+    /// of the face.
+    pub family: Option<&'q str>,
+    /// The desired weight (e.g. 400 for regular and 700 for bold).
+    pub weight: f32,
+    /// The desired width in percent of the normal width.
+    pub stretch: f32,
+    /// The desired slope.
+    pub style: FontStyle,
+    /// The language of the text. Faces that have names in this language are
+    /// preferred, which helps choosing between otherwise equal CJK fonts.
+    pub language: Option<Language>,
+    /// Codepoints that the font should support. Faces covering all of them are
+    /// preferred over those that do not.
+    pub char_coverage: Option<&'q Coverage>,
+}
+
+impl<'q> Default for FontQuery<'q> {
+    fn default() -> Self {
+        FontQuery {
+            family: None,
+            weight: 400.0,
+            stretch: 100.0,
+            style: FontStyle::Normal,
+            language: None,
+            char_coverage: None,
+        }
+    }
+}
+
+// The style properties of a face. For variable fonts the values are clamped
+// to the query so that every instance of the font is considered.
+struct FaceStyle {
+    weight: f32,
+    stretch: f32,
+    style: FontStyle,
+}
+
+fn axis_range(face: &Face<'_>, tag: Tag) -> Option<(f32, f32)> {
+    let mut info: hb_ot_var_axis_info_t = unsafe { std::mem::zeroed() };
+    let found = unsafe { hb_ot_var_find_axis_info(face.as_raw(), tag.0, &mut info) } != 0;
+    if found {
+        Some((info.min_value, info.max_value))
+    } else {
+        None
+    }
+}
+
+impl FaceStyle {
+    fn new(face: &Shared<Face<'_>>, query: &FontQuery<'_>) -> FaceStyle {
+        let font = Font::new(face.clone());
+        let closest = |tag: &[u8; 4], desired: f32| match axis_range(face, tag.into()) {
+            Some((min, max)) => desired.max(min).min(max),
+            None => font.style_value(tag),
+        };
+        let can_be = |tag: &[u8; 4], value: f32| match axis_range(face, tag.into()) {
+            Some((min, max)) => min <= value && value <= max,
+            None => font.style_value(tag) == value,
+        };
+        let style = if can_be(b"ital", 1.0) {
+            FontStyle::Italic
+        } else if font.style_value(b"slnt") != 0.0
+            || (query.style == FontStyle::Oblique && axis_range(face, b"slnt".into()).is_some())
+        {
+            FontStyle::Oblique
+        } else {
+            FontStyle::Normal
+        };
+        FaceStyle {
+            weight: closest(b"wght", query.weight),
+            stretch: closest(b"wdth", query.stretch),
+            style,
+        }
+    }
+}
+
+// Distance of `value` to `desired` that first prefers values on the side given
+// by `prefer_below` and then the other side, as done by CSS.
+fn directional_distance(value: f32, desired: f32, prefer_below: bool) -> f32 {
+    const OTHER_SIDE: f32 = 10000.0;
+    let below = value <= desired;
+    if below == prefer_below || value == desired {
+        (value - desired).abs()
+    } else {
+        (value - desired).abs() + OTHER_SIDE
+    }
+}
+
+fn weight_distance(value: f32, desired: f32) -> f32 {
+    if (400.0..=500.0).contains(&desired) {
+        // first weights up to 500, then lighter ones, then heavier ones
+        if value >= desired && value <= 500.0 {
+            value - desired
+        } else if value < desired {
+            desired - value + 10000.0
+        } else {
+            value - desired + 20000.0
+        }
+    } else {
+        directional_distance(value, desired, desired < 400.0)
+    }
+}
+
+fn style_rank(value: FontStyle, desired: FontStyle) -> u32 {
+    use FontStyle::*;
+    let order = match desired {
+        Normal => [Normal, Oblique, Italic],
+        Italic => [Italic, Oblique, Normal],
+        Oblique => [Oblique, Italic, Normal],
+    };
+    order.iter().position(|&style| style == value).unwrap_or(3) as u32
+}
+
+fn family_matches(face: &Face<'_>, family: &str) -> bool {
+    face.name_entries()
+        .into_iter()
+        .filter(|entry| {
+            entry.name_id == NameId::FONT_FAMILY || entry.name_id == NameId::TYPOGRAPHIC_FAMILY
+        })
+        .filter_map(|entry| face.name(entry.name_id, Some(entry.language)))
+        .any(|name| name.eq_ignore_ascii_case(family))
+}
+
+/// Ranks `faces` by how well they match `query` and returns their indices,
+/// best match first.
+///
+/// Faces that do not belong to the requested family are not returned. The
+/// other faces are ordered by
+///
+/// 1. whether they support all codepoints of `query.char_coverage`,
+/// 2. whether they have names in `query.language`,
+/// 3. their width, style and weight, following the rules of the
+///    [CSS font matching algorithm](https://www.w3.org/TR/css-fonts-4/#font-style-matching).
+///
+/// Variable fonts are considered to match every value in the range of their
+/// `wght` and `wdth` axes and to be italic if they have an `ital` axis.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face: Shared<Face> = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0)
+///     .unwrap()
+///     .into();
+/// let faces = vec![Face::empty().into(), face];
+/// let coverage: Coverage = "Hello".chars().collect();
+///
+/// let query = FontQuery {
+///     weight: 700.0,
+///     char_coverage: Some(&coverage),
+///     ..FontQuery::default()
+/// };
+/// assert_eq!(match_font(&faces, &query), vec![1, 0]);
+/// ```
+pub fn match_font(faces: &[Shared<Face<'_>>], query: &FontQuery<'_>) -> Vec<usize> {
+    // (missing codepoints, missing language, stretch, style, weight)
+    type MatchKey = (u32, u32, f32, u32, f32);
+    let mut candidates: Vec<(usize, MatchKey)> = faces
+        .iter()
+        .enumerate()
+        .filter(|(_, face)| {
+            query
+                .family
+                .map(|family| family_matches(face, family))
+                .unwrap_or(true)
+        })
+        .map(|(index, face)| {
+            let missing = match query.char_coverage {
+                Some(coverage) => !coverage.difference(&face.coverage_bitmap()).is_empty(),
+                None => false,
+            };
+            let has_language = match query.language {
+                Some(language) => face
+                    .name_entries()
+                    .iter()
+                    .any(|entry| entry.language == language),
+                None => true,
+            };
+            let style = FaceStyle::new(face, query);
+            let stretch =
+                directional_distance(style.stretch, query.stretch, query.stretch <= 100.0);
+            let key = (
+                missing as u32,
+                !has_language as u32,
+                stretch,
+                style_rank(style.style, query.style),
+                weight_distance(style.weight, query.weight),
+            );
+            (index, key)
+        })
+        .collect();
+    candidates.sort_by(|a, b| {
+        a.1.partial_cmp(&b.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.0.cmp(&b.0))
+    });
+    candidates.into_iter().map(|(index, _)| index).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weight_distance() {
+        let mut weights = vec![100.0, 300.0, 400.0, 500.0, 600.0, 900.0];
+        let order = |weights: &mut Vec<f32>, desired| {
+            weights.sort_by(|a: &f32, b: &f32| {
+                weight_distance(*a, desired)
+                    .partial_cmp(&weight_distance(*b, desired))
+                    .unwrap()
+            });
+            weights.clone()
+        };
+        assert_eq!(
+            order(&mut weights, 400.0),
+            vec![400.0, 500.0, 300.0, 100.0, 600.0, 900.0]
+        );
+        assert_eq!(
+            order(&mut weights, 700.0),
+            vec![900.0, 600.0, 500.0, 400.0, 300.0, 100.0]
+        );
+        assert_eq!(
+            order(&mut weights, 300.0),
+            vec![300.0, 100.0, 400.0, 500.0, 600.0, 900.0]
+        );
+    }
+
+    #[test]
+    fn test_match_font() {
+        let face: Shared<Face<'_>> = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0)
+            .unwrap()
+            .into();
+        let family = face.name(NameId::FONT_FAMILY, None).unwrap();
+        let faces = vec![Face::empty().into(), face];
+
+        let query = FontQuery {
+            family: Some(&family),
+            ..FontQuery::default()
+        };
+        assert_eq!(match_font(&faces, &query), vec![1]);
+
+        let query = FontQuery {
+            family: Some("No Such Family"),
+            ..FontQuery::default()
+        };
+        assert!(match_font(&faces, &query).is_empty());
+
+        // the variable font covers every weight of its axis
+        let style = FaceStyle::new(
+            &faces[1],
+            &FontQuery {
+                weight: 650.0,
+                ..FontQuery::default()
+            },
+        );
+        assert_eq!(style.weight, 650.0);
+        assert_eq!(style.style, FontStyle::Normal);
+    }
+}
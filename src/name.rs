@@ -0,0 +1,186 @@
+use crate::bindings::{
+    hb_language_t, hb_ot_name_get_utf8, hb_ot_name_list_names, HB_OT_NAME_ID_COPYRIGHT,
+    HB_OT_NAME_ID_DESIGNER, HB_OT_NAME_ID_FONT_FAMILY, HB_OT_NAME_ID_FONT_SUBFAMILY,
+    HB_OT_NAME_ID_FULL_NAME, HB_OT_NAME_ID_MANUFACTURER, HB_OT_NAME_ID_POSTSCRIPT_NAME,
+    HB_OT_NAME_ID_TYPOGRAPHIC_FAMILY, HB_OT_NAME_ID_TYPOGRAPHIC_SUBFAMILY, HB_OT_NAME_ID_UNIQUE_ID,
+    HB_OT_NAME_ID_VERSION_STRING, HB_OT_NAME_ID_WWS_FAMILY, HB_OT_NAME_ID_WWS_SUBFAMILY,
+};
+use crate::common::{HarfbuzzObject, Language};
+use crate::face::Face;
+
+/// The id of a record in the OpenType `name` table.
+///
+/// See the [OpenType spec](https://docs.microsoft.com/en-us/typography/opentype/spec/name#name-ids)
+/// for the meaning of the predefined ids.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NameId(pub u32);
+
+impl NameId {
+    pub const COPYRIGHT: NameId = NameId(HB_OT_NAME_ID_COPYRIGHT);
+    pub const FONT_FAMILY: NameId = NameId(HB_OT_NAME_ID_FONT_FAMILY);
+    pub const FONT_SUBFAMILY: NameId = NameId(HB_OT_NAME_ID_FONT_SUBFAMILY);
+    pub const UNIQUE_ID: NameId = NameId(HB_OT_NAME_ID_UNIQUE_ID);
+    pub const FULL_NAME: NameId = NameId(HB_OT_NAME_ID_FULL_NAME);
+    pub const VERSION_STRING: NameId = NameId(HB_OT_NAME_ID_VERSION_STRING);
+    pub const POSTSCRIPT_NAME: NameId = NameId(HB_OT_NAME_ID_POSTSCRIPT_NAME);
+    pub const MANUFACTURER: NameId = NameId(HB_OT_NAME_ID_MANUFACTURER);
+    pub const DESIGNER: NameId = NameId(HB_OT_NAME_ID_DESIGNER);
+    pub const TYPOGRAPHIC_FAMILY: NameId = NameId(HB_OT_NAME_ID_TYPOGRAPHIC_FAMILY);
+    pub const TYPOGRAPHIC_SUBFAMILY: NameId = NameId(HB_OT_NAME_ID_TYPOGRAPHIC_SUBFAMILY);
+    pub const WWS_FAMILY: NameId = NameId(HB_OT_NAME_ID_WWS_FAMILY);
+    pub const WWS_SUBFAMILY: NameId = NameId(HB_OT_NAME_ID_WWS_SUBFAMILY);
+}
+
+/// An entry of the `name` table as returned by [`Face::name_entries()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NameEntry {
+    /// The id of the name.
+    pub name_id: NameId,
+    /// The language of the name.
+    pub language: Language,
+}
+
+// Returns `true` if `entry` is `requested` or a more specific variant of it
+// (e.g. "en-us" for "en").
+fn language_matches(entry: Language, requested: Language) -> bool {
+    if entry == requested {
+        return true;
+    }
+    let (entry, requested) = (entry.to_string(), requested.to_string());
+    entry
+        .strip_prefix(&requested)
+        .map(|rest| rest.starts_with('-'))
+        == Some(true)
+}
+
+impl<'a> Face<'a> {
+    /// Returns the names contained in the `name` table of the face.
+    ///
+    /// Records with the same name id and language but different platforms
+    /// and encodings are reported only once.
+    pub fn name_entries(&self) -> Vec<NameEntry> {
+        let mut count = 0;
+        let entries = unsafe { hb_ot_name_list_names(self.as_raw(), &mut count) };
+        if entries.is_null() {
+            return Vec::new();
+        }
+        let entries = unsafe { std::slice::from_raw_parts(entries, count as usize) };
+        entries
+            .iter()
+            .map(|entry| NameEntry {
+                name_id: NameId(entry.name_id),
+                language: Language(entry.language),
+            })
+            .collect()
+    }
+
+    /// Returns the name with the given id in `language`.
+    ///
+    /// If `language` is `None`, HarfBuzz chooses the English name. The name
+    /// records are decoded from their platform encoding (e.g. UTF-16BE)
+    /// automatically. Returns `None` if there is no matching name.
+    pub fn name(&self, name_id: NameId, language: Option<Language>) -> Option<String> {
+        let language = language.map(|lang| lang.0).unwrap_or(std::ptr::null());
+        self.name_with_raw_language(name_id, language)
+    }
+
+    /// Returns the name with the given id in the first language of
+    /// `languages` that the face provides it in.
+    ///
+    /// A language also matches more specific variants of it, e.g. `en` matches
+    /// a name in `en-US`. If none of the languages is available the English
+    /// name is returned and if there is no English name either, the name in
+    /// any language.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{Face, Language, NameId};
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let languages: Vec<Language> = vec!["ja".parse().unwrap(), "en".parse().unwrap()];
+    /// let family = face.localized_name(NameId::FONT_FAMILY, &languages).unwrap();
+    /// assert!(family.starts_with("Source Sans"));
+    /// ```
+    pub fn localized_name(&self, name_id: NameId, languages: &[Language]) -> Option<String> {
+        let entries: Vec<NameEntry> = self
+            .name_entries()
+            .into_iter()
+            .filter(|entry| entry.name_id == name_id)
+            .collect();
+        languages
+            .iter()
+            .filter_map(|&requested| {
+                entries
+                    .iter()
+                    .find(|entry| language_matches(entry.language, requested))
+            })
+            .chain(std::iter::once(&NameEntry {
+                name_id,
+                language: Language(std::ptr::null()),
+            }))
+            .chain(entries.iter())
+            .find_map(|entry| self.name_with_raw_language(name_id, entry.language.0))
+    }
+
+    fn name_with_raw_language(&self, name_id: NameId, language: hb_language_t) -> Option<String> {
+        unsafe {
+            let mut len = 0;
+            let total_len = hb_ot_name_get_utf8(
+                self.as_raw(),
+                name_id.0,
+                language,
+                &mut len,
+                std::ptr::null_mut(),
+            );
+            if total_len == 0 {
+                return None;
+            }
+            // one more byte for the terminating zero
+            let mut buffer = vec![0u8; total_len as usize + 1];
+            let mut len = buffer.len() as u32;
+            hb_ot_name_get_utf8(
+                self.as_raw(),
+                name_id.0,
+                language,
+                &mut len,
+                buffer.as_mut_ptr() as *mut _,
+            );
+            buffer.truncate(len as usize);
+            String::from_utf8(buffer).ok()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_matches() {
+        let lang = |s: &str| s.parse::<Language>().unwrap();
+        assert!(language_matches(lang("en"), lang("en")));
+        assert!(language_matches(lang("en-us"), lang("en")));
+        assert!(!language_matches(lang("en"), lang("en-us")));
+        assert!(!language_matches(lang("eng"), lang("en")));
+    }
+
+    #[test]
+    fn test_names() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let entries = face.name_entries();
+        assert!(entries
+            .iter()
+            .any(|entry| entry.name_id == NameId::FONT_FAMILY));
+
+        let family = face.name(NameId::FONT_FAMILY, None).unwrap();
+        let german = "de".parse().unwrap();
+        assert_eq!(
+            face.localized_name(NameId::FONT_FAMILY, &[german]),
+            Some(family)
+        );
+        assert_eq!(face.name(NameId(1234), None), None);
+        assert_eq!(face.localized_name(NameId(1234), &[german]), None);
+        assert!(Face::empty().name_entries().is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+//! Unicode normalization of the input of a `UnicodeBuffer`.
+//!
+//! This module is only available with the `unicode-normalization` feature.
+
+use crate::buffer::{GenericBuffer, UnicodeBuffer};
+
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::UnicodeNormalization;
+
+/// A Unicode normalization form that can be applied to text before shaping.
+///
+/// HarfBuzz already composes and decomposes characters during shaping
+/// depending on which glyphs the font provides. Some fonts however only
+/// support either precomposed or decomposed forms properly (e.g. because
+/// their OpenType features expect a certain form), in which case normalizing
+/// the input beforehand gives better results.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum NormalizationForm {
+    /// Canonical decomposition followed by canonical composition (NFC).
+    Nfc,
+    /// Canonical decomposition (NFD).
+    Nfd,
+}
+
+// Splits `chars` into segments that can be normalized independently of each
+// other and adds the normalized segments to `buffer`. All characters resulting
+// from one segment get the cluster value of the segment's first character.
+fn add_normalized(
+    buffer: &mut GenericBuffer,
+    chars: impl IntoIterator<Item = (char, u32)>,
+    form: NormalizationForm,
+) {
+    fn flush(
+        buffer: &mut GenericBuffer,
+        segment: &mut String,
+        cluster: u32,
+        form: NormalizationForm,
+    ) {
+        match form {
+            NormalizationForm::Nfc => segment
+                .chars()
+                .nfc()
+                .for_each(|c| buffer.add(c as u32, cluster)),
+            NormalizationForm::Nfd => segment
+                .chars()
+                .nfd()
+                .for_each(|c| buffer.add(c as u32, cluster)),
+        }
+        segment.clear();
+    }
+
+    let mut segment = String::new();
+    let mut segment_cluster = 0;
+    for (c, cluster) in chars {
+        // A new segment starts at every starter that is not composed with the
+        // normalized segment so far. Comparing the composition of the whole
+        // segment (instead of the preceding character only) keeps runs like
+        // Hangul L, V and T jamo together, which compose into one syllable.
+        let starts_segment = !segment.is_empty()
+            && canonical_combining_class(c) == 0
+            && segment
+                .chars()
+                .chain(Some(c))
+                .nfc()
+                .eq(segment.chars().nfc().chain(std::iter::once(c).nfc()));
+        if starts_segment {
+            flush(buffer, &mut segment, segment_cluster, form);
+        }
+        if segment.is_empty() {
+            segment_cluster = cluster;
+        }
+        segment.push(c);
+    }
+    if !segment.is_empty() {
+        flush(buffer, &mut segment, segment_cluster, form);
+    }
+}
+
+impl UnicodeBuffer {
+    /// Add the string slice `str_slice` to the buffer after normalizing it to
+    /// the normalization `form`.
+    ///
+    /// # Clusters
+    ///
+    /// As with `add_str` the cluster values are byte offsets into
+    /// `str_slice`. Normalization can change the number of characters, so all
+    /// characters resulting from a base character and the combining marks
+    /// that follow it share the cluster value of the base character.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{NormalizationForm, UnicodeBuffer};
+    ///
+    /// let buffer = UnicodeBuffer::new().add_str_normalized("e\u{301}!", NormalizationForm::Nfc);
+    /// assert_eq!(buffer.string_lossy(), "é!");
+    ///
+    /// let buffer = UnicodeBuffer::new().add_str_normalized("é!", NormalizationForm::Nfd);
+    /// assert_eq!(buffer.string_lossy(), "e\u{301}!");
+    /// ```
+    pub fn add_str_normalized(mut self, str_slice: &str, form: NormalizationForm) -> UnicodeBuffer {
+        let chars = str_slice.char_indices().map(|(i, c)| (c, i as u32));
+        add_normalized(&mut self.0, chars, form);
+        self
+    }
+
+    /// Normalize the current contents of the buffer to the normalization
+    /// `form`.
+    ///
+    /// This can be used as a pre-pass before shaping when the text was added
+    /// using one of the other `add` methods. Invalid codepoints are replaced by
+    /// the buffer's replacement codepoint. Cluster values are merged as
+    /// described in [`Self::add_str_normalized()`]. The segment properties,
+    /// the other settings of the buffer and the pre- and post-context set by
+    /// the `add_*_item` methods are preserved. A context set through the raw
+    /// HarfBuzz API is unknown to this crate and is lost.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::{NormalizationForm, UnicodeBuffer};
+    ///
+    /// let buffer = UnicodeBuffer::new()
+    ///     .add_str("Å")
+    ///     .normalize(NormalizationForm::Nfd);
+    /// assert_eq!(buffer.string_lossy(), "A\u{30A}");
+    /// ```
+    pub fn normalize(mut self, form: NormalizationForm) -> UnicodeBuffer {
+        let replacement =
+            std::char::from_u32(self.get_replacement_codepoint()).unwrap_or('\u{FFFD}');
+        let chars: Vec<(char, u32)> = self
+            .0
+            .get_glyph_infos()
+            .iter()
+            .map(|info| {
+                (
+                    std::char::from_u32(info.codepoint).unwrap_or(replacement),
+                    info.cluster,
+                )
+            })
+            .collect();
+        // `clear_contents` also resets the segment properties
+        let direction = self.0.get_direction();
+        let script = self.0.get_script();
+        let language = self.0.get_language();
+        let context = self.0.context();
+        self.0.clear_contents();
+        self.0.set_direction(direction);
+        self.0.set_script(script);
+        if let Some(language) = language {
+            self.0.set_language(language);
+        }
+        self.0.set_context(&context);
+        add_normalized(&mut self.0, chars, form);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clusters(buffer: &UnicodeBuffer) -> Vec<u32> {
+        buffer
+            .0
+            .get_glyph_infos()
+            .iter()
+            .map(|info| info.cluster)
+            .collect()
+    }
+
+    #[test]
+    fn test_normalized_clusters() {
+        let buffer =
+            UnicodeBuffer::new().add_str_normalized("ae\u{301}\u{323}b", NormalizationForm::Nfc);
+        assert_eq!(buffer.string_lossy(), "aẹ\u{301}b");
+        assert_eq!(clusters(&buffer), vec![0, 1, 1, 6]);
+
+        let buffer = UnicodeBuffer::new().add_str_normalized("aéb", NormalizationForm::Nfd);
+        assert_eq!(buffer.string_lossy(), "ae\u{301}b");
+        assert_eq!(clusters(&buffer), vec![0, 1, 1, 3]);
+    }
+
+    #[test]
+    fn test_normalize_hangul() {
+        // leading consonant + vowel compose into a single syllable
+        let buffer = UnicodeBuffer::new()
+            .add_str("\u{1100}\u{1161}")
+            .normalize(NormalizationForm::Nfc);
+        assert_eq!(buffer.string_lossy(), "\u{AC00}");
+        assert_eq!(clusters(&buffer), vec![0]);
+
+        let buffer = buffer.normalize(NormalizationForm::Nfd);
+        assert_eq!(buffer.string_lossy(), "\u{1100}\u{1161}");
+        assert_eq!(clusters(&buffer), vec![0, 0]);
+
+        // leading consonant + vowel + trailing consonant compose as well
+        let buffer = UnicodeBuffer::new()
+            .add_str("\u{1100}\u{1161}\u{11A8}\u{1100}")
+            .normalize(NormalizationForm::Nfc);
+        assert_eq!(buffer.string_lossy(), "\u{AC01}\u{1100}");
+        assert_eq!(clusters(&buffer), vec![0, 9]);
+    }
+
+    #[test]
+    fn test_normalize_keeps_context() {
+        let buffer = UnicodeBuffer::new()
+            .add_str_item("abe\u{301}fgh", "abe\u{301}fgh".get(2..5).unwrap())
+            .normalize(NormalizationForm::Nfc);
+        assert_eq!(buffer.string_lossy(), "é");
+        let context = buffer.0.context();
+        assert_eq!(context.pre, vec!['a' as u32, 'b' as u32]);
+        assert_eq!(context.post, vec!['f' as u32, 'g' as u32, 'h' as u32]);
+    }
+
+    #[test]
+    fn test_normalize_keeps_segment_properties() {
+        let buffer = UnicodeBuffer::new()
+            .add_str("e\u{301}")
+            .guess_segment_properties();
+        let props = buffer.get_segment_properties();
+        let buffer = buffer.normalize(NormalizationForm::Nfc);
+        assert_eq!(buffer.get_segment_properties(), props);
+    }
+}
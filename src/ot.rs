@@ -0,0 +1,124 @@
+//! OpenType specific functionality.
+//!
+//! HarfBuzz identifies scripts and languages by their ISO 15924 and BCP 47
+//! codes, while OpenType layout tables use their own script and language
+//! system tags. The functions in this module convert between the two, so
+//! that code which inspects the layout tables of a font (e.g. for font
+//! selection or feature resolution) uses exactly the tags the shaper uses.
+
+use crate::bindings::{
+    hb_language_t, hb_ot_tags_from_script_and_language, hb_ot_tags_to_script_and_language,
+    hb_script_t, hb_tag_t,
+};
+use crate::common::{Language, Script, Tag};
+
+/// The maximum number of OpenType script tags for a script
+/// (`HB_OT_MAX_TAGS_PER_SCRIPT`).
+pub const MAX_TAGS_PER_SCRIPT: usize = 3;
+
+/// The maximum number of OpenType language system tags for a language
+/// (`HB_OT_MAX_TAGS_PER_LANGUAGE`).
+pub const MAX_TAGS_PER_LANGUAGE: usize = 3;
+
+/// The OpenType language system tag of the default language system (`dflt`).
+pub const DEFAULT_LANGUAGE_TAG: Tag = Tag(u32::from_be_bytes(*b"dflt"));
+
+/// Returns the OpenType script and language system tags for `script` and
+/// `language`, in the order of preference in which the shaper looks them up
+/// in the layout tables.
+///
+/// If `language` is `None` or has no specific language system tags, the
+/// returned language tags are empty and the default language system should be
+/// used.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::{ot, Script, Tag};
+///
+/// let language = "hi".parse().unwrap();
+/// let (scripts, languages) = ot::tags_from_script_and_language(Script::DEVANAGARI, Some(language));
+/// assert_eq!(scripts, vec![Tag::from(b"dev3"), Tag::from(b"dev2"), Tag::from(b"deva")]);
+/// assert_eq!(languages, vec![Tag::from(b"HIN ")]);
+/// ```
+pub fn tags_from_script_and_language(
+    script: Script,
+    language: Option<Language>,
+) -> (Vec<Tag>, Vec<Tag>) {
+    let mut script_tags = vec![Tag(0); MAX_TAGS_PER_SCRIPT];
+    let mut language_tags = vec![Tag(0); MAX_TAGS_PER_LANGUAGE];
+    let mut script_count = MAX_TAGS_PER_SCRIPT as u32;
+    let mut language_count = MAX_TAGS_PER_LANGUAGE as u32;
+    unsafe {
+        hb_ot_tags_from_script_and_language(
+            script.0,
+            language.map_or(std::ptr::null(), |lang| lang.0),
+            &mut script_count,
+            script_tags.as_mut_ptr() as *mut hb_tag_t,
+            &mut language_count,
+            language_tags.as_mut_ptr() as *mut hb_tag_t,
+        )
+    };
+    script_tags.truncate(script_count as usize);
+    language_tags.truncate(language_count as usize);
+    (script_tags, language_tags)
+}
+
+/// Returns the script and language for an OpenType script tag and language
+/// system tag.
+///
+/// This is the inverse of [`tags_from_script_and_language()`]. The language
+/// is `None` if `language_tag` is `None` or the default language system.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::{ot, Script, Tag};
+///
+/// let (script, language) = ot::tags_to_script_and_language(b"arab".into(), Some(b"URD ".into()));
+/// assert_eq!(script, Script::ARABIC);
+/// assert_eq!(language.unwrap().to_string(), "ur");
+/// ```
+pub fn tags_to_script_and_language(
+    script_tag: Tag,
+    language_tag: Option<Tag>,
+) -> (Script, Option<Language>) {
+    let mut script: hb_script_t = 0;
+    let mut language: hb_language_t = std::ptr::null();
+    unsafe {
+        hb_ot_tags_to_script_and_language(
+            script_tag.0,
+            language_tag.unwrap_or(DEFAULT_LANGUAGE_TAG).0,
+            &mut script,
+            &mut language,
+        )
+    };
+    let language = if language.is_null() {
+        None
+    } else {
+        Some(Language(language))
+    };
+    (Script(script), language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags_round_trip() {
+        let (scripts, languages) = tags_from_script_and_language(Script::LATIN, None);
+        assert_eq!(scripts, vec![Tag::from(b"latn")]);
+        assert!(languages.is_empty());
+        assert_eq!(
+            tags_to_script_and_language(scripts[0], None),
+            (Script::LATIN, None)
+        );
+
+        let german = "de".parse().unwrap();
+        let (scripts, languages) = tags_from_script_and_language(Script::LATIN, Some(german));
+        assert_eq!(languages, vec![Tag::from(b"DEU ")]);
+        let (script, language) = tags_to_script_and_language(scripts[0], Some(languages[0]));
+        assert_eq!((script, language), (Script::LATIN, Some(german)));
+    }
+}
@@ -0,0 +1,66 @@
+//! Shaping on a thread pool.
+//!
+//! This module is only available with the `rayon` feature.
+
+use rayon::prelude::*;
+
+use crate::batch::{Run, RunShaper, ShapedRun};
+use crate::font::Font;
+
+/// Shapes independent runs of text with `font` in parallel on rayon's global
+/// thread pool.
+///
+/// The result is the same as that of [`shape_runs()`](crate::shape_runs) and
+/// in the same order as `runs`. Fonts are thread-safe in HarfBuzz, so all
+/// threads share `font` (e.g. a [`Shared<Font>`](crate::Shared)), while every
+/// worker reuses its own buffer and shape plans for the runs it shapes.
+///
+/// Shaping a single run is cheap, so this pays off for many runs, e.g. all
+/// paragraphs of a document.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font: Shared<Font<'static>> = Font::new(face).into();
+/// let paragraphs = ["Hello", "World!"].repeat(100);
+///
+/// let runs: Vec<Run> = paragraphs.iter().map(|text| Run::new(text)).collect();
+/// let shaped = shape_par(&font, &runs);
+/// assert_eq!(shaped.len(), 200);
+/// assert_eq!(shaped[199].glyphs.len(), 6);
+/// ```
+pub fn shape_par(font: &Font<'_>, runs: &[Run<'_>]) -> Vec<ShapedRun> {
+    runs.par_iter()
+        .map_init(RunShaper::new, |shaper, run| shaper.shape(font, run))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{shape_runs, Face, Feature};
+
+    #[test]
+    fn test_shape_par_matches_shape_runs() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let smcp = [Feature::new(b"smcp", 1, ..)];
+        let texts = ["Hello", "", "fi", "Wörld", "AV"];
+        let runs: Vec<Run> = texts
+            .iter()
+            .cycle()
+            .take(50)
+            .enumerate()
+            .map(|(i, text)| Run {
+                features: if i % 2 == 0 { &smcp } else { &[] },
+                ..Run::new(text)
+            })
+            .collect();
+
+        let expected = shape_runs(&font, runs.clone());
+        assert_eq!(shape_par(&font, &runs), expected);
+    }
+}
@@ -0,0 +1,451 @@
+//! Shaping whole paragraphs.
+//!
+//! A [`Pipeline`] shapes a paragraph of text in four stages:
+//!
+//! 1. an [`Itemizer`] splits the text into runs of a single script,
+//! 2. a [`BidiResolver`] assigns a direction to each run and puts the runs
+//!    into visual order,
+//! 3. a [`RunShaper`] shapes each run with the paragraph as context and
+//! 4. a [`RunMerger`] combines the shaped runs into the final result.
+//!
+//! Every stage can be replaced by a custom implementation of its trait, e.g.
+//! to use a full implementation of the Unicode Bidirectional Algorithm,
+//! while the pipeline takes care of setting up the buffers for each run.
+
+use crate::bindings::{HB_SCRIPT_COMMON, HB_SCRIPT_INHERITED, HB_SCRIPT_UNKNOWN};
+use crate::buffer::{GlyphBuffer, UnicodeBuffer};
+use crate::common::{Direction, Language, Script};
+use crate::font::Font;
+use crate::{shape, Feature};
+
+use std::fmt;
+use std::ops::Range;
+use std::os::raw::c_uint;
+
+/// A run of a paragraph that is shaped as a unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextRun {
+    /// The byte range of the run in the paragraph.
+    pub range: Range<usize>,
+    /// The script of the run.
+    pub script: Script,
+    /// The direction of the run. This is `Direction::Invalid` until it is set
+    /// by the [`BidiResolver`].
+    pub direction: Direction,
+}
+
+/// A run together with its shaped glyphs.
+///
+/// The glyph clusters are byte offsets into the whole paragraph.
+#[derive(Debug, Clone)]
+pub struct ShapedTextRun {
+    /// The run that was shaped.
+    pub run: TextRun,
+    /// The glyphs of the run.
+    pub glyphs: GlyphBuffer,
+}
+
+/// The first stage of a [`Pipeline`] which splits a paragraph into runs.
+pub trait Itemizer {
+    /// Splits `text` into runs. The runs have to cover the text without gaps
+    /// and be in logical order.
+    fn itemize(&mut self, text: &str) -> Vec<TextRun>;
+}
+
+/// The second stage of a [`Pipeline`] which determines the direction of the
+/// runs.
+pub trait BidiResolver {
+    /// Sets the direction of each run and reorders `runs` from logical into
+    /// visual order.
+    ///
+    /// `base` is the paragraph direction requested by the user, if any.
+    /// Returns the resolved paragraph direction.
+    fn reorder(
+        &mut self,
+        text: &str,
+        base: Option<Direction>,
+        runs: &mut Vec<TextRun>,
+    ) -> Direction;
+}
+
+/// The third stage of a [`Pipeline`] which shapes the individual runs.
+pub trait RunShaper {
+    /// Shapes `buffer`, which contains the text of `run` with the whole
+    /// paragraph as context and has its segment properties set.
+    fn shape_run(&mut self, run: &TextRun, buffer: UnicodeBuffer) -> GlyphBuffer;
+}
+
+/// The last stage of a [`Pipeline`] which combines the shaped runs.
+pub trait RunMerger {
+    /// Combines `runs`, which are in visual order, into a single buffer.
+    /// `base` is the paragraph direction.
+    fn merge(&mut self, runs: Vec<ShapedTextRun>, base: Direction) -> GlyphBuffer;
+}
+
+impl<T: Itemizer + ?Sized> Itemizer for &mut T {
+    fn itemize(&mut self, text: &str) -> Vec<TextRun> {
+        (**self).itemize(text)
+    }
+}
+
+impl<T: BidiResolver + ?Sized> BidiResolver for &mut T {
+    fn reorder(
+        &mut self,
+        text: &str,
+        base: Option<Direction>,
+        runs: &mut Vec<TextRun>,
+    ) -> Direction {
+        (**self).reorder(text, base, runs)
+    }
+}
+
+impl<T: RunShaper + ?Sized> RunShaper for &mut T {
+    fn shape_run(&mut self, run: &TextRun, buffer: UnicodeBuffer) -> GlyphBuffer {
+        (**self).shape_run(run, buffer)
+    }
+}
+
+impl<T: RunMerger + ?Sized> RunMerger for &mut T {
+    fn merge(&mut self, runs: Vec<ShapedTextRun>, base: Direction) -> GlyphBuffer {
+        (**self).merge(runs, base)
+    }
+}
+
+fn is_neutral(script: Script) -> bool {
+    matches!(
+        script.0,
+        HB_SCRIPT_COMMON | HB_SCRIPT_INHERITED | HB_SCRIPT_UNKNOWN
+    )
+}
+
+/// An [`Itemizer`] that splits text at script changes.
+///
+/// Characters without a script of their own, like spaces, punctuation and
+/// combining marks, are added to the preceding run.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ScriptItemizer;
+
+impl Itemizer for ScriptItemizer {
+    fn itemize(&mut self, text: &str) -> Vec<TextRun> {
+        let mut runs: Vec<TextRun> = Vec::new();
+        for (index, c) in text.char_indices() {
+            let script = Script::from_char(c);
+            let end = index + c.len_utf8();
+            match runs.last_mut() {
+                Some(run) if is_neutral(script) || run.script == script => run.range.end = end,
+                // leading neutral characters join the first real run
+                Some(run) if is_neutral(run.script) => {
+                    run.script = script;
+                    run.range.end = end;
+                }
+                _ => runs.push(TextRun {
+                    range: index..end,
+                    script,
+                    direction: Direction::Invalid,
+                }),
+            }
+        }
+        runs
+    }
+}
+
+/// A [`BidiResolver`] that uses the natural direction of each run's script.
+///
+/// The paragraph direction defaults to the direction of the first run with a
+/// strong direction. This is a simplification of the Unicode Bidirectional
+/// Algorithm that works for runs of plain text but, for example, does not
+/// treat numbers in right-to-left text as left-to-right. Use a custom
+/// `BidiResolver` if full bidi support is needed.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ScriptBidiResolver;
+
+impl BidiResolver for ScriptBidiResolver {
+    fn reorder(
+        &mut self,
+        _text: &str,
+        base: Option<Direction>,
+        runs: &mut Vec<TextRun>,
+    ) -> Direction {
+        let strong = |run: &TextRun| match run.script.horizontal_direction() {
+            dir @ Direction::Ltr | dir @ Direction::Rtl => Some(dir),
+            _ => None,
+        };
+        let base = base
+            .or_else(|| runs.iter().find_map(strong))
+            .unwrap_or(Direction::Ltr);
+        for run in runs.iter_mut() {
+            run.direction = strong(run).unwrap_or(base);
+        }
+
+        // reverse the sequences of runs that go against the paragraph
+        // direction, and for right-to-left paragraphs the whole line
+        if base == Direction::Rtl {
+            runs.reverse();
+        }
+        let mut start = 0;
+        while start < runs.len() {
+            let end = runs[start..]
+                .iter()
+                .position(|run| run.direction == base)
+                .map(|len| start + len)
+                .unwrap_or(runs.len());
+            runs[start..end].reverse();
+            start = end + 1;
+        }
+        base
+    }
+}
+
+/// A [`RunShaper`] that shapes all runs with one font and set of features.
+#[derive(Debug)]
+pub struct FontRunShaper<'a> {
+    font: &'a Font<'a>,
+    features: Vec<Feature>,
+}
+
+impl<'a> FontRunShaper<'a> {
+    /// Creates a shaper using `font` and `features`.
+    pub fn new(font: &'a Font<'a>, features: &[Feature]) -> FontRunShaper<'a> {
+        FontRunShaper {
+            font,
+            features: features.to_vec(),
+        }
+    }
+}
+
+impl<'a> RunShaper for FontRunShaper<'a> {
+    fn shape_run(&mut self, _run: &TextRun, buffer: UnicodeBuffer) -> GlyphBuffer {
+        shape(self.font, buffer, &self.features)
+    }
+}
+
+/// A [`RunMerger`] that concatenates the runs in visual order.
+///
+/// The direction of the resulting buffer is the paragraph direction.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ConcatRunMerger;
+
+impl RunMerger for ConcatRunMerger {
+    fn merge(&mut self, runs: Vec<ShapedTextRun>, base: Direction) -> GlyphBuffer {
+        let mut output = GlyphBuffer::new();
+        for run in &runs {
+            output.0.append(&run.glyphs.0, 0, c_uint::MAX);
+        }
+        output.0.set_direction(base);
+        output
+    }
+}
+
+/// Shapes whole paragraphs using replaceable stages.
+///
+/// See the [module documentation](crate::pipeline) for a description of the
+/// stages. By default text is split into runs by script, the runs get the
+/// natural direction of their script and are shaped with a single font and
+/// concatenated in visual order.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+/// use harfbuzz_rs::pipeline::Pipeline;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let mut pipeline = Pipeline::new(&font, &[]);
+///
+/// // a left-to-right paragraph with a Hebrew word in the middle
+/// let text = "ab \u{5D0}\u{5D1} cd";
+/// let runs = pipeline.shape_runs(text);
+/// assert_eq!(runs.len(), 3);
+/// assert_eq!(runs[1].run.direction, Direction::Rtl);
+///
+/// let output = pipeline.shape(text);
+/// let clusters: Vec<u32> = output.iter().map(|glyph| glyph.cluster).collect();
+/// assert_eq!(clusters, vec![0, 1, 2, 7, 5, 3, 8, 9]);
+/// ```
+pub struct Pipeline<'a> {
+    itemizer: Box<dyn Itemizer + 'a>,
+    bidi: Box<dyn BidiResolver + 'a>,
+    shaper: Box<dyn RunShaper + 'a>,
+    merger: Box<dyn RunMerger + 'a>,
+    direction: Option<Direction>,
+    language: Option<Language>,
+}
+
+impl<'a> Pipeline<'a> {
+    /// Creates a pipeline with the default stages that shapes runs with `font`
+    /// and `features`.
+    pub fn new(font: &'a Font<'a>, features: &[Feature]) -> Pipeline<'a> {
+        Pipeline {
+            itemizer: Box::new(ScriptItemizer),
+            bidi: Box::new(ScriptBidiResolver),
+            shaper: Box::new(FontRunShaper::new(font, features)),
+            merger: Box::new(ConcatRunMerger),
+            direction: None,
+            language: None,
+        }
+    }
+
+    /// Sets the paragraph direction. By default it is determined by the
+    /// `BidiResolver`.
+    pub fn set_direction(mut self, direction: Direction) -> Pipeline<'a> {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Sets the language of the text. By default HarfBuzz uses the language
+    /// of the current locale.
+    pub fn set_language(mut self, language: Language) -> Pipeline<'a> {
+        self.language = Some(language);
+        self
+    }
+
+    /// Replaces the itemization stage.
+    pub fn set_itemizer(mut self, itemizer: impl Itemizer + 'a) -> Pipeline<'a> {
+        self.itemizer = Box::new(itemizer);
+        self
+    }
+
+    /// Replaces the bidi stage.
+    pub fn set_bidi_resolver(mut self, bidi: impl BidiResolver + 'a) -> Pipeline<'a> {
+        self.bidi = Box::new(bidi);
+        self
+    }
+
+    /// Replaces the run shaping stage.
+    pub fn set_run_shaper(mut self, shaper: impl RunShaper + 'a) -> Pipeline<'a> {
+        self.shaper = Box::new(shaper);
+        self
+    }
+
+    /// Replaces the merging stage.
+    pub fn set_run_merger(mut self, merger: impl RunMerger + 'a) -> Pipeline<'a> {
+        self.merger = Box::new(merger);
+        self
+    }
+
+    /// Shapes `text` and returns the shaped runs in visual order without
+    /// merging them.
+    pub fn shape_runs(&mut self, text: &str) -> Vec<ShapedTextRun> {
+        self.shape_runs_with_base(text).0
+    }
+
+    /// Shapes `text` and merges the runs into a single buffer.
+    pub fn shape(&mut self, text: &str) -> GlyphBuffer {
+        let (runs, base) = self.shape_runs_with_base(text);
+        self.merger.merge(runs, base)
+    }
+
+    fn shape_runs_with_base(&mut self, text: &str) -> (Vec<ShapedTextRun>, Direction) {
+        let mut runs = self.itemizer.itemize(text);
+        let base = self.bidi.reorder(text, self.direction, &mut runs);
+        let shaped = runs
+            .into_iter()
+            .map(|run| {
+                let mut buffer = UnicodeBuffer::new()
+                    .add_str_item(text, &text[run.range.clone()])
+                    .set_direction(run.direction)
+                    .set_script(run.script.to_iso15924_tag());
+                if let Some(language) = self.language {
+                    buffer = buffer.set_language(language);
+                }
+                let glyphs = self.shaper.shape_run(&run, buffer);
+                ShapedTextRun { run, glyphs }
+            })
+            .collect();
+        (shaped, base)
+    }
+}
+
+impl<'a> fmt::Debug for Pipeline<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("direction", &self.direction)
+            .field("language", &self.language)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Face;
+
+    #[test]
+    fn test_itemize() {
+        let runs = ScriptItemizer.itemize("(ab \u{5D0}\u{5D1}, cd)");
+        let ranges: Vec<_> = runs.iter().map(|run| run.range.clone()).collect();
+        assert_eq!(ranges, vec![0..4, 4..10, 10..13]);
+        assert_eq!(runs[0].script, Script::from_char('a'));
+        assert!(ScriptItemizer.itemize("").is_empty());
+        assert_eq!(ScriptItemizer.itemize("1, 2").len(), 1);
+    }
+
+    #[test]
+    fn test_bidi_reorder() {
+        let run = |script: char| TextRun {
+            range: 0..0,
+            script: Script::from_char(script),
+            direction: Direction::Invalid,
+        };
+        let (a, r) = ('a', '\u{5D0}');
+        let order = |base, scripts: &[char]| {
+            let mut runs: Vec<TextRun> = scripts.iter().map(|&c| run(c)).collect();
+            for (i, run) in runs.iter_mut().enumerate() {
+                run.range = i..i + 1;
+            }
+            let base = ScriptBidiResolver.reorder("", base, &mut runs);
+            let order: Vec<usize> = runs.iter().map(|run| run.range.start).collect();
+            (base, order)
+        };
+        assert_eq!(
+            order(None, &[a, r, r, a]),
+            (Direction::Ltr, vec![0, 2, 1, 3])
+        );
+        assert_eq!(
+            order(None, &[r, a, a, r]),
+            (Direction::Rtl, vec![3, 1, 2, 0])
+        );
+        assert_eq!(
+            order(Some(Direction::Rtl), &[a, r]),
+            (Direction::Rtl, vec![1, 0])
+        );
+        assert_eq!(order(None, &['1']), (Direction::Ltr, vec![0]));
+    }
+
+    struct CountingShaper<'a>(FontRunShaper<'a>, usize);
+
+    impl<'a> RunShaper for CountingShaper<'a> {
+        fn shape_run(&mut self, run: &TextRun, buffer: UnicodeBuffer) -> GlyphBuffer {
+            self.1 += 1;
+            self.0.shape_run(run, buffer)
+        }
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let text = "ab \u{5D0}\u{5D1} cd";
+
+        let mut pipeline = Pipeline::new(&font, &[]).set_direction(Direction::Rtl);
+        let output = pipeline.shape(text);
+        let clusters: Vec<u32> = output.iter().map(|glyph| glyph.cluster).collect();
+        assert_eq!(clusters, vec![8, 9, 7, 5, 3, 0, 1, 2]);
+        assert_eq!(output.get_segment_properties().direction, Direction::Rtl);
+
+        // the glyphs of each run match shaping the run on its own
+        let runs = pipeline.shape_runs(text);
+        let expected = shape(&font, UnicodeBuffer::new().add_str("cd"), &[]);
+        assert_eq!(runs[0].glyphs.len(), expected.len());
+        assert_eq!(runs[0].run.range, 8..10);
+
+        let mut counter = CountingShaper(FontRunShaper::new(&font, &[]), 0);
+        {
+            let mut pipeline = Pipeline::new(&font, &[]).set_run_shaper(&mut counter);
+            assert!(pipeline.shape("").is_empty());
+            assert_eq!(pipeline.shape(text).len(), 8);
+        }
+        assert_eq!(counter.1, 3);
+    }
+}
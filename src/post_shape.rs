@@ -0,0 +1,148 @@
+use crate::buffer::{GlyphBuffer, GlyphInfo, GlyphPosition};
+use crate::common::Direction;
+use crate::font::{Font, Glyph};
+
+/// The shaped glyphs of a run of text as seen by a [`PostShapePass`].
+///
+/// It gives access to the glyphs together with the font and text they were
+/// shaped from. The clusters of the glyphs are byte offsets into the text.
+#[derive(Debug)]
+pub struct GlyphRunMut<'a> {
+    font: &'a Font<'a>,
+    text: &'a str,
+    glyphs: &'a mut GlyphBuffer,
+}
+
+impl<'a> GlyphRunMut<'a> {
+    /// Creates a run of the `glyphs` that resulted from shaping `text` with
+    /// `font`.
+    pub fn new(font: &'a Font<'a>, text: &'a str, glyphs: &'a mut GlyphBuffer) -> Self {
+        GlyphRunMut { font, text, glyphs }
+    }
+
+    /// Returns the font the run was shaped with.
+    pub fn font(&self) -> &Font<'a> {
+        self.font
+    }
+
+    /// Returns the text of the run.
+    pub fn text(&self) -> &str {
+        self.text
+    }
+
+    /// Returns the direction the run was shaped in.
+    pub fn direction(&self) -> Direction {
+        self.glyphs.get_segment_properties().direction
+    }
+
+    /// Returns the number of glyphs.
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Returns `true` if the run has no glyphs.
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// Returns the glyph infos.
+    pub fn get_glyph_infos(&self) -> &[GlyphInfo] {
+        self.glyphs.get_glyph_infos()
+    }
+
+    /// Returns the glyph positions.
+    pub fn get_glyph_positions(&self) -> &[GlyphPosition] {
+        self.glyphs.get_glyph_positions()
+    }
+
+    /// Returns the glyph infos for modification.
+    pub fn get_glyph_infos_mut(&mut self) -> &mut [GlyphInfo] {
+        self.glyphs.get_glyph_infos_mut()
+    }
+
+    /// Returns the glyph positions for modification.
+    pub fn get_glyph_positions_mut(&mut self) -> &mut [GlyphPosition] {
+        self.glyphs.0.get_glyph_positions_mut()
+    }
+
+    /// Replaces the glyph at `index` with `glyph` and sets its advance to
+    /// the advance of `glyph` in the font along the direction of the run.
+    ///
+    /// The cluster and offsets of the glyph are kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn replace_glyph(&mut self, index: usize, glyph: Glyph) {
+        let direction = self.direction();
+        let font = self.font;
+        self.get_glyph_infos_mut()[index].codepoint = glyph;
+        let position = &mut self.get_glyph_positions_mut()[index];
+        match direction {
+            Direction::Ttb | Direction::Btt => {
+                position.y_advance = font.get_glyph_v_advance(glyph);
+            }
+            _ => position.x_advance = font.get_glyph_h_advance(glyph),
+        }
+    }
+
+    /// Returns the underlying buffer, e.g. to remove or insert glyphs.
+    pub fn buffer_mut(&mut self) -> &mut GlyphBuffer {
+        self.glyphs
+    }
+}
+
+/// A pass that modifies the glyphs of a run after shaping.
+///
+/// Passes are registered with [`ShapeOptions::post_shape_pass()`] and run in
+/// the order of registration after every call of
+/// [`ShapeOptions::shape()`]. They can implement policies that are outside
+/// of the scope of HarfBuzz, e.g. replacing `.notdef` glyphs with hex boxes
+/// or swapping glyphs of emoji.
+///
+/// The trait is implemented for closures taking a [`GlyphRunMut`].
+///
+/// [`ShapeOptions::post_shape_pass()`]: crate::ShapeOptions::post_shape_pass
+/// [`ShapeOptions::shape()`]: crate::ShapeOptions::shape
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// // replaces missing glyphs with the glyph of '?'
+/// struct QuestionMarks;
+///
+/// impl PostShapePass for QuestionMarks {
+///     fn process(&self, run: &mut GlyphRunMut<'_>) {
+///         let question_mark = run.font().get_nominal_glyph('?').unwrap();
+///         for i in 0..run.len() {
+///             if run.get_glyph_infos()[i].codepoint == 0 {
+///                 run.replace_glyph(i, question_mark);
+///             }
+///         }
+///     }
+/// }
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let options = ShapeOptions::new().post_shape_pass(QuestionMarks);
+///
+/// let output = options.shape(&font, "a\u{E000}");
+/// let question_mark = font.get_nominal_glyph('?').unwrap();
+/// assert_eq!(output.get_glyph_infos()[1].codepoint, question_mark);
+/// assert_eq!(
+///     output.get_glyph_positions()[1].x_advance,
+///     font.get_glyph_h_advance(question_mark)
+/// );
+/// ```
+pub trait PostShapePass {
+    /// Modifies the glyphs of `run`.
+    fn process(&self, run: &mut GlyphRunMut<'_>);
+}
+
+impl<F: Fn(&mut GlyphRunMut<'_>)> PostShapePass for F {
+    fn process(&self, run: &mut GlyphRunMut<'_>) {
+        self(run)
+    }
+}
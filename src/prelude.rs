@@ -0,0 +1,16 @@
+//! A prelude that reexports the most commonly used types and traits.
+//!
+//! ```
+//! use harfbuzz_rs::prelude::*;
+//!
+//! let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+//! let font = Font::new(face);
+//! let buffer = UnicodeBuffer::new().add_str("Hello");
+//! let output = shape(&font, buffer, &[Feature::new(b"kern", 1, ..)]);
+//! assert_eq!(output.len(), 5);
+//! ```
+
+pub use crate::{
+    shape, DrawFuncs, Face, Feature, Font, FontFuncs, GlyphBuffer, HarfbuzzObject, Owned, Shared,
+    Tag, UnicodeBuffer, Variation,
+};
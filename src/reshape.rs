@@ -0,0 +1,253 @@
+use crate::bindings::HB_BUFFER_CONTENT_TYPE_GLYPHS;
+use crate::buffer::{GlyphBuffer, GlyphInfo, UnicodeBuffer};
+use crate::common::Direction;
+use crate::font::Font;
+use crate::{shape, Feature};
+
+use std::ops::Range;
+use std::os::raw::c_uint;
+
+// A part of the reshaped output in logical order, given by the range of
+// glyph indices in the original buffer or in a reshaped buffer.
+enum Piece {
+    Kept(Range<usize>),
+    Reshaped(GlyphBuffer, Range<usize>),
+}
+
+// Returns the range of indices of the glyphs whose clusters are in the byte
+// range `clusters`.
+fn glyph_range(infos: &[GlyphInfo], clusters: Range<usize>) -> Range<usize> {
+    let mut indices = (0..infos.len()).filter(|&i| clusters.contains(&(infos[i].cluster as usize)));
+    match indices.next() {
+        Some(start) => start..indices.next_back().unwrap_or(start) + 1,
+        None => 0..0,
+    }
+}
+
+// Returns `true` if `infos` can be split before the cluster starting at byte
+// `offset`, i.e. if the cluster was not merged with the previous one and
+// breaking before it is safe.
+fn can_split(infos: &[GlyphInfo], offset: usize) -> bool {
+    let mut glyphs = infos
+        .iter()
+        .filter(|info| info.cluster as usize == offset)
+        .peekable();
+    glyphs.peek().is_some() && glyphs.all(|info| !info.glyph_flags().unsafe_to_break())
+}
+
+/// Reshapes only the clusters of `buffer` that contain the byte offsets in
+/// `dirty_clusters` and splices the results into a copy of `buffer`.
+///
+/// `buffer` must be the result of shaping `text` (e.g. added with
+/// [`UnicodeBuffer::add_str()`]), so that its clusters are byte offsets into
+/// `text`. The text of the dirty clusters may have changed since, as long as
+/// the byte offsets of the clusters are unchanged, e.g. because a character
+/// was replaced by one of the same length or because the features changed.
+///
+/// The dirty spans are extended to the nearest positions where HarfBuzz
+/// reports that breaking is safe (see
+/// [`GlyphFlags::unsafe_to_break()`](crate::GlyphFlags::unsafe_to_break)) and
+/// reshaped together with their neighbouring clusters, with the rest of
+/// `text` as context and the segment properties of `buffer`. The spans are
+/// widened further until breaking at their ends is also safe in the reshaped
+/// glyphs, so the result matches shaping the whole text again as far as the
+/// flags of HarfBuzz can tell, while doing only a fraction of the work for
+/// long paragraphs.
+///
+/// # Panics
+///
+/// Panics if the clusters of `buffer` are not valid offsets into `text`.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let output = shape(&font, UnicodeBuffer::new().add_str("Hello World"), &[]);
+///
+/// // the user replaced the `o` at byte 7
+/// let text = "Hello Wxrld";
+/// let updated = reshape_clusters(&font, text, &output, &[7], &[]);
+///
+/// let expected = shape(&font, UnicodeBuffer::new().add_str(text), &[]);
+/// assert!(updated.iter().eq(expected.iter()));
+/// ```
+pub fn reshape_clusters(
+    font: &Font<'_>,
+    text: &str,
+    buffer: &GlyphBuffer,
+    dirty_clusters: &[u32],
+    features: &[Feature],
+) -> GlyphBuffer {
+    let infos = buffer.get_glyph_infos();
+    let props = buffer.get_segment_properties();
+    let backwards = matches!(props.direction, Direction::Rtl | Direction::Btt);
+
+    // the clusters in logical order and whether breaking before them is unsafe
+    let logical: Box<dyn Iterator<Item = usize>> = if backwards {
+        Box::new((0..infos.len()).rev())
+    } else {
+        Box::new(0..infos.len())
+    };
+    let mut clusters: Vec<(u32, bool)> = Vec::new();
+    for i in logical {
+        let info = &infos[i];
+        if clusters.last().map(|&(cluster, _)| cluster) != Some(info.cluster) {
+            clusters.push((info.cluster, info.glyph_flags().unsafe_to_break()));
+        }
+    }
+    let cluster_offset = |index: usize| {
+        clusters
+            .get(index)
+            .map(|&(cluster, _)| cluster as usize)
+            .unwrap_or(text.len())
+    };
+    // extends a range of indices into `clusters` to the nearest positions
+    // where breaking was safe in `buffer`
+    let widen = |mut range: Range<usize>| {
+        while range.start > 0 && clusters[range.start].1 {
+            range.start -= 1;
+        }
+        while range.end < clusters.len() && clusters[range.end].1 {
+            range.end += 1;
+        }
+        range
+    };
+
+    // the ranges of indices into `clusters` to reshape
+    let mut dirty: Vec<Range<usize>> = dirty_clusters
+        .iter()
+        .filter_map(|&offset| {
+            let index = clusters.partition_point(|&(cluster, _)| cluster <= offset);
+            Some(widen(index.checked_sub(1)?..index))
+        })
+        .collect();
+    if dirty.is_empty() {
+        return buffer.clone();
+    }
+    dirty.sort_by_key(|range| range.start);
+
+    let mut pieces = Vec::new();
+    let mut kept_start = 0;
+    let mut dirty = dirty.into_iter().peekable();
+    while let Some(mut range) = dirty.next() {
+        let reshaped = loop {
+            // merge overlapping and adjacent ranges
+            while let Some(next) = dirty.peek() {
+                if next.start > range.end {
+                    break;
+                }
+                range.end = range.end.max(next.end);
+                dirty.next();
+            }
+            // the neighbouring clusters are reshaped as well, so that the
+            // flags at the ends of the range can be checked
+            let context = range.start.saturating_sub(1)..clusters.len().min(range.end + 1);
+            let item = &text[cluster_offset(context.start)..cluster_offset(context.end)];
+            let reshaped = UnicodeBuffer::new()
+                .add_str_item(text, item)
+                .set_segment_properties(props);
+            let reshaped = shape(font, reshaped, features);
+            let reshaped_infos = reshaped.get_glyph_infos();
+            let start_safe = range.start == context.start
+                || can_split(reshaped_infos, cluster_offset(range.start));
+            let end_safe =
+                range.end == context.end || can_split(reshaped_infos, cluster_offset(range.end));
+            if start_safe && end_safe {
+                break reshaped;
+            }
+            if !start_safe {
+                range = widen(range.start - 1..range.end);
+            }
+            if !end_safe {
+                range = widen(range.start..range.end + 1);
+            }
+        };
+        if kept_start < range.start {
+            let clusters = cluster_offset(kept_start)..cluster_offset(range.start);
+            pieces.push(Piece::Kept(glyph_range(infos, clusters)));
+        }
+        let clusters = cluster_offset(range.start)..cluster_offset(range.end);
+        let glyphs = glyph_range(reshaped.get_glyph_infos(), clusters);
+        pieces.push(Piece::Reshaped(reshaped, glyphs));
+        kept_start = range.end;
+    }
+    if kept_start < clusters.len() {
+        let clusters = cluster_offset(kept_start)..text.len();
+        pieces.push(Piece::Kept(glyph_range(infos, clusters)));
+    }
+
+    if backwards {
+        pieces.reverse();
+    }
+    let mut output = buffer.0.create_similar();
+    for piece in &pieces {
+        let (source, range) = match piece {
+            Piece::Kept(range) => (buffer, range),
+            Piece::Reshaped(glyphs, range) => (glyphs, range),
+        };
+        output.append(&source.0, range.start as c_uint, range.end as c_uint);
+    }
+    output.set_content_type(HB_BUFFER_CONTENT_TYPE_GLYPHS);
+    GlyphBuffer(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Face;
+
+    #[test]
+    fn test_reshape_clusters() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let shape_str = |text: &str, direction| {
+            let buffer = UnicodeBuffer::new().add_str(text).set_direction(direction);
+            shape(&font, buffer, &[])
+        };
+
+        for &direction in &[Direction::Ltr, Direction::Rtl] {
+            let old = shape_str("AVATAR To WAVE", direction);
+            for &(text, dirty) in &[
+                ("AVXTAR To WAVE", &[2][..]),
+                ("AVATAR To WAVE", &[0, 13][..]),
+                ("XVATAR Tx WAVX", &[13, 8, 0][..]),
+                ("AVATAR To WAVE", &[][..]),
+            ] {
+                let updated = reshape_clusters(&font, text, &old, dirty, &[]);
+                let expected = shape_str(text, direction);
+                assert!(updated.iter().eq(expected.iter()), "{} {:?}", text, dirty);
+            }
+        }
+
+        // every replacement of a single character gives the same result as
+        // shaping again
+        let alphabet = ['A', 'V', 'W', 'L', 'T', 'o', 'f', 'i', '.', ' '];
+        for &direction in &[Direction::Ltr, Direction::Rtl] {
+            for a in &alphabet {
+                for b in &alphabet {
+                    for c in &alphabet {
+                        let text: String = [*a, *b, *c].iter().collect();
+                        let old = shape_str(&text, direction);
+                        for position in 0..3 {
+                            for replacement in &alphabet {
+                                let mut chars: Vec<char> = text.chars().collect();
+                                chars[position] = *replacement;
+                                let text: String = chars.into_iter().collect();
+                                let dirty = [position as u32];
+                                let updated = reshape_clusters(&font, &text, &old, &dirty, &[]);
+                                let expected = shape_str(&text, direction);
+                                assert!(updated.iter().eq(expected.iter()), "{:?}", text);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let empty = shape_str("", Direction::Ltr);
+        assert!(reshape_clusters(&font, "", &empty, &[0], &[]).is_empty());
+    }
+}
@@ -0,0 +1,528 @@
+// This file is generated by update.sh from the `hb_script_t` values in
+// src/bindings.rs. Do not edit it by hand.
+
+use crate::bindings::*;
+use crate::common::Script;
+
+impl Script {
+    /// The Common script (`Zyyy`).
+    pub const COMMON: Script = Script(HB_SCRIPT_COMMON);
+    /// The Inherited script (`Zinh`).
+    pub const INHERITED: Script = Script(HB_SCRIPT_INHERITED);
+    /// The Unknown script (`Zzzz`).
+    pub const UNKNOWN: Script = Script(HB_SCRIPT_UNKNOWN);
+    /// The Arabic script (`Arab`).
+    pub const ARABIC: Script = Script(HB_SCRIPT_ARABIC);
+    /// The Armenian script (`Armn`).
+    pub const ARMENIAN: Script = Script(HB_SCRIPT_ARMENIAN);
+    /// The Bengali script (`Beng`).
+    pub const BENGALI: Script = Script(HB_SCRIPT_BENGALI);
+    /// The Cyrillic script (`Cyrl`).
+    pub const CYRILLIC: Script = Script(HB_SCRIPT_CYRILLIC);
+    /// The Devanagari script (`Deva`).
+    pub const DEVANAGARI: Script = Script(HB_SCRIPT_DEVANAGARI);
+    /// The Georgian script (`Geor`).
+    pub const GEORGIAN: Script = Script(HB_SCRIPT_GEORGIAN);
+    /// The Greek script (`Grek`).
+    pub const GREEK: Script = Script(HB_SCRIPT_GREEK);
+    /// The Gujarati script (`Gujr`).
+    pub const GUJARATI: Script = Script(HB_SCRIPT_GUJARATI);
+    /// The Gurmukhi script (`Guru`).
+    pub const GURMUKHI: Script = Script(HB_SCRIPT_GURMUKHI);
+    /// The Hangul script (`Hang`).
+    pub const HANGUL: Script = Script(HB_SCRIPT_HANGUL);
+    /// The Han script (`Hani`).
+    pub const HAN: Script = Script(HB_SCRIPT_HAN);
+    /// The Hebrew script (`Hebr`).
+    pub const HEBREW: Script = Script(HB_SCRIPT_HEBREW);
+    /// The Hiragana script (`Hira`).
+    pub const HIRAGANA: Script = Script(HB_SCRIPT_HIRAGANA);
+    /// The Kannada script (`Knda`).
+    pub const KANNADA: Script = Script(HB_SCRIPT_KANNADA);
+    /// The Katakana script (`Kana`).
+    pub const KATAKANA: Script = Script(HB_SCRIPT_KATAKANA);
+    /// The Lao script (`Laoo`).
+    pub const LAO: Script = Script(HB_SCRIPT_LAO);
+    /// The Latin script (`Latn`).
+    pub const LATIN: Script = Script(HB_SCRIPT_LATIN);
+    /// The Malayalam script (`Mlym`).
+    pub const MALAYALAM: Script = Script(HB_SCRIPT_MALAYALAM);
+    /// The Oriya script (`Orya`).
+    pub const ORIYA: Script = Script(HB_SCRIPT_ORIYA);
+    /// The Tamil script (`Taml`).
+    pub const TAMIL: Script = Script(HB_SCRIPT_TAMIL);
+    /// The Telugu script (`Telu`).
+    pub const TELUGU: Script = Script(HB_SCRIPT_TELUGU);
+    /// The Thai script (`Thai`).
+    pub const THAI: Script = Script(HB_SCRIPT_THAI);
+    /// The Tibetan script (`Tibt`).
+    pub const TIBETAN: Script = Script(HB_SCRIPT_TIBETAN);
+    /// The Bopomofo script (`Bopo`).
+    pub const BOPOMOFO: Script = Script(HB_SCRIPT_BOPOMOFO);
+    /// The Braille script (`Brai`).
+    pub const BRAILLE: Script = Script(HB_SCRIPT_BRAILLE);
+    /// The Canadian_Syllabics script (`Cans`).
+    pub const CANADIAN_SYLLABICS: Script = Script(HB_SCRIPT_CANADIAN_SYLLABICS);
+    /// The Cherokee script (`Cher`).
+    pub const CHEROKEE: Script = Script(HB_SCRIPT_CHEROKEE);
+    /// The Ethiopic script (`Ethi`).
+    pub const ETHIOPIC: Script = Script(HB_SCRIPT_ETHIOPIC);
+    /// The Khmer script (`Khmr`).
+    pub const KHMER: Script = Script(HB_SCRIPT_KHMER);
+    /// The Mongolian script (`Mong`).
+    pub const MONGOLIAN: Script = Script(HB_SCRIPT_MONGOLIAN);
+    /// The Myanmar script (`Mymr`).
+    pub const MYANMAR: Script = Script(HB_SCRIPT_MYANMAR);
+    /// The Ogham script (`Ogam`).
+    pub const OGHAM: Script = Script(HB_SCRIPT_OGHAM);
+    /// The Runic script (`Runr`).
+    pub const RUNIC: Script = Script(HB_SCRIPT_RUNIC);
+    /// The Sinhala script (`Sinh`).
+    pub const SINHALA: Script = Script(HB_SCRIPT_SINHALA);
+    /// The Syriac script (`Syrc`).
+    pub const SYRIAC: Script = Script(HB_SCRIPT_SYRIAC);
+    /// The Thaana script (`Thaa`).
+    pub const THAANA: Script = Script(HB_SCRIPT_THAANA);
+    /// The Yi script (`Yiii`).
+    pub const YI: Script = Script(HB_SCRIPT_YI);
+    /// The Deseret script (`Dsrt`).
+    pub const DESERET: Script = Script(HB_SCRIPT_DESERET);
+    /// The Gothic script (`Goth`).
+    pub const GOTHIC: Script = Script(HB_SCRIPT_GOTHIC);
+    /// The Old_Italic script (`Ital`).
+    pub const OLD_ITALIC: Script = Script(HB_SCRIPT_OLD_ITALIC);
+    /// The Buhid script (`Buhd`).
+    pub const BUHID: Script = Script(HB_SCRIPT_BUHID);
+    /// The Hanunoo script (`Hano`).
+    pub const HANUNOO: Script = Script(HB_SCRIPT_HANUNOO);
+    /// The Tagalog script (`Tglg`).
+    pub const TAGALOG: Script = Script(HB_SCRIPT_TAGALOG);
+    /// The Tagbanwa script (`Tagb`).
+    pub const TAGBANWA: Script = Script(HB_SCRIPT_TAGBANWA);
+    /// The Cypriot script (`Cprt`).
+    pub const CYPRIOT: Script = Script(HB_SCRIPT_CYPRIOT);
+    /// The Limbu script (`Limb`).
+    pub const LIMBU: Script = Script(HB_SCRIPT_LIMBU);
+    /// The Linear_B script (`Linb`).
+    pub const LINEAR_B: Script = Script(HB_SCRIPT_LINEAR_B);
+    /// The Osmanya script (`Osma`).
+    pub const OSMANYA: Script = Script(HB_SCRIPT_OSMANYA);
+    /// The Shavian script (`Shaw`).
+    pub const SHAVIAN: Script = Script(HB_SCRIPT_SHAVIAN);
+    /// The Tai_Le script (`Tale`).
+    pub const TAI_LE: Script = Script(HB_SCRIPT_TAI_LE);
+    /// The Ugaritic script (`Ugar`).
+    pub const UGARITIC: Script = Script(HB_SCRIPT_UGARITIC);
+    /// The Buginese script (`Bugi`).
+    pub const BUGINESE: Script = Script(HB_SCRIPT_BUGINESE);
+    /// The Coptic script (`Copt`).
+    pub const COPTIC: Script = Script(HB_SCRIPT_COPTIC);
+    /// The Glagolitic script (`Glag`).
+    pub const GLAGOLITIC: Script = Script(HB_SCRIPT_GLAGOLITIC);
+    /// The Kharoshthi script (`Khar`).
+    pub const KHAROSHTHI: Script = Script(HB_SCRIPT_KHAROSHTHI);
+    /// The New_Tai_Lue script (`Talu`).
+    pub const NEW_TAI_LUE: Script = Script(HB_SCRIPT_NEW_TAI_LUE);
+    /// The Old_Persian script (`Xpeo`).
+    pub const OLD_PERSIAN: Script = Script(HB_SCRIPT_OLD_PERSIAN);
+    /// The Syloti_Nagri script (`Sylo`).
+    pub const SYLOTI_NAGRI: Script = Script(HB_SCRIPT_SYLOTI_NAGRI);
+    /// The Tifinagh script (`Tfng`).
+    pub const TIFINAGH: Script = Script(HB_SCRIPT_TIFINAGH);
+    /// The Balinese script (`Bali`).
+    pub const BALINESE: Script = Script(HB_SCRIPT_BALINESE);
+    /// The Cuneiform script (`Xsux`).
+    pub const CUNEIFORM: Script = Script(HB_SCRIPT_CUNEIFORM);
+    /// The Nko script (`Nkoo`).
+    pub const NKO: Script = Script(HB_SCRIPT_NKO);
+    /// The Phags_Pa script (`Phag`).
+    pub const PHAGS_PA: Script = Script(HB_SCRIPT_PHAGS_PA);
+    /// The Phoenician script (`Phnx`).
+    pub const PHOENICIAN: Script = Script(HB_SCRIPT_PHOENICIAN);
+    /// The Carian script (`Cari`).
+    pub const CARIAN: Script = Script(HB_SCRIPT_CARIAN);
+    /// The Cham script (`Cham`).
+    pub const CHAM: Script = Script(HB_SCRIPT_CHAM);
+    /// The Kayah_Li script (`Kali`).
+    pub const KAYAH_LI: Script = Script(HB_SCRIPT_KAYAH_LI);
+    /// The Lepcha script (`Lepc`).
+    pub const LEPCHA: Script = Script(HB_SCRIPT_LEPCHA);
+    /// The Lycian script (`Lyci`).
+    pub const LYCIAN: Script = Script(HB_SCRIPT_LYCIAN);
+    /// The Lydian script (`Lydi`).
+    pub const LYDIAN: Script = Script(HB_SCRIPT_LYDIAN);
+    /// The Ol_Chiki script (`Olck`).
+    pub const OL_CHIKI: Script = Script(HB_SCRIPT_OL_CHIKI);
+    /// The Rejang script (`Rjng`).
+    pub const REJANG: Script = Script(HB_SCRIPT_REJANG);
+    /// The Saurashtra script (`Saur`).
+    pub const SAURASHTRA: Script = Script(HB_SCRIPT_SAURASHTRA);
+    /// The Sundanese script (`Sund`).
+    pub const SUNDANESE: Script = Script(HB_SCRIPT_SUNDANESE);
+    /// The Vai script (`Vaii`).
+    pub const VAI: Script = Script(HB_SCRIPT_VAI);
+    /// The Avestan script (`Avst`).
+    pub const AVESTAN: Script = Script(HB_SCRIPT_AVESTAN);
+    /// The Bamum script (`Bamu`).
+    pub const BAMUM: Script = Script(HB_SCRIPT_BAMUM);
+    /// The Egyptian_Hieroglyphs script (`Egyp`).
+    pub const EGYPTIAN_HIEROGLYPHS: Script = Script(HB_SCRIPT_EGYPTIAN_HIEROGLYPHS);
+    /// The Imperial_Aramaic script (`Armi`).
+    pub const IMPERIAL_ARAMAIC: Script = Script(HB_SCRIPT_IMPERIAL_ARAMAIC);
+    /// The Inscriptional_Pahlavi script (`Phli`).
+    pub const INSCRIPTIONAL_PAHLAVI: Script = Script(HB_SCRIPT_INSCRIPTIONAL_PAHLAVI);
+    /// The Inscriptional_Parthian script (`Prti`).
+    pub const INSCRIPTIONAL_PARTHIAN: Script = Script(HB_SCRIPT_INSCRIPTIONAL_PARTHIAN);
+    /// The Javanese script (`Java`).
+    pub const JAVANESE: Script = Script(HB_SCRIPT_JAVANESE);
+    /// The Kaithi script (`Kthi`).
+    pub const KAITHI: Script = Script(HB_SCRIPT_KAITHI);
+    /// The Lisu script (`Lisu`).
+    pub const LISU: Script = Script(HB_SCRIPT_LISU);
+    /// The Meetei_Mayek script (`Mtei`).
+    pub const MEETEI_MAYEK: Script = Script(HB_SCRIPT_MEETEI_MAYEK);
+    /// The Old_South_Arabian script (`Sarb`).
+    pub const OLD_SOUTH_ARABIAN: Script = Script(HB_SCRIPT_OLD_SOUTH_ARABIAN);
+    /// The Old_Turkic script (`Orkh`).
+    pub const OLD_TURKIC: Script = Script(HB_SCRIPT_OLD_TURKIC);
+    /// The Samaritan script (`Samr`).
+    pub const SAMARITAN: Script = Script(HB_SCRIPT_SAMARITAN);
+    /// The Tai_Tham script (`Lana`).
+    pub const TAI_THAM: Script = Script(HB_SCRIPT_TAI_THAM);
+    /// The Tai_Viet script (`Tavt`).
+    pub const TAI_VIET: Script = Script(HB_SCRIPT_TAI_VIET);
+    /// The Batak script (`Batk`).
+    pub const BATAK: Script = Script(HB_SCRIPT_BATAK);
+    /// The Brahmi script (`Brah`).
+    pub const BRAHMI: Script = Script(HB_SCRIPT_BRAHMI);
+    /// The Mandaic script (`Mand`).
+    pub const MANDAIC: Script = Script(HB_SCRIPT_MANDAIC);
+    /// The Chakma script (`Cakm`).
+    pub const CHAKMA: Script = Script(HB_SCRIPT_CHAKMA);
+    /// The Meroitic_Cursive script (`Merc`).
+    pub const MEROITIC_CURSIVE: Script = Script(HB_SCRIPT_MEROITIC_CURSIVE);
+    /// The Meroitic_Hieroglyphs script (`Mero`).
+    pub const MEROITIC_HIEROGLYPHS: Script = Script(HB_SCRIPT_MEROITIC_HIEROGLYPHS);
+    /// The Miao script (`Plrd`).
+    pub const MIAO: Script = Script(HB_SCRIPT_MIAO);
+    /// The Sharada script (`Shrd`).
+    pub const SHARADA: Script = Script(HB_SCRIPT_SHARADA);
+    /// The Sora_Sompeng script (`Sora`).
+    pub const SORA_SOMPENG: Script = Script(HB_SCRIPT_SORA_SOMPENG);
+    /// The Takri script (`Takr`).
+    pub const TAKRI: Script = Script(HB_SCRIPT_TAKRI);
+    /// The Bassa_Vah script (`Bass`).
+    pub const BASSA_VAH: Script = Script(HB_SCRIPT_BASSA_VAH);
+    /// The Caucasian_Albanian script (`Aghb`).
+    pub const CAUCASIAN_ALBANIAN: Script = Script(HB_SCRIPT_CAUCASIAN_ALBANIAN);
+    /// The Duployan script (`Dupl`).
+    pub const DUPLOYAN: Script = Script(HB_SCRIPT_DUPLOYAN);
+    /// The Elbasan script (`Elba`).
+    pub const ELBASAN: Script = Script(HB_SCRIPT_ELBASAN);
+    /// The Grantha script (`Gran`).
+    pub const GRANTHA: Script = Script(HB_SCRIPT_GRANTHA);
+    /// The Khojki script (`Khoj`).
+    pub const KHOJKI: Script = Script(HB_SCRIPT_KHOJKI);
+    /// The Khudawadi script (`Sind`).
+    pub const KHUDAWADI: Script = Script(HB_SCRIPT_KHUDAWADI);
+    /// The Linear_A script (`Lina`).
+    pub const LINEAR_A: Script = Script(HB_SCRIPT_LINEAR_A);
+    /// The Mahajani script (`Mahj`).
+    pub const MAHAJANI: Script = Script(HB_SCRIPT_MAHAJANI);
+    /// The Manichaean script (`Mani`).
+    pub const MANICHAEAN: Script = Script(HB_SCRIPT_MANICHAEAN);
+    /// The Mende_Kikakui script (`Mend`).
+    pub const MENDE_KIKAKUI: Script = Script(HB_SCRIPT_MENDE_KIKAKUI);
+    /// The Modi script (`Modi`).
+    pub const MODI: Script = Script(HB_SCRIPT_MODI);
+    /// The Mro script (`Mroo`).
+    pub const MRO: Script = Script(HB_SCRIPT_MRO);
+    /// The Nabataean script (`Nbat`).
+    pub const NABATAEAN: Script = Script(HB_SCRIPT_NABATAEAN);
+    /// The Old_North_Arabian script (`Narb`).
+    pub const OLD_NORTH_ARABIAN: Script = Script(HB_SCRIPT_OLD_NORTH_ARABIAN);
+    /// The Old_Permic script (`Perm`).
+    pub const OLD_PERMIC: Script = Script(HB_SCRIPT_OLD_PERMIC);
+    /// The Pahawh_Hmong script (`Hmng`).
+    pub const PAHAWH_HMONG: Script = Script(HB_SCRIPT_PAHAWH_HMONG);
+    /// The Palmyrene script (`Palm`).
+    pub const PALMYRENE: Script = Script(HB_SCRIPT_PALMYRENE);
+    /// The Pau_Cin_Hau script (`Pauc`).
+    pub const PAU_CIN_HAU: Script = Script(HB_SCRIPT_PAU_CIN_HAU);
+    /// The Psalter_Pahlavi script (`Phlp`).
+    pub const PSALTER_PAHLAVI: Script = Script(HB_SCRIPT_PSALTER_PAHLAVI);
+    /// The Siddham script (`Sidd`).
+    pub const SIDDHAM: Script = Script(HB_SCRIPT_SIDDHAM);
+    /// The Tirhuta script (`Tirh`).
+    pub const TIRHUTA: Script = Script(HB_SCRIPT_TIRHUTA);
+    /// The Warang_Citi script (`Wara`).
+    pub const WARANG_CITI: Script = Script(HB_SCRIPT_WARANG_CITI);
+    /// The Ahom script (`Ahom`).
+    pub const AHOM: Script = Script(HB_SCRIPT_AHOM);
+    /// The Anatolian_Hieroglyphs script (`Hluw`).
+    pub const ANATOLIAN_HIEROGLYPHS: Script = Script(HB_SCRIPT_ANATOLIAN_HIEROGLYPHS);
+    /// The Hatran script (`Hatr`).
+    pub const HATRAN: Script = Script(HB_SCRIPT_HATRAN);
+    /// The Multani script (`Mult`).
+    pub const MULTANI: Script = Script(HB_SCRIPT_MULTANI);
+    /// The Old_Hungarian script (`Hung`).
+    pub const OLD_HUNGARIAN: Script = Script(HB_SCRIPT_OLD_HUNGARIAN);
+    /// The SignWriting script (`Sgnw`).
+    pub const SIGNWRITING: Script = Script(HB_SCRIPT_SIGNWRITING);
+    /// The Adlam script (`Adlm`).
+    pub const ADLAM: Script = Script(HB_SCRIPT_ADLAM);
+    /// The Bhaiksuki script (`Bhks`).
+    pub const BHAIKSUKI: Script = Script(HB_SCRIPT_BHAIKSUKI);
+    /// The Marchen script (`Marc`).
+    pub const MARCHEN: Script = Script(HB_SCRIPT_MARCHEN);
+    /// The Osage script (`Osge`).
+    pub const OSAGE: Script = Script(HB_SCRIPT_OSAGE);
+    /// The Tangut script (`Tang`).
+    pub const TANGUT: Script = Script(HB_SCRIPT_TANGUT);
+    /// The Newa script (`Newa`).
+    pub const NEWA: Script = Script(HB_SCRIPT_NEWA);
+    /// The Masaram_Gondi script (`Gonm`).
+    pub const MASARAM_GONDI: Script = Script(HB_SCRIPT_MASARAM_GONDI);
+    /// The Nushu script (`Nshu`).
+    pub const NUSHU: Script = Script(HB_SCRIPT_NUSHU);
+    /// The Soyombo script (`Soyo`).
+    pub const SOYOMBO: Script = Script(HB_SCRIPT_SOYOMBO);
+    /// The Zanabazar_Square script (`Zanb`).
+    pub const ZANABAZAR_SQUARE: Script = Script(HB_SCRIPT_ZANABAZAR_SQUARE);
+    /// The Dogra script (`Dogr`).
+    pub const DOGRA: Script = Script(HB_SCRIPT_DOGRA);
+    /// The Gunjala_Gondi script (`Gong`).
+    pub const GUNJALA_GONDI: Script = Script(HB_SCRIPT_GUNJALA_GONDI);
+    /// The Hanifi_Rohingya script (`Rohg`).
+    pub const HANIFI_ROHINGYA: Script = Script(HB_SCRIPT_HANIFI_ROHINGYA);
+    /// The Makasar script (`Maka`).
+    pub const MAKASAR: Script = Script(HB_SCRIPT_MAKASAR);
+    /// The Medefaidrin script (`Medf`).
+    pub const MEDEFAIDRIN: Script = Script(HB_SCRIPT_MEDEFAIDRIN);
+    /// The Old_Sogdian script (`Sogo`).
+    pub const OLD_SOGDIAN: Script = Script(HB_SCRIPT_OLD_SOGDIAN);
+    /// The Sogdian script (`Sogd`).
+    pub const SOGDIAN: Script = Script(HB_SCRIPT_SOGDIAN);
+    /// The Elymaic script (`Elym`).
+    pub const ELYMAIC: Script = Script(HB_SCRIPT_ELYMAIC);
+    /// The Nandinagari script (`Nand`).
+    pub const NANDINAGARI: Script = Script(HB_SCRIPT_NANDINAGARI);
+    /// The Nyiakeng_Puachue_Hmong script (`Hmnp`).
+    pub const NYIAKENG_PUACHUE_HMONG: Script = Script(HB_SCRIPT_NYIAKENG_PUACHUE_HMONG);
+    /// The Wancho script (`Wcho`).
+    pub const WANCHO: Script = Script(HB_SCRIPT_WANCHO);
+    /// The Chorasmian script (`Chrs`).
+    pub const CHORASMIAN: Script = Script(HB_SCRIPT_CHORASMIAN);
+    /// The Dives_Akuru script (`Diak`).
+    pub const DIVES_AKURU: Script = Script(HB_SCRIPT_DIVES_AKURU);
+    /// The Khitan_Small_Script script (`Kits`).
+    pub const KHITAN_SMALL_SCRIPT: Script = Script(HB_SCRIPT_KHITAN_SMALL_SCRIPT);
+    /// The Yezidi script (`Yezi`).
+    pub const YEZIDI: Script = Script(HB_SCRIPT_YEZIDI);
+    /// The Cypro_Minoan script (`Cpmn`).
+    pub const CYPRO_MINOAN: Script = Script(HB_SCRIPT_CYPRO_MINOAN);
+    /// The Old_Uyghur script (`Ougr`).
+    pub const OLD_UYGHUR: Script = Script(HB_SCRIPT_OLD_UYGHUR);
+    /// The Tangsa script (`Tnsa`).
+    pub const TANGSA: Script = Script(HB_SCRIPT_TANGSA);
+    /// The Toto script (`Toto`).
+    pub const TOTO: Script = Script(HB_SCRIPT_TOTO);
+    /// The Vithkuqi script (`Vith`).
+    pub const VITHKUQI: Script = Script(HB_SCRIPT_VITHKUQI);
+    /// The Mathematical notation script (`Zmth`).
+    pub const MATH: Script = Script(HB_SCRIPT_MATH);
+    /// The Kawi script (`Kawi`).
+    pub const KAWI: Script = Script(HB_SCRIPT_KAWI);
+    /// The Nag_Mundari script (`Nagm`).
+    pub const NAG_MUNDARI: Script = Script(HB_SCRIPT_NAG_MUNDARI);
+    /// The Garay script (`Gara`).
+    pub const GARAY: Script = Script(HB_SCRIPT_GARAY);
+    /// The Gurung_Khema script (`Gukh`).
+    pub const GURUNG_KHEMA: Script = Script(HB_SCRIPT_GURUNG_KHEMA);
+    /// The Kirat_Rai script (`Krai`).
+    pub const KIRAT_RAI: Script = Script(HB_SCRIPT_KIRAT_RAI);
+    /// The Ol_Onal script (`Onao`).
+    pub const OL_ONAL: Script = Script(HB_SCRIPT_OL_ONAL);
+    /// The Sunuwar script (`Sunu`).
+    pub const SUNUWAR: Script = Script(HB_SCRIPT_SUNUWAR);
+    /// The Todhri script (`Todr`).
+    pub const TODHRI: Script = Script(HB_SCRIPT_TODHRI);
+    /// The Tulu_Tigalari script (`Tutg`).
+    pub const TULU_TIGALARI: Script = Script(HB_SCRIPT_TULU_TIGALARI);
+}
+
+/// All scripts known to HarfBuzz with their Unicode names.
+pub(crate) const SCRIPT_NAMES: &[(Script, &str)] = &[
+    (Script::COMMON, "Common"),
+    (Script::INHERITED, "Inherited"),
+    (Script::UNKNOWN, "Unknown"),
+    (Script::ARABIC, "Arabic"),
+    (Script::ARMENIAN, "Armenian"),
+    (Script::BENGALI, "Bengali"),
+    (Script::CYRILLIC, "Cyrillic"),
+    (Script::DEVANAGARI, "Devanagari"),
+    (Script::GEORGIAN, "Georgian"),
+    (Script::GREEK, "Greek"),
+    (Script::GUJARATI, "Gujarati"),
+    (Script::GURMUKHI, "Gurmukhi"),
+    (Script::HANGUL, "Hangul"),
+    (Script::HAN, "Han"),
+    (Script::HEBREW, "Hebrew"),
+    (Script::HIRAGANA, "Hiragana"),
+    (Script::KANNADA, "Kannada"),
+    (Script::KATAKANA, "Katakana"),
+    (Script::LAO, "Lao"),
+    (Script::LATIN, "Latin"),
+    (Script::MALAYALAM, "Malayalam"),
+    (Script::ORIYA, "Oriya"),
+    (Script::TAMIL, "Tamil"),
+    (Script::TELUGU, "Telugu"),
+    (Script::THAI, "Thai"),
+    (Script::TIBETAN, "Tibetan"),
+    (Script::BOPOMOFO, "Bopomofo"),
+    (Script::BRAILLE, "Braille"),
+    (Script::CANADIAN_SYLLABICS, "Canadian_Syllabics"),
+    (Script::CHEROKEE, "Cherokee"),
+    (Script::ETHIOPIC, "Ethiopic"),
+    (Script::KHMER, "Khmer"),
+    (Script::MONGOLIAN, "Mongolian"),
+    (Script::MYANMAR, "Myanmar"),
+    (Script::OGHAM, "Ogham"),
+    (Script::RUNIC, "Runic"),
+    (Script::SINHALA, "Sinhala"),
+    (Script::SYRIAC, "Syriac"),
+    (Script::THAANA, "Thaana"),
+    (Script::YI, "Yi"),
+    (Script::DESERET, "Deseret"),
+    (Script::GOTHIC, "Gothic"),
+    (Script::OLD_ITALIC, "Old_Italic"),
+    (Script::BUHID, "Buhid"),
+    (Script::HANUNOO, "Hanunoo"),
+    (Script::TAGALOG, "Tagalog"),
+    (Script::TAGBANWA, "Tagbanwa"),
+    (Script::CYPRIOT, "Cypriot"),
+    (Script::LIMBU, "Limbu"),
+    (Script::LINEAR_B, "Linear_B"),
+    (Script::OSMANYA, "Osmanya"),
+    (Script::SHAVIAN, "Shavian"),
+    (Script::TAI_LE, "Tai_Le"),
+    (Script::UGARITIC, "Ugaritic"),
+    (Script::BUGINESE, "Buginese"),
+    (Script::COPTIC, "Coptic"),
+    (Script::GLAGOLITIC, "Glagolitic"),
+    (Script::KHAROSHTHI, "Kharoshthi"),
+    (Script::NEW_TAI_LUE, "New_Tai_Lue"),
+    (Script::OLD_PERSIAN, "Old_Persian"),
+    (Script::SYLOTI_NAGRI, "Syloti_Nagri"),
+    (Script::TIFINAGH, "Tifinagh"),
+    (Script::BALINESE, "Balinese"),
+    (Script::CUNEIFORM, "Cuneiform"),
+    (Script::NKO, "Nko"),
+    (Script::PHAGS_PA, "Phags_Pa"),
+    (Script::PHOENICIAN, "Phoenician"),
+    (Script::CARIAN, "Carian"),
+    (Script::CHAM, "Cham"),
+    (Script::KAYAH_LI, "Kayah_Li"),
+    (Script::LEPCHA, "Lepcha"),
+    (Script::LYCIAN, "Lycian"),
+    (Script::LYDIAN, "Lydian"),
+    (Script::OL_CHIKI, "Ol_Chiki"),
+    (Script::REJANG, "Rejang"),
+    (Script::SAURASHTRA, "Saurashtra"),
+    (Script::SUNDANESE, "Sundanese"),
+    (Script::VAI, "Vai"),
+    (Script::AVESTAN, "Avestan"),
+    (Script::BAMUM, "Bamum"),
+    (Script::EGYPTIAN_HIEROGLYPHS, "Egyptian_Hieroglyphs"),
+    (Script::IMPERIAL_ARAMAIC, "Imperial_Aramaic"),
+    (Script::INSCRIPTIONAL_PAHLAVI, "Inscriptional_Pahlavi"),
+    (Script::INSCRIPTIONAL_PARTHIAN, "Inscriptional_Parthian"),
+    (Script::JAVANESE, "Javanese"),
+    (Script::KAITHI, "Kaithi"),
+    (Script::LISU, "Lisu"),
+    (Script::MEETEI_MAYEK, "Meetei_Mayek"),
+    (Script::OLD_SOUTH_ARABIAN, "Old_South_Arabian"),
+    (Script::OLD_TURKIC, "Old_Turkic"),
+    (Script::SAMARITAN, "Samaritan"),
+    (Script::TAI_THAM, "Tai_Tham"),
+    (Script::TAI_VIET, "Tai_Viet"),
+    (Script::BATAK, "Batak"),
+    (Script::BRAHMI, "Brahmi"),
+    (Script::MANDAIC, "Mandaic"),
+    (Script::CHAKMA, "Chakma"),
+    (Script::MEROITIC_CURSIVE, "Meroitic_Cursive"),
+    (Script::MEROITIC_HIEROGLYPHS, "Meroitic_Hieroglyphs"),
+    (Script::MIAO, "Miao"),
+    (Script::SHARADA, "Sharada"),
+    (Script::SORA_SOMPENG, "Sora_Sompeng"),
+    (Script::TAKRI, "Takri"),
+    (Script::BASSA_VAH, "Bassa_Vah"),
+    (Script::CAUCASIAN_ALBANIAN, "Caucasian_Albanian"),
+    (Script::DUPLOYAN, "Duployan"),
+    (Script::ELBASAN, "Elbasan"),
+    (Script::GRANTHA, "Grantha"),
+    (Script::KHOJKI, "Khojki"),
+    (Script::KHUDAWADI, "Khudawadi"),
+    (Script::LINEAR_A, "Linear_A"),
+    (Script::MAHAJANI, "Mahajani"),
+    (Script::MANICHAEAN, "Manichaean"),
+    (Script::MENDE_KIKAKUI, "Mende_Kikakui"),
+    (Script::MODI, "Modi"),
+    (Script::MRO, "Mro"),
+    (Script::NABATAEAN, "Nabataean"),
+    (Script::OLD_NORTH_ARABIAN, "Old_North_Arabian"),
+    (Script::OLD_PERMIC, "Old_Permic"),
+    (Script::PAHAWH_HMONG, "Pahawh_Hmong"),
+    (Script::PALMYRENE, "Palmyrene"),
+    (Script::PAU_CIN_HAU, "Pau_Cin_Hau"),
+    (Script::PSALTER_PAHLAVI, "Psalter_Pahlavi"),
+    (Script::SIDDHAM, "Siddham"),
+    (Script::TIRHUTA, "Tirhuta"),
+    (Script::WARANG_CITI, "Warang_Citi"),
+    (Script::AHOM, "Ahom"),
+    (Script::ANATOLIAN_HIEROGLYPHS, "Anatolian_Hieroglyphs"),
+    (Script::HATRAN, "Hatran"),
+    (Script::MULTANI, "Multani"),
+    (Script::OLD_HUNGARIAN, "Old_Hungarian"),
+    (Script::SIGNWRITING, "SignWriting"),
+    (Script::ADLAM, "Adlam"),
+    (Script::BHAIKSUKI, "Bhaiksuki"),
+    (Script::MARCHEN, "Marchen"),
+    (Script::OSAGE, "Osage"),
+    (Script::TANGUT, "Tangut"),
+    (Script::NEWA, "Newa"),
+    (Script::MASARAM_GONDI, "Masaram_Gondi"),
+    (Script::NUSHU, "Nushu"),
+    (Script::SOYOMBO, "Soyombo"),
+    (Script::ZANABAZAR_SQUARE, "Zanabazar_Square"),
+    (Script::DOGRA, "Dogra"),
+    (Script::GUNJALA_GONDI, "Gunjala_Gondi"),
+    (Script::HANIFI_ROHINGYA, "Hanifi_Rohingya"),
+    (Script::MAKASAR, "Makasar"),
+    (Script::MEDEFAIDRIN, "Medefaidrin"),
+    (Script::OLD_SOGDIAN, "Old_Sogdian"),
+    (Script::SOGDIAN, "Sogdian"),
+    (Script::ELYMAIC, "Elymaic"),
+    (Script::NANDINAGARI, "Nandinagari"),
+    (Script::NYIAKENG_PUACHUE_HMONG, "Nyiakeng_Puachue_Hmong"),
+    (Script::WANCHO, "Wancho"),
+    (Script::CHORASMIAN, "Chorasmian"),
+    (Script::DIVES_AKURU, "Dives_Akuru"),
+    (Script::KHITAN_SMALL_SCRIPT, "Khitan_Small_Script"),
+    (Script::YEZIDI, "Yezidi"),
+    (Script::CYPRO_MINOAN, "Cypro_Minoan"),
+    (Script::OLD_UYGHUR, "Old_Uyghur"),
+    (Script::TANGSA, "Tangsa"),
+    (Script::TOTO, "Toto"),
+    (Script::VITHKUQI, "Vithkuqi"),
+    (Script::MATH, "Mathematical notation"),
+    (Script::KAWI, "Kawi"),
+    (Script::NAG_MUNDARI, "Nag_Mundari"),
+    (Script::GARAY, "Garay"),
+    (Script::GURUNG_KHEMA, "Gurung_Khema"),
+    (Script::KIRAT_RAI, "Kirat_Rai"),
+    (Script::OL_ONAL, "Ol_Onal"),
+    (Script::SUNUWAR, "Sunuwar"),
+    (Script::TODHRI, "Todhri"),
+    (Script::TULU_TIGALARI, "Tulu_Tigalari"),
+];
@@ -0,0 +1,237 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::RangeInclusive;
+use std::ptr::NonNull;
+
+use crate::bindings::{
+    hb_set_add, hb_set_add_range, hb_set_clear, hb_set_copy, hb_set_create, hb_set_del,
+    hb_set_destroy, hb_set_get_population, hb_set_has, hb_set_is_empty, hb_set_is_equal,
+    hb_set_next, hb_set_reference, hb_set_t, hb_set_union,
+};
+use crate::buffer::GlyphBuffer;
+use crate::common::{HarfbuzzObject, Owned};
+
+// `HB_SET_VALUE_INVALID` from `hb-set.h`, which is not part of the bindings.
+const SET_VALUE_INVALID: u32 = u32::MAX;
+
+/// A wrapper around `hb_set_t`, a set of integers like glyph ids or
+/// codepoints.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::Set;
+///
+/// let mut set: harfbuzz_rs::Owned<Set> = vec![3, 1, 2].into_iter().collect();
+/// set.insert_range(10..=12);
+/// assert!(set.contains(11));
+/// assert_eq!(set.len(), 6);
+/// assert_eq!(set.iter().collect::<Vec<_>>(), vec![1, 2, 3, 10, 11, 12]);
+/// ```
+pub struct Set {
+    raw: NonNull<hb_set_t>,
+}
+
+impl Set {
+    /// Creates a new, empty set.
+    pub fn new() -> Owned<Set> {
+        unsafe { Owned::from_raw(hb_set_create()) }
+    }
+
+    /// Creates a copy of the set that can be modified independently.
+    pub fn copy(&self) -> Owned<Set> {
+        unsafe { Owned::from_raw(hb_set_copy(self.as_raw())) }
+    }
+
+    /// Returns the number of elements in the set.
+    pub fn len(&self) -> usize {
+        unsafe { hb_set_get_population(self.as_raw()) as usize }
+    }
+
+    /// Returns `true` if the set contains no elements.
+    pub fn is_empty(&self) -> bool {
+        unsafe { hb_set_is_empty(self.as_raw()) == 1 }
+    }
+
+    /// Returns `true` if the set contains `value`.
+    pub fn contains(&self, value: u32) -> bool {
+        unsafe { hb_set_has(self.as_raw(), value) == 1 }
+    }
+
+    /// Adds `value` to the set.
+    pub fn insert(&mut self, value: u32) {
+        unsafe { hb_set_add(self.as_raw(), value) }
+    }
+
+    /// Adds all values in `range` to the set.
+    pub fn insert_range(&mut self, range: RangeInclusive<u32>) {
+        unsafe { hb_set_add_range(self.as_raw(), *range.start(), *range.end()) }
+    }
+
+    /// Removes `value` from the set.
+    pub fn remove(&mut self, value: u32) {
+        unsafe { hb_set_del(self.as_raw(), value) }
+    }
+
+    /// Removes all elements from the set.
+    pub fn clear(&mut self) {
+        unsafe { hb_set_clear(self.as_raw()) }
+    }
+
+    /// Adds all elements of `other` to the set.
+    pub fn union(&mut self, other: &Set) {
+        unsafe { hb_set_union(self.as_raw(), other.as_raw()) }
+    }
+
+    /// Returns an iterator over the elements of the set in ascending order.
+    pub fn iter(&self) -> SetIter<'_> {
+        SetIter {
+            set: self,
+            current: SET_VALUE_INVALID,
+        }
+    }
+}
+
+impl fmt::Debug for Set {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for Set {
+    fn eq(&self, other: &Set) -> bool {
+        unsafe { hb_set_is_equal(self.as_raw(), other.as_raw()) == 1 }
+    }
+}
+
+impl Eq for Set {}
+
+impl Extend<u32> for Set {
+    fn extend<I: IntoIterator<Item = u32>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl FromIterator<u32> for Owned<Set> {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Owned<Set> {
+        let mut set = Set::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a> IntoIterator for &'a Set {
+    type Item = u32;
+    type IntoIter = SetIter<'a>;
+
+    fn into_iter(self) -> SetIter<'a> {
+        self.iter()
+    }
+}
+
+unsafe impl HarfbuzzObject for Set {
+    type Raw = hb_set_t;
+
+    unsafe fn from_raw(raw: *const hb_set_t) -> Self {
+        Set {
+            raw: NonNull::new(raw as *mut _).unwrap(),
+        }
+    }
+
+    fn as_raw(&self) -> *mut Self::Raw {
+        self.raw.as_ptr()
+    }
+
+    unsafe fn reference(&self) {
+        hb_set_reference(self.as_raw());
+    }
+
+    unsafe fn dereference(&self) {
+        hb_set_destroy(self.as_raw());
+    }
+}
+
+unsafe impl Send for Set {}
+unsafe impl Sync for Set {}
+
+/// An iterator over the elements of a `Set` in ascending order.
+///
+/// This is obtained through the `Set::iter` method.
+#[derive(Debug, Clone)]
+pub struct SetIter<'a> {
+    set: &'a Set,
+    current: u32,
+}
+
+impl<'a> Iterator for SetIter<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if unsafe { hb_set_next(self.set.as_raw(), &mut self.current) } == 1 {
+            Some(self.current)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the set of distinct glyphs in `runs`, e.g. to rasterize the
+/// glyphs missing from a glyph atlas before rendering the runs.
+///
+/// Glyph ids are only meaningful for a particular font, so all runs must be
+/// shaped with the same font (or with fonts of the same face). Collect the
+/// glyphs of runs shaped with different fonts separately. To also preload
+/// the glyphs that other features could substitute for these glyphs, pass
+/// the result to [`Face::closure_glyphs()`](crate::Face::closure_glyphs).
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let first = shape(&font, UnicodeBuffer::new().add_str("abba"), &[]);
+/// let second = shape(&font, UnicodeBuffer::new().add_str("cab"), &[]);
+///
+/// let glyphs = collect_unique_glyphs(vec![&first, &second]);
+/// assert_eq!(glyphs.len(), 3);
+///
+/// // the glyphs needed if small caps are enabled later on
+/// let preload = font.face().closure_glyphs(&glyphs, &[Tag::from(b"smcp")]);
+/// assert!(preload.len() >= glyphs.len());
+/// ```
+pub fn collect_unique_glyphs<'a>(runs: impl IntoIterator<Item = &'a GlyphBuffer>) -> Owned<Set> {
+    let mut glyphs = Set::new();
+    for run in runs {
+        glyphs.extend(run.get_glyph_infos().iter().map(|info| info.codepoint));
+    }
+    glyphs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set() {
+        let mut set = Set::new();
+        assert!(set.is_empty());
+        set.extend(vec![5, 1, 5]);
+        set.insert_range(7..=8);
+        set.remove(1);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![5, 7, 8]);
+        assert_eq!(format!("{:?}", *set), "{5, 7, 8}");
+
+        let mut copy = set.copy();
+        copy.insert(100);
+        assert_eq!(set.len(), 3);
+        assert_ne!(*copy, *set);
+        set.union(&copy);
+        assert_eq!(*copy, *set);
+        set.clear();
+        assert!(set.is_empty());
+    }
+}
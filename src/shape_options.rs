@@ -0,0 +1,258 @@
+use crate::bindings::hb_ot_font_set_funcs;
+use crate::buffer::{BufferFlags, ClusterLevel, GlyphBuffer, UnicodeBuffer};
+use crate::common::{Direction, HarfbuzzObject, Language, Script, Shared, Tag};
+use crate::font::Font;
+use crate::post_shape::{GlyphRunMut, PostShapePass};
+use crate::{shape, Feature, Variation};
+
+use std::fmt;
+use std::sync::Arc;
+
+/// Bundles the settings for shaping text in a single value.
+///
+/// Shaping text correctly requires setting up a [`UnicodeBuffer`] with the
+/// right segment properties and flags, applying variations to a [`Font`] and
+/// passing the features to [`shape()`]. A `ShapeOptions` collects all of
+/// these settings with a builder API and applies them in one call to
+/// [`shape()`](ShapeOptions::shape). Settings that are not given are guessed
+/// from the text or left at their defaults, like `shape` does.
+///
+/// The options can be reused to shape many texts the same way.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+///
+/// let options = ShapeOptions::new()
+///     .direction(Direction::Ltr)
+///     .script(Script::LATIN)
+///     .language("en".parse().unwrap())
+///     .cluster_level(ClusterLevel::Characters)
+///     .feature(Feature::new(b"kern", 0, ..))
+///     .variation(Variation::new(b"wght", 700.0));
+///
+/// let output = options.shape(&font, "Hello");
+/// assert_eq!(output.len(), 5);
+/// ```
+#[derive(Clone, Default)]
+pub struct ShapeOptions {
+    direction: Option<Direction>,
+    script: Option<Script>,
+    language: Option<Language>,
+    cluster_level: ClusterLevel,
+    flags: BufferFlags,
+    features: Vec<Feature>,
+    variations: Vec<Variation>,
+    passes: Vec<Arc<dyn PostShapePass + Send + Sync>>,
+}
+
+impl fmt::Debug for ShapeOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShapeOptions")
+            .field("direction", &self.direction)
+            .field("script", &self.script)
+            .field("language", &self.language)
+            .field("cluster_level", &self.cluster_level)
+            .field("flags", &self.flags)
+            .field("features", &self.features)
+            .field("variations", &self.variations)
+            .field("post_shape_passes", &self.passes.len())
+            .finish()
+    }
+}
+
+impl ShapeOptions {
+    /// Creates options that guess the segment properties from the text and
+    /// apply no additional features or variations.
+    pub fn new() -> ShapeOptions {
+        ShapeOptions::default()
+    }
+
+    /// Sets the text direction instead of guessing it from the script.
+    pub fn direction(mut self, direction: Direction) -> ShapeOptions {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Sets the script instead of guessing it from the text.
+    pub fn script(mut self, script: Script) -> ShapeOptions {
+        self.script = Some(script);
+        self
+    }
+
+    /// Sets the language instead of using the language of the current
+    /// locale.
+    pub fn language(mut self, language: Language) -> ShapeOptions {
+        self.language = Some(language);
+        self
+    }
+
+    /// Sets the cluster level. See [`UnicodeBuffer::set_cluster_level()`].
+    pub fn cluster_level(mut self, cluster_level: ClusterLevel) -> ShapeOptions {
+        self.cluster_level = cluster_level;
+        self
+    }
+
+    /// Sets the buffer flags. See [`UnicodeBuffer::set_flags()`].
+    pub fn flags(mut self, flags: BufferFlags) -> ShapeOptions {
+        self.flags = flags;
+        self
+    }
+
+    /// Adds a feature to apply.
+    pub fn feature(mut self, feature: Feature) -> ShapeOptions {
+        self.features.push(feature);
+        self
+    }
+
+    /// Adds several features to apply.
+    pub fn features(mut self, features: impl IntoIterator<Item = Feature>) -> ShapeOptions {
+        self.features.extend(features);
+        self
+    }
+
+    /// Adds a variation to apply to the font.
+    pub fn variation(mut self, variation: Variation) -> ShapeOptions {
+        self.variations.push(variation);
+        self
+    }
+
+    /// Adds several variations to apply to the font.
+    pub fn variations(mut self, variations: impl IntoIterator<Item = Variation>) -> ShapeOptions {
+        self.variations.extend(variations);
+        self
+    }
+
+    /// Adds a pass that modifies the glyphs after shaping.
+    ///
+    /// The passes run in the order they were added. See [`PostShapePass`].
+    pub fn post_shape_pass(
+        mut self,
+        pass: impl PostShapePass + Send + Sync + 'static,
+    ) -> ShapeOptions {
+        self.passes.push(Arc::new(pass));
+        self
+    }
+
+    /// Returns the features that are applied.
+    pub fn get_features(&self) -> &[Feature] {
+        &self.features
+    }
+
+    /// Returns the variations that are applied.
+    pub fn get_variations(&self) -> &[Variation] {
+        &self.variations
+    }
+
+    /// Creates a buffer containing `text` with the segment properties, cluster
+    /// level and flags of the options.
+    pub fn buffer(&self, text: &str) -> UnicodeBuffer {
+        let mut buffer = UnicodeBuffer::new()
+            .add_str(text)
+            .set_cluster_level(self.cluster_level)
+            .set_flags(self.flags);
+        if let Some(direction) = self.direction {
+            buffer = buffer.set_direction(direction);
+        }
+        if let Some(script) = self.script {
+            buffer = buffer.set_script(Tag(script.0));
+        }
+        if let Some(language) = self.language {
+            buffer = buffer.set_language(language);
+        }
+        buffer
+    }
+
+    /// Shapes `text` with `font` according to the options.
+    ///
+    /// If variations are set, the text is shaped with a sub font of `font`
+    /// (see [`Font::create_sub_font`]) with the variations applied, so `font`
+    /// itself is left unchanged. The sub font inherits the scale, ppem and
+    /// synthetic slant and emboldening of `font`, but measures the glyphs with
+    /// the OpenType font functions instead of custom font functions of `font`
+    /// so that the variations take effect.
+    ///
+    /// The post-shaping passes run with the font the text was shaped with.
+    pub fn shape(&self, font: &Font<'_>, text: &str) -> GlyphBuffer {
+        let buffer = self.buffer(text);
+        if self.variations.is_empty() {
+            let mut output = shape(font, buffer, &self.features);
+            self.run_passes(font, text, &mut output);
+            return output;
+        }
+        // the sub font keeps a reference to `font`, so it does not outlive it
+        let parent: Shared<Font<'_>> = unsafe { Shared::from_raw_ref(font.as_raw()) };
+        let mut varied = Font::create_sub_font(parent);
+        // the default functions of a sub font forward to the parent, which
+        // would measure the glyphs without the variations
+        unsafe { hb_ot_font_set_funcs(varied.as_raw()) };
+        varied.set_variations(&self.variations);
+        let mut output = shape(&varied, buffer, &self.features);
+        self.run_passes(&varied, text, &mut output);
+        output
+    }
+
+    fn run_passes(&self, font: &Font<'_>, text: &str, output: &mut GlyphBuffer) {
+        for pass in &self.passes {
+            pass.process(&mut GlyphRunMut::new(font, text, output));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Face;
+
+    #[test]
+    fn test_shape_options_match_manual_setup() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let mut font = Font::new(face);
+        font.set_scale(2048, 2048);
+        let features = [Feature::new(b"liga", 0, ..)];
+        let options = ShapeOptions::new()
+            .direction(Direction::Rtl)
+            .cluster_level(ClusterLevel::MonotoneCharacters)
+            .features(features.iter().copied())
+            .variation(Variation::new(b"wght", 900.0));
+
+        let output = options.shape(&font, "fix");
+        let props = output.get_segment_properties();
+        assert_eq!(props.direction, Direction::Rtl);
+        assert_eq!(props.script, Script::LATIN);
+
+        let buffer = UnicodeBuffer::new()
+            .add_str("fix")
+            .set_direction(Direction::Rtl)
+            .set_cluster_level(ClusterLevel::MonotoneCharacters);
+        let regular = shape(&font, buffer.clone(), &features);
+        assert_ne!(output.to_string(), regular.to_string());
+        font.set_variations(options.get_variations());
+        let expected = shape(&font, buffer, &features);
+        assert_eq!(output.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_post_shape_passes_run_in_order() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let options = ShapeOptions::new()
+            .variation(Variation::new(b"wght", 900.0))
+            .post_shape_pass(|run: &mut GlyphRunMut<'_>| {
+                assert_eq!(run.text(), "ab");
+                assert_eq!(run.font().variation(b"wght"), Some(900.0));
+                run.buffer_mut().truncate(1);
+            })
+            .post_shape_pass(|run: &mut GlyphRunMut<'_>| {
+                assert_eq!(run.len(), 1);
+                run.get_glyph_positions_mut()[0].x_offset = 7;
+            });
+        let output = options.clone().shape(&font, "ab");
+        assert_eq!(output.x_offsets().collect::<Vec<_>>(), vec![7]);
+        assert!(format!("{:?}", options).contains("post_shape_passes: 2"));
+    }
+}
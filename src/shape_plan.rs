@@ -0,0 +1,503 @@
+use std::os::raw::{c_char, c_uint, c_void};
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::bindings::{
+    hb_shape_plan_create2, hb_shape_plan_create_cached2, hb_shape_plan_destroy,
+    hb_shape_plan_execute, hb_shape_plan_get_user_data, hb_shape_plan_reference,
+    hb_shape_plan_set_user_data, hb_shape_plan_t,
+};
+use crate::buffer::{GlyphBuffer, SegmentProperties, UnicodeBuffer};
+use crate::common::{HarfbuzzObject, Owned, Shared, UserDataKey};
+use crate::font::Font;
+use crate::Feature;
+
+/// A wrapper around `hb_shape_plan_t`.
+///
+/// A shape plan contains the work HarfBuzz does before shaping a buffer, like
+/// selecting the shaper and collecting the lookups of the features for the
+/// segment properties. [`shape()`](crate::shape) creates a plan for every
+/// call (unless a cache is enabled with [`set_shape_plan_cache_capacity()`]),
+/// so code that shapes many runs with the same properties can save that work
+/// by creating the plan once and executing it repeatedly.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let buffer = UnicodeBuffer::new().add_str("Hello").guess_segment_properties();
+/// let plan = ShapePlan::new(&font, &buffer.get_segment_properties(), &[]);
+///
+/// let output = plan.execute(&font, buffer, &[]);
+/// assert_eq!(output.len(), 5);
+/// ```
+#[derive(Debug)]
+pub struct ShapePlan {
+    raw: NonNull<hb_shape_plan_t>,
+}
+
+static DETERMINISTIC_SHAPING: AtomicBool = AtomicBool::new(false);
+
+// A null terminated list of shaper names.
+struct ShaperList([*const c_char; 2]);
+
+// The list only points to static strings.
+unsafe impl Sync for ShaperList {}
+
+static OT_SHAPER: ShaperList = ShaperList([b"ot\0".as_ptr() as *const c_char, std::ptr::null()]);
+
+// The shapers HarfBuzz may use, `null` meaning all shapers.
+pub(crate) fn shaper_list() -> *const *const c_char {
+    if deterministic_shaping() {
+        OT_SHAPER.0.as_ptr()
+    } else {
+        std::ptr::null()
+    }
+}
+
+/// Enables or disables deterministic shaping.
+///
+/// HarfBuzz can be built with platform shapers (CoreText, Uniscribe or
+/// DirectWrite) that it prefers over its own OpenType shaper, and it uses the
+/// language of the current locale for buffers without a language. Both make
+/// the shaping results depend on the machine. In deterministic mode,
+/// [`shape()`](crate::shape), [`ShapePlan`] and the functions based on them
+/// always use the OpenType shaper of HarfBuzz and shape buffers without a
+/// language with the default language system of the font instead of guessing
+/// the language from the locale. The results then only depend on the font,
+/// the text, the features and the version of HarfBuzz, so machines with
+/// different operating systems produce identical output.
+///
+/// The mode is global and disabled by default.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+///
+/// set_deterministic_shaping(true);
+/// let output = shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]);
+/// assert_eq!(output.get_segment_properties().language, Language(std::ptr::null()));
+///
+/// // the output only depends on the inputs
+/// let json = |output: &GlyphBuffer| {
+///     output.display_with(Some(&font), SerializeFormat::Json, SerializeFlags::default()).to_string()
+/// };
+/// let other_font = Font::new(Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap());
+/// let again = shape(&other_font, UnicodeBuffer::new().add_str("Hello"), &[]);
+/// assert_eq!(json(&output), json(&again));
+/// assert_eq!(output.get_segment_properties(), again.get_segment_properties());
+///
+/// // an explicit language is kept
+/// let german = UnicodeBuffer::new().add_str("Hello").set_language("de".parse().unwrap());
+/// let output = shape(&font, german, &[]);
+/// assert_eq!(output.get_segment_properties().language.to_string(), "de");
+/// # set_deterministic_shaping(false);
+/// ```
+pub fn set_deterministic_shaping(enabled: bool) {
+    DETERMINISTIC_SHAPING.store(enabled, Ordering::SeqCst);
+}
+
+/// Returns `true` if deterministic shaping is enabled (see
+/// [`set_deterministic_shaping()`]).
+pub fn deterministic_shaping() -> bool {
+    DETERMINISTIC_SHAPING.load(Ordering::SeqCst)
+}
+
+// The face and segment properties a plan was created for. HarfBuzz asserts
+// that they match the font and buffer a plan is executed with, so they are
+// stored as user data of the plan and checked before executing it.
+//
+// The plan does not keep its face alive, so the face is identified by its id,
+// which unlike its address is not reused by a later face.
+struct PlanTarget {
+    face: u64,
+    props: SegmentProperties,
+}
+
+static PLAN_TARGET: UserDataKey<PlanTarget> = UserDataKey::new();
+
+// Stores the target of `plan` unless it is already set, which is the case for
+// plans from the cache of the face.
+fn set_plan_target(plan: *mut hb_shape_plan_t, font: &Font<'_>, props: &SegmentProperties) {
+    extern "C" fn destroy(data: *mut c_void) {
+        unsafe { drop(Box::from_raw(data as *mut PlanTarget)) };
+    }
+    let data = Box::into_raw(Box::new(PlanTarget {
+        face: font.face().id(),
+        props: *props,
+    }));
+    let ok = unsafe {
+        hb_shape_plan_set_user_data(plan, PLAN_TARGET.as_raw(), data as *mut _, Some(destroy), 0)
+            == 1
+    };
+    if !ok {
+        // HarfBuzz does not call `destroy` if it did not store the data
+        unsafe { drop(Box::from_raw(data)) };
+    }
+}
+
+impl ShapePlan {
+    /// Creates a plan for shaping text with the segment properties `props` and
+    /// the user `features` with `font`.
+    ///
+    /// The plan can be executed with every font of the same face and the same
+    /// variation coordinates as `font`. In deterministic mode (see
+    /// [`set_deterministic_shaping()`]) the plan uses the OpenType shaper.
+    pub fn new(font: &Font<'_>, props: &SegmentProperties, features: &[Feature]) -> Owned<Self> {
+        let coords = font.var_coords_normalized();
+        let plan = unsafe {
+            hb_shape_plan_create2(
+                font.face().as_raw(),
+                &props.into_raw(),
+                features.as_ptr() as *const _,
+                features.len() as c_uint,
+                coords.as_ptr(),
+                coords.len() as c_uint,
+                shaper_list(),
+            )
+        };
+        set_plan_target(plan, font, props);
+        unsafe { Owned::from_raw(plan) }
+    }
+
+    /// Like [`new()`](Self::new) but reuses a plan from the cache HarfBuzz
+    /// keeps in each face.
+    ///
+    /// HarfBuzz only caches plans whose features apply to the whole buffer.
+    pub fn new_cached(
+        font: &Font<'_>,
+        props: &SegmentProperties,
+        features: &[Feature],
+    ) -> Shared<Self> {
+        let coords = font.var_coords_normalized();
+        let plan = unsafe {
+            hb_shape_plan_create_cached2(
+                font.face().as_raw(),
+                &props.into_raw(),
+                features.as_ptr() as *const _,
+                features.len() as c_uint,
+                coords.as_ptr(),
+                coords.len() as c_uint,
+                shaper_list(),
+            )
+        };
+        set_plan_target(plan, font, props);
+        unsafe { Shared::from_raw_owned(plan) }
+    }
+
+    /// Shapes the contents of `buffer` with `font` according to the plan.
+    ///
+    /// `features` must be the features the plan was created with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the face of `font` or the segment properties of `buffer`
+    /// differ from those the plan was created for.
+    pub fn execute(
+        &self,
+        font: &Font<'_>,
+        buffer: UnicodeBuffer,
+        features: &[Feature],
+    ) -> GlyphBuffer {
+        let target = unsafe {
+            (hb_shape_plan_get_user_data(self.as_raw(), PLAN_TARGET.as_raw()) as *const PlanTarget)
+                .as_ref()
+        };
+        // plans without target are only created by HarfBuzz when it runs out
+        // of memory and do not shape at all
+        if let Some(target) = target {
+            assert!(
+                target.face == font.face().id(),
+                "the font must use the face the shape plan was created for"
+            );
+            assert_eq!(
+                target.props,
+                buffer.get_segment_properties(),
+                "the buffer must have the segment properties the shape plan was created for"
+            );
+        }
+        unsafe {
+            hb_shape_plan_execute(
+                self.as_raw(),
+                font.as_raw(),
+                buffer.0.as_raw(),
+                features.as_ptr() as *const _,
+                features.len() as c_uint,
+            )
+        };
+        GlyphBuffer(buffer.0)
+    }
+}
+
+unsafe impl HarfbuzzObject for ShapePlan {
+    type Raw = hb_shape_plan_t;
+
+    unsafe fn from_raw(raw: *const hb_shape_plan_t) -> Self {
+        ShapePlan {
+            raw: NonNull::new(raw as *mut _).unwrap(),
+        }
+    }
+
+    fn as_raw(&self) -> *mut Self::Raw {
+        self.raw.as_ptr()
+    }
+
+    unsafe fn reference(&self) {
+        hb_shape_plan_reference(self.as_raw());
+    }
+
+    unsafe fn dereference(&self) {
+        hb_shape_plan_destroy(self.as_raw());
+    }
+}
+
+unsafe impl Send for ShapePlan {}
+unsafe impl Sync for ShapePlan {}
+
+#[derive(PartialEq, Eq)]
+struct PlanKey {
+    // the plan does not keep its face alive, so the face is identified by its
+    // id, which is not reused by faces created after it is destroyed
+    face: u64,
+    props: SegmentProperties,
+    features: Vec<(u32, u32, usize, usize)>,
+    coords: Vec<i32>,
+    deterministic: bool,
+}
+
+impl PlanKey {
+    fn new(font: &Font<'_>, props: &SegmentProperties, features: &[Feature]) -> PlanKey {
+        PlanKey {
+            face: font.face().id(),
+            props: *props,
+            features: features
+                .iter()
+                .map(|f| (f.tag().0, f.value(), f.start(), f.end()))
+                .collect(),
+            coords: font.var_coords_normalized().to_vec(),
+            deterministic: deterministic_shaping(),
+        }
+    }
+}
+
+// The least recently used plan is at the front.
+pub(crate) struct PlanCache {
+    capacity: usize,
+    plans: Vec<(PlanKey, Shared<ShapePlan>)>,
+}
+
+impl PlanCache {
+    pub(crate) const fn new(capacity: usize) -> PlanCache {
+        PlanCache {
+            capacity,
+            plans: Vec::new(),
+        }
+    }
+
+    // Returns the plan for shaping with the given parameters, creating it if
+    // it is not in the cache.
+    pub(crate) fn get(
+        &mut self,
+        font: &Font<'_>,
+        props: &SegmentProperties,
+        features: &[Feature],
+    ) -> Shared<ShapePlan> {
+        let key = PlanKey::new(font, props, features);
+        let plan = match self.plans.iter().position(|(k, _)| *k == key) {
+            Some(index) => self.plans.remove(index).1,
+            None => {
+                if self.plans.len() >= self.capacity {
+                    self.plans.remove(0);
+                }
+                ShapePlan::new(font, props, features).into()
+            }
+        };
+        self.plans.push((key, plan.clone()));
+        plan
+    }
+}
+
+static PLAN_CACHE: Mutex<PlanCache> = Mutex::new(PlanCache::new(0));
+// A copy of the capacity of `PLAN_CACHE`, so that shaping does not lock the
+// cache while it is disabled.
+static PLAN_CACHE_CAPACITY: AtomicUsize = AtomicUsize::new(0);
+
+fn plan_cache() -> std::sync::MutexGuard<'static, PlanCache> {
+    // the cache is always in a consistent state, so poisoning can be ignored
+    PLAN_CACHE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Sets the number of shape plans kept by the crate-level shape plan cache.
+///
+/// The cache is disabled by default (capacity `0`). When enabled,
+/// [`shape()`](crate::shape) and the functions based on it look up a plan for
+/// the face, segment properties, features and variation coordinates of each
+/// call in the cache before creating a new one and evict the least recently
+/// used plan when the cache is full. Plans of destroyed faces are never
+/// reused and stay in the cache until they are evicted. Reducing the capacity
+/// evicts plans immediately.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+///
+/// set_shape_plan_cache_capacity(16);
+/// let first = shape(&font, UnicodeBuffer::new().add_str("Hello"), &[]);
+/// // this reuses the plan of the first call
+/// let second = shape(&font, UnicodeBuffer::new().add_str("World"), &[]);
+/// # assert_eq!(first.len(), second.len());
+/// set_shape_plan_cache_capacity(0);
+/// ```
+pub fn set_shape_plan_cache_capacity(capacity: usize) {
+    let mut cache = plan_cache();
+    cache.capacity = capacity;
+    PLAN_CACHE_CAPACITY.store(capacity, Ordering::SeqCst);
+    let excess = cache.plans.len().saturating_sub(capacity);
+    cache.plans.drain(..excess);
+}
+
+/// Returns the capacity of the crate-level shape plan cache.
+pub fn shape_plan_cache_capacity() -> usize {
+    PLAN_CACHE_CAPACITY.load(Ordering::SeqCst)
+}
+
+/// Removes all plans from the crate-level shape plan cache without changing
+/// its capacity.
+pub fn clear_shape_plan_cache() {
+    plan_cache().plans.clear();
+}
+
+// Returns the plan for shaping with the given parameters from the
+// crate-level cache, or `None` if the cache is disabled.
+pub(crate) fn cached_plan(
+    font: &Font<'_>,
+    props: &SegmentProperties,
+    features: &[Feature],
+) -> Option<Shared<ShapePlan>> {
+    if PLAN_CACHE_CAPACITY.load(Ordering::Relaxed) == 0 {
+        return None;
+    }
+    let mut cache = plan_cache();
+    if cache.capacity == 0 {
+        return None;
+    }
+    Some(cache.get(font, props, features))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Direction;
+    use crate::face::Face;
+    use crate::shape;
+
+    #[test]
+    fn test_plan_cache() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let props = UnicodeBuffer::new()
+            .add_str("a")
+            .guess_segment_properties()
+            .get_segment_properties();
+        let mut cache = PlanCache::new(2);
+        let plan = cache.get(&font, &props, &[]);
+        assert_eq!(plan.as_raw(), cache.get(&font, &props, &[]).as_raw());
+
+        // the least recently used plan is evicted
+        let rtl = SegmentProperties {
+            direction: Direction::Rtl,
+            ..props
+        };
+        let kern = [Feature::new(b"kern", 0, ..)];
+        cache.get(&font, &rtl, &[]);
+        cache.get(&font, &props, &kern);
+        assert_eq!(cache.plans.len(), 2);
+        assert_ne!(plan.as_raw(), cache.get(&font, &props, &[]).as_raw());
+    }
+
+    #[test]
+    fn test_plan_cache_with_reloaded_face() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let props = UnicodeBuffer::new()
+            .add_str("a")
+            .guess_segment_properties()
+            .get_segment_properties();
+        let mut cache = PlanCache::new(2);
+        let font = Font::new(Face::from_file(path, 0).unwrap());
+        cache.get(&font, &props, &[]);
+        drop(font);
+        // a later face may get the address of the destroyed one, but it must
+        // not get its plan
+        let font = Font::new(Face::from_file(path, 0).unwrap());
+        let plan = cache.get(&font, &props, &[]);
+        assert_eq!(cache.plans.len(), 2);
+        let buffer = UnicodeBuffer::new()
+            .add_str("a")
+            .set_segment_properties(props);
+        assert_eq!(plan.execute(&font, buffer, &[]).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "segment properties the shape plan was created for")]
+    fn test_execute_with_other_props() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let buffer = UnicodeBuffer::new().add_str("a").guess_segment_properties();
+        let plan = ShapePlan::new(&font, &buffer.get_segment_properties(), &[]);
+        plan.execute(&font, buffer.set_direction(Direction::Rtl), &[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "face the shape plan was created for")]
+    fn test_execute_with_other_face() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let font = Font::new(Face::from_file(path, 0).unwrap());
+        let other_font = Font::new(Face::from_file(path, 0).unwrap());
+        let buffer = UnicodeBuffer::new().add_str("a").guess_segment_properties();
+        let plan = ShapePlan::new_cached(&font, &buffer.get_segment_properties(), &[]);
+        plan.execute(&other_font, buffer, &[]);
+    }
+
+    #[test]
+    fn test_shape_with_plans() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let text = "AVATAR office";
+        let kern = [Feature::new(b"kern", 0, ..)];
+        let uncached = shape(&font, UnicodeBuffer::new().add_str(text), &kern);
+
+        let props = uncached.get_segment_properties();
+        let mut cache = PlanCache::new(4);
+        for _ in 0..2 {
+            let buffer = UnicodeBuffer::new()
+                .add_str(text)
+                .set_segment_properties(props);
+            let cached = cache
+                .get(&font, &props, &kern)
+                .execute(&font, buffer, &kern);
+            assert!(cached.iter().eq(uncached.iter()));
+        }
+        assert_eq!(cache.plans.len(), 1);
+        // the crate-level cache is disabled by default
+        assert!(cached_plan(&font, &props, &kern).is_none());
+
+        let plan = ShapePlan::new_cached(&font, &props, &kern);
+        let buffer = UnicodeBuffer::new()
+            .add_str(text)
+            .set_segment_properties(props);
+        let output = plan.execute(&font, buffer, &kern);
+        assert!(output.iter().eq(uncached.iter()));
+    }
+}
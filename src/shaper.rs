@@ -0,0 +1,217 @@
+use std::ffi::CStr;
+use std::fmt;
+use std::os::raw::{c_char, c_uint};
+
+use crate::bindings::{hb_shape_full, hb_shape_list_shapers};
+use crate::buffer::{GlyphBuffer, UnicodeBuffer};
+use crate::common::HarfbuzzObject;
+use crate::font::Font;
+use crate::{resolve_segment_properties, Feature};
+
+/// A shaper backend of HarfBuzz.
+///
+/// Which shapers are available depends on the platform and on how HarfBuzz
+/// was built, which can be checked at runtime with
+/// [`is_available()`](Shaper::is_available).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Shaper {
+    /// The OpenType shaper of HarfBuzz (`ot`).
+    Ot,
+    /// The shaper for Graphite fonts (`graphite2`).
+    Graphite2,
+    /// The Uniscribe shaper on Windows (`uniscribe`).
+    Uniscribe,
+    /// The DirectWrite shaper on Windows (`directwrite`).
+    DirectWrite,
+    /// The CoreText shaper on Apple platforms (`coretext`).
+    CoreText,
+    /// The fallback shaper, which maps characters to glyphs without any
+    /// layout features (`fallback`).
+    Fallback,
+}
+
+impl Shaper {
+    const ALL: [Shaper; 6] = [
+        Shaper::Ot,
+        Shaper::Graphite2,
+        Shaper::Uniscribe,
+        Shaper::DirectWrite,
+        Shaper::CoreText,
+        Shaper::Fallback,
+    ];
+
+    // the name as a null terminated string
+    fn c_name(self) -> &'static [u8] {
+        match self {
+            Shaper::Ot => b"ot\0",
+            Shaper::Graphite2 => b"graphite2\0",
+            Shaper::Uniscribe => b"uniscribe\0",
+            Shaper::DirectWrite => b"directwrite\0",
+            Shaper::CoreText => b"coretext\0",
+            Shaper::Fallback => b"fallback\0",
+        }
+    }
+
+    /// Returns the name HarfBuzz uses for the shaper.
+    pub fn name(self) -> &'static str {
+        let name = self.c_name();
+        std::str::from_utf8(&name[..name.len() - 1]).unwrap()
+    }
+
+    /// Returns the shapers that the linked HarfBuzz library supports, in the
+    /// order in which [`shape()`](crate::shape) tries them.
+    ///
+    /// Shapers unknown to this crate are left out.
+    pub fn available() -> Vec<Shaper> {
+        let mut shapers = Vec::new();
+        unsafe {
+            let mut list = hb_shape_list_shapers();
+            while !(*list).is_null() {
+                let name = CStr::from_ptr(*list).to_bytes();
+                shapers.extend(
+                    Shaper::ALL
+                        .iter()
+                        .find(|shaper| shaper.name().as_bytes() == name),
+                );
+                list = list.add(1);
+            }
+        }
+        shapers
+    }
+
+    /// Returns `true` if the linked HarfBuzz library supports the shaper.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::Shaper;
+    ///
+    /// assert!(Shaper::Ot.is_available());
+    /// if Shaper::DirectWrite.is_available() {
+    ///     // match the shaping of native Windows applications
+    /// }
+    /// ```
+    pub fn is_available(self) -> bool {
+        Shaper::available().contains(&self)
+    }
+}
+
+impl fmt::Display for Shaper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The error returned by [`shape_full()`] if none of the requested shapers
+/// could shape the buffer.
+///
+/// It contains the unshaped buffer, which can be retrieved with
+/// [`into_buffer()`](ShaperError::into_buffer), e.g. to shape it with
+/// another shaper.
+#[derive(Debug)]
+pub struct ShaperError(UnicodeBuffer);
+
+impl ShaperError {
+    /// Returns the buffer that could not be shaped.
+    pub fn into_buffer(self) -> UnicodeBuffer {
+        self.0
+    }
+}
+
+impl fmt::Display for ShaperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "none of the requested shapers could shape the buffer")
+    }
+}
+
+impl std::error::Error for ShaperError {}
+
+/// Shapes `buffer` like [`shape()`](crate::shape) with the first of
+/// `shapers` that succeeds (`hb_shape_full`).
+///
+/// This allows using a platform shaper like [`Shaper::DirectWrite`] to match
+/// the shaping of native applications exactly. Shapers that are not
+/// available are skipped. If `shapers` is empty, all available shapers are
+/// tried in the default order or, in deterministic mode (see
+/// [`set_deterministic_shaping()`](crate::set_deterministic_shaping)), only
+/// the OpenType shaper is used.
+///
+/// Returns an error containing the unshaped buffer if no shaper succeeded.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let buffer = UnicodeBuffer::new().add_str("Hello");
+///
+/// let output = match shape_full(&font, buffer, &[], &[Shaper::Uniscribe]) {
+///     Ok(output) => output,
+///     // Uniscribe is only available on Windows
+///     Err(err) => shape_full(&font, err.into_buffer(), &[], &[Shaper::Ot]).unwrap(),
+/// };
+/// assert_eq!(output.len(), 5);
+/// ```
+pub fn shape_full(
+    font: &Font<'_>,
+    buffer: UnicodeBuffer,
+    features: &[Feature],
+    shapers: &[Shaper],
+) -> Result<GlyphBuffer, ShaperError> {
+    let buffer = resolve_segment_properties(buffer);
+    let mut shaper_list: Vec<*const c_char> = shapers
+        .iter()
+        .map(|shaper| shaper.c_name().as_ptr() as *const c_char)
+        .collect();
+    shaper_list.push(std::ptr::null());
+    let shaper_list = if shapers.is_empty() {
+        crate::shape_plan::shaper_list()
+    } else {
+        shaper_list.as_ptr()
+    };
+    let success = unsafe {
+        hb_shape_full(
+            font.as_raw(),
+            buffer.0.as_raw(),
+            features.as_ptr() as *mut _,
+            features.len() as c_uint,
+            shaper_list,
+        )
+    };
+    if success == 1 {
+        Ok(GlyphBuffer(buffer.0))
+    } else {
+        Err(ShaperError(buffer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{shape, Face};
+
+    #[test]
+    fn test_shape_full() {
+        let available = Shaper::available();
+        assert!(available.contains(&Shaper::Ot));
+        assert!(available.contains(&Shaper::Fallback));
+        assert_eq!(Shaper::DirectWrite.to_string(), "directwrite");
+
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let buffer = || UnicodeBuffer::new().add_str("fi");
+
+        let expected = shape(&font, buffer(), &[]);
+        let output = shape_full(&font, buffer(), &[], &[Shaper::Ot]).unwrap();
+        assert_eq!(output.to_string(), expected.to_string());
+        let output = shape_full(&font, buffer(), &[], &[]).unwrap();
+        assert_eq!(output.to_string(), expected.to_string());
+
+        if !Shaper::CoreText.is_available() {
+            let err = shape_full(&font, buffer(), &[], &[Shaper::CoreText]).unwrap_err();
+            assert_eq!(err.into_buffer().string_lossy(), "fi");
+        }
+    }
+}
@@ -0,0 +1,459 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+
+use std::ops::Range;
+
+use crate::batch::{Run, RunShaper, ShapedRun};
+use crate::buffer::{GlyphBuffer, SegmentProperties};
+use crate::font::Font;
+use crate::shape_plan::deterministic_shaping;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    face: u64,
+    coords: Vec<i32>,
+    scale: (i32, i32),
+    ppem: (u32, u32),
+    features: Vec<(u32, u32, usize, usize)>,
+    props: Option<SegmentProperties>,
+    deterministic: bool,
+    text: String,
+}
+
+impl CacheKey {
+    fn new(font: &Font<'_>, run: &Run<'_>) -> CacheKey {
+        CacheKey {
+            face: font.face().id(),
+            coords: font.var_coords_normalized().to_vec(),
+            scale: font.scale(),
+            ppem: font.ppem(),
+            features: run
+                .features
+                .iter()
+                .map(|f| (f.tag().0, f.value(), f.start(), f.end()))
+                .collect(),
+            props: run.segment_properties,
+            deterministic: deterministic_shaping(),
+            text: run.text.to_string(),
+        }
+    }
+}
+
+struct CacheEntry {
+    run: Arc<ShapedRun>,
+    last_used: u64,
+}
+
+/// A least-recently-used cache of shaping results.
+///
+/// Text engines shape the same words over and over again, e.g. while the
+/// user edits a paragraph or when a document is laid out at different
+/// widths. [`shape_cached()`] looks up the result of shaping a [`Run`] in the
+/// cache and only shapes it if it is not cached yet.
+///
+/// Results are identified by the face of the font, the variation
+/// coordinates, scale and ppem of the font, the features, the segment
+/// properties and the text of the run. Faces are compared by identity, so all
+/// fonts created from the same [`Face`](crate::Face) share their cached
+/// results, while the same font file loaded twice is cached separately (a
+/// [`FontCache`] avoids loading fonts twice). The same holds for the shape
+/// plans the cache keeps. Fonts with custom font functions should not be used
+/// with a cache, since the results depend on the font functions, which are
+/// not part of the key.
+///
+/// [`FontCache`]: crate::font_cache::FontCache
+///
+/// Shaping results depend on the context of the text, so a cache should only
+/// be used for runs whose shaping does not depend on their context, e.g. the
+/// segments returned by [`segment_cacheable_words()`].
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let mut cache = ShapingCache::new(1000);
+///
+/// let first = shape_cached(&font, &Run::new("Hello"), &mut cache);
+/// let second = shape_cached(&font, &Run::new("Hello"), &mut cache);
+/// assert_eq!(first.glyphs.len(), 5);
+/// assert!(std::sync::Arc::ptr_eq(&first, &second));
+/// assert_eq!((cache.hits(), cache.misses()), (1, 1));
+/// ```
+pub struct ShapingCache {
+    capacity: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    entries: HashMap<Arc<CacheKey>, CacheEntry>,
+    // The keys in the order of their use, oldest first. A key is pushed again
+    // on every use, and only its last occurrence (the one whose time matches
+    // `CacheEntry::last_used`) is valid.
+    order: VecDeque<(Arc<CacheKey>, u64)>,
+    shaper: RunShaper,
+}
+
+impl ShapingCache {
+    /// Creates an empty cache that holds at most `capacity` shaped runs.
+    pub fn new(capacity: usize) -> ShapingCache {
+        ShapingCache {
+            capacity,
+            clock: 0,
+            hits: 0,
+            misses: 0,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            shaper: RunShaper::new(),
+        }
+    }
+
+    /// Returns the number of cached runs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the cache holds no runs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the maximum number of cached runs.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes the maximum number of cached runs, evicting the least recently
+    /// used runs if the cache holds more.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// Returns the number of lookups that found a cached result.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the number of lookups that had to shape the run.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Removes all runs from the cache and resets the statistics.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    fn is_current(&self, key: &CacheKey, time: u64) -> bool {
+        self.entries.get(key).map(|entry| entry.last_used) == Some(time)
+    }
+
+    // Marks the entry of `key` as the most recently used one.
+    fn touch(&mut self, key: Arc<CacheKey>) {
+        self.order.push_back((key, self.clock));
+        // drop outdated uses once they make up most of the queue
+        if self.order.len() > 2 * self.entries.len() + 16 {
+            let mut order = std::mem::take(&mut self.order);
+            order.retain(|(key, time)| self.is_current(key, *time));
+            self.order = order;
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            match self.order.pop_front() {
+                Some((key, time)) => {
+                    if self.is_current(&key, time) {
+                        self.entries.remove(&key);
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+impl Default for ShapingCache {
+    /// Creates a cache for 10000 runs.
+    fn default() -> Self {
+        ShapingCache::new(10_000)
+    }
+}
+
+impl fmt::Debug for ShapingCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShapingCache")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .field("hits", &self.hits)
+            .field("misses", &self.misses)
+            .finish()
+    }
+}
+
+/// Returns the result of shaping `run` with `font` from `cache`, shaping and
+/// caching it if necessary.
+///
+/// The result is the same as that of [`shape_runs()`](crate::shape_runs).
+/// See [`ShapingCache`] for when results can be reused.
+pub fn shape_cached(font: &Font<'_>, run: &Run<'_>, cache: &mut ShapingCache) -> Arc<ShapedRun> {
+    let key = CacheKey::new(font, run);
+    cache.clock += 1;
+    let clock = cache.clock;
+    if let Some((key, entry)) = cache.entries.get_key_value(&key) {
+        let (key, run) = (key.clone(), entry.run.clone());
+        if let Some(entry) = cache.entries.get_mut(&key) {
+            entry.last_used = clock;
+        }
+        cache.touch(key);
+        cache.hits += 1;
+        return run;
+    }
+    cache.misses += 1;
+    let shaped = Arc::new(cache.shaper.shape(font, run));
+    if cache.capacity > 0 {
+        let key = Arc::new(key);
+        cache.entries.insert(
+            key.clone(),
+            CacheEntry {
+                run: shaped.clone(),
+                last_used: clock,
+            },
+        );
+        cache.touch(key);
+        cache.evict();
+    }
+    shaped
+}
+
+/// Splits `text` into segments whose shaping does not depend on the
+/// surrounding text, so they can be shaped separately and cached (e.g. with
+/// [`shape_cached()`]).
+///
+/// `buffer` must be the result of shaping the whole `text`, added with
+/// [`UnicodeBuffer::add_str()`](crate::UnicodeBuffer::add_str) so that the
+/// clusters are byte offsets into `text`. The segments are the words of the
+/// text (runs of non-whitespace characters) in logical order. A segment only
+/// ends at a word boundary where the shaper allows breaking the glyphs on
+/// both sides of the whitespace (see
+/// [`GlyphFlags::unsafe_to_break()`](crate::GlyphFlags::unsafe_to_break));
+/// otherwise it extends over the next word, e.g. if the font kerns across
+/// spaces. Words at the start or end of the text that are not safe to break
+/// from their context are left out, as is the whitespace between the
+/// segments.
+///
+/// Shaping every segment on its own produces the same glyphs as shaping the
+/// whole text, so the results can be looked up per segment in a word cache.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::*;
+///
+/// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+/// let font = Font::new(face);
+/// let text = "Hello brave new world";
+/// let output = shape(&font, UnicodeBuffer::new().add_str(text), &[]);
+///
+/// let words: Vec<&str> = segment_cacheable_words(text, &output)
+///     .into_iter()
+///     .map(|range| &text[range])
+///     .collect();
+/// assert_eq!(words, vec!["Hello", "brave", "new", "world"]);
+/// ```
+pub fn segment_cacheable_words(text: &str, buffer: &GlyphBuffer) -> Vec<Range<usize>> {
+    // maps each cluster to whether all of its glyphs are safe to break before
+    let mut safe_clusters: HashMap<usize, bool> = HashMap::new();
+    for info in buffer.get_glyph_infos() {
+        let safe = safe_clusters.entry(info.cluster as usize).or_insert(true);
+        *safe &= !info.glyph_flags().unsafe_to_break();
+    }
+    let is_safe = |offset: usize| {
+        // offsets inside clusters are never safe
+        offset == 0 || offset == text.len() || safe_clusters.get(&offset).copied().unwrap_or(false)
+    };
+
+    let mut words = Vec::new();
+    let mut start = None;
+    for (offset, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(offset),
+            (true, Some(word_start)) => {
+                words.push(word_start..offset);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(word_start) = start {
+        words.push(word_start..text.len());
+    }
+
+    let mut segments: Vec<Range<usize>> = Vec::new();
+    let mut previous_end_safe = false;
+    for word in words {
+        match segments.last_mut() {
+            Some(segment) if !(previous_end_safe && is_safe(word.start)) => segment.end = word.end,
+            _ => segments.push(word.clone()),
+        }
+        previous_end_safe = is_safe(word.end);
+    }
+    segments.retain(|segment| is_safe(segment.start) && is_safe(segment.end));
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::HB_GLYPH_FLAG_UNSAFE_TO_BREAK;
+    use crate::{shape, shape_runs, Face, Feature, Shared, Tag, UnicodeBuffer, Variation};
+
+    #[test]
+    fn test_shaping_cache() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let font = Font::new(Face::from_file(path, 0).unwrap());
+        let mut cache = ShapingCache::new(2);
+        let kern = [Feature::new(b"kern", 0, ..)];
+        let runs = [
+            Run::new("AV"),
+            Run {
+                features: &kern,
+                ..Run::new("AV")
+            },
+        ];
+
+        for run in &runs {
+            let expected = shape_runs(&font, vec![run.clone()]);
+            assert_eq!(*shape_cached(&font, run, &mut cache), expected[0]);
+        }
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (0, 2, 2));
+
+        // fonts with the same face share the results, while the same face
+        // loaded again does not
+        let same_face = Font::new(font.face());
+        shape_cached(&same_face, &runs[0], &mut cache);
+        assert_eq!(cache.hits(), 1);
+        let reloaded = Font::new(Face::from_file(path, 0).unwrap());
+        assert_ne!(reloaded.face().id(), font.face().id());
+        assert_eq!(same_face.face().id(), font.face().id());
+
+        // other variations and scales are cached separately
+        let mut bold = Font::new(Face::from_file(path, 0).unwrap());
+        bold.set_variations(&[Variation::new(b"wght", 900.0)]);
+        let mut large = Font::new(Face::from_file(path, 0).unwrap());
+        large.set_scale(2000, 2000);
+        shape_cached(&bold, &runs[0], &mut cache);
+        shape_cached(&large, &runs[0], &mut cache);
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 4, 2));
+
+        // the run with `kern` disabled was evicted first
+        shape_cached(&font, &runs[1], &mut cache);
+        assert_eq!(cache.misses(), 5);
+        cache.set_capacity(0);
+        assert!(cache.is_empty());
+        shape_cached(&font, &runs[1], &mut cache);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_shaping_cache_with_reloaded_face() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let mut cache = ShapingCache::new(4);
+        let run = Run::new("office");
+        let font = Font::new(Face::from_file(path, 0).unwrap());
+        shape_cached(&font, &run, &mut cache);
+        drop(font);
+
+        // a face without layout tables may get the address of the destroyed
+        // face, but must neither get its results nor its shape plans
+        let original: Shared<Face<'static>> = Face::from_file(path, 0).unwrap().into();
+        let face = Face::from_table_func(move |tag| {
+            if tag == Tag::new('G', 'S', 'U', 'B') || tag == Tag::new('G', 'P', 'O', 'S') {
+                None
+            } else {
+                original.table_with_tag(tag)
+            }
+        });
+        let font = Font::new(face);
+        let expected = shape_runs(&font, vec![run.clone()]);
+        assert_eq!(*shape_cached(&font, &run, &mut cache), expected[0]);
+        assert_eq!((cache.hits(), cache.misses()), (0, 2));
+    }
+
+    #[test]
+    fn test_shaping_cache_lru_order() {
+        let path = "testfiles/SourceSansVariable-Roman.ttf";
+        let font = Font::new(Face::from_file(path, 0).unwrap());
+        let mut cache = ShapingCache::new(2);
+        let (a, b, c) = (Run::new("a"), Run::new("b"), Run::new("c"));
+
+        shape_cached(&font, &a, &mut cache);
+        shape_cached(&font, &b, &mut cache);
+        // many hits on "a" make "b" the least recently used run
+        for _ in 0..100 {
+            shape_cached(&font, &a, &mut cache);
+        }
+        assert!(cache.order.len() <= 2 * cache.len() + 16);
+        shape_cached(&font, &c, &mut cache);
+        assert_eq!((cache.hits(), cache.misses()), (100, 3));
+        shape_cached(&font, &a, &mut cache);
+        shape_cached(&font, &c, &mut cache);
+        assert_eq!((cache.hits(), cache.misses()), (102, 3));
+        shape_cached(&font, &b, &mut cache);
+        assert_eq!(cache.misses(), 4);
+    }
+
+    #[test]
+    fn test_segment_cacheable_words() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let text = " one two\tthree  four ";
+        let mut output = shape(&font, UnicodeBuffer::new().add_str(text), &[]);
+        let segments = segment_cacheable_words(text, &output);
+        assert_eq!(segments, vec![1..4, 5..8, 9..14, 16..20]);
+        for segment in segments {
+            let alone = shape(
+                &font,
+                UnicodeBuffer::new().add_str(&text[segment.clone()]),
+                &[],
+            );
+            let in_context: Vec<_> = output
+                .iter()
+                .filter(|glyph| segment.contains(&(glyph.cluster as usize)))
+                .map(|glyph| (glyph.gid, glyph.x_advance))
+                .collect();
+            let alone: Vec<_> = alone
+                .iter()
+                .map(|glyph| (glyph.gid, glyph.x_advance))
+                .collect();
+            assert_eq!(alone, in_context);
+        }
+
+        // a word that is not safe to break from the following space is merged
+        // with the next word
+        let space = output
+            .get_glyph_infos()
+            .iter()
+            .position(|i| i.cluster == 4)
+            .unwrap();
+        output.get_glyph_infos_mut()[space].set_mask(HB_GLYPH_FLAG_UNSAFE_TO_BREAK);
+        assert_eq!(
+            segment_cacheable_words(text, &output),
+            vec![1..8, 9..14, 16..20]
+        );
+        // as is one that is not safe to break from the preceding space
+        let first = output
+            .get_glyph_infos()
+            .iter()
+            .position(|i| i.cluster == 1)
+            .unwrap();
+        output.get_glyph_infos_mut()[first].set_mask(HB_GLYPH_FLAG_UNSAFE_TO_BREAK);
+        assert_eq!(segment_cacheable_words(text, &output), vec![9..14, 16..20]);
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt::Write;
+
+use crate::draw_funcs::{DrawFuncs, DrawState};
+use crate::font::{Font, Glyph};
+
+/// Options for [`Font::glyph_to_svg_path_with_options()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SvgPathOptions {
+    /// Negate the y coordinates, so that the glyph is upright in the SVG
+    /// coordinate system, where the y axis points down. The baseline stays at
+    /// y = 0.
+    pub flip_y: bool,
+    /// The maximum number of decimal places of the coordinates. Trailing
+    /// zeros are omitted.
+    pub precision: usize,
+}
+
+impl Default for SvgPathOptions {
+    fn default() -> SvgPathOptions {
+        SvgPathOptions {
+            flip_y: true,
+            precision: 2,
+        }
+    }
+}
+
+// Writes the commands of an SVG path.
+struct SvgPathBuilder {
+    options: SvgPathOptions,
+    path: String,
+}
+
+impl SvgPathBuilder {
+    fn command(&mut self, command: char, points: &[(f32, f32)]) {
+        self.path.push(command);
+        for (i, &(x, y)) in points.iter().enumerate() {
+            if i > 0 {
+                self.path.push(' ');
+            }
+            let y = if self.options.flip_y { -y } else { y };
+            self.number(x);
+            self.path.push(' ');
+            self.number(y);
+        }
+    }
+
+    fn number(&mut self, value: f32) {
+        let start = self.path.len();
+        write!(self.path, "{:.*}", self.options.precision, value).unwrap();
+        if self.path[start..].contains('.') {
+            let len = self.path.trim_end_matches('0').trim_end_matches('.').len();
+            self.path.truncate(len);
+        }
+        if &self.path[start..] == "-0" {
+            self.path.remove(start);
+        }
+    }
+}
+
+impl DrawFuncs for SvgPathBuilder {
+    fn move_to(&mut self, _: &DrawState, to_x: f32, to_y: f32) {
+        self.command('M', &[(to_x, to_y)]);
+    }
+
+    fn line_to(&mut self, _: &DrawState, to_x: f32, to_y: f32) {
+        self.command('L', &[(to_x, to_y)]);
+    }
+
+    fn quadratic_to(
+        &mut self,
+        _: &DrawState,
+        control_x: f32,
+        control_y: f32,
+        to_x: f32,
+        to_y: f32,
+    ) {
+        self.command('Q', &[(control_x, control_y), (to_x, to_y)]);
+    }
+
+    fn cubic_to(
+        &mut self,
+        _: &DrawState,
+        control1_x: f32,
+        control1_y: f32,
+        control2_x: f32,
+        control2_y: f32,
+        to_x: f32,
+        to_y: f32,
+    ) {
+        self.command(
+            'C',
+            &[
+                (control1_x, control1_y),
+                (control2_x, control2_y),
+                (to_x, to_y),
+            ],
+        );
+    }
+
+    fn close_path(&mut self, _: &DrawState) {
+        self.path.push('Z');
+    }
+}
+
+impl<'a> Font<'a> {
+    /// Returns the outline of `glyph` as the `d` attribute of an SVG path,
+    /// with the default [`SvgPathOptions`].
+    ///
+    /// The coordinates are in the scale of the font and flipped vertically,
+    /// so the glyph is upright in SVG. The result is empty if the glyph has
+    /// no outline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harfbuzz_rs::*;
+    ///
+    /// let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+    /// let font = Font::new(face);
+    ///
+    /// let hyphen = font.get_nominal_glyph('-').unwrap();
+    /// assert_eq!(
+    ///     font.glyph_to_svg_path(hyphen),
+    ///     "M40 -234L40 -264L256 -264L256 -234L40 -234Z"
+    /// );
+    /// ```
+    pub fn glyph_to_svg_path(&self, glyph: Glyph) -> String {
+        self.glyph_to_svg_path_with_options(glyph, SvgPathOptions::default())
+    }
+
+    /// Returns the outline of `glyph` as the `d` attribute of an SVG path,
+    /// formatted according to `options`.
+    pub fn glyph_to_svg_path_with_options(&self, glyph: Glyph, options: SvgPathOptions) -> String {
+        let mut builder = SvgPathBuilder {
+            options,
+            path: String::new(),
+        };
+        self.draw_glyph(glyph, &mut builder);
+        builder.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Face;
+
+    #[test]
+    fn test_glyph_to_svg_path() {
+        let face = Face::from_file("testfiles/SourceSansVariable-Roman.ttf", 0).unwrap();
+        let font = Font::new(face);
+        let options = SvgPathOptions {
+            flip_y: false,
+            precision: 1,
+        };
+        assert_eq!(
+            font.glyph_to_svg_path_with_options(2, options),
+            "M10 0L246 660L274 660L510 0L476 0L338 396Q317 456 298.5 510Q280 564 262 626\
+             L258 626Q240 564 221.5 510Q203 456 182 396L42 0L10 0Z\
+             M112 236L112 264L405 264L405 236L112 236Z"
+        );
+        let rounded = SvgPathOptions {
+            precision: 0,
+            ..SvgPathOptions::default()
+        };
+        let path = font.glyph_to_svg_path_with_options(2, rounded);
+        assert!(path.starts_with("M10 0L246 -660"));
+        assert!(!path.contains('.'));
+
+        let space = font.get_nominal_glyph(' ').unwrap();
+        assert_eq!(font.glyph_to_svg_path(space), "");
+    }
+}
@@ -0,0 +1,56 @@
+//! Helpers for command line tools and quick debugging.
+//!
+//! These functions trade flexibility for convenience. They are meant for
+//! inspecting the shaping results of a font, similar to the `hb-shape` tool
+//! that comes with HarfBuzz.
+
+use crate::buffer::{SerializeFlags, SerializeFormat, UnicodeBuffer};
+use crate::face::Face;
+use crate::font::Font;
+use crate::{shape, Feature};
+
+use std::io;
+use std::path::Path;
+
+/// Shapes `text` with the first face of the font file at `font_path` and
+/// returns the resolved segment properties and the serialized shaping output.
+///
+/// The first line of the result lists the direction, script and language
+/// that were guessed from `text`. The second line contains the glyphs in the
+/// text format of `hb-shape`, i.e. glyph names (or ids if the font has no
+/// names), clusters and positions. The language is the default language of
+/// the current locale.
+///
+/// # Examples
+///
+/// ```
+/// use harfbuzz_rs::tools::preview;
+///
+/// let output = preview("testfiles/SourceSansVariable-Roman.ttf", "Hi", &[]).unwrap();
+/// let mut lines = output.lines();
+/// assert!(lines.next().unwrap().starts_with("direction=Ltr script=Latn"));
+/// // the font has no glyph names, so glyph ids are shown
+/// assert_eq!(lines.next(), Some("[gid9=0+634|gid36=1+222]"));
+/// ```
+pub fn preview(
+    font_path: impl AsRef<Path>,
+    text: &str,
+    features: &[Feature],
+) -> io::Result<String> {
+    let face = Face::from_file(font_path, 0)?;
+    let font = Font::new(face);
+    let buffer = UnicodeBuffer::new().add_str(text);
+    let output = shape(&font, buffer, features);
+    let props = output.get_segment_properties();
+    Ok(format!(
+        "direction={:?} script={} language={}\n{}\n",
+        props.direction,
+        props.script,
+        props.language,
+        output.display_with(
+            Some(&font),
+            SerializeFormat::Text,
+            SerializeFlags::default()
+        )
+    ))
+}
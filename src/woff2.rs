@@ -0,0 +1,1153 @@
+//! Decoding of WOFF2 compressed fonts.
+//!
+//! This module is only available with the `woff2` feature.
+//!
+//! WOFF2 is the compression format used for web fonts. HarfBuzz can only read
+//! uncompressed OpenType (SFNT) fonts, so WOFF2 files have to be decoded first.
+//! The implementation follows the [WOFF2 specification], including the
+//! reconstruction of transformed `glyf`, `loca` and `hmtx` tables and font
+//! collections.
+//!
+//! [WOFF2 specification]: https://www.w3.org/TR/WOFF2/
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::Read;
+
+use crate::blob::Blob;
+use crate::common::{Owned, Shared, Tag};
+use crate::face::Face;
+
+const WOFF2_SIGNATURE: u32 = 0x774F_4632; // 'wOF2'
+const TTC_TAG: u32 = 0x7474_6366; // 'ttcf'
+                                  // The largest decompressed or decoded font that is accepted, the same limit as
+                                  // in the reference decoder. The sizes in the table directory are not trusted,
+                                  // so this keeps malformed fonts from allocating huge amounts of memory.
+const MAX_FONT_SIZE: usize = 30 * 1024 * 1024;
+
+const GLYF: Tag = Tag::new('g', 'l', 'y', 'f');
+const LOCA: Tag = Tag::new('l', 'o', 'c', 'a');
+const HMTX: Tag = Tag::new('h', 'm', 't', 'x');
+const HHEA: Tag = Tag::new('h', 'h', 'e', 'a');
+const HEAD: Tag = Tag::new('h', 'e', 'a', 'd');
+
+// The tags that can be referenced by their index in the table directory.
+const KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+/// The error type returned when decoding a WOFF2 font fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Woff2Error {
+    /// The data does not start with a WOFF2 header.
+    NotWoff2,
+    /// The font data could not be decompressed.
+    Decompression,
+    /// The font data is malformed. The string describes the problem.
+    Malformed(&'static str),
+}
+
+impl fmt::Display for Woff2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Woff2Error::NotWoff2 => write!(f, "not a WOFF2 font"),
+            Woff2Error::Decompression => write!(f, "failed to decompress WOFF2 font data"),
+            Woff2Error::Malformed(what) => write!(f, "malformed WOFF2 font: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for Woff2Error {}
+
+type Result<T> = std::result::Result<T, Woff2Error>;
+
+/// A cursor over big-endian font data.
+#[derive(Debug, Clone)]
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or(Woff2Error::Malformed("unexpected end of data"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u255_16(&mut self) -> Result<u16> {
+        const WORD_CODE: u8 = 253;
+        const ONE_MORE_BYTE_CODE2: u8 = 254;
+        const ONE_MORE_BYTE_CODE1: u8 = 255;
+        const LOWEST_U_CODE: u16 = 253;
+        match self.u8()? {
+            WORD_CODE => self.u16(),
+            ONE_MORE_BYTE_CODE1 => Ok(self.u8()? as u16 + LOWEST_U_CODE),
+            ONE_MORE_BYTE_CODE2 => Ok(self.u8()? as u16 + LOWEST_U_CODE * 2),
+            code => Ok(code as u16),
+        }
+    }
+
+    fn uint_base128(&mut self) -> Result<u32> {
+        let mut accum: u32 = 0;
+        for i in 0..5 {
+            let byte = self.u8()?;
+            if i == 0 && byte == 0x80 {
+                return Err(Woff2Error::Malformed("UIntBase128 with leading zeros"));
+            }
+            if accum & 0xFE00_0000 != 0 {
+                return Err(Woff2Error::Malformed("UIntBase128 overflow"));
+            }
+            accum = (accum << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(accum);
+            }
+        }
+        Err(Woff2Error::Malformed("UIntBase128 too long"))
+    }
+}
+
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn pad4(out: &mut Vec<u8>) {
+    while out.len() & 3 != 0 {
+        out.push(0);
+    }
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+#[derive(Debug)]
+struct TableEntry {
+    tag: Tag,
+    transformed: bool,
+    orig_length: u32,
+    // offset and length inside the decompressed data
+    src_offset: usize,
+    src_length: usize,
+}
+
+#[derive(Debug)]
+struct FontEntry {
+    flavor: u32,
+    tables: Vec<usize>,
+}
+
+/// Decodes the WOFF2 font `data` into an uncompressed OpenType font or font
+/// collection that can be passed to [`Face::new()`].
+///
+/// Like the reference decoder, this rejects fonts whose tables are larger than
+/// 30 MiB in total with [`Woff2Error::Malformed`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use harfbuzz_rs::{decode_woff2, Face};
+///
+/// let woff2 = std::fs::read("font.woff2").unwrap();
+/// let sfnt = decode_woff2(&woff2).expect("invalid WOFF2 font");
+/// let face = Face::new(sfnt, 0);
+/// ```
+pub fn decode_woff2(data: &[u8]) -> Result<Vec<u8>> {
+    let mut header = Reader::new(data);
+    if data.len() < 48 || header.u32()? != WOFF2_SIGNATURE {
+        return Err(Woff2Error::NotWoff2);
+    }
+    let flavor = header.u32()?;
+    let _length = header.u32()?;
+    let num_tables = header.u16()?;
+    let _reserved = header.u16()?;
+    let _total_sfnt_size = header.u32()?;
+    let total_compressed_size = header.u32()? as usize;
+    // version, metadata and private data are not needed to decode the font
+    header.bytes(4 + 5 * 4)?;
+
+    if num_tables == 0 {
+        return Err(Woff2Error::Malformed("no tables"));
+    }
+
+    let mut tables = Vec::with_capacity(num_tables as usize);
+    let mut src_offset = 0usize;
+    let mut orig_size = 0usize;
+    for _ in 0..num_tables {
+        let flags = header.u8()?;
+        let tag = match flags & 0x3F {
+            0x3F => Tag(header.u32()?),
+            index => {
+                let tag = KNOWN_TAGS[index as usize];
+                Tag::new(
+                    tag[0] as char,
+                    tag[1] as char,
+                    tag[2] as char,
+                    tag[3] as char,
+                )
+            }
+        };
+        let version = flags >> 6;
+        // For `glyf` and `loca` version 0 is the transform and 3 the null
+        // transform, for all other tables it is the other way round.
+        let transformed = if tag == GLYF || tag == LOCA {
+            version != 3
+        } else {
+            version != 0
+        };
+        let orig_length = header.uint_base128()?;
+        let src_length = if transformed {
+            header.uint_base128()?
+        } else {
+            orig_length
+        } as usize;
+        if transformed && tag != GLYF && tag != LOCA && tag != HMTX {
+            return Err(Woff2Error::Malformed("unknown table transform"));
+        }
+        if transformed && tag == LOCA && src_length != 0 {
+            return Err(Woff2Error::Malformed("transformed loca table is not empty"));
+        }
+        tables.push(TableEntry {
+            tag,
+            transformed,
+            orig_length,
+            src_offset,
+            src_length,
+        });
+        src_offset = src_offset
+            .checked_add(src_length)
+            .ok_or(Woff2Error::Malformed("table too large"))?;
+        orig_size = orig_size
+            .checked_add(orig_length as usize)
+            .ok_or(Woff2Error::Malformed("table too large"))?;
+        if src_offset > MAX_FONT_SIZE || orig_size > MAX_FONT_SIZE {
+            return Err(Woff2Error::Malformed("font too large"));
+        }
+    }
+    let uncompressed_size = src_offset;
+
+    let fonts = if flavor == TTC_TAG {
+        let ttc_version = header.u32()?;
+        let num_fonts = header.u255_16()?;
+        let mut fonts = Vec::with_capacity(num_fonts as usize);
+        for _ in 0..num_fonts {
+            let num_tables = header.u255_16()?;
+            let flavor = header.u32()?;
+            let mut font_tables = Vec::with_capacity(num_tables as usize);
+            for _ in 0..num_tables {
+                let index = header.u255_16()? as usize;
+                if index >= tables.len() {
+                    return Err(Woff2Error::Malformed("table index out of range"));
+                }
+                font_tables.push(index);
+            }
+            fonts.push(FontEntry {
+                flavor,
+                tables: font_tables,
+            });
+        }
+        Some((ttc_version, fonts))
+    } else {
+        None
+    };
+
+    let compressed = header.bytes(total_compressed_size)?;
+    let mut decompressed = Vec::with_capacity(uncompressed_size);
+    // reading one byte more than expected is enough to detect a mismatch
+    // without decompressing all of the data
+    brotli_decompressor::Decompressor::new(compressed, 4096)
+        .take(uncompressed_size as u64 + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(|_| Woff2Error::Decompression)?;
+    if decompressed.len() != uncompressed_size {
+        return Err(Woff2Error::Decompression);
+    }
+
+    match fonts {
+        None => {
+            let font = FontEntry {
+                flavor,
+                tables: (0..tables.len()).collect(),
+            };
+            let mut reconstructed = HashMap::new();
+            reconstruct_tables(&tables, &font, &decompressed, &mut reconstructed)?;
+            let mut out = Vec::new();
+            let mut offsets = HashMap::new();
+            write_font(
+                &mut out,
+                &tables,
+                &font,
+                &decompressed,
+                &reconstructed,
+                &mut offsets,
+            )?;
+            fix_head_checksum(&mut out, 0);
+            Ok(out)
+        }
+        Some((ttc_version, fonts)) => {
+            let mut reconstructed = HashMap::new();
+            for font in &fonts {
+                reconstruct_tables(&tables, font, &decompressed, &mut reconstructed)?;
+            }
+            let mut out = Vec::new();
+            push_u32(&mut out, TTC_TAG);
+            push_u32(&mut out, ttc_version);
+            push_u32(&mut out, fonts.len() as u32);
+            let offsets_pos = out.len();
+            out.resize(out.len() + 4 * fonts.len(), 0);
+            if ttc_version == 0x0002_0000 {
+                // no DSIG table
+                out.resize(out.len() + 12, 0);
+            }
+            let mut table_offsets = HashMap::new();
+            let mut font_offsets = Vec::with_capacity(fonts.len());
+            for (i, font) in fonts.iter().enumerate() {
+                let font_offset = out.len();
+                out[offsets_pos + 4 * i..offsets_pos + 4 * i + 4]
+                    .copy_from_slice(&(font_offset as u32).to_be_bytes());
+                write_font(
+                    &mut out,
+                    &tables,
+                    font,
+                    &decompressed,
+                    &reconstructed,
+                    &mut table_offsets,
+                )?;
+                font_offsets.push(font_offset);
+            }
+            for font_offset in font_offsets {
+                fix_head_checksum(&mut out, font_offset);
+            }
+            Ok(out)
+        }
+    }
+}
+
+// Reconstructs the transformed tables of `font`. The result is stored in
+// `reconstructed` keyed by the index of the table entry.
+fn reconstruct_tables(
+    tables: &[TableEntry],
+    font: &FontEntry,
+    data: &[u8],
+    reconstructed: &mut HashMap<usize, Vec<u8>>,
+) -> Result<()> {
+    let find = |tag: Tag| font.tables.iter().copied().find(|&i| tables[i].tag == tag);
+    let table_data = |i: usize| &data[tables[i].src_offset..][..tables[i].src_length];
+
+    let glyf = find(GLYF);
+    let loca = find(LOCA);
+    if let Some(glyf) = glyf {
+        if tables[glyf].transformed && !reconstructed.contains_key(&glyf) {
+            let loca = loca.ok_or(Woff2Error::Malformed("glyf table without loca table"))?;
+            let (glyf_data, loca_data) = reconstruct_glyf(table_data(glyf))?;
+            if loca_data.len() != tables[loca].orig_length as usize {
+                return Err(Woff2Error::Malformed("loca table has the wrong size"));
+            }
+            reconstructed.insert(glyf, glyf_data);
+            reconstructed.insert(loca, loca_data);
+        }
+    } else if loca.map(|loca| tables[loca].transformed) == Some(true) {
+        return Err(Woff2Error::Malformed("loca table without glyf table"));
+    }
+
+    if let Some(hmtx) = find(HMTX) {
+        if tables[hmtx].transformed && !reconstructed.contains_key(&hmtx) {
+            let get = |index: Option<usize>| -> Result<&[u8]> {
+                let index = index.ok_or(Woff2Error::Malformed("hmtx transform needs glyf"))?;
+                Ok(match reconstructed.get(&index) {
+                    Some(data) => data,
+                    None => table_data(index),
+                })
+            };
+            let hhea = find(HHEA).ok_or(Woff2Error::Malformed("hmtx table without hhea"))?;
+            let hmtx_data = reconstruct_hmtx(
+                table_data(hmtx),
+                get(glyf)?,
+                get(loca)?,
+                table_data(hhea),
+                find(HEAD).map(table_data),
+            )?;
+            if hmtx_data.len() != tables[hmtx].orig_length as usize {
+                return Err(Woff2Error::Malformed("hmtx table has the wrong size"));
+            }
+            reconstructed.insert(hmtx, hmtx_data);
+        }
+    }
+    Ok(())
+}
+
+// Writes the table directory and the tables of `font` to `out`. Tables that
+// were already written for another font of a collection are shared.
+fn write_font(
+    out: &mut Vec<u8>,
+    tables: &[TableEntry],
+    font: &FontEntry,
+    data: &[u8],
+    reconstructed: &HashMap<usize, Vec<u8>>,
+    offsets: &mut HashMap<usize, u32>,
+) -> Result<()> {
+    let mut sorted = font.tables.clone();
+    sorted.sort_by_key(|&i| tables[i].tag.0);
+    sorted.dedup();
+
+    let num_tables = u16::try_from(sorted.len())
+        .ok()
+        .filter(|&num_tables| num_tables > 0)
+        .ok_or(Woff2Error::Malformed("invalid number of tables in font"))?;
+    let directory_size = num_tables
+        .checked_mul(16)
+        .ok_or(Woff2Error::Malformed("invalid number of tables in font"))?;
+    let entry_selector = 15 - num_tables.leading_zeros() as u16;
+    let search_range = (1u16 << entry_selector) * 16;
+    push_u32(out, font.flavor);
+    push_u16(out, num_tables);
+    push_u16(out, search_range);
+    push_u16(out, entry_selector);
+    push_u16(out, directory_size - search_range);
+
+    let records_pos = out.len();
+    out.resize(out.len() + 16 * sorted.len(), 0);
+
+    for (i, &index) in sorted.iter().enumerate() {
+        let entry = &tables[index];
+        let table = match reconstructed.get(&index) {
+            Some(table) => &table[..],
+            None if entry.transformed => {
+                return Err(Woff2Error::Malformed("transformed table cannot be decoded"))
+            }
+            None => &data[entry.src_offset..][..entry.src_length],
+        };
+        let offset = match offsets.get(&index) {
+            Some(&offset) => offset,
+            None => {
+                let offset = out.len() as u32;
+                out.extend_from_slice(table);
+                pad4(out);
+                offsets.insert(index, offset);
+                offset
+            }
+        };
+        let record = &mut out[records_pos + 16 * i..][..16];
+        record[0..4].copy_from_slice(&entry.tag.0.to_be_bytes());
+        record[4..8].copy_from_slice(&checksum(table).to_be_bytes());
+        record[8..12].copy_from_slice(&offset.to_be_bytes());
+        record[12..16].copy_from_slice(&(table.len() as u32).to_be_bytes());
+    }
+    Ok(())
+}
+
+// Fixes up the `checkSumAdjustment` of the `head` table of the font whose
+// table directory was written at `directory` by `write_font()`. The
+// adjustment covers the directory and the tables of that font only, so the
+// fonts of a collection are fixed up one by one. A `head` table shared by
+// several fonts keeps the adjustment of the last one.
+fn fix_head_checksum(out: &mut [u8], directory: usize) {
+    let num_tables = u16::from_be_bytes([out[directory + 4], out[directory + 5]]) as usize;
+    let records = directory + 12;
+    let record = |out: &[u8], i: usize, field: usize| {
+        let pos = records + 16 * i + 4 * field;
+        u32::from_be_bytes([out[pos], out[pos + 1], out[pos + 2], out[pos + 3]])
+    };
+    let head = (0..num_tables).find(|&i| record(out, i, 0) == HEAD.0 && record(out, i, 3) >= 12);
+    let head = match head {
+        Some(head) => head,
+        None => return,
+    };
+    let offset = record(out, head, 2) as usize;
+    let length = record(out, head, 3) as usize;
+    // the checksum of `head` is computed with an adjustment of zero
+    out[offset + 8..offset + 12].copy_from_slice(&[0; 4]);
+    let head_checksum = checksum(&out[offset..offset + length]);
+    out[records + 16 * head + 4..][..4].copy_from_slice(&head_checksum.to_be_bytes());
+
+    let sum = (0..num_tables).fold(
+        checksum(&out[directory..records + 16 * num_tables]),
+        |sum, i| sum.wrapping_add(record(out, i, 1)),
+    );
+    let adjustment = 0xB1B0_AFBAu32.wrapping_sub(sum);
+    out[offset + 8..offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+}
+
+fn with_sign(flag: u8, value: i32) -> i32 {
+    if flag & 1 != 0 {
+        value
+    } else {
+        -value
+    }
+}
+
+// Decodes a point from the glyph stream using the triplet encoding. Returns
+// `(dx, dy, on_curve)`.
+fn decode_triplet(flag: u8, glyphs: &mut Reader<'_>) -> Result<(i32, i32, bool)> {
+    let on_curve = flag & 0x80 == 0;
+    let flag = flag & 0x7F;
+    let (dx, dy) = if flag < 10 {
+        let b0 = glyphs.u8()? as i32;
+        (0, with_sign(flag, (((flag & 14) as i32) << 7) + b0))
+    } else if flag < 20 {
+        let b0 = glyphs.u8()? as i32;
+        (with_sign(flag, ((((flag - 10) & 14) as i32) << 7) + b0), 0)
+    } else if flag < 84 {
+        let b0 = (flag - 20) as i32;
+        let b1 = glyphs.u8()? as i32;
+        (
+            with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+            with_sign(flag >> 1, 1 + ((b0 & 0x0C) << 2) + (b1 & 0x0F)),
+        )
+    } else if flag < 120 {
+        let b0 = (flag - 84) as i32;
+        let b = glyphs.bytes(2)?;
+        (
+            with_sign(flag, 1 + ((b0 / 12) << 8) + b[0] as i32),
+            with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b[1] as i32),
+        )
+    } else if flag < 124 {
+        let b = glyphs.bytes(3)?;
+        (
+            with_sign(flag, ((b[0] as i32) << 4) + ((b[1] as i32) >> 4)),
+            with_sign(flag >> 1, (((b[1] & 0x0F) as i32) << 8) + b[2] as i32),
+        )
+    } else {
+        let b = glyphs.bytes(4)?;
+        (
+            with_sign(flag, ((b[0] as i32) << 8) + b[1] as i32),
+            with_sign(flag >> 1, ((b[2] as i32) << 8) + b[3] as i32),
+        )
+    };
+    Ok((dx, dy, on_curve))
+}
+
+// Reconstructs the `glyf` and `loca` tables from a transformed `glyf` table.
+fn reconstruct_glyf(data: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    const ON_CURVE_POINT: u8 = 0x01;
+    const X_SHORT_VECTOR: u8 = 0x02;
+    const REPEAT_FLAG: u8 = 0x08;
+    const Y_SHORT_VECTOR: u8 = 0x04;
+    const X_IS_SAME_OR_POSITIVE: u8 = 0x10;
+    const Y_IS_SAME_OR_POSITIVE: u8 = 0x20;
+    const OVERLAP_SIMPLE: u8 = 0x40;
+
+    const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+    const WE_HAVE_A_SCALE: u16 = 0x0008;
+    const MORE_COMPONENTS: u16 = 0x0020;
+    const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+    const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+    const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+    let mut header = Reader::new(data);
+    let _version = header.u16()?;
+    let option_flags = header.u16()?;
+    let num_glyphs = header.u16()? as usize;
+    let index_format = header.u16()?;
+    let mut streams = Vec::with_capacity(7);
+    let mut offset: usize = 8 + 7 * 4;
+    for _ in 0..7 {
+        let size = header.u32()? as usize;
+        let stream = offset
+            .checked_add(size)
+            .and_then(|end| data.get(offset..end))
+            .ok_or(Woff2Error::Malformed("glyf stream out of bounds"))?;
+        streams.push(Reader::new(stream));
+        offset += size;
+    }
+    let mut streams = streams.into_iter();
+    let mut n_contours = streams.next().unwrap();
+    let mut n_points = streams.next().unwrap();
+    let mut flags = streams.next().unwrap();
+    let mut glyphs = streams.next().unwrap();
+    let mut composites = streams.next().unwrap();
+    let mut bboxes = streams.next().unwrap();
+    let mut instructions = streams.next().unwrap();
+    let overlap_bitmap = if option_flags & 1 != 0 {
+        let len = num_glyphs.div_ceil(8);
+        Some(
+            data.get(offset..offset + len)
+                .ok_or(Woff2Error::Malformed("overlap bitmap out of bounds"))?,
+        )
+    } else {
+        None
+    };
+    let bbox_bitmap = bboxes.bytes(4 * num_glyphs.div_ceil(32))?;
+    let has_bit = |bitmap: &[u8], i: usize| bitmap[i >> 3] & (0x80 >> (i & 7)) != 0;
+
+    let mut glyf = Vec::new();
+    let mut loca_offsets = Vec::with_capacity(num_glyphs + 1);
+    for glyph_id in 0..num_glyphs {
+        loca_offsets.push(glyf.len() as u32);
+        let num_contours = n_contours.i16()?;
+        let explicit_bbox = has_bit(bbox_bitmap, glyph_id);
+        if num_contours == 0 {
+            if explicit_bbox {
+                return Err(Woff2Error::Malformed("empty glyph with bounding box"));
+            }
+            continue;
+        }
+
+        push_u16(&mut glyf, num_contours as u16);
+        let bbox_pos = glyf.len();
+        glyf.resize(glyf.len() + 8, 0);
+        if explicit_bbox {
+            glyf[bbox_pos..bbox_pos + 8].copy_from_slice(bboxes.bytes(8)?);
+        }
+
+        if num_contours > 0 {
+            let mut total_points = 0u32;
+            for _ in 0..num_contours {
+                total_points += n_points.u255_16()? as u32;
+                if total_points > 0xFFFF {
+                    return Err(Woff2Error::Malformed("too many points"));
+                }
+                let end_point = total_points
+                    .checked_sub(1)
+                    .ok_or(Woff2Error::Malformed("contour without points"))?;
+                push_u16(&mut glyf, end_point as u16);
+            }
+
+            let mut point_flags = Vec::with_capacity(total_points as usize);
+            let mut x_coords = Vec::new();
+            let mut y_coords = Vec::new();
+            let (mut x, mut y) = (0i32, 0i32);
+            let (mut x_min, mut y_min, mut x_max, mut y_max) =
+                (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
+            for _ in 0..total_points {
+                let (dx, dy, on_curve) = decode_triplet(flags.u8()?, &mut glyphs)?;
+                let mut flag = if on_curve { ON_CURVE_POINT } else { 0 };
+                if dx == 0 {
+                    flag |= X_IS_SAME_OR_POSITIVE;
+                } else if dx > -256 && dx < 256 {
+                    flag |= X_SHORT_VECTOR;
+                    if dx > 0 {
+                        flag |= X_IS_SAME_OR_POSITIVE;
+                    }
+                    x_coords.push(dx.unsigned_abs() as u8);
+                } else {
+                    push_u16(&mut x_coords, dx as u16);
+                }
+                if dy == 0 {
+                    flag |= Y_IS_SAME_OR_POSITIVE;
+                } else if dy > -256 && dy < 256 {
+                    flag |= Y_SHORT_VECTOR;
+                    if dy > 0 {
+                        flag |= Y_IS_SAME_OR_POSITIVE;
+                    }
+                    y_coords.push(dy.unsigned_abs() as u8);
+                } else {
+                    push_u16(&mut y_coords, dy as u16);
+                }
+                point_flags.push(flag);
+
+                x = x
+                    .checked_add(dx)
+                    .ok_or(Woff2Error::Malformed("coordinate overflow"))?;
+                y = y
+                    .checked_add(dy)
+                    .ok_or(Woff2Error::Malformed("coordinate overflow"))?;
+                x_min = x_min.min(x);
+                y_min = y_min.min(y);
+                x_max = x_max.max(x);
+                y_max = y_max.max(y);
+            }
+            if let Some(first) = point_flags.first_mut() {
+                if overlap_bitmap.map(|bitmap| has_bit(bitmap, glyph_id)) == Some(true) {
+                    *first |= OVERLAP_SIMPLE;
+                }
+            }
+            // runs of equal flags are stored with the repeat flag, like the
+            // reference decoder does
+            let mut packed_flags = Vec::with_capacity(point_flags.len());
+            let mut repeat_count = 0u8;
+            for (i, &flag) in point_flags.iter().enumerate() {
+                if i > 0 && flag == point_flags[i - 1] && repeat_count != 255 {
+                    if let Some(last) = packed_flags.last_mut() {
+                        *last |= REPEAT_FLAG;
+                    }
+                    repeat_count += 1;
+                } else {
+                    if repeat_count != 0 {
+                        packed_flags.push(repeat_count);
+                    }
+                    packed_flags.push(flag);
+                    repeat_count = 0;
+                }
+            }
+            if repeat_count != 0 {
+                packed_flags.push(repeat_count);
+            }
+
+            let instruction_len = glyphs.u255_16()?;
+            push_u16(&mut glyf, instruction_len);
+            glyf.extend_from_slice(instructions.bytes(instruction_len as usize)?);
+            glyf.extend_from_slice(&packed_flags);
+            glyf.extend_from_slice(&x_coords);
+            glyf.extend_from_slice(&y_coords);
+
+            if !explicit_bbox && total_points > 0 {
+                let bbox = &mut glyf[bbox_pos..bbox_pos + 8];
+                bbox[0..2].copy_from_slice(&(x_min as i16).to_be_bytes());
+                bbox[2..4].copy_from_slice(&(y_min as i16).to_be_bytes());
+                bbox[4..6].copy_from_slice(&(x_max as i16).to_be_bytes());
+                bbox[6..8].copy_from_slice(&(y_max as i16).to_be_bytes());
+            }
+        } else {
+            if !explicit_bbox {
+                return Err(Woff2Error::Malformed(
+                    "composite glyph without bounding box",
+                ));
+            }
+            let start = composites.pos;
+            let mut have_instructions = false;
+            loop {
+                let flags = composites.u16()?;
+                let mut len = 2 + if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                    4
+                } else {
+                    2
+                };
+                if flags & WE_HAVE_A_SCALE != 0 {
+                    len += 2;
+                } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                    len += 4;
+                } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                    len += 8;
+                }
+                composites.bytes(len)?;
+                have_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+                if flags & MORE_COMPONENTS == 0 {
+                    break;
+                }
+            }
+            glyf.extend_from_slice(&composites.data[start..composites.pos]);
+            if have_instructions {
+                let instruction_len = glyphs.u255_16()?;
+                push_u16(&mut glyf, instruction_len);
+                glyf.extend_from_slice(instructions.bytes(instruction_len as usize)?);
+            }
+        }
+        pad4(&mut glyf);
+    }
+    loca_offsets.push(glyf.len() as u32);
+
+    let mut loca = Vec::new();
+    for offset in loca_offsets {
+        if index_format == 0 {
+            if offset / 2 > 0xFFFF {
+                return Err(Woff2Error::Malformed("glyf table too large for short loca"));
+            }
+            push_u16(&mut loca, (offset / 2) as u16);
+        } else {
+            push_u32(&mut loca, offset);
+        }
+    }
+    Ok((glyf, loca))
+}
+
+// Reconstructs the `hmtx` table from a transformed `hmtx` table. Left side
+// bearings that were left out are taken from the glyph bounding boxes.
+fn reconstruct_hmtx(
+    data: &[u8],
+    glyf: &[u8],
+    loca: &[u8],
+    hhea: &[u8],
+    head: Option<&[u8]>,
+) -> Result<Vec<u8>> {
+    let num_h_metrics = Reader::new(hhea.get(34..).unwrap_or(&[])).u16()? as usize;
+    let long_loca = match head {
+        Some(head) => Reader::new(head.get(50..).unwrap_or(&[])).i16()? == 1,
+        _ => return Err(Woff2Error::Malformed("hmtx transform needs head table")),
+    };
+    let loca_entry = if long_loca { 4 } else { 2 };
+    let num_glyphs = (loca.len() / loca_entry).saturating_sub(1);
+    if num_h_metrics == 0 || num_h_metrics > num_glyphs {
+        return Err(Woff2Error::Malformed(
+            "invalid number of horizontal metrics",
+        ));
+    }
+
+    let x_min = |glyph: usize| -> Result<i16> {
+        let mut loca = Reader::new(loca);
+        loca.pos = glyph * loca_entry;
+        let (start, end) = if long_loca {
+            (loca.u32()? as usize, loca.u32()? as usize)
+        } else {
+            (loca.u16()? as usize * 2, loca.u16()? as usize * 2)
+        };
+        if start == end {
+            return Ok(0);
+        }
+        let mut glyph = Reader::new(glyf.get(start..end).unwrap_or(&[]));
+        glyph.u16()?;
+        glyph.i16()
+    };
+
+    let mut reader = Reader::new(data);
+    let flags = reader.u8()?;
+    let mut advances = Vec::with_capacity(num_h_metrics);
+    for _ in 0..num_h_metrics {
+        advances.push(reader.u16()?);
+    }
+    let mut lsbs = Vec::with_capacity(num_glyphs);
+    for glyph in 0..num_glyphs {
+        let omitted = if glyph < num_h_metrics {
+            flags & 1 != 0
+        } else {
+            flags & 2 != 0
+        };
+        lsbs.push(if omitted {
+            x_min(glyph)?
+        } else {
+            reader.i16()?
+        });
+    }
+
+    let mut hmtx = Vec::with_capacity(2 * (num_h_metrics + num_glyphs));
+    for (glyph, lsb) in lsbs.into_iter().enumerate() {
+        if glyph < num_h_metrics {
+            push_u16(&mut hmtx, advances[glyph]);
+        }
+        push_u16(&mut hmtx, lsb as u16);
+    }
+    Ok(hmtx)
+}
+
+impl Face<'static> {
+    /// Create a new face from the WOFF2 compressed font `data` and an index
+    /// specifying which font to read from a font collection.
+    ///
+    /// The font data is decompressed and converted to an uncompressed OpenType
+    /// font which is owned by the returned face. See also [`decode_woff2()`].
+    ///
+    /// This function is only available with the `woff2` feature.
+    pub fn from_woff2(data: &[u8], index: u32) -> Result<Owned<Face<'static>>> {
+        let sfnt = decode_woff2(data)?;
+        let blob: Shared<Blob<'static>> = sfnt.into();
+        Ok(Face::new(blob, index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writes bits in the order used by brotli streams.
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        bit: u32,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: u32, bits: u32) {
+            for i in 0..bits {
+                if self.bit & 7 == 0 {
+                    self.bytes.push(0);
+                }
+                let last = self.bytes.last_mut().unwrap();
+                *last |= (((value >> i) & 1) as u8) << (self.bit % 8);
+                self.bit += 1;
+            }
+        }
+    }
+
+    // Creates a brotli stream that stores `data` uncompressed.
+    fn brotli_stored(data: &[u8]) -> Vec<u8> {
+        let mut writer = BitWriter::default();
+        writer.write(0, 1); // window size of 16 bits
+        for chunk in data.chunks(0x10000) {
+            writer.write(0, 1); // ISLAST
+            writer.write(0, 2); // 4 nibbles for the length
+            writer.write(chunk.len() as u32 - 1, 16);
+            writer.write(1, 1); // ISUNCOMPRESSED
+            writer.bit = writer.bit.div_ceil(8) * 8;
+            writer.bytes.extend_from_slice(chunk);
+        }
+        writer.write(0b11, 2); // ISLAST, ISLASTEMPTY
+        writer.bytes
+    }
+
+    fn base128(mut value: u32, out: &mut Vec<u8>) {
+        let mut bytes = vec![(value & 0x7F) as u8];
+        value >>= 7;
+        while value != 0 {
+            bytes.push((value & 0x7F) as u8 | 0x80);
+            value >>= 7;
+        }
+        out.extend(bytes.iter().rev());
+    }
+
+    // Converts an OpenType font into a WOFF2 font without any table
+    // transforms.
+    fn encode_woff2(font: &[u8]) -> Vec<u8> {
+        encode_woff2_collection(font, &[])
+    }
+
+    // Like `encode_woff2()`, but creates a collection if `fonts` is not
+    // empty. Each font consists of the tables with the given indices.
+    fn encode_woff2_collection(font: &[u8], fonts: &[&[u8]]) -> Vec<u8> {
+        let face = Face::from_bytes(font, 0);
+        let mut directory = Vec::new();
+        let mut table_data = Vec::new();
+        for tag in face.table_tags() {
+            let table = face.table_with_tag(tag).unwrap();
+            let version = if tag == GLYF || tag == LOCA {
+                3 << 6
+            } else {
+                0
+            };
+            directory.push(version | 0x3F);
+            push_u32(&mut directory, tag.0);
+            base128(table.len() as u32, &mut directory);
+            table_data.extend_from_slice(&table);
+        }
+        let compressed = brotli_stored(&table_data);
+
+        if !fonts.is_empty() {
+            push_u32(&mut directory, 0x0001_0000);
+            directory.push(fonts.len() as u8);
+            for tables in fonts {
+                directory.push(tables.len() as u8);
+                directory.extend_from_slice(&font[0..4]);
+                directory.extend_from_slice(tables);
+            }
+        }
+
+        let mut woff2 = Vec::new();
+        push_u32(&mut woff2, WOFF2_SIGNATURE);
+        if fonts.is_empty() {
+            woff2.extend_from_slice(&font[0..4]);
+        } else {
+            push_u32(&mut woff2, TTC_TAG);
+        }
+        push_u32(&mut woff2, 0); // length
+        push_u16(&mut woff2, face.table_tags().len() as u16);
+        push_u16(&mut woff2, 0);
+        push_u32(&mut woff2, font.len() as u32);
+        push_u32(&mut woff2, compressed.len() as u32);
+        woff2.resize(48, 0);
+        woff2.extend_from_slice(&directory);
+        woff2.extend_from_slice(&compressed);
+        woff2
+    }
+
+    #[test]
+    fn test_decode_woff2() {
+        let font = std::fs::read("testfiles/SourceSansVariable-Roman.ttf").unwrap();
+        let woff2 = encode_woff2(&font);
+
+        let face = Face::from_woff2(&woff2, 0).unwrap();
+        let original = Face::from_bytes(&font, 0);
+        assert_eq!(face.table_tags(), original.table_tags());
+        for tag in original.table_tags() {
+            if tag != HEAD {
+                assert_eq!(
+                    &face.table_with_tag(tag).unwrap()[..],
+                    &original.table_with_tag(tag).unwrap()[..]
+                );
+            }
+        }
+        assert_eq!(face.glyph_count(), original.glyph_count());
+        assert_eq!(checksum(&decode_woff2(&woff2).unwrap()), 0xB1B0_AFBA);
+    }
+
+    // Returns the sum of the checksums of the table directory and the tables
+    // of the font at `index` in a collection, which is the checksum of the
+    // font as a single font file.
+    fn font_checksum(collection: &[u8], index: usize) -> u32 {
+        let mut reader = Reader::new(collection);
+        reader.pos = 12 + 4 * index;
+        let directory = reader.u32().unwrap() as usize;
+        reader.pos = directory + 4;
+        let num_tables = reader.u16().unwrap() as usize;
+        let records = directory + 12;
+        let mut sum = checksum(&collection[directory..records + 16 * num_tables]);
+        for i in 0..num_tables {
+            reader.pos = records + 16 * i + 8;
+            let offset = reader.u32().unwrap() as usize;
+            let length = reader.u32().unwrap() as usize;
+            sum = sum.wrapping_add(checksum(&collection[offset..offset + length]));
+        }
+        sum
+    }
+
+    #[test]
+    fn test_decode_woff2_collection() {
+        let font = std::fs::read("testfiles/SourceSansVariable-Roman.ttf").unwrap();
+        let num_tables = Face::from_bytes(&font, 0).table_tags().len() as u8;
+        let all: Vec<u8> = (0..num_tables).collect();
+        let woff2 = encode_woff2_collection(&font, &[&all, &all]);
+
+        let collection = decode_woff2(&woff2).unwrap();
+        assert_eq!(&collection[0..4], b"ttcf");
+        for index in 0..2 {
+            assert_eq!(font_checksum(&collection, index), 0xB1B0_AFBA);
+            let face = Face::from_woff2(&woff2, index as u32).unwrap();
+            assert_eq!(face.glyph_count(), Face::from_bytes(&font, 0).glyph_count());
+        }
+
+        let woff2 = encode_woff2_collection(&font, &[&all, &[]]);
+        assert_eq!(
+            decode_woff2(&woff2),
+            Err(Woff2Error::Malformed("invalid number of tables in font"))
+        );
+    }
+
+    #[test]
+    fn test_decode_woff2_fixture() {
+        // encoded by the reference encoder with transformed glyf and loca
+        // tables
+        let woff2 = std::fs::read("testfiles/OpenSans-Regular.woff2").unwrap();
+        let sfnt = decode_woff2(&woff2).unwrap();
+        // the size of the font as computed by the encoder
+        assert_eq!(sfnt.len(), 93908);
+        assert_eq!(checksum(&sfnt), 0xB1B0_AFBA);
+
+        let face = Face::from_bytes(&sfnt, 0);
+        assert_eq!(face.glyph_count(), 902);
+        // the reconstructed tables have the lengths of the tables of the
+        // original font stored in the table directory
+        assert_eq!(face.table_with_tag(GLYF).unwrap().len(), 76440);
+        assert_eq!(face.table_with_tag(LOCA).unwrap().len(), 1806);
+
+        // decoding is byte-exact: encoding the result again without
+        // transforms round-trips
+        assert_eq!(decode_woff2(&encode_woff2(&sfnt)).unwrap(), sfnt);
+
+        let font = crate::Font::new(Face::from_bytes(&sfnt, 0));
+        let hyphen = font.get_nominal_glyph('-').unwrap();
+        assert_eq!(
+            font.glyph_to_svg_path(hyphen),
+            "M84 -473L84 -625L575 -625L575 -473L84 -473Z"
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid_woff2() {
+        let font = std::fs::read("testfiles/SourceSansVariable-Roman.ttf").unwrap();
+        assert_eq!(decode_woff2(&font), Err(Woff2Error::NotWoff2));
+
+        let woff2 = encode_woff2(&font);
+        assert!(decode_woff2(&woff2[..woff2.len() - 100]).is_err());
+    }
+
+    // Creates a WOFF2 font with a single untransformed table whose length in
+    // the table directory is `length` and whose data is `data`.
+    fn single_table_woff2(length: u32, data: &[u8]) -> Vec<u8> {
+        let compressed = brotli_stored(data);
+        let mut woff2 = Vec::new();
+        push_u32(&mut woff2, WOFF2_SIGNATURE);
+        push_u32(&mut woff2, 0x0001_0000);
+        push_u32(&mut woff2, 0); // length
+        push_u16(&mut woff2, 1);
+        push_u16(&mut woff2, 0);
+        push_u32(&mut woff2, 0); // totalSfntSize
+        push_u32(&mut woff2, compressed.len() as u32);
+        woff2.resize(48, 0);
+        woff2.push(0x3F);
+        woff2.extend_from_slice(b"name");
+        base128(length, &mut woff2);
+        woff2.extend_from_slice(&compressed);
+        woff2
+    }
+
+    #[test]
+    fn test_decode_oversized_woff2() {
+        // the declared size is rejected before anything is allocated
+        let woff2 = single_table_woff2(u32::MAX, &[0; 16]);
+        assert_eq!(
+            decode_woff2(&woff2),
+            Err(Woff2Error::Malformed("font too large"))
+        );
+        let woff2 = single_table_woff2(MAX_FONT_SIZE as u32 + 1, &[0; 16]);
+        assert_eq!(
+            decode_woff2(&woff2),
+            Err(Woff2Error::Malformed("font too large"))
+        );
+
+        // more data than declared is a decompression error
+        let woff2 = single_table_woff2(16, &[0; 100_000]);
+        assert_eq!(decode_woff2(&woff2), Err(Woff2Error::Decompression));
+    }
+
+    #[test]
+    fn test_reconstruct_glyf() {
+        let mut transformed = Vec::new();
+        push_u16(&mut transformed, 0); // version
+        push_u16(&mut transformed, 0); // option flags
+        push_u16(&mut transformed, 2); // number of glyphs
+        push_u16(&mut transformed, 0); // short loca format
+        let n_contours = [0u8, 0, 0, 1];
+        let n_points = [3u8];
+        // three on-curve points: (0, 0), (100, 0) and (50, 80)
+        let flags = [1u8, 11, 86];
+        let glyphs = [0u8, 100, 49, 79, 0];
+        let bboxes = [0u8; 4];
+        let streams: [&[u8]; 7] = [&n_contours, &n_points, &flags, &glyphs, &[], &bboxes, &[]];
+        for stream in &streams {
+            push_u32(&mut transformed, stream.len() as u32);
+        }
+        for stream in &streams {
+            transformed.extend_from_slice(stream);
+        }
+
+        let (glyf, loca) = reconstruct_glyf(&transformed).unwrap();
+        // contours need at least one point
+        let mut empty_contour = transformed.clone();
+        empty_contour[8 + 7 * 4 + n_contours.len()] = 0;
+        assert_eq!(
+            reconstruct_glyf(&empty_contour),
+            Err(Woff2Error::Malformed("contour without points"))
+        );
+        assert_eq!(loca, [0, 0, 0, 0, 0, 10]);
+        #[rustfmt::skip]
+        assert_eq!(glyf, [
+            0, 1, // number of contours
+            0, 0, 0, 0, 0, 100, 0, 80, // bounding box
+            0, 2, // end point of the contour
+            0, 0, // no instructions
+            0x31, 0x33, 0x27, // flags
+            100, 50, // x coordinates
+            80, // y coordinates
+        ]);
+    }
+}
@@ -8,3 +8,37 @@ git checkout $VERSION
 popd
 
 bindgen --no-prepend-enum-name --allowlist-function hb_.\* --allowlist-type hb_.\* wrapper.h > src/bindings.rs
+
+# generate the `Script` constants from the script values in the bindings
+script_name() {
+    echo "$1" | sed -E 's/([A-Z])([A-Z0-9]*)/\1\L\2/g'
+}
+{
+    echo "// This file is generated by update.sh from the \`hb_script_t\` values in"
+    echo "// src/bindings.rs. Do not edit it by hand."
+    echo
+    echo "use crate::bindings::*;"
+    echo "use crate::common::Script;"
+    echo
+    echo "impl Script {"
+    grep -E '^pub const HB_SCRIPT_[A-Z0-9_]+: hb_script_t = [1-9]' src/bindings.rs |
+        sed -E 's/^pub const HB_SCRIPT_([A-Z0-9_]+): hb_script_t = ([0-9]+);/\1 \2/' |
+        while read -r name value; do
+            tag=""
+            for shift in 24 16 8 0; do
+                tag+=$(printf "\\x$(printf %x $((value >> shift & 255)))")
+            done
+            echo "    /// The $(script_name "$name") script (\`$tag\`)."
+            echo "    pub const $name: Script = Script(HB_SCRIPT_$name);"
+        done
+    echo "}"
+    echo
+    echo "/// All scripts known to HarfBuzz with their Unicode names."
+    echo "pub(crate) const SCRIPT_NAMES: &[(Script, &str)] = &["
+    grep -oE '^pub const HB_SCRIPT_[A-Z0-9_]+: hb_script_t = [1-9]' src/bindings.rs |
+        sed -E 's/^pub const HB_SCRIPT_([A-Z0-9_]+).*/\1/' |
+        while read -r name; do
+            echo "    (Script::$name, \"$(script_name "$name")\"),"
+        done
+    echo "];"
+} > src/scripts.rs